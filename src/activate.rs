@@ -0,0 +1,322 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::backends::{ghostty, neovim, state_home, zellij, BackendOptions, Target, ThemeSetting};
+use crate::cli::NeovimLayout;
+
+/// File under `$XDG_STATE_HOME/nuri` recording activations, so that
+/// [`deactivate`] can put back what was there before.
+const STATE_FILE: &str = "activations";
+
+/// Appended to a config file's name for the copy made before each edit.
+pub const BACKUP_SUFFIX: &str = ".nuri.bak";
+
+/// An app whose own config file selects its theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum App {
+    /// `theme = <name>` in the Ghostty config.
+    Ghostty,
+    /// `theme "<name>"` in Zellij's `config.kdl`.
+    Zellij,
+    /// A colorscheme call in Neovim's `init.lua`.
+    Neovim,
+}
+
+impl App {
+    /// The app a target's themes are for, if it has a config to select them in.
+    pub fn from_target(target: &Target) -> Option<App> {
+        match target {
+            Target::Ghostty => Some(App::Ghostty),
+            Target::Zellij => Some(App::Zellij),
+            Target::Neovim => Some(App::Neovim),
+            _ => None,
+        }
+    }
+
+    /// Like [`App::from_target`], but only if the installed theme can be
+    /// selected: Neovim needs the plugin layout, whose `colors/` loader is
+    /// what `:colorscheme` looks for.
+    pub fn for_target(target: &Target, options: &BackendOptions) -> Option<App> {
+        match App::from_target(target)? {
+            App::Neovim if options.neovim_layout != NeovimLayout::Plugin => None,
+            app => Some(app),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            App::Ghostty => "Ghostty",
+            App::Zellij => "Zellij",
+            App::Neovim => "Neovim",
+        }
+    }
+
+    /// The `--target` name, also used in the state file.
+    fn key(self) -> &'static str {
+        match self {
+            App::Ghostty => "ghostty",
+            App::Zellij => "zellij",
+            App::Neovim => "neovim",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<App> {
+        [App::Ghostty, App::Zellij, App::Neovim]
+            .into_iter()
+            .find(|app| app.key() == key)
+    }
+
+    /// The config file that selects the theme.
+    pub fn config_path(self) -> Result<PathBuf> {
+        match self {
+            App::Ghostty => ghostty::config_path(),
+            App::Zellij => zellij::config_path(),
+            App::Neovim => neovim::init_path(),
+        }
+    }
+
+    fn find(self, config: &str) -> Option<ThemeSetting> {
+        match self {
+            App::Ghostty => ghostty::find_theme(config),
+            App::Zellij => zellij::find_config_theme(config),
+            App::Neovim => neovim::find_colorscheme(config),
+        }
+    }
+
+    fn select(self, config: &str, theme_name: &str) -> String {
+        match self {
+            App::Ghostty => ghostty::set_theme(config, theme_name),
+            App::Zellij => zellij::set_config_theme(config, theme_name),
+            App::Neovim => neovim::set_colorscheme(config, theme_name),
+        }
+    }
+}
+
+/// A theme selected by [`activate`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Activation {
+    pub app: App,
+    pub config: PathBuf,
+    pub theme: String,
+    /// What the config selected before the first activation, if anything.
+    pub previous: Option<String>,
+}
+
+/// Select the installed theme in the app's config, keeping the rest of the
+/// file as it is. The file is backed up first (see [`BACKUP_SUFFIX`]), and
+/// created if missing. `installed` is where the theme was installed; Neovim
+/// selects the plugin by its directory name.
+///
+/// Activating again, e.g. with a new wallpaper, still remembers the theme
+/// from before the first activation, unless the config was changed by hand
+/// in between.
+pub fn activate(app: App, theme_name: &str, installed: &Path) -> Result<Activation> {
+    let theme = match app {
+        App::Neovim => installed
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .context("plugin path has no directory name")?,
+        _ => theme_name.to_string(),
+    };
+    let config = app.config_path()?;
+    let content = read_config(&config)?;
+    let current = app.find(&content).map(|s| s.value(&content).to_string());
+
+    let state_path = state_path()?;
+    let mut state = read_state(&state_path)?;
+    let previous = match state.iter().position(|a| a.config == config) {
+        Some(i) => {
+            let earlier = state.remove(i);
+            if current.as_deref() == Some(earlier.theme.as_str()) {
+                earlier.previous
+            } else {
+                current
+            }
+        }
+        None => current,
+    };
+
+    write_config(&config, &content, &app.select(&content, &theme))?;
+    let activation = Activation {
+        app,
+        config,
+        theme,
+        previous,
+    };
+    state.push(activation.clone());
+    write_state(&state_path, &state)?;
+    Ok(activation)
+}
+
+/// What [`deactivate`] did with one activation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Deactivation {
+    /// The previous theme was selected again, or the setting removed if
+    /// there was none.
+    Restored(Activation),
+    /// The config selects something else now (the current value), so it was
+    /// left alone.
+    Changed(Activation, Option<String>),
+}
+
+/// Undo the activations for `apps`, or all of them if `apps` is empty.
+pub fn deactivate(apps: &[App]) -> Result<Vec<Deactivation>> {
+    let state_path = state_path()?;
+    let (undo, keep): (Vec<Activation>, Vec<Activation>) = read_state(&state_path)?
+        .into_iter()
+        .partition(|a| apps.is_empty() || apps.contains(&a.app));
+
+    let mut done = Vec::new();
+    for activation in undo {
+        let content = read_config(&activation.config)?;
+        let setting = activation
+            .app
+            .find(&content)
+            .filter(|s| s.value(&content) == activation.theme);
+        let Some(setting) = setting else {
+            let current = activation
+                .app
+                .find(&content)
+                .map(|s| s.value(&content).to_string());
+            done.push(Deactivation::Changed(activation, current));
+            continue;
+        };
+        let restored = match &activation.previous {
+            Some(previous) => setting.replace(&content, previous),
+            None => setting.remove(&content),
+        };
+        write_config(&activation.config, &content, &restored)?;
+        done.push(Deactivation::Restored(activation));
+    }
+    write_state(&state_path, &keep)?;
+    Ok(done)
+}
+
+/// A config's content, or nothing if it doesn't exist yet.
+fn read_config(path: &Path) -> Result<String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e).with_context(|| format!("failed to read {}", path.display())),
+    }
+}
+
+/// Replace `old` with `new` at `path`, backing up the existing file first.
+fn write_config(path: &Path, old: &str, new: &str) -> Result<()> {
+    if old == new {
+        return Ok(());
+    }
+    if path.exists() {
+        let backup = backup_path(path);
+        std::fs::copy(path, &backup)
+            .with_context(|| format!("failed to back up {}", path.display()))?;
+    } else if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    std::fs::write(path, new).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Where the copy of `config` is kept, e.g. `config.kdl.nuri.bak`.
+pub fn backup_path(config: &Path) -> PathBuf {
+    let mut name = config.file_name().unwrap_or_default().to_os_string();
+    name.push(BACKUP_SUFFIX);
+    config.with_file_name(name)
+}
+
+fn state_path() -> Result<PathBuf> {
+    Ok(state_home()?.join("nuri").join(STATE_FILE))
+}
+
+/// One activation per line: app, config path, theme, and previous theme
+/// (empty for none), separated by tabs.
+fn read_state(path: &Path) -> Result<Vec<Activation>> {
+    let content = read_config(path)?;
+    let mut activations = Vec::new();
+    for (n, line) in content.lines().enumerate() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [key, config, theme, previous] = fields[..] else {
+            bail!("{} line {}: expected 4 fields", path.display(), n + 1);
+        };
+        let Some(app) = App::from_key(key) else {
+            bail!("{} line {}: unknown app '{key}'", path.display(), n + 1);
+        };
+        activations.push(Activation {
+            app,
+            config: PathBuf::from(config),
+            theme: theme.to_string(),
+            previous: (!previous.is_empty()).then(|| previous.to_string()),
+        });
+    }
+    Ok(activations)
+}
+
+fn write_state(path: &Path, activations: &[Activation]) -> Result<()> {
+    let mut out = String::new();
+    for a in activations {
+        out.push_str(&format!(
+            "{}\t{}\t{}\t{}\n",
+            a.app.key(),
+            a.config.display(),
+            a.theme,
+            a.previous.as_deref().unwrap_or_default()
+        ));
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    std::fs::write(path, out).with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neovim_needs_the_plugin_layout() {
+        let single = BackendOptions::default();
+        let plugin = BackendOptions {
+            neovim_layout: NeovimLayout::Plugin,
+        };
+        assert_eq!(
+            App::for_target(&Target::Ghostty, &single),
+            Some(App::Ghostty)
+        );
+        assert_eq!(App::for_target(&Target::Neovim, &single), None);
+        assert_eq!(App::for_target(&Target::Neovim, &plugin), Some(App::Neovim));
+        assert_eq!(App::for_target(&Target::Vim, &plugin), None);
+    }
+
+    #[test]
+    fn state_round_trips() {
+        let path = std::env::temp_dir().join("nuri-test-activate-state/activations");
+        let activations = vec![
+            Activation {
+                app: App::Zellij,
+                config: PathBuf::from("/home/u/.config/zellij/config.kdl"),
+                theme: "sunset".to_string(),
+                previous: Some("dracula".to_string()),
+            },
+            Activation {
+                app: App::Neovim,
+                config: PathBuf::from("/home/u/.config/nvim/init.lua"),
+                theme: "sunset".to_string(),
+                previous: None,
+            },
+        ];
+        write_state(&path, &activations).unwrap();
+        let read = read_state(&path).unwrap();
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+        assert_eq!(read, activations);
+    }
+
+    #[test]
+    fn backups_sit_next_to_the_config() {
+        assert_eq!(
+            backup_path(Path::new("/c/zellij/config.kdl")),
+            PathBuf::from("/c/zellij/config.kdl.nuri.bak")
+        );
+    }
+}
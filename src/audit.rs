@@ -0,0 +1,414 @@
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::backends::neovim::derive_surface;
+use crate::backends::{get_backend, guess_target, Target};
+use crate::color::{Color, Cvd};
+use crate::pipeline::assign::{
+    hue_distance, AnsiPalette, MAX_HUE_DISTANCE, SLOT_NAMES, TARGET_HUES,
+};
+use crate::pipeline::contrast::SELECTION_MIN_CONTRAST;
+use crate::pipeline::cvd::{CVD_MIN_DISTANCE, CVD_PAIRS};
+
+/// Minimum contrast ratio for foreground vs background.
+const FOREGROUND_MIN_CONTRAST: f32 = 7.0;
+
+/// Minimum contrast ratio for bright black (slot 8) vs background.
+const BRIGHT_BLACK_MIN_CONTRAST: f32 = 3.0;
+
+/// Minimum contrast for text drawn on raised surfaces (WCAG large-text / UI
+/// component level).
+const OVERLAY_MIN_CONTRAST: f32 = 3.0;
+
+/// Below this Oklch chroma an accent has no usable hue.
+const MIN_ACCENT_CHROMA: f32 = 0.02;
+
+/// One contrast measurement and the minimum it must meet, if any.
+#[derive(Debug, Clone, Copy)]
+pub struct Contrast {
+    pub ratio: f32,
+    pub min: Option<f32>,
+}
+
+impl Contrast {
+    fn new(a: &Color, b: &Color, min: Option<f32>) -> Self {
+        Self {
+            ratio: Color::contrast_ratio(a, b),
+            min,
+        }
+    }
+
+    pub fn passes(&self) -> bool {
+        self.min.is_none_or(|min| self.ratio >= min)
+    }
+}
+
+/// Contrast of one slot against the background, the selection, and the surface.
+#[derive(Debug, Clone)]
+pub struct SlotContrast {
+    pub slot: usize,
+    pub color: Color,
+    pub background: Contrast,
+    pub selection: Contrast,
+    pub surface: Contrast,
+}
+
+/// Two accents that become hard to tell apart under a deficiency.
+#[derive(Debug, Clone)]
+pub struct Confusable {
+    pub cvd: Cvd,
+    pub a: usize,
+    pub b: usize,
+    pub distance: f32,
+    /// Whether this fails the audit rather than warning. Always, except with
+    /// `--warn-only`, where only the pairs `--cvd-safe` generation keeps apart
+    /// fail, and only when auditing for it.
+    pub fails: bool,
+}
+
+/// An accent whose hue has wandered away from its ANSI role.
+#[derive(Debug, Clone)]
+pub struct HueDrift {
+    pub slot: usize,
+    pub target: f32,
+    /// Hue of the slot, or `None` when it is too gray to have one.
+    pub hue: Option<f32>,
+    /// Whether this fails the audit rather than warning (unless `--warn-only`).
+    pub fails: bool,
+}
+
+/// Result of auditing a palette.
+#[derive(Debug, Clone)]
+pub struct AuditReport {
+    pub contrast: Vec<SlotContrast>,
+    pub selection_text: Contrast,
+    pub confusable: Vec<Confusable>,
+    pub hue_drift: Vec<HueDrift>,
+}
+
+impl AuditReport {
+    /// Number of failed checks: every contrast minimum, confusable pair, and
+    /// hue drift, except those `--warn-only` turns into warnings.
+    pub fn failures(&self) -> usize {
+        let contrast = self
+            .contrast
+            .iter()
+            .flat_map(|c| [c.background, c.selection, c.surface])
+            .filter(|c| !c.passes())
+            .count();
+        contrast
+            + usize::from(!self.selection_text.passes())
+            + self.confusable.iter().filter(|c| c.fails).count()
+            + self.hue_drift.iter().filter(|d| d.fails).count()
+    }
+
+    /// Number of problems reported without failing the audit: with
+    /// `--warn-only`, confusable accents and hue drift.
+    pub fn warnings(&self) -> usize {
+        self.confusable.iter().filter(|c| !c.fails).count()
+            + self.hue_drift.iter().filter(|d| !d.fails).count()
+    }
+}
+
+/// Read a theme file, guessing its format from the extension unless given.
+pub fn load_theme(path: &Path, target: Option<Target>) -> Result<AnsiPalette> {
+    let target = target.unwrap_or_else(|| guess_target(path));
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read theme: {}", path.display()))?;
+    get_backend(target)
+        .parse(&content)
+        .with_context(|| format!("failed to parse theme: {}", path.display()))
+}
+
+/// Check a palette's contrast, color-blind safety, and hue roles.
+///
+/// Contrast thresholds match what generation enforces: `accent_min` for
+/// accents, 7:1 for the foreground, 3:1 for bright black and for selection
+/// text. Every check that doesn't hold fails, except that with `warn_only`
+/// confusable accents and hue drift are only warnings; `cvd_safe` then still
+/// fails confusable red/green and blue/magenta pairs, as `--cvd-safe`
+/// generation keeps them apart.
+pub fn audit(
+    palette: &AnsiPalette,
+    accent_min: f32,
+    cvd_safe: bool,
+    warn_only: bool,
+) -> AuditReport {
+    let p = palette;
+    let surface = derive_surface(p);
+
+    let contrast = (0..16)
+        .map(|slot| {
+            let color = p.slots[slot];
+            let background_min = match slot {
+                1..=6 | 9..=14 => Some(accent_min),
+                8 => Some(BRIGHT_BLACK_MIN_CONTRAST),
+                15 => Some(FOREGROUND_MIN_CONTRAST),
+                _ => None,
+            };
+            let overlay_min = match slot {
+                1..=6 | 9..=15 => Some(OVERLAY_MIN_CONTRAST),
+                _ => None,
+            };
+            SlotContrast {
+                slot,
+                color,
+                background: Contrast::new(&color, &p.background, background_min),
+                // Selected text is redrawn in the selection foreground, so
+                // accents only need to stand out from the selection itself
+                selection: Contrast::new(&color, &p.selection_bg, None),
+                surface: Contrast::new(&color, &surface, overlay_min),
+            }
+        })
+        .collect();
+
+    let selection_text = Contrast::new(
+        &p.selection_fg,
+        &p.selection_bg,
+        Some(SELECTION_MIN_CONTRAST),
+    );
+
+    // Every pair of base accents, plus the bright pairs --cvd-safe covers
+    let pairs = (1..=6)
+        .flat_map(|a| ((a + 1)..=6).map(move |b| (a, b)))
+        .chain(CVD_PAIRS.into_iter().filter(|&(a, _)| a > 8));
+    let mut confusable = Vec::new();
+    for cvd in Cvd::ALL {
+        for (a, b) in pairs.clone() {
+            let distance =
+                Color::oklab_distance(&p.slots[a].simulate_cvd(cvd), &p.slots[b].simulate_cvd(cvd));
+            if distance < CVD_MIN_DISTANCE {
+                confusable.push(Confusable {
+                    cvd,
+                    a,
+                    b,
+                    distance,
+                    fails: !warn_only || (cvd_safe && CVD_PAIRS.contains(&(a, b))),
+                });
+            }
+        }
+    }
+
+    let mut hue_drift = Vec::new();
+    for (slot, target) in TARGET_HUES {
+        for slot in [slot, slot + 8] {
+            let oklch = p.slots[slot].to_oklch();
+            let hue =
+                (oklch.chroma >= MIN_ACCENT_CHROMA).then(|| oklch.hue.into_positive_degrees());
+            if hue.is_none_or(|hue| hue_distance(hue, target) > MAX_HUE_DISTANCE) {
+                hue_drift.push(HueDrift {
+                    slot,
+                    target,
+                    hue,
+                    fails: !warn_only,
+                });
+            }
+        }
+    }
+    hue_drift.sort_by_key(|d| d.slot);
+
+    AuditReport {
+        contrast,
+        selection_text,
+        confusable,
+        hue_drift,
+    }
+}
+
+impl fmt::Display for Contrast {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mark = if self.passes() { ' ' } else { '!' };
+        write!(f, "{:>6.2}{}", self.ratio, mark)
+    }
+}
+
+impl fmt::Display for AuditReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Contrast (! = below minimum)")?;
+        writeln!(
+            f,
+            "  {:<3} {:<15} {:<8} {:>7} {:>7} {:>7}",
+            "", "slot", "color", "bg", "sel", "surface"
+        )?;
+        for c in &self.contrast {
+            writeln!(
+                f,
+                "  {:<3} {:<15} {:<8} {} {} {}",
+                c.slot,
+                SLOT_NAMES[c.slot],
+                c.color.to_hex(),
+                c.background,
+                c.selection,
+                c.surface
+            )?;
+        }
+        writeln!(f, "  selection text on selection: {}", self.selection_text)?;
+
+        writeln!(f)?;
+        writeln!(f, "Color vision (! = fails, others are warnings)")?;
+        if self.confusable.is_empty() {
+            writeln!(f, "  no confusable accents")?;
+        }
+        for c in &self.confusable {
+            writeln!(
+                f,
+                "  {}: {} and {} are confusable (distance {:.3}, minimum {CVD_MIN_DISTANCE}){}",
+                c.cvd.name(),
+                SLOT_NAMES[c.a],
+                SLOT_NAMES[c.b],
+                c.distance,
+                if c.fails { " !" } else { "" }
+            )?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Hue roles (! = fails, others are warnings)")?;
+        if self.hue_drift.is_empty() {
+            writeln!(f, "  all accents within {MAX_HUE_DISTANCE}° of their role")?;
+        }
+        for d in &self.hue_drift {
+            let mark = if d.fails { " !" } else { "" };
+            match d.hue {
+                Some(hue) => writeln!(
+                    f,
+                    "  {} {}: hue {:.0}° is {:.0}° from {:.0}°{mark}",
+                    d.slot,
+                    SLOT_NAMES[d.slot],
+                    hue,
+                    hue_distance(hue, d.target),
+                    d.target
+                )?,
+                None => writeln!(
+                    f,
+                    "  {} {}: too gray to read as its role{mark}",
+                    d.slot, SLOT_NAMES[d.slot]
+                )?,
+            }
+        }
+
+        writeln!(f)?;
+        match (self.failures(), self.warnings()) {
+            (0, 0) => writeln!(f, "All checks passed"),
+            (0, w) => writeln!(f, "All checks passed, {w} warning(s)"),
+            (n, 0) => writeln!(f, "{n} check(s) failed"),
+            (n, w) => writeln!(f, "{n} check(s) failed, {w} warning(s)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support;
+    use crate::pipeline::contrast::{enforce_contrast, DEFAULT_ACCENT_CONTRAST};
+
+    fn test_palette() -> AnsiPalette {
+        let mut palette = test_support::test_palette();
+        enforce_contrast(&mut palette, DEFAULT_ACCENT_CONTRAST);
+        palette
+    }
+
+    #[test]
+    fn generated_palette_meets_background_contrast() {
+        let report = audit(&test_palette(), DEFAULT_ACCENT_CONTRAST, false, false);
+        for c in &report.contrast {
+            assert!(
+                c.background.passes(),
+                "slot {} failed: {}",
+                c.slot,
+                c.background
+            );
+        }
+        assert!(report.hue_drift.is_empty(), "{:?}", report.hue_drift);
+    }
+
+    #[test]
+    fn low_contrast_accent_fails() {
+        let mut palette = test_palette();
+        palette.slots[4] = palette.background.adjust_lightness(0.05);
+        let report = audit(&palette, DEFAULT_ACCENT_CONTRAST, false, false);
+        assert!(!report.contrast[4].background.passes());
+        assert!(report.failures() > 0);
+        assert!(report.to_string().contains("check(s) failed"));
+    }
+
+    #[test]
+    fn unconstrained_slots_have_no_minimum() {
+        let report = audit(&test_palette(), DEFAULT_ACCENT_CONTRAST, false, false);
+        assert!(report.contrast[0].background.min.is_none());
+        assert!(report.contrast[7].background.min.is_none());
+        assert_eq!(
+            report.contrast[15].background.min,
+            Some(FOREGROUND_MIN_CONTRAST)
+        );
+    }
+
+    #[test]
+    fn red_green_confusion_is_reported() {
+        let mut palette = test_palette();
+        palette.slots[1] = Color::new(190, 80, 40);
+        palette.slots[2] = Color::new(110, 140, 40);
+        let report = audit(&palette, DEFAULT_ACCENT_CONTRAST, false, false);
+        assert!(report
+            .confusable
+            .iter()
+            .any(|c| c.cvd == Cvd::Deuteranopia && (c.a, c.b) == (1, 2)));
+        assert!(report.confusable.iter().all(|c| c.fails));
+        assert!(report.to_string().contains("are confusable (distance"));
+        // Only a warning with --warn-only, unless auditing for what
+        // --cvd-safe guarantees
+        let lenient = audit(&palette, DEFAULT_ACCENT_CONTRAST, false, true);
+        assert!(lenient.failures() < report.failures());
+        assert!(lenient.warnings() > 0);
+        let cvd_safe = audit(&palette, DEFAULT_ACCENT_CONTRAST, true, true);
+        assert!(cvd_safe.failures() > lenient.failures());
+    }
+
+    #[test]
+    fn hue_drift_and_gray_accents_are_reported() {
+        let mut palette = test_palette();
+        // Blue where green should be, and a gray magenta
+        palette.slots[2] = palette.slots[4];
+        palette.slots[5] = Color::new(150, 150, 150);
+        let report = audit(&palette, DEFAULT_ACCENT_CONTRAST, false, false);
+        let slots: Vec<usize> = report.hue_drift.iter().map(|d| d.slot).collect();
+        assert!(slots.contains(&2) && slots.contains(&5), "{slots:?}");
+        assert!(report
+            .hue_drift
+            .iter()
+            .any(|d| d.slot == 5 && d.hue.is_none()));
+        assert!(report.to_string().contains("5 magenta: too gray"));
+        assert!(report.failures() >= report.hue_drift.len());
+        let lenient = audit(&palette, DEFAULT_ACCENT_CONTRAST, false, true);
+        assert!(lenient.warnings() >= lenient.hue_drift.len());
+        assert_eq!(
+            lenient.failures(),
+            report.failures() - report.confusable.len() - report.hue_drift.len()
+        );
+    }
+
+    #[test]
+    fn load_theme_guesses_format_from_extension() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri-test-audit-load");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for (target, file) in [
+            (Target::Ghostty, "theme"),
+            (Target::Zellij, "theme.kdl"),
+            (Target::Json, "theme.json"),
+        ] {
+            let path = dir.join(file);
+            get_backend(target).write_to(&palette, "t", &path).unwrap();
+            let loaded = load_theme(&path, None).unwrap();
+            assert_eq!(loaded.slots[..8], palette.slots[..8], "{file}");
+        }
+
+        let err = load_theme(&dir.join("theme"), Some(Target::Vim)).unwrap_err();
+        assert!(format!("{err:#}").contains("reading Vim themes is not supported"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
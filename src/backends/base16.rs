@@ -0,0 +1,283 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::color::Color;
+use crate::pipeline::assign::AnsiPalette;
+
+use super::json::escape;
+use super::neovim::derive_surface;
+use super::zellij::derive_orange;
+use super::{data_home, ThemeBackend};
+
+/// base16 scheme backend (tinted-theming YAML format).
+pub struct Base16Backend;
+
+/// base24 scheme backend: base16 plus two deeper backgrounds and the bright accents.
+pub struct Base24Backend;
+
+impl ThemeBackend for Base16Backend {
+    fn name(&self) -> &str {
+        "base16"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        serialize_scheme("base16", &base16_colors(palette), palette, theme_name)
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        install_scheme(self, "base16", palette, theme_name)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ".yaml"
+    }
+}
+
+impl ThemeBackend for Base24Backend {
+    fn name(&self) -> &str {
+        "base24"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        serialize_scheme("base24", &base24_colors(palette), palette, theme_name)
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        install_scheme(self, "base24", palette, theme_name)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ".yaml"
+    }
+}
+
+/// Map the palette onto base16 roles (`base00`..`base0F`).
+///
+/// The grayscale ramp runs from the background through the Neovim surface and
+/// selection colors to the foreground; accents follow the base16 styling
+/// guidelines (08 red, 09 orange, 0A yellow, 0B green, 0C cyan, 0D blue,
+/// 0E magenta, 0F brown).
+pub fn base16_colors(palette: &AnsiPalette) -> [Color; 16] {
+    let p = palette;
+    let is_dark = p.background.to_oklch().l < 0.5;
+    let toward_fg = if is_dark { 1.0 } else { -1.0 };
+    let orange = derive_orange(p);
+
+    [
+        p.background,
+        derive_surface(p),
+        p.selection_bg,
+        p.slots[8],
+        p.slots[7],
+        p.foreground,
+        p.slots[15],
+        p.foreground.adjust_lightness(0.08 * toward_fg),
+        p.slots[1],
+        orange,
+        p.slots[3],
+        p.slots[2],
+        p.slots[6],
+        p.slots[4],
+        p.slots[5],
+        orange
+            .adjust_lightness(-0.15 * toward_fg)
+            .adjust_chroma(-0.05),
+    ]
+}
+
+/// Map the palette onto base24 roles: the base16 colors, then `base10`/`base11`
+/// (darker and darkest background) and `base12`..`base17` (bright red, yellow,
+/// green, cyan, blue, magenta).
+pub fn base24_colors(palette: &AnsiPalette) -> [Color; 24] {
+    let p = palette;
+    let is_dark = p.background.to_oklch().l < 0.5;
+    let away_from_fg = if is_dark { -1.0 } else { 1.0 };
+    let base16 = base16_colors(p);
+
+    let mut colors = [Color::new(0, 0, 0); 24];
+    colors[..16].copy_from_slice(&base16);
+    colors[16] = p.background.adjust_lightness(0.03 * away_from_fg);
+    colors[17] = p.background.adjust_lightness(0.06 * away_from_fg);
+    colors[18] = p.slots[9];
+    colors[19] = p.slots[11];
+    colors[20] = p.slots[10];
+    colors[21] = p.slots[14];
+    colors[22] = p.slots[12];
+    colors[23] = p.slots[13];
+    colors
+}
+
+/// Render a scheme in the tinted-theming YAML format.
+fn serialize_scheme(
+    system: &str,
+    colors: &[Color],
+    palette: &AnsiPalette,
+    theme_name: &str,
+) -> String {
+    let is_dark = palette.background.to_oklch().l < 0.5;
+    let mut out = String::new();
+
+    out.push_str(&format!("system: \"{}\"\n", system));
+    // YAML double-quoted strings take the same escapes as JSON
+    out.push_str(&format!("name: \"{}\"\n", escape(theme_name)));
+    out.push_str("author: \"nuri\"\n");
+    out.push_str(&format!(
+        "variant: \"{}\"\n",
+        if is_dark { "dark" } else { "light" }
+    ));
+    out.push_str("palette:\n");
+    for (i, color) in colors.iter().enumerate() {
+        out.push_str(&format!("  base{:02X}: \"{}\"\n", i, color.to_hex()));
+    }
+
+    out
+}
+
+/// Install a scheme where tinty looks for custom schemes.
+fn install_scheme(
+    backend: &dyn ThemeBackend,
+    system: &str,
+    palette: &AnsiPalette,
+    theme_name: &str,
+) -> Result<PathBuf> {
+    let dir = schemes_dir()?.join(system);
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("failed to create schemes directory: {}", dir.display()))?;
+
+    let path = dir.join(format!("{}.yaml", theme_name));
+    backend.write_to(palette, theme_name, &path)?;
+    Ok(path)
+}
+
+/// Resolve the tinty custom schemes directory.
+fn schemes_dir() -> Result<PathBuf> {
+    Ok(data_home()?
+        .join("tinted-theming")
+        .join("tinty")
+        .join("custom-schemes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    #[test]
+    fn base16_has_header_and_16_entries() {
+        let output = Base16Backend.serialize(&test_palette(), "my-theme");
+        assert!(output.starts_with("system: \"base16\"\nname: \"my-theme\"\n"));
+        assert!(output.contains("variant: \"dark\"\n"));
+
+        let entry_re = regex::Regex::new(r##"^  base[0-9A-F]{2}: "#[0-9a-f]{6}"$"##).unwrap();
+        let entries: Vec<&str> = output.lines().filter(|l| entry_re.is_match(l)).collect();
+        assert_eq!(entries.len(), 16);
+        assert!(entries[0].starts_with("  base00: "));
+        assert!(entries[15].starts_with("  base0F: "));
+    }
+
+    #[test]
+    fn base24_extends_base16() {
+        let palette = test_palette();
+        let base16 = Base16Backend.serialize(&palette, "test");
+        let base24 = Base24Backend.serialize(&palette, "test");
+
+        assert!(base24.starts_with("system: \"base24\"\n"));
+        let base16_palette = &base16[base16.find("palette:\n").unwrap()..];
+        assert!(base24.contains(base16_palette));
+        for i in 0x10..=0x17 {
+            assert!(
+                base24.contains(&format!("  base{i:02X}: \"#")),
+                "missing base{i:02X}"
+            );
+        }
+        assert!(!base24.contains("base18"));
+    }
+
+    #[test]
+    fn roles_follow_base16_guidelines() {
+        let palette = test_palette();
+        let colors = base16_colors(&palette);
+
+        assert_eq!(colors[0x00], palette.background);
+        assert_eq!(colors[0x01], derive_surface(&palette));
+        assert_eq!(colors[0x02], palette.selection_bg);
+        assert_eq!(colors[0x05], palette.foreground);
+        assert_eq!(colors[0x08], palette.slots[1]);
+        assert_eq!(colors[0x09], derive_orange(&palette));
+        assert_eq!(colors[0x0A], palette.slots[3]);
+        assert_eq!(colors[0x0B], palette.slots[2]);
+        assert_eq!(colors[0x0C], palette.slots[6]);
+        assert_eq!(colors[0x0D], palette.slots[4]);
+        assert_eq!(colors[0x0E], palette.slots[5]);
+    }
+
+    #[test]
+    fn base24_bright_accents_use_bright_slots() {
+        let palette = test_palette();
+        let colors = base24_colors(&palette);
+        assert_eq!(
+            &colors[0x12..],
+            &[
+                palette.slots[9],
+                palette.slots[11],
+                palette.slots[10],
+                palette.slots[14],
+                palette.slots[12],
+                palette.slots[13],
+            ]
+        );
+    }
+
+    #[test]
+    fn background_ramp_gets_deeper_in_dark_mode() {
+        let palette = test_palette();
+        let colors = base24_colors(&palette);
+        let l = |c: Color| c.to_oklch().l;
+
+        assert!(l(colors[0x11]) < l(colors[0x10]));
+        assert!(l(colors[0x10]) < l(colors[0x00]));
+        assert!(l(colors[0x00]) < l(colors[0x01]));
+        assert!(l(colors[0x05]) < l(colors[0x07]));
+    }
+
+    #[test]
+    fn theme_name_is_escaped() {
+        let output = Base16Backend.serialize(&test_palette(), "say \"hi\"\\");
+        assert!(
+            output.contains("name: \"say \\\"hi\\\"\\\\\"\n"),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn write_to_creates_file() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri-test-base16-backend");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test-theme.yaml");
+
+        Base16Backend
+            .write_to(&palette, "test-theme", &path)
+            .unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, Base16Backend.serialize(&palette, "test-theme"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
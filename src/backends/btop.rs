@@ -0,0 +1,196 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::color::Color;
+use crate::pipeline::assign::AnsiPalette;
+
+use super::neovim::derive_surface;
+use super::{config_home, ThemeBackend};
+
+/// btop `.theme` backend.
+///
+/// Meter gradients run between two accents; the middle stop is interpolated
+/// in Oklch so it stays as saturated as the ends instead of going muddy.
+pub struct BtopBackend;
+
+impl ThemeBackend for BtopBackend {
+    fn name(&self) -> &str {
+        "btop"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        let p = palette;
+        let surface = derive_surface(p);
+        let [_, red, green, yellow, blue, magenta, cyan, _, bright_black, ..] = p.slots;
+        let mut out = String::new();
+
+        out.push_str(&format!("# nuri: {}\n", theme_name));
+        out.push_str("# Generated by nuri\n");
+        out.push('\n');
+
+        let colors = [
+            ("main_bg", p.background),
+            ("main_fg", p.foreground),
+            ("title", p.foreground),
+            ("hi_fg", blue),
+            ("selected_bg", surface),
+            ("selected_fg", p.foreground),
+            ("inactive_fg", bright_black),
+            ("graph_text", p.foreground),
+            ("meter_bg", surface),
+            ("proc_misc", cyan),
+            ("cpu_box", blue),
+            ("mem_box", green),
+            ("net_box", magenta),
+            ("proc_box", yellow),
+            ("div_line", bright_black),
+        ];
+        for (key, color) in colors {
+            push_theme(&mut out, key, &color);
+        }
+
+        let gradients = [
+            ("temp", blue, red),
+            ("cpu", green, red),
+            ("free", cyan, green),
+            ("cached", blue, cyan),
+            ("available", yellow, green),
+            ("used", yellow, red),
+            ("download", blue, magenta),
+            ("upload", green, yellow),
+            ("process", cyan, magenta),
+        ];
+        for (name, start, end) in gradients {
+            out.push('\n');
+            for (stop, color) in gradient(start, end) {
+                push_theme(&mut out, &format!("{}_{}", name, stop), &color);
+            }
+        }
+
+        out
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        let dir = themes_dir()?;
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create themes directory: {}", dir.display()))?;
+
+        let path = dir.join(format!("{}.theme", theme_name));
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ".theme"
+    }
+}
+
+/// Start, middle, and end stops of a two-color gradient.
+fn gradient(start: Color, end: Color) -> [(&'static str, Color); 3] {
+    [
+        ("start", start),
+        ("mid", start.lerp_oklch(end, 0.5)),
+        ("end", end),
+    ]
+}
+
+/// Append a `theme[key]="#rrggbb"` line.
+fn push_theme(out: &mut String, key: &str, color: &Color) {
+    out.push_str(&format!("theme[{}]=\"{}\"\n", key, color.to_hex()));
+}
+
+/// Resolve the btop user themes directory.
+fn themes_dir() -> Result<PathBuf> {
+    Ok(config_home()?.join("btop").join("themes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    #[test]
+    fn every_line_is_a_theme_entry() {
+        let output = BtopBackend.serialize(&test_palette(), "test");
+        let entry_re = regex::Regex::new(r##"^theme\[[a-z_]+\]="#[0-9a-f]{6}"$"##).unwrap();
+        for line in output
+            .lines()
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        {
+            assert!(entry_re.is_match(line), "invalid entry: '{line}'");
+        }
+    }
+
+    #[test]
+    fn gradients_have_three_stops() {
+        let output = BtopBackend.serialize(&test_palette(), "test");
+        for name in [
+            "temp",
+            "cpu",
+            "free",
+            "cached",
+            "available",
+            "used",
+            "download",
+            "upload",
+            "process",
+        ] {
+            for stop in ["start", "mid", "end"] {
+                assert!(
+                    output.contains(&format!("theme[{name}_{stop}]=")),
+                    "missing {name}_{stop}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn mid_stop_is_oklch_interpolation() {
+        let palette = test_palette();
+        let output = BtopBackend.serialize(&palette, "test");
+        let (blue, red) = (palette.slots[4], palette.slots[1]);
+
+        assert!(output.contains(&format!("theme[temp_start]=\"{}\"", blue.to_hex())));
+        assert!(output.contains(&format!(
+            "theme[temp_mid]=\"{}\"",
+            blue.lerp_oklch(red, 0.5).to_hex()
+        )));
+        assert!(output.contains(&format!("theme[temp_end]=\"{}\"", red.to_hex())));
+    }
+
+    #[test]
+    fn mid_stop_keeps_chroma() {
+        let palette = test_palette();
+        let (blue, red) = (palette.slots[4], palette.slots[1]);
+        let mid = gradient(blue, red)[1].1.to_oklch();
+        let min_end = blue.to_oklch().chroma.min(red.to_oklch().chroma);
+        assert!(
+            mid.chroma > min_end * 0.7,
+            "mid chroma {} collapsed (ends >= {min_end})",
+            mid.chroma
+        );
+    }
+
+    #[test]
+    fn write_to_creates_file() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri-test-btop-backend");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.theme");
+
+        BtopBackend.write_to(&palette, "test", &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, BtopBackend.serialize(&palette, "test"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
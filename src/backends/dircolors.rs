@@ -0,0 +1,261 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::color::Color;
+use crate::pipeline::assign::AnsiPalette;
+
+use super::{config_home, ThemeBackend};
+
+/// GNU `dircolors` database backend, with 24-bit colors from the palette.
+pub struct DircolorsBackend;
+
+/// `LS_COLORS` backend: a shell `export` line built from the same roles as
+/// `DircolorsBackend`.
+pub struct LsColorsBackend;
+
+/// File types: dircolors keyword, `LS_COLORS` code, palette slot, bold.
+///
+/// Roles follow the `ls` sample in the TUI preview: directories blue,
+/// executables green.
+const FILE_TYPES: &[(&str, &str, usize, bool)] = &[
+    ("DIR", "di", 4, true),
+    ("LINK", "ln", 6, false),
+    ("EXEC", "ex", 2, true),
+    ("FIFO", "pi", 3, false),
+    ("SOCK", "so", 5, false),
+    ("BLK", "bd", 3, true),
+    ("CHR", "cd", 3, true),
+    ("ORPHAN", "or", 1, false),
+    ("MISSING", "mi", 1, false),
+];
+
+/// Extension groups: heading, palette slot, extensions.
+const EXTENSIONS: &[(&str, usize, &[&str])] = &[
+    (
+        "Archives",
+        1,
+        &[
+            "7z", "bz2", "deb", "gz", "jar", "lz", "lzma", "rar", "rpm", "tar", "tbz2", "tgz",
+            "txz", "xz", "zip", "zst",
+        ],
+    ),
+    (
+        "Images",
+        5,
+        &[
+            "avif", "bmp", "gif", "heic", "ico", "jpeg", "jpg", "png", "svg", "tif", "tiff", "webp",
+        ],
+    ),
+    (
+        "Audio and video",
+        5,
+        &[
+            "aac", "avi", "flac", "m4a", "mkv", "mov", "mp3", "mp4", "ogg", "opus", "wav", "webm",
+        ],
+    ),
+    (
+        "Configuration",
+        3,
+        &["cfg", "conf", "ini", "json", "lock", "toml", "yaml", "yml"],
+    ),
+    ("Backups", 8, &["bak", "old", "orig", "swp", "tmp"]),
+];
+
+impl ThemeBackend for DircolorsBackend {
+    fn name(&self) -> &str {
+        "dircolors"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        let mut out = String::new();
+
+        out.push_str(&format!("# nuri: {}\n", theme_name));
+        out.push_str("# Generated by nuri\n");
+        out.push('\n');
+        out.push_str("COLORTERM ?*\n");
+        out.push_str("TERM *\n");
+        out.push('\n');
+
+        out.push_str("# File types\n");
+        for &(keyword, _, slot, bold) in FILE_TYPES {
+            out.push_str(&format!(
+                "{} {}\n",
+                keyword,
+                sgr(&palette.slots[slot], bold)
+            ));
+        }
+
+        for &(heading, slot, extensions) in EXTENSIONS {
+            out.push('\n');
+            out.push_str(&format!("# {}\n", heading));
+            let code = sgr(&palette.slots[slot], false);
+            for ext in extensions {
+                out.push_str(&format!(".{} {}\n", ext, code));
+            }
+        }
+
+        out
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        let dir = dircolors_dir()?;
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create dircolors directory: {}", dir.display()))?;
+
+        let path = dir.join(theme_name);
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ""
+    }
+}
+
+impl ThemeBackend for LsColorsBackend {
+    fn name(&self) -> &str {
+        "LS_COLORS"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        format!(
+            "# nuri: {}\nexport LS_COLORS='{}'\n",
+            theme_name,
+            ls_colors(palette)
+        )
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        let dir = dircolors_dir()?;
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create dircolors directory: {}", dir.display()))?;
+
+        let path = dir.join(format!("{}.sh", theme_name));
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ".sh"
+    }
+}
+
+/// Build the `LS_COLORS` value, e.g. `di=01;38;2;r;g;b:*.tar=38;2;r;g;b`.
+pub fn ls_colors(palette: &AnsiPalette) -> String {
+    let mut entries = Vec::new();
+    for &(_, code, slot, bold) in FILE_TYPES {
+        entries.push(format!("{}={}", code, sgr(&palette.slots[slot], bold)));
+    }
+    for &(_, slot, extensions) in EXTENSIONS {
+        let code = sgr(&palette.slots[slot], false);
+        for ext in extensions {
+            entries.push(format!("*.{}={}", ext, code));
+        }
+    }
+    entries.join(":")
+}
+
+/// SGR parameters for a 24-bit foreground color, optionally bold.
+fn sgr(color: &Color, bold: bool) -> String {
+    let fg = format!("38;2;{};{};{}", color.r, color.g, color.b);
+    if bold {
+        format!("01;{}", fg)
+    } else {
+        fg
+    }
+}
+
+/// Resolve the directory for generated dircolors files.
+fn dircolors_dir() -> Result<PathBuf> {
+    Ok(config_home()?.join("dircolors"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    fn truecolor(color: &Color) -> String {
+        format!("38;2;{};{};{}", color.r, color.g, color.b)
+    }
+
+    #[test]
+    fn semantic_roles_use_palette_slots() {
+        let palette = test_palette();
+        let output = DircolorsBackend.serialize(&palette, "test");
+
+        assert!(output.contains(&format!("DIR 01;{}\n", truecolor(&palette.slots[4]))));
+        assert!(output.contains(&format!("EXEC 01;{}\n", truecolor(&palette.slots[2]))));
+        assert!(output.contains(&format!(".tar {}\n", truecolor(&palette.slots[1]))));
+        assert!(output.contains(&format!(".png {}\n", truecolor(&palette.slots[5]))));
+        assert!(output.contains(&format!(".mp4 {}\n", truecolor(&palette.slots[5]))));
+        assert!(output.contains(&format!(".toml {}\n", truecolor(&palette.slots[3]))));
+    }
+
+    #[test]
+    fn dircolors_uses_truecolor_not_ansi_indices() {
+        let output = DircolorsBackend.serialize(&test_palette(), "test");
+        let entry_re = regex::Regex::new(r"^(\.[a-z0-9]+|[A-Z]+) (01;)?38;2;\d+;\d+;\d+$").unwrap();
+        for line in output
+            .lines()
+            .filter(|l| !l.is_empty() && !l.starts_with('#') && !l.starts_with("TERM"))
+            .filter(|l| !l.starts_with("COLORTERM"))
+        {
+            assert!(entry_re.is_match(line), "invalid entry: '{line}'");
+        }
+    }
+
+    #[test]
+    fn ls_colors_matches_dircolors_entries() {
+        let palette = test_palette();
+        let database = DircolorsBackend.serialize(&palette, "test");
+        let value = ls_colors(&palette);
+
+        let db_entries = database
+            .lines()
+            .filter(|l| l.starts_with('.') || FILE_TYPES.iter().any(|t| l.starts_with(t.0)))
+            .count();
+        assert_eq!(value.split(':').count(), db_entries);
+        assert!(value.starts_with(&format!("di=01;{}:", truecolor(&palette.slots[4]))));
+        assert!(value.contains(&format!(":*.zip={}:", truecolor(&palette.slots[1]))));
+    }
+
+    #[test]
+    fn ls_colors_export_is_single_quoted() {
+        let output = LsColorsBackend.serialize(&test_palette(), "test");
+        let line = output.lines().find(|l| l.starts_with("export ")).unwrap();
+        assert!(line.starts_with("export LS_COLORS='"));
+        assert!(line.ends_with('\''));
+        assert_eq!(line.matches('\'').count(), 2);
+    }
+
+    #[test]
+    fn write_to_creates_file() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri-test-dircolors-backend");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test");
+
+        DircolorsBackend.write_to(&palette, "test", &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, DircolorsBackend.serialize(&palette, "test"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
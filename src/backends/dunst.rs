@@ -0,0 +1,117 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::pipeline::assign::AnsiPalette;
+
+use super::{config_home, ThemeBackend};
+
+/// dunst notification colors, written as a `dunstrc.d` drop-in.
+pub struct DunstBackend;
+
+impl ThemeBackend for DunstBackend {
+    fn name(&self) -> &str {
+        "dunst"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        let p = palette;
+        let mut out = String::new();
+
+        out.push_str(&format!("# nuri: {}\n", theme_name));
+        out.push_str("# Generated by nuri\n");
+
+        let urgencies = [
+            ("urgency_low", p.slots[8]),
+            ("urgency_normal", p.slots[4]),
+            ("urgency_critical", p.slots[1]),
+        ];
+        for (section, frame) in urgencies {
+            out.push('\n');
+            out.push_str(&format!("[{}]\n", section));
+            out.push_str(&format!("    background = \"{}\"\n", p.background.to_hex()));
+            out.push_str(&format!("    foreground = \"{}\"\n", p.foreground.to_hex()));
+            out.push_str(&format!("    frame_color = \"{}\"\n", frame.to_hex()));
+            out.push_str(&format!("    highlight = \"{}\"\n", frame.to_hex()));
+        }
+
+        out
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        let dir = dropin_dir()?;
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create dunst directory: {}", dir.display()))?;
+
+        let path = dir.join("90-nuri.conf");
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ".conf"
+    }
+}
+
+/// Resolve the dunst drop-in directory, read after `dunstrc`.
+fn dropin_dir() -> Result<PathBuf> {
+    Ok(config_home()?.join("dunst").join("dunstrc.d"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    #[test]
+    fn all_urgency_sections_present() {
+        let output = DunstBackend.serialize(&test_palette(), "test");
+        for section in ["urgency_low", "urgency_normal", "urgency_critical"] {
+            assert!(
+                output.contains(&format!("[{section}]\n")),
+                "missing {section}"
+            );
+        }
+    }
+
+    #[test]
+    fn values_are_quoted_hex() {
+        let output = DunstBackend.serialize(&test_palette(), "test");
+        let value_re = regex::Regex::new(r##"^    [a-z_]+ = "#[0-9a-f]{6}"$"##).unwrap();
+        let values: Vec<&str> = output.lines().filter(|l| l.starts_with("    ")).collect();
+        assert_eq!(values.len(), 12);
+        for line in values {
+            assert!(value_re.is_match(line), "invalid value line: '{line}'");
+        }
+    }
+
+    #[test]
+    fn critical_frame_is_red() {
+        let palette = test_palette();
+        let output = DunstBackend.serialize(&palette, "test");
+        let critical = &output[output.find("[urgency_critical]").unwrap()..];
+        assert!(critical.contains(&format!("frame_color = \"{}\"", palette.slots[1].to_hex())));
+    }
+
+    #[test]
+    fn write_to_creates_file() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri-test-dunst-backend");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("90-nuri.conf");
+
+        DunstBackend.write_to(&palette, "test", &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, DunstBackend.serialize(&palette, "test"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,299 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::pipeline::assign::AnsiPalette;
+
+use super::neovim::{color_entries, derive_surface, resolve_group, sanitize_name, Highlight};
+use super::{home_dir, ThemeBackend};
+
+/// Emacs `deftheme` backend (Emacs Lisp format).
+pub struct EmacsBackend;
+
+/// Emacs faces and the Neovim group each one takes its style from.
+///
+/// Going through the Neovim tables keeps both editors' syntax decisions in one
+/// place: a string is `String` (green) in Neovim, so `font-lock-string-face`
+/// is green in Emacs as well.
+const FACES: &[(&str, &str)] = &[
+    // Editor UI
+    ("default", "Normal"),
+    ("cursor", "Cursor"),
+    ("region", "Visual"),
+    ("hl-line", "CursorLine"),
+    ("fringe", "SignColumn"),
+    ("line-number", "LineNr"),
+    ("line-number-current-line", "CursorLineNr"),
+    ("mode-line", "StatusLine"),
+    ("mode-line-inactive", "StatusLineNC"),
+    ("vertical-border", "WinSeparator"),
+    ("minibuffer-prompt", "Question"),
+    ("isearch", "IncSearch"),
+    ("lazy-highlight", "Search"),
+    ("show-paren-match", "MatchParen"),
+    ("link", "Underlined"),
+    ("error", "ErrorMsg"),
+    ("warning", "WarningMsg"),
+    ("success", "MoreMsg"),
+    // Syntax
+    ("font-lock-comment-face", "Comment"),
+    ("font-lock-comment-delimiter-face", "Comment"),
+    ("font-lock-doc-face", "SpecialComment"),
+    ("font-lock-string-face", "String"),
+    ("font-lock-constant-face", "Constant"),
+    ("font-lock-number-face", "Number"),
+    ("font-lock-keyword-face", "Keyword"),
+    ("font-lock-builtin-face", "Special"),
+    ("font-lock-function-name-face", "Function"),
+    ("font-lock-variable-name-face", "Identifier"),
+    ("font-lock-type-face", "Type"),
+    ("font-lock-preprocessor-face", "PreProc"),
+    ("font-lock-operator-face", "Operator"),
+    ("font-lock-delimiter-face", "Delimiter"),
+    ("font-lock-escape-face", "SpecialChar"),
+    ("font-lock-negation-char-face", "Operator"),
+    ("font-lock-warning-face", "Todo"),
+];
+
+/// ANSI color names, in slot order, as used by `ansi-color-<name>` faces.
+const ANSI_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+impl ThemeBackend for EmacsBackend {
+    fn name(&self) -> &str {
+        "Emacs"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        let safe_name = sanitize_name(theme_name);
+        let surface = derive_surface(palette);
+
+        let mut out = String::new();
+
+        // Header
+        out.push_str(&format!(
+            ";;; {}-theme.el --- nuri theme -*- lexical-binding: t -*-\n",
+            safe_name
+        ));
+        out.push_str(";; Generated by nuri\n");
+        out.push('\n');
+        out.push_str(&format!(
+            "(deftheme {} \"Generated by nuri from a wallpaper.\")\n",
+            safe_name
+        ));
+        out.push('\n');
+
+        // Color bindings
+        out.push_str("(let ((class '((class color) (min-colors 89)))");
+        for (name, color) in &color_entries(palette, &surface) {
+            out.push_str(&format!(
+                "\n      ({} \"{}\")",
+                elisp_name(name),
+                color.to_hex()
+            ));
+        }
+        out.push_str(")\n");
+
+        // Faces
+        out.push_str("  (custom-theme-set-faces\n");
+        out.push_str(&format!("   '{}", safe_name));
+        for (face, group) in FACES {
+            if let Some(highlight) = resolve_group(group) {
+                out.push_str(&format!(
+                    "\n   `({} ((,class {})))",
+                    face,
+                    face_attributes(&highlight)
+                ));
+            }
+        }
+        for name in ANSI_NAMES {
+            for color in [name.to_string(), format!("bright-{name}")] {
+                out.push_str(&format!(
+                    "\n   `(ansi-color-{color} ((,class (:foreground ,{color} :background ,{color}))))"
+                ));
+            }
+        }
+        out.push_str("))\n");
+        out.push('\n');
+
+        // Footer
+        out.push_str(";;;###autoload\n");
+        out.push_str("(when load-file-name\n");
+        out.push_str("  (add-to-list 'custom-theme-load-path\n");
+        out.push_str(
+            "               (file-name-as-directory (file-name-directory load-file-name))))\n",
+        );
+        out.push('\n');
+        out.push_str(&format!("(provide-theme '{})\n", safe_name));
+        out.push_str(&format!(";;; {}-theme.el ends here\n", safe_name));
+
+        out
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        let safe_name = sanitize_name(theme_name);
+        let dir = themes_dir()?;
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create themes directory: {}", dir.display()))?;
+
+        let path = dir.join(format!("{}-theme.el", safe_name));
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        "-theme.el"
+    }
+}
+
+/// Color table names use `_`; Emacs Lisp symbols conventionally use `-`.
+fn elisp_name(name: &str) -> String {
+    name.replace('_', "-")
+}
+
+/// Render a highlight as a face attribute plist, e.g. `(:foreground ,fg :weight bold)`.
+fn face_attributes(highlight: &Highlight) -> String {
+    let mut attrs = Vec::new();
+    if let Some(fg) = highlight.fg {
+        attrs.push(format!(":foreground ,{}", elisp_name(fg)));
+    }
+    if let Some(bg) = highlight.bg {
+        attrs.push(format!(":background ,{}", elisp_name(bg)));
+    }
+    if highlight.bold {
+        attrs.push(":weight bold".to_string());
+    }
+    if highlight.italic {
+        attrs.push(":slant italic".to_string());
+    }
+    if highlight.undercurl {
+        match highlight.sp {
+            Some(sp) => attrs.push(format!(
+                ":underline (:style wave :color ,{})",
+                elisp_name(sp)
+            )),
+            None => attrs.push(":underline (:style wave)".to_string()),
+        }
+    } else if highlight.underline {
+        attrs.push(":underline t".to_string());
+    }
+    format!("({})", attrs.join(" "))
+}
+
+/// Resolve the Emacs custom themes directory.
+fn themes_dir() -> Result<PathBuf> {
+    Ok(home_dir()?.join(".emacs.d").join("themes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    #[test]
+    fn deftheme_and_provide_theme_match() {
+        let output = EmacsBackend.serialize(&test_palette(), "my-theme");
+        assert!(output.starts_with(";;; my-theme-theme.el"));
+        assert!(output.contains("(deftheme my-theme "));
+        assert!(output.contains("(provide-theme 'my-theme)"));
+        assert!(output.contains("(custom-theme-set-faces\n   'my-theme"));
+    }
+
+    #[test]
+    fn required_faces_present() {
+        let output = EmacsBackend.serialize(&test_palette(), "test");
+        let faces = [
+            "default",
+            "cursor",
+            "region",
+            "mode-line",
+            "font-lock-comment-face",
+            "font-lock-string-face",
+            "font-lock-keyword-face",
+            "font-lock-function-name-face",
+            "error",
+            "warning",
+            "success",
+            "ansi-color-red",
+            "ansi-color-bright-white",
+        ];
+        for face in faces {
+            assert!(
+                output.contains(&format!("`({face} ((")),
+                "missing face: {face}"
+            );
+        }
+    }
+
+    #[test]
+    fn syntax_faces_follow_neovim_groups() {
+        let palette = test_palette();
+        let output = EmacsBackend.serialize(&palette, "test");
+
+        assert!(output.contains(&format!("(green \"{}\")", palette.slots[2].to_hex())));
+        assert!(output.contains("`(font-lock-string-face ((,class (:foreground ,green))))"));
+        assert!(output.contains(
+            "`(font-lock-comment-face ((,class (:foreground ,bright-black :slant italic))))"
+        ));
+        assert!(output.contains("`(font-lock-function-name-face ((,class (:foreground ,blue))))"));
+    }
+
+    #[test]
+    fn ansi_faces_cover_all_16_slots() {
+        let output = EmacsBackend.serialize(&test_palette(), "test");
+        let count = output.matches("`(ansi-color-").count();
+        assert_eq!(count, 16);
+        assert!(output.contains(
+            "`(ansi-color-bright-white ((,class (:foreground ,bright-white :background ,bright-white))))"
+        ));
+    }
+
+    #[test]
+    fn all_color_refs_are_bound() {
+        let output = EmacsBackend.serialize(&test_palette(), "test");
+
+        let binding_re = regex::Regex::new(r##"\(([a-z-]+) "#[0-9a-f]{6}"\)"##).unwrap();
+        let bound: Vec<String> = binding_re
+            .captures_iter(&output)
+            .map(|cap| cap[1].to_string())
+            .collect();
+
+        let ref_re = regex::Regex::new(r",([a-z-]+)").unwrap();
+        for cap in ref_re.captures_iter(&output) {
+            let name = &cap[1];
+            if name == "class" {
+                continue;
+            }
+            assert!(bound.contains(&name.to_string()), ",{name} is not bound");
+        }
+    }
+
+    #[test]
+    fn balanced_parens() {
+        let output = EmacsBackend.serialize(&test_palette(), "test");
+        assert_eq!(output.matches('(').count(), output.matches(')').count());
+    }
+
+    #[test]
+    fn write_to_creates_file() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri_test_emacs_backend");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test-theme.el");
+
+        EmacsBackend.write_to(&palette, "test", &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, EmacsBackend.serialize(&palette, "test"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
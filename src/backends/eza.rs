@@ -0,0 +1,252 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::color::Color;
+use crate::pipeline::assign::AnsiPalette;
+
+use super::{config_home, ThemeBackend};
+
+/// eza `theme.yml` backend.
+///
+/// File kinds and types use the same roles as the dircolors backend:
+/// directories blue, executables green, archives red, media magenta.
+pub struct EzaBackend;
+
+impl ThemeBackend for EzaBackend {
+    fn name(&self) -> &str {
+        "eza"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        let p = palette;
+        let [_, red, green, yellow, blue, magenta, cyan, _, bright_black, ..] = p.slots;
+        let fg = p.foreground;
+        let mut out = String::new();
+
+        out.push_str(&format!("# nuri: {}\n", theme_name));
+        out.push_str("# Generated by nuri\n");
+        out.push_str("colourful: true\n");
+
+        push_section(
+            &mut out,
+            "filekinds",
+            &[
+                ("normal", style(&fg, false)),
+                ("directory", style(&blue, true)),
+                ("symlink", style(&cyan, false)),
+                ("pipe", style(&yellow, false)),
+                ("block_device", style(&yellow, true)),
+                ("char_device", style(&yellow, true)),
+                ("socket", style(&magenta, false)),
+                ("special", style(&yellow, false)),
+                ("executable", style(&green, true)),
+                ("mount_point", style(&blue, true)),
+            ],
+        );
+
+        push_section(
+            &mut out,
+            "perms",
+            &[
+                ("user_read", style(&yellow, true)),
+                ("user_write", style(&red, true)),
+                ("user_execute_file", style(&green, true)),
+                ("user_execute_other", style(&green, true)),
+                ("group_read", style(&yellow, false)),
+                ("group_write", style(&red, false)),
+                ("group_execute", style(&green, false)),
+                ("other_read", style(&yellow, false)),
+                ("other_write", style(&red, false)),
+                ("other_execute", style(&green, false)),
+                ("special_user_file", style(&magenta, false)),
+                ("special_other", style(&magenta, false)),
+                ("attribute", style(&bright_black, false)),
+            ],
+        );
+
+        push_section(
+            &mut out,
+            "size",
+            &[
+                ("major", style(&green, true)),
+                ("minor", style(&green, false)),
+                ("number_byte", style(&green, false)),
+                ("number_kilo", style(&green, false)),
+                ("number_mega", style(&green, true)),
+                ("number_giga", style(&yellow, true)),
+                ("number_huge", style(&red, true)),
+                ("unit_byte", style(&green, false)),
+                ("unit_kilo", style(&green, false)),
+                ("unit_mega", style(&green, false)),
+                ("unit_giga", style(&yellow, false)),
+                ("unit_huge", style(&red, false)),
+            ],
+        );
+
+        push_section(
+            &mut out,
+            "users",
+            &[
+                ("user_you", style(&yellow, true)),
+                ("user_root", style(&red, false)),
+                ("user_other", style(&fg, false)),
+                ("group_yours", style(&yellow, false)),
+                ("group_other", style(&fg, false)),
+                ("group_root", style(&red, false)),
+            ],
+        );
+
+        push_section(
+            &mut out,
+            "git",
+            &[
+                ("new", style(&green, false)),
+                ("modified", style(&blue, false)),
+                ("deleted", style(&red, false)),
+                ("renamed", style(&yellow, false)),
+                ("typechange", style(&magenta, false)),
+                ("ignored", style(&bright_black, false)),
+                ("conflicted", style(&red, true)),
+            ],
+        );
+
+        push_section(
+            &mut out,
+            "file_type",
+            &[
+                ("image", style(&magenta, false)),
+                ("video", style(&magenta, true)),
+                ("music", style(&magenta, false)),
+                ("lossless", style(&magenta, true)),
+                ("crypto", style(&green, true)),
+                ("document", style(&fg, false)),
+                ("compressed", style(&red, false)),
+                ("temp", style(&bright_black, false)),
+                ("compiled", style(&bright_black, false)),
+                ("build", style(&yellow, true)),
+                ("source", style(&fg, false)),
+            ],
+        );
+
+        out.push('\n');
+        out.push_str(&format!("punctuation: {}\n", style(&bright_black, false)));
+        out.push_str(&format!("date: {}\n", style(&blue, false)));
+        out.push_str(&format!("inode: {}\n", style(&magenta, false)));
+        out.push_str(&format!("blocks: {}\n", style(&cyan, false)));
+        out.push_str(&format!("header: {}\n", style(&fg, true)));
+        out.push_str(&format!("octal: {}\n", style(&magenta, false)));
+        out.push_str(&format!("symlink_path: {}\n", style(&cyan, false)));
+        out.push_str(&format!("control_char: {}\n", style(&red, false)));
+        out.push_str(&format!("broken_symlink: {}\n", style(&red, false)));
+
+        out
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        let dir = eza_dir()?;
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create eza directory: {}", dir.display()))?;
+
+        let path = dir.join("theme.yml");
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ".yml"
+    }
+}
+
+/// Append a YAML mapping of style entries.
+fn push_section(out: &mut String, name: &str, entries: &[(&str, String)]) {
+    out.push('\n');
+    out.push_str(&format!("{}:\n", name));
+    for (key, value) in entries {
+        out.push_str(&format!("  {}: {}\n", key, value));
+    }
+}
+
+/// An eza style as a YAML flow mapping.
+fn style(color: &Color, bold: bool) -> String {
+    if bold {
+        format!("{{foreground: \"{}\", is_bold: true}}", color.to_hex())
+    } else {
+        format!("{{foreground: \"{}\"}}", color.to_hex())
+    }
+}
+
+/// Resolve the eza config directory.
+fn eza_dir() -> Result<PathBuf> {
+    Ok(config_home()?.join("eza"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    #[test]
+    fn file_roles_match_ls_preview() {
+        let palette = test_palette();
+        let output = EzaBackend.serialize(&palette, "test");
+
+        let blue = palette.slots[4].to_hex();
+        let green = palette.slots[2].to_hex();
+        let red = palette.slots[1].to_hex();
+        let magenta = palette.slots[5].to_hex();
+        assert!(output.contains(&format!(
+            "  directory: {{foreground: \"{blue}\", is_bold: true}}\n"
+        )));
+        assert!(output.contains(&format!(
+            "  executable: {{foreground: \"{green}\", is_bold: true}}\n"
+        )));
+        assert!(output.contains(&format!("  compressed: {{foreground: \"{red}\"}}\n")));
+        assert!(output.contains(&format!("  image: {{foreground: \"{magenta}\"}}\n")));
+    }
+
+    #[test]
+    fn top_level_sections_present() {
+        let output = EzaBackend.serialize(&test_palette(), "test");
+        for section in ["filekinds", "perms", "size", "users", "git", "file_type"] {
+            assert!(
+                output.contains(&format!("\n{section}:\n")),
+                "missing section: {section}"
+            );
+        }
+    }
+
+    #[test]
+    fn entries_are_valid_flow_mappings() {
+        let output = EzaBackend.serialize(&test_palette(), "test");
+        let entry_re = regex::Regex::new(
+            r#"^ {0,2}[a-z_]+: \{foreground: "\#[0-9a-f]{6}"(, is_bold: true)?\}$"#,
+        )
+        .unwrap();
+        for line in output.lines().filter(|l| l.contains('{')) {
+            assert!(entry_re.is_match(line), "invalid entry: '{line}'");
+        }
+    }
+
+    #[test]
+    fn write_to_creates_file() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri-test-eza-backend");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("theme.yml");
+
+        EzaBackend.write_to(&palette, "test", &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, EzaBackend.serialize(&palette, "test"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,156 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::color::Color;
+use crate::pipeline::assign::AnsiPalette;
+
+use super::{config_home, ThemeBackend};
+
+/// foot terminal theme backend (INI format).
+pub struct FootBackend;
+
+impl ThemeBackend for FootBackend {
+    fn name(&self) -> &str {
+        "foot"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        let p = palette;
+        let mut out = String::new();
+
+        out.push_str(&format!("# nuri: {}\n", theme_name));
+        out.push_str("# Generated by nuri\n");
+        out.push('\n');
+
+        out.push_str("[cursor]\n");
+        out.push_str(&format!(
+            "color={} {}\n",
+            bare_hex(&p.cursor_text),
+            bare_hex(&p.cursor_color)
+        ));
+        out.push('\n');
+
+        out.push_str("[colors]\n");
+        out.push_str(&format!("foreground={}\n", bare_hex(&p.foreground)));
+        out.push_str(&format!("background={}\n", bare_hex(&p.background)));
+        for (i, color) in p.slots[..8].iter().enumerate() {
+            out.push_str(&format!("regular{}={}\n", i, bare_hex(color)));
+        }
+        for (i, color) in p.slots[8..].iter().enumerate() {
+            out.push_str(&format!("bright{}={}\n", i, bare_hex(color)));
+        }
+        out.push_str(&format!(
+            "selection-foreground={}\n",
+            bare_hex(&p.selection_fg)
+        ));
+        out.push_str(&format!(
+            "selection-background={}\n",
+            bare_hex(&p.selection_bg)
+        ));
+
+        out
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        let dir = themes_dir()?;
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create themes directory: {}", dir.display()))?;
+
+        let path = dir.join(theme_name);
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ""
+    }
+}
+
+/// foot expects `rrggbb` without the leading `#`.
+fn bare_hex(color: &Color) -> String {
+    color.to_hex().trim_start_matches('#').to_string()
+}
+
+/// Resolve the foot themes directory.
+fn themes_dir() -> Result<PathBuf> {
+    Ok(config_home()?.join("foot").join("themes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    #[test]
+    fn colors_section_has_all_keys() {
+        let output = FootBackend.serialize(&test_palette(), "test");
+        let section = &output[output.find("[colors]\n").unwrap()..];
+
+        let mut keys = vec![
+            "foreground".to_string(),
+            "background".to_string(),
+            "selection-foreground".to_string(),
+            "selection-background".to_string(),
+        ];
+        keys.extend((0..8).map(|i| format!("regular{i}")));
+        keys.extend((0..8).map(|i| format!("bright{i}")));
+
+        for key in keys {
+            assert!(
+                section.lines().any(|l| l.starts_with(&format!("{key}="))),
+                "missing key: {key}"
+            );
+        }
+    }
+
+    #[test]
+    fn hex_values_have_no_hash() {
+        let output = FootBackend.serialize(&test_palette(), "test");
+        let hex_re = regex::Regex::new(r"^[0-9a-f]{6}( [0-9a-f]{6})?$").unwrap();
+
+        for line in output.lines().filter(|l| l.contains('=')) {
+            let value = line.split('=').nth(1).unwrap();
+            assert!(
+                !value.contains('#'),
+                "value should not contain '#': '{line}'"
+            );
+            assert!(hex_re.is_match(value), "invalid hex value: '{line}'");
+        }
+    }
+
+    #[test]
+    fn slots_map_to_regular_and_bright() {
+        let palette = test_palette();
+        let output = FootBackend.serialize(&palette, "test");
+
+        assert!(output.contains(&format!("regular1={}\n", &palette.slots[1].to_hex()[1..])));
+        assert!(output.contains(&format!("bright7={}\n", &palette.slots[15].to_hex()[1..])));
+        assert!(output.contains(&format!(
+            "background={}\n",
+            &palette.background.to_hex()[1..]
+        )));
+    }
+
+    #[test]
+    fn write_to_creates_file() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri-test-foot-backend");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test-theme");
+
+        FootBackend.write_to(&palette, "test-theme", &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, FootBackend.serialize(&palette, "test-theme"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,145 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::pipeline::assign::AnsiPalette;
+
+use super::neovim::derive_surface;
+use super::{config_home, ThemeBackend};
+
+/// fzf backend: a shell snippet appending `--color=` options to `FZF_DEFAULT_OPTS`.
+pub struct FzfBackend;
+
+impl ThemeBackend for FzfBackend {
+    fn name(&self) -> &str {
+        "fzf"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        let p = palette;
+        let surface = derive_surface(p);
+        let mut out = String::new();
+
+        out.push_str(&format!("# nuri: {}\n", theme_name));
+        out.push_str("export FZF_DEFAULT_OPTS=\"$FZF_DEFAULT_OPTS");
+
+        let groups = [
+            vec![
+                ("fg", p.foreground),
+                ("bg", p.background),
+                ("hl", p.slots[4]),
+            ],
+            vec![
+                ("fg+", p.foreground),
+                ("bg+", surface),
+                ("hl+", p.slots[12]),
+            ],
+            vec![
+                ("info", p.slots[6]),
+                ("prompt", p.slots[4]),
+                ("pointer", p.slots[5]),
+                ("marker", p.slots[2]),
+                ("spinner", p.slots[5]),
+                ("header", p.slots[8]),
+            ],
+            vec![
+                ("border", p.slots[8]),
+                ("gutter", p.background),
+                ("query", p.foreground),
+            ],
+        ];
+        for group in groups {
+            let pairs: Vec<String> = group
+                .iter()
+                .map(|(key, color)| format!("{}:{}", key, color.to_hex()))
+                .collect();
+            out.push_str(&format!(" \\\n  --color={}", pairs.join(",")));
+        }
+        out.push_str("\"\n");
+
+        out
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        let dir = fzf_dir()?;
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create fzf directory: {}", dir.display()))?;
+
+        let path = dir.join(format!("{}.sh", theme_name));
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ".sh"
+    }
+}
+
+/// Resolve the directory for fzf color snippets.
+fn fzf_dir() -> Result<PathBuf> {
+    Ok(config_home()?.join("fzf"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    #[test]
+    fn snippet_appends_to_default_opts() {
+        let output = FzfBackend.serialize(&test_palette(), "test");
+        assert!(output.contains("export FZF_DEFAULT_OPTS=\"$FZF_DEFAULT_OPTS \\\n"));
+        assert!(output.ends_with("\"\n"));
+        assert_eq!(output.matches('"').count(), 2);
+    }
+
+    #[test]
+    fn color_options_are_valid() {
+        let output = FzfBackend.serialize(&test_palette(), "test");
+        let option_re =
+            regex::Regex::new(r"^  --color=([a-z+]+:#[0-9a-f]{6})(,[a-z+]+:#[0-9a-f]{6})*")
+                .unwrap();
+        let options: Vec<&str> = output
+            .lines()
+            .filter(|l| l.starts_with("  --color="))
+            .collect();
+        assert_eq!(options.len(), 4);
+        for line in options {
+            assert!(option_re.is_match(line), "invalid option: '{line}'");
+        }
+    }
+
+    #[test]
+    fn base_colors_match_palette() {
+        let palette = test_palette();
+        let output = FzfBackend.serialize(&palette, "test");
+        assert!(output.contains(&format!(
+            "--color=fg:{},bg:{},hl:{}",
+            palette.foreground.to_hex(),
+            palette.background.to_hex(),
+            palette.slots[4].to_hex()
+        )));
+    }
+
+    #[test]
+    fn write_to_creates_file() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri-test-fzf-backend");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.sh");
+
+        FzfBackend.write_to(&palette, "test", &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, FzfBackend.serialize(&palette, "test"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
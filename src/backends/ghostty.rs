@@ -1,10 +1,13 @@
 use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 
-use crate::pipeline::assign::AnsiPalette;
+use crate::color::{Color, PaletteColor, WideColor};
+use crate::pipeline::assign::{AnsiPalette, Palette, WidePalette};
 
-use super::ThemeBackend;
+use super::{
+    append_line, config_home, lines_with_offsets, DisplayP3Backend, ThemeBackend, ThemeSetting,
+};
 
 /// Ghostty terminal theme backend.
 pub struct GhosttyBackend;
@@ -15,35 +18,38 @@ impl ThemeBackend for GhosttyBackend {
     }
 
     fn serialize(&self, palette: &AnsiPalette, _theme_name: &str) -> String {
-        let p = palette;
-        let mut out = String::new();
-
-        out.push_str(&format!("background = {}\n", p.background.to_hex()));
-        out.push_str(&format!("foreground = {}\n", p.foreground.to_hex()));
-        out.push_str(&format!("cursor-color = {}\n", p.cursor_color.to_hex()));
-        out.push_str(&format!("cursor-text = {}\n", p.cursor_text.to_hex()));
-        out.push_str(&format!(
-            "selection-background = {}\n",
-            p.selection_bg.to_hex()
-        ));
-        out.push_str(&format!(
-            "selection-foreground = {}\n",
-            p.selection_fg.to_hex()
-        ));
-
-        for (i, color) in p.slots.iter().enumerate() {
-            out.push_str(&format!("palette = {}={}\n", i, color.to_hex()));
+        color_lines(palette, Color::to_hex)
+    }
+
+    /// Read a Ghostty theme. Comments and unrelated keys are ignored; a missing
+    /// cursor or selection color falls back to Ghostty's defaults (foreground
+    /// cursor, inverted selection). Display P3 themes are mapped into sRGB.
+    fn parse(&self, content: &str) -> Result<AnsiPalette> {
+        let palette = parse_colors(content)?;
+        if is_display_p3(content) {
+            return Ok(palette.map(|c| {
+                let p3 = [c.r, c.g, c.b].map(|v| f32::from(v) / 255.0);
+                WideColor::from_display_p3(p3).to_srgb()
+            }));
         }
+        Ok(palette)
+    }
 
-        out
+    /// Rewrite the changed color lines, keeping comments, unrelated keys, and
+    /// the file's own spacing. Display P3 themes stay in Display P3.
+    fn update(&self, content: &str, palette: &AnsiPalette, _theme_name: &str) -> Result<String> {
+        if is_display_p3(content) {
+            let p3 = palette.map(|c| {
+                let [r, g, b] = WideColor::from(c).to_display_p3_u8();
+                Color::new(r, g, b)
+            });
+            return Ok(update_colors(content, &p3));
+        }
+        Ok(update_colors(content, palette))
     }
 
     fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
-        let dir = themes_dir()?;
-        std::fs::create_dir_all(&dir)
-            .with_context(|| format!("failed to create themes directory: {}", dir.display()))?;
-
-        let path = dir.join(theme_name);
+        let path = install_path(theme_name)?;
         self.write_to(palette, theme_name, &path)?;
         Ok(path)
     }
@@ -58,17 +64,309 @@ impl ThemeBackend for GhosttyBackend {
     fn extension(&self) -> &str {
         ""
     }
+
+    fn display_p3(&self) -> Option<&dyn DisplayP3Backend> {
+        Some(self)
+    }
+
+    /// Signal running Ghostty processes to reload their configuration. The
+    /// config itself is left alone: selecting the theme there is what
+    /// `--activate` does, after backing the file up.
+    fn reload(&self, theme_name: &str, _path: &Path) -> Result<Option<String>> {
+        let signalled = signal_reload()?;
+        let config = config_path()?;
+        let content = std::fs::read_to_string(&config).unwrap_or_default();
+        let selected = find_theme(&content).is_some_and(|s| s.value(&content) == theme_name);
+        let note = if selected {
+            String::new()
+        } else {
+            format!(
+                "; {} does not select '{theme_name}' (see --activate)",
+                config.display()
+            )
+        };
+        Ok(Some(format!(
+            "{signalled} running instance(s) signalled{note}"
+        )))
+    }
+}
+
+/// `window-colorspace = display-p3` makes Ghostty read every color in the
+/// config as Display P3. Ghostty only honors it on macOS.
+impl DisplayP3Backend for GhosttyBackend {
+    fn serialize_display_p3(&self, palette: &WidePalette, _theme_name: &str) -> String {
+        let mut out = String::from("window-colorspace = display-p3\n");
+        out.push_str(&color_lines(palette, WideColor::to_display_p3_hex));
+        out
+    }
+
+    fn install_display_p3(&self, palette: &WidePalette, theme_name: &str) -> Result<PathBuf> {
+        let path = install_path(theme_name)?;
+        self.write_display_p3_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+}
+
+/// Render the color keys, writing each color with `hex`.
+fn color_lines<C: PaletteColor>(palette: &Palette<C>, hex: impl Fn(C) -> String) -> String {
+    let p = palette;
+    let mut out = String::new();
+
+    out.push_str(&format!("background = {}\n", hex(p.background)));
+    out.push_str(&format!("foreground = {}\n", hex(p.foreground)));
+    out.push_str(&format!("cursor-color = {}\n", hex(p.cursor_color)));
+    out.push_str(&format!("cursor-text = {}\n", hex(p.cursor_text)));
+    out.push_str(&format!("selection-background = {}\n", hex(p.selection_bg)));
+    out.push_str(&format!("selection-foreground = {}\n", hex(p.selection_fg)));
+
+    for (i, color) in p.slots.iter().enumerate() {
+        out.push_str(&format!("palette = {}={}\n", i, hex(*color)));
+    }
+
+    out
+}
+
+/// Whether the theme declares its colors to be in Display P3.
+fn is_display_p3(content: &str) -> bool {
+    content.lines().any(|line| {
+        line.split_once('=')
+            .is_some_and(|(k, v)| k.trim() == "window-colorspace" && v.trim() == "display-p3")
+    })
+}
+
+/// Read the color keys as written, without interpreting the color space.
+fn parse_colors(content: &str) -> Result<AnsiPalette> {
+    let mut slots: [Option<Color>; 16] = [None; 16];
+    let get = |key: &str| -> Result<Option<Color>> {
+        let mut found = None;
+        for (n, line) in content.lines().enumerate() {
+            let Some((k, v)) = line.split_once('=') else {
+                continue;
+            };
+            if k.trim() == key {
+                found = Some(
+                    Color::from_hex(v.trim())
+                        .with_context(|| format!("line {}: invalid {key}", n + 1))?,
+                );
+            }
+        }
+        Ok(found)
+    };
+
+    let background = get("background")?;
+    let foreground = get("foreground")?;
+    let cursor_color = get("cursor-color")?;
+    let cursor_text = get("cursor-text")?;
+    let selection_bg = get("selection-background")?;
+    let selection_fg = get("selection-foreground")?;
+
+    for (n, line) in content.lines().enumerate() {
+        let Some((k, v)) = line.split_once('=') else {
+            continue;
+        };
+        if k.trim() != "palette" {
+            continue;
+        }
+        let Some((index, hex)) = v.split_once('=') else {
+            bail!("line {}: expected palette = N=#rrggbb", n + 1);
+        };
+        let index: usize = index
+            .trim()
+            .parse()
+            .with_context(|| format!("line {}: invalid palette index", n + 1))?;
+        let color = Color::from_hex(hex.trim())
+            .with_context(|| format!("line {}: invalid palette color", n + 1))?;
+        // Ghostty accepts indices up to 255; only the first 16 are themed here
+        if let Some(slot) = slots.get_mut(index) {
+            *slot = Some(color);
+        }
+    }
+
+    let missing: Vec<String> = (0..16)
+        .filter(|&i| slots[i].is_none())
+        .map(|i| i.to_string())
+        .collect();
+    if !missing.is_empty() {
+        bail!("missing palette entries: {}", missing.join(", "));
+    }
+    let (Some(background), Some(foreground)) = (background, foreground) else {
+        bail!("theme must set both background and foreground");
+    };
+
+    Ok(AnsiPalette {
+        slots: slots.map(|c| c.unwrap_or(background)),
+        background,
+        foreground,
+        cursor_color: cursor_color.unwrap_or(foreground),
+        cursor_text: cursor_text.unwrap_or(background),
+        selection_bg: selection_bg.unwrap_or(foreground),
+        selection_fg: selection_fg.unwrap_or(background),
+    })
+}
+
+/// Replace the color values that differ from `palette`, keeping each line's
+/// ending. Keys the theme leaves out are appended when `palette` no longer
+/// matches the default [`parse_colors`] assumes for them.
+fn update_colors(content: &str, palette: &AnsiPalette) -> String {
+    let p = palette;
+    let specials = [
+        ("background", p.background, None),
+        ("foreground", p.foreground, None),
+        ("cursor-color", p.cursor_color, Some(p.foreground)),
+        ("cursor-text", p.cursor_text, Some(p.background)),
+        ("selection-background", p.selection_bg, Some(p.foreground)),
+        ("selection-foreground", p.selection_fg, Some(p.background)),
+    ];
+    let mut seen_specials = [false; 6];
+    let mut seen_slots = [false; 16];
+
+    let mut out = String::with_capacity(content.len());
+    for (_, line) in lines_with_offsets(content) {
+        let body = line.trim_end_matches(['\r', '\n']);
+        let updated = body.split_once('=').and_then(|(key, value)| {
+            if key.trim() == "palette" {
+                let (index, hex) = value.split_once('=')?;
+                let slot = index.trim().parse::<usize>().ok()?;
+                let color = p.slots.get(slot)?;
+                seen_slots[slot] = true;
+                replace_value(hex, color).map(|hex| format!("{key}={index}={hex}"))
+            } else {
+                let i = specials.iter().position(|(k, _, _)| *k == key.trim())?;
+                seen_specials[i] = true;
+                replace_value(value, &specials[i].1).map(|value| format!("{key}={value}"))
+            }
+        });
+        out.push_str(updated.as_deref().unwrap_or(body));
+        out.push_str(&line[body.len()..]);
+    }
+
+    let mut missing: Vec<String> = specials
+        .iter()
+        .zip(seen_specials)
+        .filter(|((_, color, default), seen)| !seen && *default != Some(*color))
+        .map(|((key, color, _), _)| format!("{key} = {}", color.to_hex()))
+        .collect();
+    missing.extend(
+        (0..16)
+            .filter(|&slot| !seen_slots[slot])
+            .map(|slot| format!("palette = {slot}={}", p.slots[slot].to_hex())),
+    );
+    if !missing.is_empty() {
+        let newline = if content.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push_str(newline);
+        }
+        for line in missing {
+            out.push_str(&line);
+            out.push_str(newline);
+        }
+    }
+    out
+}
+
+/// Replace a color value, keeping its leading whitespace. Returns `None` when
+/// the value already holds this color.
+fn replace_value(value: &str, color: &Color) -> Option<String> {
+    if Color::from_hex(value.trim()).ok().as_ref() == Some(color) {
+        return None;
+    }
+    let lead = &value[..value.len() - value.trim_start().len()];
+    Some(format!("{lead}{}", color.to_hex()))
+}
+
+/// Point the config's `theme` key at `theme_name`, keeping everything else,
+/// including the line's own spacing. Without a `theme` line, one is
+/// appended.
+pub fn set_theme(config: &str, theme_name: &str) -> String {
+    match find_theme(config) {
+        Some(setting) => setting.replace(config, theme_name),
+        None => append_line(config, &format!("theme = {theme_name}")),
+    }
+}
+
+/// The config's `theme` line. Only the last one counts in Ghostty.
+pub fn find_theme(config: &str) -> Option<ThemeSetting> {
+    lines_with_offsets(config)
+        .filter_map(|(start, line)| {
+            let (key, value) = line.split_once('=')?;
+            if key.trim() != "theme" {
+                return None;
+            }
+            let value_start = start + key.len() + 1;
+            let lead = value.len() - value.trim_start().len();
+            let name = value.trim();
+            Some(ThemeSetting {
+                line: start..start + line.len(),
+                name: value_start + lead..value_start + lead + name.len(),
+            })
+        })
+        .last()
+}
+
+/// Send SIGUSR2, which makes Ghostty reload its config, to each Ghostty
+/// process. Returns how many were signalled.
+#[cfg(target_os = "linux")]
+fn signal_reload() -> Result<usize> {
+    let mut signalled = 0;
+    for entry in std::fs::read_dir("/proc")
+        .context("failed to list processes")?
+        .flatten()
+    {
+        let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse().ok()) else {
+            continue;
+        };
+        let comm = std::fs::read_to_string(entry.path().join("comm")).unwrap_or_default();
+        // SAFETY: kill has no memory-safety requirements; failures (another
+        // user's process, or one that just exited) are skipped.
+        if comm.trim_end() == "ghostty" && unsafe { libc::kill(pid, libc::SIGUSR2) } == 0 {
+            signalled += 1;
+        }
+    }
+    Ok(signalled)
+}
+
+/// Without /proc, leave finding the processes to pkill, which can only say
+/// whether any matched.
+#[cfg(not(target_os = "linux"))]
+fn signal_reload() -> Result<usize> {
+    let status = std::process::Command::new("pkill")
+        .args(["-USR2", "-x", "ghostty"])
+        .status()
+        .context("failed to run pkill")?;
+    Ok(usize::from(status.success()))
+}
+
+/// Resolve the Ghostty config directory.
+fn config_dir() -> Result<PathBuf> {
+    Ok(config_home()?.join("ghostty"))
+}
+
+/// The main config file: `config.ghostty` if present, else `config`.
+pub fn config_path() -> Result<PathBuf> {
+    let dir = config_dir()?;
+    let modern = dir.join("config.ghostty");
+    if modern.exists() {
+        Ok(modern)
+    } else {
+        Ok(dir.join("config"))
+    }
 }
 
 /// Resolve the Ghostty themes directory.
 fn themes_dir() -> Result<PathBuf> {
-    let config_home = std::env::var("XDG_CONFIG_HOME")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| {
-            let home = std::env::var("HOME").unwrap_or_else(|_| "~".to_string());
-            PathBuf::from(home).join(".config")
-        });
-    Ok(config_home.join("ghostty").join("themes"))
+    Ok(config_dir()?.join("themes"))
+}
+
+/// Create the themes directory and return the path for `theme_name` in it.
+fn install_path(theme_name: &str) -> Result<PathBuf> {
+    let dir = themes_dir()?;
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("failed to create themes directory: {}", dir.display()))?;
+    Ok(dir.join(theme_name))
 }
 
 /// Resolve the full path where a theme with the given name would be installed.
@@ -79,32 +377,7 @@ pub fn theme_path(name: &str) -> Result<PathBuf> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::cli::ThemeMode;
-    use crate::color::Color;
-    use crate::pipeline::assign::assign_slots;
-    use crate::pipeline::extract::ExtractedColor;
-    use palette::Oklch;
-
-    fn make_extracted(l: f32, chroma: f32, hue: f32, weight: f32) -> ExtractedColor {
-        ExtractedColor {
-            color: Color::from_oklch(Oklch::new(l, chroma, hue)),
-            weight,
-        }
-    }
-
-    fn test_palette() -> AnsiPalette {
-        let colors = vec![
-            make_extracted(0.60, 0.20, 25.0, 0.12),
-            make_extracted(0.60, 0.20, 145.0, 0.12),
-            make_extracted(0.70, 0.20, 90.0, 0.12),
-            make_extracted(0.55, 0.20, 260.0, 0.12),
-            make_extracted(0.60, 0.20, 325.0, 0.12),
-            make_extracted(0.65, 0.20, 195.0, 0.10),
-            make_extracted(0.10, 0.01, 0.0, 0.15),
-            make_extracted(0.95, 0.01, 0.0, 0.15),
-        ];
-        assign_slots(&colors, ThemeMode::Dark)
-    }
+    use crate::pipeline::assign::test_support::test_palette;
 
     #[test]
     fn serialization_format_is_correct() {
@@ -187,6 +460,145 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_round_trips_serialize() {
+        let palette = test_palette();
+        let parsed = GhosttyBackend
+            .parse(&GhosttyBackend.serialize(&palette, "test"))
+            .unwrap();
+        assert_eq!(parsed.slots, palette.slots);
+        assert_eq!(parsed.background, palette.background);
+        assert_eq!(parsed.foreground, palette.foreground);
+        assert_eq!(parsed.cursor_color, palette.cursor_color);
+        assert_eq!(parsed.cursor_text, palette.cursor_text);
+        assert_eq!(parsed.selection_bg, palette.selection_bg);
+        assert_eq!(parsed.selection_fg, palette.selection_fg);
+    }
+
+    #[test]
+    fn parse_accepts_hand_written_themes() {
+        let mut content = String::from("# my theme\nbackground = 1d1f21\nforeground=#C5C8C6\n\n");
+        for i in 0..16 {
+            content.push_str(&format!(
+                "palette={i}=#{:02x}{:02x}{:02x}\n",
+                i * 10,
+                i,
+                255 - i
+            ));
+        }
+        content.push_str("palette = 200=#ffffff\nfont-size = 12\n");
+
+        let parsed = GhosttyBackend.parse(&content).unwrap();
+        assert_eq!(parsed.background, Color::new(0x1d, 0x1f, 0x21));
+        assert_eq!(parsed.foreground, Color::new(0xc5, 0xc8, 0xc6));
+        assert_eq!(parsed.slots[3], Color::new(30, 3, 252));
+        assert_eq!(parsed.cursor_color, parsed.foreground);
+        assert_eq!(parsed.selection_bg, parsed.foreground);
+        assert_eq!(parsed.selection_fg, parsed.background);
+    }
+
+    #[test]
+    fn parse_reports_missing_entries() {
+        let output = GhosttyBackend.serialize(&test_palette(), "test");
+        let without: String = output
+            .lines()
+            .filter(|l| !l.starts_with("palette = 3=") && !l.starts_with("palette = 12="))
+            .map(|l| format!("{l}\n"))
+            .collect();
+        let err = GhosttyBackend.parse(&without).unwrap_err();
+        assert_eq!(err.to_string(), "missing palette entries: 3, 12");
+
+        let err = GhosttyBackend.parse("palette = 1=#zzzzzz").unwrap_err();
+        assert_eq!(err.to_string(), "line 1: invalid palette color");
+    }
+
+    #[test]
+    fn update_preserves_comments_and_other_keys() {
+        let mut content = String::from(
+            "# Community theme\nbackground=#1d1f21\nforeground = C5C8C6\nfont-family = Iosevka\n",
+        );
+        for i in 0..16 {
+            content.push_str(&format!(
+                "palette = {i}=#{:02x}{:02x}{:02x}\n",
+                i * 10,
+                i,
+                255 - i
+            ));
+        }
+        let mut palette = GhosttyBackend.parse(&content).unwrap();
+        palette.slots[8] = Color::new(0x80, 0x80, 0x80);
+        palette.background = Color::new(0, 0, 0);
+
+        let updated = GhosttyBackend
+            .update(&content, &palette, "ignored")
+            .unwrap();
+        // The colors that defaulted to the old background now need their own keys
+        let expected = content
+            .replace("background=#1d1f21", "background=#000000")
+            .replace("palette = 8=#5008f7", "palette = 8=#808080")
+            + "cursor-text = #1d1f21\nselection-foreground = #1d1f21\n";
+        assert_eq!(updated, expected);
+        assert_eq!(GhosttyBackend.parse(&updated).unwrap(), palette);
+    }
+
+    #[test]
+    fn update_keeps_line_endings_and_appends_missing_keys() {
+        let mut content = String::from("background = #1d1f21\r\nforeground = #c5c8c6\r\n");
+        for i in 0..16 {
+            content.push_str(&format!(
+                "palette = {i}=#{:02x}{:02x}{:02x}\r\n",
+                i * 10,
+                i,
+                99
+            ));
+        }
+        let content = content.trim_end().to_string();
+        let mut palette = GhosttyBackend.parse(&content).unwrap();
+        palette.slots[1] = Color::new(0xff, 0, 0);
+        palette.selection_fg = Color::new(0x10, 0x10, 0x10);
+
+        let updated = GhosttyBackend
+            .update(&content, &palette, "ignored")
+            .unwrap();
+        let expected = content.replace("palette = 1=#0a0163", "palette = 1=#ff0000")
+            + "\r\nselection-foreground = #101010\r\n";
+        assert_eq!(updated, expected);
+        assert_eq!(GhosttyBackend.parse(&updated).unwrap(), palette);
+    }
+
+    #[test]
+    fn display_p3_theme_parses_back_to_srgb() {
+        let palette = test_palette();
+        let output = GhosttyBackend.serialize_display_p3(&palette.map(WideColor::from), "test");
+        assert!(output.starts_with("window-colorspace = display-p3\n"));
+        assert_ne!(
+            output,
+            format!(
+                "window-colorspace = display-p3\n{}",
+                GhosttyBackend.serialize(&palette, "test")
+            )
+        );
+
+        let parsed = GhosttyBackend.parse(&output).unwrap();
+        for (slot, (a, b)) in palette.slots.iter().zip(parsed.slots).enumerate() {
+            for (x, y) in [(a.r, b.r), (a.g, b.g), (a.b, b.b)] {
+                assert!(x.abs_diff(y) <= 1, "slot {slot}: {a} parsed as {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn update_keeps_display_p3_encoding() {
+        let content =
+            GhosttyBackend.serialize_display_p3(&test_palette().map(WideColor::from), "test");
+        let mut palette = GhosttyBackend.parse(&content).unwrap();
+        palette.slots[1] = Color::new(255, 0, 0);
+
+        let updated = GhosttyBackend.update(&content, &palette, "test").unwrap();
+        assert!(updated.starts_with("window-colorspace = display-p3\n"));
+        assert!(updated.contains("palette = 1=#ea3323\n"), "{updated}");
+    }
+
     #[test]
     fn write_to_creates_file() {
         let backend = GhosttyBackend;
@@ -222,4 +634,18 @@ mod tests {
         std::fs::remove_dir_all(&temp_dir).unwrap();
         std::env::remove_var("XDG_CONFIG_HOME");
     }
+
+    #[test]
+    fn set_theme_keeps_the_rest_of_the_config() {
+        let config = "# my config\nfont-size = 13\n# theme = old\ntheme =  first\ntheme=second\r\nwindow-padding-x = 4";
+        assert_eq!(
+            set_theme(config, "nuri"),
+            "# my config\nfont-size = 13\n# theme = old\ntheme =  first\ntheme=nuri\r\nwindow-padding-x = 4"
+        );
+        assert_eq!(
+            set_theme("font-size = 13", "nuri"),
+            "font-size = 13\ntheme = nuri\n"
+        );
+        assert_eq!(set_theme("", "nuri"), "theme = nuri\n");
+    }
 }
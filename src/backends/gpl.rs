@@ -0,0 +1,129 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::pipeline::assign::{AnsiPalette, SLOT_NAMES};
+
+use super::neovim::derive_surface;
+use super::{config_home, ThemeBackend};
+
+/// GIMP/Inkscape `.gpl` palette backend.
+pub struct GplBackend;
+
+impl ThemeBackend for GplBackend {
+    fn name(&self) -> &str {
+        "gpl"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        let p = palette;
+        let mut out = String::new();
+
+        out.push_str("GIMP Palette\n");
+        out.push_str(&format!("Name: {}\n", theme_name));
+        out.push_str("Columns: 8\n");
+        out.push_str("# Generated by nuri\n");
+
+        let specials = [
+            ("background", p.background),
+            ("foreground", p.foreground),
+            ("cursor", p.cursor_color),
+            ("cursor text", p.cursor_text),
+            ("selection background", p.selection_bg),
+            ("selection foreground", p.selection_fg),
+            ("surface", derive_surface(p)),
+        ];
+        let slots = SLOT_NAMES.iter().copied().zip(p.slots);
+        for (name, color) in specials.into_iter().chain(slots) {
+            out.push_str(&format!(
+                "{:3} {:3} {:3}\t{}\n",
+                color.r, color.g, color.b, name
+            ));
+        }
+
+        out
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        let dir = palettes_dir()?;
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create palettes directory: {}", dir.display()))?;
+
+        let path = dir.join(format!("{}.gpl", theme_name));
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ".gpl"
+    }
+}
+
+/// Resolve the Inkscape palettes directory. GIMP reads the same format but
+/// keeps palettes under a versioned directory, so import it from there.
+fn palettes_dir() -> Result<PathBuf> {
+    Ok(config_home()?.join("inkscape").join("palettes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    #[test]
+    fn header_names_palette() {
+        let output = GplBackend.serialize(&test_palette(), "sunset");
+        assert!(output.starts_with("GIMP Palette\nName: sunset\nColumns: 8\n"));
+    }
+
+    #[test]
+    fn every_entry_is_rgb_and_name() {
+        let output = GplBackend.serialize(&test_palette(), "test");
+        let entry_re = regex::Regex::new(r"^[ \d]{3} [ \d]{3} [ \d]{3}\t[a-z ]+$").unwrap();
+        let entries: Vec<&str> = output
+            .lines()
+            .skip(3)
+            .filter(|l| !l.starts_with('#'))
+            .collect();
+        assert_eq!(entries.len(), 7 + 16);
+        for line in entries {
+            assert!(entry_re.is_match(line), "invalid entry: '{line}'");
+        }
+    }
+
+    #[test]
+    fn slots_follow_specials_in_order() {
+        let palette = test_palette();
+        let output = GplBackend.serialize(&palette, "test");
+        let red = palette.slots[1];
+        assert!(output.contains(&format!("{:3} {:3} {:3}\tred\n", red.r, red.g, red.b)));
+        let bg = palette.background;
+        assert!(output.contains(&format!(
+            "# Generated by nuri\n{:3} {:3} {:3}\tbackground\n",
+            bg.r, bg.g, bg.b
+        )));
+        assert!(output.ends_with("\tbright white\n"));
+    }
+
+    #[test]
+    fn write_to_creates_file() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri-test-gpl-backend");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.gpl");
+
+        GplBackend.write_to(&palette, "test", &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, GplBackend.serialize(&palette, "test"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
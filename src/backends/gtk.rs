@@ -0,0 +1,222 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::color::{Color, PaletteColor, WideColor};
+use crate::pipeline::assign::{AnsiPalette, Palette, WidePalette};
+
+use super::neovim::derive_surface;
+use super::{config_home, DisplayP3Backend, ThemeBackend};
+
+/// GTK CSS backend: libadwaita named colors as `@define-color` rules.
+pub struct GtkBackend;
+
+impl ThemeBackend for GtkBackend {
+    fn name(&self) -> &str {
+        "GTK"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        define_colors(palette, theme_name, Color::to_hex)
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        let path = colors_path()?;
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ".css"
+    }
+
+    fn display_p3(&self) -> Option<&dyn DisplayP3Backend> {
+        Some(self)
+    }
+}
+
+/// GTK 4.16 and later parse CSS Color 4 `color()` values.
+impl DisplayP3Backend for GtkBackend {
+    fn serialize_display_p3(&self, palette: &WidePalette, theme_name: &str) -> String {
+        define_colors(palette, theme_name, WideColor::to_css_display_p3)
+    }
+
+    fn install_display_p3(&self, palette: &WidePalette, theme_name: &str) -> Result<PathBuf> {
+        let path = colors_path()?;
+        self.write_display_p3_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+}
+
+/// Render the `@define-color` rules, writing each color with `value`.
+fn define_colors<C: PaletteColor>(
+    palette: &Palette<C>,
+    theme_name: &str,
+    value: impl Fn(C) -> String,
+) -> String {
+    let p = palette;
+    let surface = derive_surface(p);
+    let accent = p.slots[4];
+    let mut out = String::new();
+
+    out.push_str(&format!("/* nuri: {} */\n", theme_name));
+    out.push_str("/* Generated by nuri */\n");
+    out.push('\n');
+
+    let colors = [
+        ("accent_color", accent),
+        ("accent_bg_color", accent),
+        ("accent_fg_color", text_on(p, &accent)),
+        ("destructive_color", p.slots[1]),
+        ("destructive_bg_color", p.slots[1]),
+        ("destructive_fg_color", text_on(p, &p.slots[1])),
+        ("success_color", p.slots[2]),
+        ("success_bg_color", p.slots[2]),
+        ("success_fg_color", text_on(p, &p.slots[2])),
+        ("warning_color", p.slots[3]),
+        ("warning_bg_color", p.slots[3]),
+        ("warning_fg_color", text_on(p, &p.slots[3])),
+        ("error_color", p.slots[1]),
+        ("error_bg_color", p.slots[1]),
+        ("error_fg_color", text_on(p, &p.slots[1])),
+        ("window_bg_color", p.background),
+        ("window_fg_color", p.foreground),
+        ("view_bg_color", p.background),
+        ("view_fg_color", p.foreground),
+        ("headerbar_bg_color", surface),
+        ("headerbar_fg_color", p.foreground),
+        ("headerbar_backdrop_color", p.background),
+        ("sidebar_bg_color", surface),
+        ("sidebar_fg_color", p.foreground),
+        ("card_bg_color", surface),
+        ("card_fg_color", p.foreground),
+        ("dialog_bg_color", surface),
+        ("dialog_fg_color", p.foreground),
+        ("popover_bg_color", surface),
+        ("popover_fg_color", p.foreground),
+    ];
+    for (name, color) in colors {
+        out.push_str(&format!("@define-color {} {};\n", name, value(color)));
+    }
+
+    out
+}
+
+/// Pick the background or foreground, whichever reads better on `color`.
+fn text_on<C: PaletteColor>(palette: &Palette<C>, color: &C) -> C {
+    if C::contrast_ratio(&palette.background, color)
+        >= C::contrast_ratio(&palette.foreground, color)
+    {
+        palette.background
+    } else {
+        palette.foreground
+    }
+}
+
+/// Create the GTK 4 config directory and return the colors file inside it.
+fn colors_path() -> Result<PathBuf> {
+    let dir = gtk_dir()?;
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("failed to create GTK directory: {}", dir.display()))?;
+    Ok(dir.join("colors.css"))
+}
+
+/// Resolve the GTK 4 config directory, where `gtk.css` can `@import` the colors.
+fn gtk_dir() -> Result<PathBuf> {
+    Ok(config_home()?.join("gtk-4.0"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    #[test]
+    fn every_rule_is_define_color() {
+        let output = GtkBackend.serialize(&test_palette(), "test");
+        let rule_re = regex::Regex::new(r"^@define-color [a-z_]+ #[0-9a-f]{6};$").unwrap();
+        for line in output
+            .lines()
+            .filter(|l| !l.is_empty() && !l.starts_with("/*"))
+        {
+            assert!(rule_re.is_match(line), "invalid rule: '{line}'");
+        }
+    }
+
+    #[test]
+    fn display_p3_rules_use_color_function() {
+        let wide = test_palette().map(WideColor::from);
+        let output = GtkBackend.serialize_display_p3(&wide, "test");
+        let rule_re = regex::Regex::new(
+            r"^@define-color [a-z_]+ color\(display-p3 [01]\.\d{4} [01]\.\d{4} [01]\.\d{4}\);$",
+        )
+        .unwrap();
+        for line in output
+            .lines()
+            .filter(|l| !l.is_empty() && !l.starts_with("/*"))
+        {
+            assert!(rule_re.is_match(line), "invalid rule: '{line}'");
+        }
+        assert!(output.contains(&format!(
+            "@define-color window_bg_color {};",
+            wide.background.to_css_display_p3()
+        )));
+    }
+
+    #[test]
+    fn libadwaita_colors_present() {
+        let palette = test_palette();
+        let output = GtkBackend.serialize(&palette, "test");
+        assert!(output.contains(&format!(
+            "@define-color window_bg_color {};",
+            palette.background.to_hex()
+        )));
+        assert!(output.contains(&format!(
+            "@define-color accent_bg_color {};",
+            palette.slots[4].to_hex()
+        )));
+        for name in ["window_fg_color", "headerbar_bg_color", "card_bg_color"] {
+            assert!(
+                output.contains(&format!("@define-color {name} ")),
+                "missing {name}"
+            );
+        }
+    }
+
+    #[test]
+    fn accent_text_is_readable() {
+        let palette = test_palette();
+        let fg = text_on(&palette, &palette.slots[4]);
+        let other = if fg == palette.background {
+            palette.foreground
+        } else {
+            palette.background
+        };
+        assert!(
+            Color::contrast_ratio(&fg, &palette.slots[4])
+                >= Color::contrast_ratio(&other, &palette.slots[4])
+        );
+    }
+
+    #[test]
+    fn write_to_creates_file() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri-test-gtk-backend");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("colors.css");
+
+        GtkBackend.write_to(&palette, "test", &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, GtkBackend.serialize(&palette, "test"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
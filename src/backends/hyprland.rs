@@ -0,0 +1,133 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::color::Color;
+use crate::pipeline::assign::AnsiPalette;
+
+use super::{config_home, ThemeBackend};
+
+/// Hyprland color variables and window border colors, meant to be `source`d
+/// from `hyprland.conf`. Each theme installs to its own file under
+/// `hypr/nuri/`, so the user's own files are never touched.
+pub struct HyprlandBackend;
+
+impl ThemeBackend for HyprlandBackend {
+    fn name(&self) -> &str {
+        "Hyprland"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        let p = palette;
+        let mut out = String::new();
+
+        out.push_str(&format!("# nuri: {}\n", theme_name));
+        out.push_str("# Generated by nuri\n");
+        out.push('\n');
+
+        out.push_str(&format!("$background = {}\n", rgb(&p.background)));
+        out.push_str(&format!("$foreground = {}\n", rgb(&p.foreground)));
+        for (i, color) in p.slots.iter().enumerate() {
+            out.push_str(&format!("$color{} = {}\n", i, rgb(color)));
+        }
+        out.push('\n');
+
+        out.push_str("general {\n");
+        out.push_str("    col.active_border = $color4 $color5 45deg\n");
+        out.push_str("    col.inactive_border = $color8\n");
+        out.push_str("}\n");
+
+        out
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        let dir = themes_dir()?;
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create Hyprland directory: {}", dir.display()))?;
+
+        let path = dir.join(format!("{theme_name}.conf"));
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ".conf"
+    }
+}
+
+/// Hyprland's `rgb(rrggbb)` color syntax.
+fn rgb(color: &Color) -> String {
+    format!("rgb({})", color.to_hex().trim_start_matches('#'))
+}
+
+/// Resolve the directory for nuri's Hyprland color files.
+fn themes_dir() -> Result<PathBuf> {
+    Ok(config_home()?.join("hypr").join("nuri"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    #[test]
+    fn variables_use_rgb_syntax() {
+        let output = HyprlandBackend.serialize(&test_palette(), "test");
+        let var_re = regex::Regex::new(r"^\$[a-z0-9]+ = rgb\([0-9a-f]{6}\)$").unwrap();
+        let vars: Vec<&str> = output.lines().filter(|l| l.starts_with('$')).collect();
+        assert_eq!(vars.len(), 18);
+        for line in vars {
+            assert!(var_re.is_match(line), "invalid variable: '{line}'");
+        }
+    }
+
+    #[test]
+    fn border_colors_reference_defined_variables() {
+        let output = HyprlandBackend.serialize(&test_palette(), "test");
+        let general = &output[output.find("general {").unwrap()..];
+        assert!(general.contains("col.active_border = "));
+        assert!(general.contains("col.inactive_border = "));
+
+        let ref_re = regex::Regex::new(r"\$([a-z0-9]+)").unwrap();
+        for cap in ref_re.captures_iter(general) {
+            assert!(
+                output.contains(&format!("\n${} = ", &cap[1])),
+                "${} is not defined",
+                &cap[1]
+            );
+        }
+    }
+
+    #[test]
+    fn active_border_uses_blue() {
+        let palette = test_palette();
+        let output = HyprlandBackend.serialize(&palette, "test");
+        assert!(output.contains(&format!(
+            "$color4 = rgb({})",
+            &palette.slots[4].to_hex()[1..]
+        )));
+        assert!(output.contains("col.active_border = $color4 "));
+    }
+
+    #[test]
+    fn write_to_creates_file() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri-test-hyprland-backend");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.conf");
+
+        HyprlandBackend.write_to(&palette, "test", &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, HyprlandBackend.serialize(&palette, "test"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
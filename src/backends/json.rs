@@ -0,0 +1,415 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::color::Color;
+use crate::pipeline::assign::AnsiPalette;
+
+use super::{data_home, ThemeBackend};
+
+/// nuri's own JSON palette format, for scripts and for reading a palette back
+/// in (`nuri audit`).
+pub struct JsonBackend;
+
+/// Special color keys, in output order.
+const SPECIAL_KEYS: [&str; 6] = [
+    "background",
+    "foreground",
+    "cursor",
+    "cursor_text",
+    "selection_background",
+    "selection_foreground",
+];
+
+impl ThemeBackend for JsonBackend {
+    fn name(&self) -> &str {
+        "JSON"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        let mut out = String::new();
+
+        out.push_str("{\n");
+        out.push_str(&format!("  \"name\": \"{}\",\n", escape(theme_name)));
+        write_palette_fields(&mut out, palette, "  ");
+        out.push_str("}\n");
+
+        out
+    }
+
+    fn parse(&self, content: &str) -> Result<AnsiPalette> {
+        let Value::Object(fields) = parse_json(content)? else {
+            bail!("expected a JSON object");
+        };
+        palette_from_fields(&fields)
+    }
+
+    /// Re-serialize under the palette's own `name`, if it has one.
+    fn update(&self, content: &str, palette: &AnsiPalette, theme_name: &str) -> Result<String> {
+        let name = match parse_json(content)? {
+            Value::Object(fields) => fields.into_iter().find_map(|(key, value)| match value {
+                Value::String(name) if key == "name" => Some(name),
+                _ => None,
+            }),
+            _ => None,
+        };
+        Ok(self.serialize(palette, name.as_deref().unwrap_or(theme_name)))
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        let dir = palettes_dir()?;
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create palettes directory: {}", dir.display()))?;
+
+        let path = dir.join(format!("{}.json", theme_name));
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ".json"
+    }
+}
+
+/// Write the palette's colors as JSON object fields, one per line at the given
+/// indentation. The `slots` array comes last, without a trailing comma.
+pub(crate) fn write_palette_fields(out: &mut String, palette: &AnsiPalette, indent: &str) {
+    let p = palette;
+    let specials = [
+        p.background,
+        p.foreground,
+        p.cursor_color,
+        p.cursor_text,
+        p.selection_bg,
+        p.selection_fg,
+    ];
+    for (key, color) in SPECIAL_KEYS.iter().zip(specials) {
+        out.push_str(&format!("{indent}\"{}\": \"{}\",\n", key, color.to_hex()));
+    }
+    out.push_str(&format!("{indent}\"slots\": [\n"));
+    for (i, color) in p.slots.iter().enumerate() {
+        let comma = if i < 15 { "," } else { "" };
+        out.push_str(&format!("{indent}  \"{}\"{}\n", color.to_hex(), comma));
+    }
+    out.push_str(&format!("{indent}]\n"));
+}
+
+/// Read a palette from the fields of a JSON object, as written by
+/// [`write_palette_fields`].
+pub(crate) fn palette_from_fields(fields: &[(String, Value)]) -> Result<AnsiPalette> {
+    let get = |key: &str| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+    let color = |key: &str| -> Result<Color> {
+        match get(key) {
+            Some(Value::String(hex)) => {
+                Color::from_hex(hex).with_context(|| format!("invalid color for \"{key}\""))
+            }
+            Some(_) => bail!("\"{key}\" must be a hex color string"),
+            None => bail!("missing \"{key}\""),
+        }
+    };
+
+    let Some(Value::Array(items)) = get("slots") else {
+        bail!("missing \"slots\" array");
+    };
+    if items.len() != 16 {
+        bail!("\"slots\" must have 16 colors, found {}", items.len());
+    }
+    let mut slots = [Color::new(0, 0, 0); 16];
+    for (i, item) in items.iter().enumerate() {
+        let Value::String(hex) = item else {
+            bail!("slot {i} must be a hex color string");
+        };
+        slots[i] = Color::from_hex(hex).with_context(|| format!("invalid color for slot {i}"))?;
+    }
+
+    Ok(AnsiPalette {
+        slots,
+        background: color("background")?,
+        foreground: color("foreground")?,
+        cursor_color: color("cursor")?,
+        cursor_text: color("cursor_text")?,
+        selection_bg: color("selection_background")?,
+        selection_fg: color("selection_foreground")?,
+    })
+}
+
+/// Parse a JSON document.
+pub(crate) fn parse_json(content: &str) -> Result<Value> {
+    Parser::new(content).parse_document()
+}
+
+/// Escape text for a JSON string.
+pub(crate) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Resolve the directory for exported JSON palettes.
+fn palettes_dir() -> Result<PathBuf> {
+    Ok(data_home()?.join("nuri").join("palettes"))
+}
+
+/// A parsed JSON value. Numbers are kept as text; the palette format has none.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+/// Minimal recursive-descent JSON parser, enough to read palettes back.
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            chars: input.char_indices().peekable(),
+        }
+    }
+
+    fn parse_document(mut self) -> Result<Value> {
+        let value = self.parse_value()?;
+        self.skip_whitespace();
+        if let Some((pos, c)) = self.chars.next() {
+            bail!("unexpected '{c}' at byte {pos} after JSON value");
+        }
+        Ok(value)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((pos, c)) => bail!("expected '{expected}' at byte {pos}, found '{c}'"),
+            None => bail!("expected '{expected}', found end of input"),
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value> {
+        self.skip_whitespace();
+        match self.chars.peek().copied() {
+            Some((_, '{')) => self.parse_object(),
+            Some((_, '[')) => self.parse_array(),
+            Some((_, '"')) => Ok(Value::String(self.parse_string()?)),
+            Some((_, c)) if c == '-' || c.is_ascii_digit() => {
+                let mut number = String::new();
+                while let Some((_, c)) = self
+                    .chars
+                    .next_if(|(_, c)| c.is_ascii_digit() || "+-.eE".contains(*c))
+                {
+                    number.push(c);
+                }
+                Ok(Value::Number(number))
+            }
+            Some((pos, c)) if c.is_ascii_alphabetic() => {
+                let mut word = String::new();
+                while let Some((_, c)) = self.chars.next_if(|(_, c)| c.is_ascii_alphabetic()) {
+                    word.push(c);
+                }
+                match word.as_str() {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    "null" => Ok(Value::Null),
+                    _ => bail!("unexpected '{word}' at byte {pos}"),
+                }
+            }
+            Some((pos, c)) => bail!("unexpected '{c}' at byte {pos}"),
+            None => bail!("unexpected end of input"),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Value> {
+        self.expect('{')?;
+        let mut fields = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if(|(_, c)| *c == '}').is_some() {
+            return Ok(Value::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.expect(':')?;
+            fields.push((key, self.parse_value()?));
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some((_, ',')) => continue,
+                Some((_, '}')) => return Ok(Value::Object(fields)),
+                Some((pos, c)) => bail!("expected ',' or '}}' at byte {pos}, found '{c}'"),
+                None => bail!("unterminated object"),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Value> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if(|(_, c)| *c == ']').is_some() {
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some((_, ',')) => continue,
+                Some((_, ']')) => return Ok(Value::Array(items)),
+                Some((pos, c)) => bail!("expected ',' or ']' at byte {pos}, found '{c}'"),
+                None => bail!("unterminated array"),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(out),
+                Some((pos, '\\')) => match self.chars.next() {
+                    Some((_, '"')) => out.push('"'),
+                    Some((_, '\\')) => out.push('\\'),
+                    Some((_, '/')) => out.push('/'),
+                    Some((_, 'n')) => out.push('\n'),
+                    Some((_, 't')) => out.push('\t'),
+                    Some((_, 'r')) => out.push('\r'),
+                    Some((_, 'b')) => out.push('\u{8}'),
+                    Some((_, 'f')) => out.push('\u{c}'),
+                    Some((_, 'u')) => {
+                        let hex: String = (0..4)
+                            .filter_map(|_| self.chars.next())
+                            .map(|(_, c)| c)
+                            .collect();
+                        let code = u32::from_str_radix(&hex, 16)
+                            .with_context(|| format!("invalid \\u escape at byte {pos}"))?;
+                        out.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+                    }
+                    _ => bail!("invalid escape at byte {pos}"),
+                },
+                Some((_, c)) => out.push(c),
+                None => bail!("unterminated string"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    #[test]
+    fn serialize_parse_round_trip() {
+        let palette = test_palette();
+        let parsed = JsonBackend
+            .parse(&JsonBackend.serialize(&palette, "test"))
+            .unwrap();
+        assert_eq!(parsed.slots, palette.slots);
+        assert_eq!(parsed.background, palette.background);
+        assert_eq!(parsed.foreground, palette.foreground);
+        assert_eq!(parsed.cursor_color, palette.cursor_color);
+        assert_eq!(parsed.cursor_text, palette.cursor_text);
+        assert_eq!(parsed.selection_bg, palette.selection_bg);
+        assert_eq!(parsed.selection_fg, palette.selection_fg);
+    }
+
+    #[test]
+    fn theme_name_is_escaped() {
+        let output = JsonBackend.serialize(&test_palette(), "say \"hi\"\\");
+        let Value::Object(fields) = Parser::new(&output).parse_document().unwrap() else {
+            panic!("expected an object");
+        };
+        assert_eq!(
+            fields[0],
+            (
+                "name".to_string(),
+                Value::String("say \"hi\"\\".to_string())
+            )
+        );
+    }
+
+    #[test]
+    fn parser_handles_general_json() {
+        let value = Parser::new(r#" {"a": [1, -2.5e3, true, null], "b": {}, "c": "é\n"} "#)
+            .parse_document()
+            .unwrap();
+        assert_eq!(
+            value,
+            Value::Object(vec![
+                (
+                    "a".to_string(),
+                    Value::Array(vec![
+                        Value::Number("1".to_string()),
+                        Value::Number("-2.5e3".to_string()),
+                        Value::Bool(true),
+                        Value::Null,
+                    ])
+                ),
+                ("b".to_string(), Value::Object(vec![])),
+                ("c".to_string(), Value::String("é\n".to_string())),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_reports_problems() {
+        let valid = JsonBackend.serialize(&test_palette(), "test");
+
+        let err = JsonBackend.parse("{\"slots\": []").unwrap_err();
+        assert!(err.to_string().contains("unterminated"), "{err}");
+
+        let missing = valid.replace("\"cursor\":", "\"caret\":");
+        let err = JsonBackend.parse(&missing).unwrap_err();
+        assert_eq!(err.to_string(), "missing \"cursor\"");
+
+        let extra = valid.replacen("\"slots\": [\n", "\"slots\": [\n    \"#000000\",\n", 1);
+        let err = JsonBackend.parse(&extra).unwrap_err();
+        assert!(err.to_string().contains("16 colors, found 17"), "{err}");
+    }
+
+    #[test]
+    fn update_keeps_palette_name() {
+        let palette = test_palette();
+        let content = JsonBackend.serialize(&palette, "Tokyo Night");
+        let updated = JsonBackend.update(&content, &palette, "file-stem").unwrap();
+        assert_eq!(updated, content);
+    }
+
+    #[test]
+    fn write_to_creates_file() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri-test-json-backend");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.json");
+
+        JsonBackend.write_to(&palette, "test", &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, JsonBackend.serialize(&palette, "test"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,179 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::color::Color;
+use crate::pipeline::assign::AnsiPalette;
+
+use super::{data_home, ThemeBackend};
+
+/// Konsole color scheme backend (`.colorscheme` INI format).
+pub struct KonsoleBackend;
+
+impl ThemeBackend for KonsoleBackend {
+    fn name(&self) -> &str {
+        "Konsole"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        let p = palette;
+        let mut out = String::new();
+
+        out.push_str(&format!("# nuri: {}\n", theme_name));
+        out.push_str("# Generated by nuri\n");
+
+        push_section(&mut out, "Background", &p.background);
+        push_section(&mut out, "BackgroundIntense", &p.background);
+
+        for i in 0..8 {
+            push_section(&mut out, &format!("Color{i}"), &p.slots[i]);
+            push_section(&mut out, &format!("Color{i}Intense"), &p.slots[i + 8]);
+        }
+
+        push_section(&mut out, "Foreground", &p.foreground);
+        push_section(&mut out, "ForegroundIntense", &p.foreground);
+
+        out.push('\n');
+        out.push_str("[General]\n");
+        out.push_str(&format!("Description={}\n", theme_name));
+        out.push_str("Opacity=1\n");
+
+        out
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        let dir = schemes_dir()?;
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create schemes directory: {}", dir.display()))?;
+
+        let path = dir.join(format!("{}.colorscheme", theme_name));
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ".colorscheme"
+    }
+}
+
+/// Append a `[Section]` with a decimal `Color=r,g,b` entry.
+fn push_section(out: &mut String, section: &str, color: &Color) {
+    out.push('\n');
+    out.push_str(&format!("[{}]\n", section));
+    out.push_str(&format!("Color={},{},{}\n", color.r, color.g, color.b));
+}
+
+/// Resolve the Konsole color scheme directory.
+fn schemes_dir() -> Result<PathBuf> {
+    Ok(data_home()?.join("konsole"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    /// Return the `Color=` value in the given section.
+    fn section_value<'a>(output: &'a str, section: &str) -> &'a str {
+        let header = format!("[{section}]\n");
+        let start = output.find(&header).unwrap() + header.len();
+        output[start..]
+            .lines()
+            .next()
+            .unwrap()
+            .strip_prefix("Color=")
+            .unwrap()
+    }
+
+    #[test]
+    fn all_sections_present() {
+        let output = KonsoleBackend.serialize(&test_palette(), "test");
+        let mut sections = vec!["Background".to_string(), "Foreground".to_string()];
+        for i in 0..8 {
+            sections.push(format!("Color{i}"));
+            sections.push(format!("Color{i}Intense"));
+        }
+        for section in sections {
+            assert!(
+                output.contains(&format!("[{section}]\n")),
+                "missing section: {section}"
+            );
+        }
+    }
+
+    #[test]
+    fn colors_are_decimal_rgb_triplets() {
+        let output = KonsoleBackend.serialize(&test_palette(), "test");
+        let triplet_re = regex::Regex::new(r"^Color=(\d{1,3}),(\d{1,3}),(\d{1,3})$").unwrap();
+
+        let color_lines: Vec<&str> = output.lines().filter(|l| l.starts_with("Color=")).collect();
+        assert_eq!(color_lines.len(), 20);
+        for line in color_lines {
+            let cap = triplet_re
+                .captures(line)
+                .unwrap_or_else(|| panic!("not an RGB triplet: '{line}'"));
+            for i in 1..=3 {
+                assert!(
+                    cap[i].parse::<u16>().unwrap() <= 255,
+                    "out of range: '{line}'"
+                );
+            }
+        }
+        assert!(output
+            .lines()
+            .filter(|l| !l.starts_with('#'))
+            .all(|l| !l.contains('#')));
+    }
+
+    #[test]
+    fn slots_map_to_normal_and_intense() {
+        let palette = test_palette();
+        let output = KonsoleBackend.serialize(&palette, "test");
+
+        let red = palette.slots[1];
+        assert_eq!(
+            section_value(&output, "Color1"),
+            format!("{},{},{}", red.r, red.g, red.b)
+        );
+        let bright_red = palette.slots[9];
+        assert_eq!(
+            section_value(&output, "Color1Intense"),
+            format!("{},{},{}", bright_red.r, bright_red.g, bright_red.b)
+        );
+        let bg = palette.background;
+        assert_eq!(
+            section_value(&output, "Background"),
+            format!("{},{},{}", bg.r, bg.g, bg.b)
+        );
+    }
+
+    #[test]
+    fn general_section_names_scheme() {
+        let output = KonsoleBackend.serialize(&test_palette(), "my-theme");
+        assert!(output.contains("[General]\nDescription=my-theme\n"));
+    }
+
+    #[test]
+    fn write_to_creates_file() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri-test-konsole-backend");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test-theme.colorscheme");
+
+        KonsoleBackend
+            .write_to(&palette, "test-theme", &path)
+            .unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, KonsoleBackend.serialize(&palette, "test-theme"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,140 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::pipeline::assign::AnsiPalette;
+
+use super::neovim::derive_surface;
+use super::{config_home, ThemeBackend};
+
+/// lazygit backend: a `gui.theme` config fragment, loadable alongside the
+/// main config via `LG_CONFIG_FILE`.
+pub struct LazygitBackend;
+
+impl ThemeBackend for LazygitBackend {
+    fn name(&self) -> &str {
+        "lazygit"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        let p = palette;
+        let surface = derive_surface(p);
+        let mut out = String::new();
+
+        out.push_str(&format!("# nuri: {}\n", theme_name));
+        out.push_str("# Generated by nuri\n");
+        out.push_str("gui:\n");
+        out.push_str("  theme:\n");
+
+        let entries = [
+            ("activeBorderColor", p.slots[4], true),
+            ("inactiveBorderColor", p.slots[8], false),
+            ("searchingActiveBorderColor", p.slots[3], true),
+            ("optionsTextColor", p.slots[4], false),
+            ("selectedLineBgColor", surface, false),
+            ("inactiveViewSelectedLineBgColor", surface, false),
+            ("cherryPickedCommitFgColor", p.slots[4], false),
+            ("cherryPickedCommitBgColor", p.selection_bg, false),
+            ("markedBaseCommitFgColor", p.slots[6], false),
+            ("markedBaseCommitBgColor", p.selection_bg, false),
+            ("unstagedChangesColor", p.slots[1], false),
+            ("defaultFgColor", p.foreground, false),
+        ];
+        for (key, color, bold) in entries {
+            out.push_str(&format!("    {}:\n", key));
+            out.push_str(&format!("      - \"{}\"\n", color.to_hex()));
+            if bold {
+                out.push_str("      - bold\n");
+            }
+        }
+
+        out
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        let dir = themes_dir()?;
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create themes directory: {}", dir.display()))?;
+
+        let path = dir.join(format!("{}.yml", theme_name));
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ".yml"
+    }
+}
+
+/// Resolve the directory for lazygit theme fragments.
+fn themes_dir() -> Result<PathBuf> {
+    Ok(config_home()?.join("lazygit").join("themes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    #[test]
+    fn nested_under_gui_theme() {
+        let output = LazygitBackend.serialize(&test_palette(), "test");
+        assert!(output.contains("gui:\n  theme:\n    activeBorderColor:\n"));
+    }
+
+    #[test]
+    fn every_key_has_a_color_list() {
+        let output = LazygitBackend.serialize(&test_palette(), "test");
+        let lines: Vec<&str> = output.lines().collect();
+        let color_re = regex::Regex::new(r##"^      - "#[0-9a-f]{6}"$"##).unwrap();
+
+        let keys: Vec<usize> = (0..lines.len())
+            .filter(|&i| lines[i].starts_with("    ") && !lines[i].starts_with("     "))
+            .collect();
+        assert_eq!(keys.len(), 12);
+        for i in keys {
+            assert!(
+                lines[i].ends_with(':'),
+                "key should open a list: '{}'",
+                lines[i]
+            );
+            assert!(
+                color_re.is_match(lines[i + 1]),
+                "first item should be a color: '{}'",
+                lines[i + 1]
+            );
+        }
+    }
+
+    #[test]
+    fn active_border_is_bold_blue() {
+        let palette = test_palette();
+        let output = LazygitBackend.serialize(&palette, "test");
+        assert!(output.contains(&format!(
+            "    activeBorderColor:\n      - \"{}\"\n      - bold\n",
+            palette.slots[4].to_hex()
+        )));
+    }
+
+    #[test]
+    fn write_to_creates_file() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri-test-lazygit-backend");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.yml");
+
+        LazygitBackend.write_to(&palette, "test", &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, LazygitBackend.serialize(&palette, "test"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,12 +1,35 @@
+pub mod base16;
+pub mod btop;
+pub mod dircolors;
+pub mod dunst;
+pub mod emacs;
+pub mod eza;
+pub mod foot;
+pub mod fzf;
 pub mod ghostty;
+pub mod gpl;
+pub mod gtk;
+pub mod hyprland;
+pub mod json;
+pub mod konsole;
+pub mod lazygit;
 pub mod neovim;
+pub mod rofi;
+pub mod template;
+pub mod tmtheme;
+pub mod vim;
+pub mod waybar;
+pub mod xresources;
 pub mod zellij;
 
+use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 
-use crate::pipeline::assign::AnsiPalette;
+use crate::cli::NeovimLayout;
+use crate::pipeline::assign::{AnsiPalette, WidePalette};
 
 /// A theme output backend that serializes an `AnsiPalette` into a target format.
 pub trait ThemeBackend {
@@ -25,14 +48,268 @@ pub trait ThemeBackend {
 
     /// File extension for this backend (e.g., ".kdl"), or empty string for none.
     fn extension(&self) -> &str;
+
+    /// Read a theme in this format back into a palette.
+    ///
+    /// Formats that cannot be read back keep the default, which errors.
+    fn parse(&self, _content: &str) -> Result<AnsiPalette> {
+        bail!("reading {} themes is not supported", self.name())
+    }
+
+    /// Rewrite an existing theme file's content with new colors.
+    ///
+    /// Backends that can keep unrelated content (comments, other settings)
+    /// override this; the default re-serializes the whole theme.
+    fn update(&self, _content: &str, palette: &AnsiPalette, theme_name: &str) -> Result<String> {
+        Ok(self.serialize(palette, theme_name))
+    }
+
+    /// Display P3 output, for formats that can store wide-gamut colors.
+    fn display_p3(&self) -> Option<&dyn DisplayP3Backend> {
+        None
+    }
+
+    /// Make running instances of the app switch to the theme just installed
+    /// at `path`. Returns a short note on what was done, or `None` if the
+    /// app cannot be told to reload (the default).
+    fn reload(&self, _theme_name: &str, _path: &Path) -> Result<Option<String>> {
+        Ok(None)
+    }
+}
+
+/// Wide-gamut output for a [`ThemeBackend`] whose format can declare colors
+/// in Display P3.
+pub trait DisplayP3Backend {
+    /// Serialize the palette with Display P3 colors.
+    fn serialize_display_p3(&self, palette: &WidePalette, theme_name: &str) -> String;
+
+    /// Install the theme where [`ThemeBackend::install`] would.
+    fn install_display_p3(&self, palette: &WidePalette, theme_name: &str) -> Result<PathBuf>;
+
+    /// Write the theme to an arbitrary path.
+    fn write_display_p3_to(
+        &self,
+        palette: &WidePalette,
+        theme_name: &str,
+        path: &Path,
+    ) -> Result<()> {
+        let content = self.serialize_display_p3(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// Where a config file selects a theme, as byte ranges of the file: the
+/// whole line (with its line ending) and the theme name within it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThemeSetting {
+    pub line: Range<usize>,
+    pub name: Range<usize>,
+}
+
+impl ThemeSetting {
+    /// The selected theme.
+    pub fn value<'a>(&self, config: &'a str) -> &'a str {
+        &config[self.name.clone()]
+    }
+
+    /// `config` selecting `theme_name` instead, otherwise unchanged.
+    pub fn replace(&self, config: &str, theme_name: &str) -> String {
+        let mut out = config.to_string();
+        out.replace_range(self.name.clone(), theme_name);
+        out
+    }
+
+    /// `config` without the setting's line.
+    pub fn remove(&self, config: &str) -> String {
+        let mut out = config.to_string();
+        out.replace_range(self.line.clone(), "");
+        out
+    }
+}
+
+/// The user's home directory: `$HOME`, or else the system's record of it.
+pub fn home_dir() -> Result<PathBuf> {
+    std::env::home_dir()
+        .filter(|home| !home.as_os_str().is_empty())
+        .context("cannot find the home directory; set $HOME")
+}
+
+/// Resolve `$XDG_CONFIG_HOME`, falling back to `~/.config`.
+pub fn config_home() -> Result<PathBuf> {
+    xdg_home("XDG_CONFIG_HOME", &[".config"])
+}
+
+/// Resolve `$XDG_DATA_HOME`, falling back to `~/.local/share`.
+pub fn data_home() -> Result<PathBuf> {
+    xdg_home("XDG_DATA_HOME", &[".local", "share"])
+}
+
+/// Resolve `$XDG_STATE_HOME`, falling back to `~/.local/state`.
+pub fn state_home() -> Result<PathBuf> {
+    xdg_home("XDG_STATE_HOME", &[".local", "state"])
+}
+
+/// Resolve `$XDG_CACHE_HOME`, falling back to `~/.cache`.
+pub fn cache_home() -> Result<PathBuf> {
+    xdg_home("XDG_CACHE_HOME", &[".cache"])
+}
+
+/// The directory in `var`, or `fallback` under the home directory when it is
+/// unset or empty, as the XDG base directory spec asks.
+fn xdg_home(var: &str, fallback: &[&str]) -> Result<PathBuf> {
+    match std::env::var_os(var) {
+        Some(dir) if !dir.is_empty() => Ok(PathBuf::from(dir)),
+        _ => Ok(fallback
+            .iter()
+            .fold(home_dir()?, |dir, part| dir.join(part))),
+    }
+}
+
+/// `config` with `line` added at the end, on a line of its own.
+pub fn append_line(config: &str, line: &str) -> String {
+    let mut out = config.to_string();
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str(line);
+    out.push('\n');
+    out
+}
+
+/// Lines of `config` with their byte offsets, line endings included.
+pub(crate) fn lines_with_offsets(config: &str) -> impl Iterator<Item = (usize, &str)> {
+    config.split_inclusive('\n').scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        Some((start, line))
+    })
+}
+
+/// Run `backend`'s reload hook for a theme just installed at `path`,
+/// reporting the outcome. A failed reload only warns: the theme is installed
+/// either way.
+pub fn reload_installed(backend: &dyn ThemeBackend, theme_name: &str, path: &Path) {
+    match backend.reload(theme_name, path) {
+        Ok(Some(note)) => eprintln!("Reloaded {}: {note}", backend.name()),
+        Ok(None) => eprintln!("note: {} has no reload hook", backend.name()),
+        Err(e) => eprintln!("warning: failed to reload {}: {e:#}", backend.name()),
+    }
 }
 
 /// Supported output targets.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Target {
     Ghostty,
     Zellij,
     Neovim,
+    Vim,
+    Emacs,
+    Foot,
+    Konsole,
+    Xresources,
+    Base16,
+    Base24,
+    Gtk,
+    Waybar,
+    Rofi,
+    Dunst,
+    Hyprland,
+    Dircolors,
+    LsColors,
+    Eza,
+    TmTheme,
+    Fzf,
+    Lazygit,
+    Btop,
+    Gpl,
+    Json,
+    /// A user template from `$XDG_CONFIG_HOME/nuri/templates`, selected with
+    /// `template:<name>`.
+    Template(template::Template),
+}
+
+/// Names accepted by `--target`, besides `template:<name>`, and the targets
+/// they select. Parsing, the possible values in errors, and the `--target`
+/// help all come from this table.
+pub const TARGETS: [(&str, Target); 24] = [
+    ("ghostty", Target::Ghostty),
+    ("zellij", Target::Zellij),
+    ("neovim", Target::Neovim),
+    ("vim", Target::Vim),
+    ("emacs", Target::Emacs),
+    ("foot", Target::Foot),
+    ("konsole", Target::Konsole),
+    ("xresources", Target::Xresources),
+    ("base16", Target::Base16),
+    ("base24", Target::Base24),
+    ("gtk", Target::Gtk),
+    ("waybar", Target::Waybar),
+    ("rofi", Target::Rofi),
+    ("dunst", Target::Dunst),
+    ("hyprland", Target::Hyprland),
+    ("dircolors", Target::Dircolors),
+    ("ls-colors", Target::LsColors),
+    ("eza", Target::Eza),
+    ("tmtheme", Target::TmTheme),
+    ("fzf", Target::Fzf),
+    ("lazygit", Target::Lazygit),
+    ("btop", Target::Btop),
+    ("gpl", Target::Gpl),
+    ("json", Target::Json),
+];
+
+/// The names in [`TARGETS`], comma-separated.
+pub fn target_names() -> String {
+    TARGETS
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    /// Parse a `--target` value. Templates are loaded and checked here, so a
+    /// broken template is reported before any image work starts.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(name) = s.strip_prefix("template:") {
+            return template::Template::load(name)
+                .map(Target::Template)
+                .map_err(|e| format!("{e:#}"));
+        }
+        TARGETS
+            .iter()
+            .find(|(name, _)| *name == s)
+            .map(|(_, target)| target.clone())
+            .ok_or_else(|| {
+                format!(
+                    "unknown target '{s}' [possible values: {}, template:<name>]",
+                    target_names()
+                )
+            })
+    }
+}
+
+/// Derive a theme name from the image filename stem (or directory name).
+pub fn default_theme_name(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("theme")
+        .to_string()
+}
+
+/// Guess the format of an existing theme file from its extension, among the
+/// targets that can be read back. Anything unrecognized is taken as Ghostty,
+/// whose themes have no extension.
+pub fn guess_target(path: &Path) -> Target {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("kdl") => Target::Zellij,
+        Some("json") => Target::Json,
+        _ => Target::Ghostty,
+    }
 }
 
 /// Return the backend for a given target.
@@ -41,6 +318,42 @@ pub fn get_backend(target: Target) -> Box<dyn ThemeBackend> {
         Target::Ghostty => Box::new(ghostty::GhosttyBackend),
         Target::Zellij => Box::new(zellij::ZellijBackend),
         Target::Neovim => Box::new(neovim::NeovimBackend),
+        Target::Vim => Box::new(vim::VimBackend),
+        Target::Emacs => Box::new(emacs::EmacsBackend),
+        Target::Foot => Box::new(foot::FootBackend),
+        Target::Konsole => Box::new(konsole::KonsoleBackend),
+        Target::Xresources => Box::new(xresources::XresourcesBackend),
+        Target::Base16 => Box::new(base16::Base16Backend),
+        Target::Base24 => Box::new(base16::Base24Backend),
+        Target::Gtk => Box::new(gtk::GtkBackend),
+        Target::Waybar => Box::new(waybar::WaybarBackend),
+        Target::Rofi => Box::new(rofi::RofiBackend),
+        Target::Dunst => Box::new(dunst::DunstBackend),
+        Target::Hyprland => Box::new(hyprland::HyprlandBackend),
+        Target::Dircolors => Box::new(dircolors::DircolorsBackend),
+        Target::LsColors => Box::new(dircolors::LsColorsBackend),
+        Target::Eza => Box::new(eza::EzaBackend),
+        Target::TmTheme => Box::new(tmtheme::TmThemeBackend),
+        Target::Fzf => Box::new(fzf::FzfBackend),
+        Target::Lazygit => Box::new(lazygit::LazygitBackend),
+        Target::Btop => Box::new(btop::BtopBackend),
+        Target::Gpl => Box::new(gpl::GplBackend),
+        Target::Json => Box::new(json::JsonBackend),
+        Target::Template(template) => Box::new(template::TemplateBackend::new(template)),
+    }
+}
+
+/// Command-line options that change how a target is written.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BackendOptions {
+    pub neovim_layout: NeovimLayout,
+}
+
+/// Return the backend for a given target, honoring backend-specific options.
+pub fn get_backend_with(target: Target, options: &BackendOptions) -> Box<dyn ThemeBackend> {
+    match (&target, options.neovim_layout) {
+        (Target::Neovim, NeovimLayout::Plugin) => Box::new(neovim::NeovimPluginBackend),
+        _ => get_backend(target),
     }
 }
 
@@ -53,5 +366,121 @@ mod tests {
         assert_eq!(get_backend(Target::Ghostty).name(), "Ghostty");
         assert_eq!(get_backend(Target::Zellij).name(), "Zellij");
         assert_eq!(get_backend(Target::Neovim).name(), "Neovim");
+        assert_eq!(get_backend(Target::Vim).name(), "Vim");
+        assert_eq!(get_backend(Target::Emacs).name(), "Emacs");
+        assert_eq!(get_backend(Target::Foot).name(), "foot");
+        assert_eq!(get_backend(Target::Konsole).name(), "Konsole");
+        assert_eq!(get_backend(Target::Xresources).name(), "Xresources");
+        assert_eq!(get_backend(Target::Base16).name(), "base16");
+        assert_eq!(get_backend(Target::Base24).name(), "base24");
+        assert_eq!(get_backend(Target::Gtk).name(), "GTK");
+        assert_eq!(get_backend(Target::Waybar).name(), "Waybar");
+        assert_eq!(get_backend(Target::Rofi).name(), "rofi");
+        assert_eq!(get_backend(Target::Dunst).name(), "dunst");
+        assert_eq!(get_backend(Target::Hyprland).name(), "Hyprland");
+        assert_eq!(get_backend(Target::Dircolors).name(), "dircolors");
+        assert_eq!(get_backend(Target::LsColors).name(), "LS_COLORS");
+        assert_eq!(get_backend(Target::Eza).name(), "eza");
+        assert_eq!(get_backend(Target::TmTheme).name(), "tmTheme");
+        assert_eq!(get_backend(Target::Fzf).name(), "fzf");
+        assert_eq!(get_backend(Target::Lazygit).name(), "lazygit");
+        assert_eq!(get_backend(Target::Btop).name(), "btop");
+        assert_eq!(get_backend(Target::Gpl).name(), "gpl");
+        assert_eq!(get_backend(Target::Json).name(), "JSON");
+    }
+
+    #[test]
+    fn get_backend_with_default_options_matches_get_backend() {
+        let options = BackendOptions::default();
+        for target in [
+            Target::Ghostty,
+            Target::Zellij,
+            Target::Neovim,
+            Target::Vim,
+            Target::Emacs,
+            Target::Foot,
+            Target::Konsole,
+            Target::Xresources,
+            Target::Base16,
+            Target::Base24,
+            Target::Gtk,
+            Target::Waybar,
+            Target::Rofi,
+            Target::Dunst,
+            Target::Hyprland,
+            Target::Dircolors,
+            Target::LsColors,
+            Target::Eza,
+            Target::TmTheme,
+            Target::Fzf,
+            Target::Lazygit,
+            Target::Btop,
+            Target::Gpl,
+            Target::Json,
+        ] {
+            assert_eq!(
+                get_backend_with(target.clone(), &options).extension(),
+                get_backend(target).extension()
+            );
+        }
+    }
+
+    #[test]
+    fn target_names_round_trip() {
+        for (name, target) in TARGETS {
+            assert_eq!(name.parse::<Target>().unwrap(), target);
+            assert_eq!(
+                get_backend(target).name().to_lowercase().replace('_', "-"),
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn xdg_home_falls_back_when_unset_or_empty() {
+        // A variable of its own, so tests reading the real ones are unaffected
+        let var = "NURI_TEST_XDG_HOME";
+        let fallback = home_dir().unwrap().join(".local").join("share");
+        std::env::remove_var(var);
+        assert_eq!(xdg_home(var, &[".local", "share"]).unwrap(), fallback);
+        std::env::set_var(var, "");
+        assert_eq!(xdg_home(var, &[".local", "share"]).unwrap(), fallback);
+        std::env::set_var(var, "/tmp/data");
+        assert_eq!(
+            xdg_home(var, &[".local", "share"]).unwrap(),
+            PathBuf::from("/tmp/data")
+        );
+        std::env::remove_var(var);
+    }
+
+    #[test]
+    fn unknown_target_lists_possible_values() {
+        let err = "kitty".parse::<Target>().unwrap_err();
+        assert!(err.contains("unknown target 'kitty'"));
+        assert!(err.contains("ghostty"));
+        assert!(err.contains("template:<name>"));
+    }
+
+    #[test]
+    fn guess_target_uses_extension() {
+        assert_eq!(guess_target(Path::new("a/sunset.kdl")), Target::Zellij);
+        assert_eq!(guess_target(Path::new("sunset.json")), Target::Json);
+        assert_eq!(guess_target(Path::new("themes/sunset")), Target::Ghostty);
+    }
+
+    #[test]
+    fn parse_defaults_to_unsupported() {
+        let err = get_backend(Target::Vim).parse("").unwrap_err();
+        assert_eq!(err.to_string(), "reading Vim themes is not supported");
+    }
+
+    #[test]
+    fn neovim_plugin_layout_selects_plugin_backend() {
+        let options = BackendOptions {
+            neovim_layout: NeovimLayout::Plugin,
+        };
+        let backend = get_backend_with(Target::Neovim, &options);
+        assert_eq!(backend.name(), "Neovim");
+        assert_eq!(backend.extension(), "");
     }
 }
@@ -1,12 +1,13 @@
 use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use palette::Oklch;
 
-use crate::color::Color;
-use crate::pipeline::assign::AnsiPalette;
+use crate::color::{Color, PaletteColor};
+use crate::msgpack::{self, Value};
+use crate::pipeline::assign::{AnsiPalette, Palette};
 
-use super::ThemeBackend;
+use super::{append_line, config_home, data_home, lines_with_offsets, ThemeBackend, ThemeSetting};
 
 /// Neovim colorscheme backend (Lua format).
 pub struct NeovimBackend;
@@ -79,10 +80,437 @@ impl ThemeBackend for NeovimBackend {
     fn extension(&self) -> &str {
         ".lua"
     }
+
+    /// Run the installed file in each running Neovim; it sets the highlights
+    /// itself.
+    fn reload(&self, _theme_name: &str, path: &Path) -> Result<Option<String>> {
+        let path = path.to_string_lossy();
+        let calls = [(
+            "nvim_exec_lua",
+            vec![
+                "dofile(...)".into(),
+                Value::Array(vec![path.as_ref().into()]),
+            ],
+        )];
+        let reloaded = broadcast(&runtime_dir()?, &calls)?;
+        Ok(Some(format!("reloaded {reloaded} running instance(s)")))
+    }
+}
+
+/// Neovim colorscheme packaged as a plugin directory.
+///
+/// Produces a thin `colors/<name>.lua` loader, a `lua/<name>/` module with the
+/// palette and a `setup()` API, and a matching lualine theme.
+pub struct NeovimPluginBackend;
+
+impl ThemeBackend for NeovimPluginBackend {
+    fn name(&self) -> &str {
+        "Neovim"
+    }
+
+    /// All plugin files concatenated, each introduced by a `-- file:` comment.
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        let mut out = String::new();
+        for (i, (path, content)) in plugin_files(palette, theme_name).iter().enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            out.push_str(&format!("-- file: {}\n", path.display()));
+            out.push_str(content);
+        }
+        out
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        self.install_in(palette, theme_name, &data_home()?)
+    }
+
+    /// Write the plugin tree with `path` as its root directory.
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        for (relative, content) in plugin_files(palette, theme_name) {
+            let file = path.join(&relative);
+            if let Some(parent) = file.parent() {
+                std::fs::create_dir_all(parent).with_context(|| {
+                    format!("failed to create plugin directory: {}", parent.display())
+                })?;
+            }
+            std::fs::write(&file, content)
+                .with_context(|| format!("failed to write theme to {}", file.display()))?;
+        }
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ""
+    }
+
+    /// `:colorscheme` in each running Neovim. A plugin installed after
+    /// startup is added to the runtimepath first, and the cached palette
+    /// module is dropped so the new colors are read.
+    fn reload(&self, _theme_name: &str, path: &Path) -> Result<Option<String>> {
+        // Installed as <pack>/<safe name>, so the name needs no sanitizing
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .context("plugin path has no directory name")?;
+        let dir = path.to_string_lossy();
+        let calls = [
+            (
+                "nvim_exec_lua",
+                vec![
+                    PREPARE_PLUGIN_RELOAD.into(),
+                    Value::Array(vec![dir.as_ref().into(), name.as_str().into()]),
+                ],
+            ),
+            (
+                "nvim_command",
+                vec![format!("colorscheme {name}").as_str().into()],
+            ),
+        ];
+        let reloaded = broadcast(&runtime_dir()?, &calls)?;
+        Ok(Some(format!("reloaded {reloaded} running instance(s)")))
+    }
+}
+
+/// Lua run before `:colorscheme` on reload; takes the plugin directory and
+/// theme name.
+const PREPARE_PLUGIN_RELOAD: &str = "\
+local dir, name = ...
+if not vim.tbl_contains(vim.api.nvim_list_runtime_paths(), dir) then
+  vim.opt.runtimepath:prepend(dir)
+end
+package.loaded[name .. \".palette\"] = nil";
+
+/// Send each `(method, params)` call to every Neovim listening under
+/// `runtime_dir`, where Neovim puts its default `nvim.<pid>.0` server socket.
+/// Sockets left behind by exited instances are skipped. Returns how many
+/// instances ran all the calls.
+#[cfg(unix)]
+pub fn broadcast(runtime_dir: &Path, calls: &[(&str, Vec<Value>)]) -> Result<usize> {
+    use std::os::unix::net::UnixStream;
+    use std::time::Duration;
+
+    let entries = std::fs::read_dir(runtime_dir)
+        .with_context(|| format!("failed to list {}", runtime_dir.display()))?;
+    let mut sockets: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().starts_with("nvim."))
+        .map(|e| e.path())
+        .collect();
+    sockets.sort();
+
+    let mut reloaded = 0;
+    let mut failures = Vec::new();
+    for socket in &sockets {
+        let Ok(mut stream) = UnixStream::connect(socket) else {
+            continue;
+        };
+        let timeout = Some(Duration::from_secs(2));
+        stream.set_read_timeout(timeout)?;
+        stream.set_write_timeout(timeout)?;
+        let result = calls
+            .iter()
+            .zip(1..)
+            .try_for_each(|((method, params), id)| rpc_call(&mut stream, id, method, params));
+        match result {
+            Ok(()) => reloaded += 1,
+            Err(e) => failures.push(format!("{}: {e:#}", socket.display())),
+        }
+    }
+    if !failures.is_empty() {
+        bail!(
+            "{} of {} Neovim instance(s) failed to reload: {}",
+            failures.len(),
+            reloaded + failures.len(),
+            failures.join("; ")
+        );
+    }
+    Ok(reloaded)
+}
+
+#[cfg(not(unix))]
+pub fn broadcast(_runtime_dir: &Path, _calls: &[(&str, Vec<Value>)]) -> Result<usize> {
+    bail!("reloading Neovim needs Unix sockets")
+}
+
+/// Send one msgpack-RPC request and wait for its response, skipping any
+/// notifications in between.
+#[cfg(unix)]
+fn rpc_call(
+    stream: &mut std::os::unix::net::UnixStream,
+    id: i64,
+    method: &str,
+    params: &[Value],
+) -> Result<()> {
+    let request = Value::Array(vec![
+        Value::Int(0),
+        Value::Int(id),
+        method.into(),
+        Value::Array(params.to_vec()),
+    ]);
+    msgpack::write(stream, &request).context("failed to send request")?;
+    loop {
+        let message = msgpack::read(stream).context("failed to read response")?;
+        let Value::Array(parts) = &message else {
+            continue;
+        };
+        // Responses are [1, id, error, result]
+        if let [Value::Int(1), Value::Int(msgid), error, _] = &parts[..] {
+            if *msgid != id {
+                continue;
+            }
+            return match error {
+                Value::Nil => Ok(()),
+                // Neovim sends errors as [type, message]
+                Value::Array(error) => match error.iter().find_map(Value::as_str) {
+                    Some(message) => bail!("{method}: {message}"),
+                    None => bail!("{method} failed"),
+                },
+                other => bail!("{method}: {other:?}"),
+            };
+        }
+    }
+}
+
+/// Select `theme_name` in an `init.lua`, keeping everything else. Without a
+/// colorscheme call, `vim.cmd.colorscheme("<name>")` is appended.
+pub fn set_colorscheme(init: &str, theme_name: &str) -> String {
+    match find_colorscheme(init) {
+        Some(setting) => setting.replace(init, theme_name),
+        None => append_line(init, &format!("vim.cmd.colorscheme(\"{theme_name}\")")),
+    }
+}
+
+/// The last colorscheme set in an `init.lua`, in any of the usual forms:
+/// `vim.cmd.colorscheme("x")`, `vim.cmd("colorscheme x")`,
+/// `vim.cmd([[colorscheme x]])`. Names built at runtime, like
+/// `"colorscheme " .. name`, are not recognized.
+pub fn find_colorscheme(init: &str) -> Option<ThemeSetting> {
+    const KEYWORD: &str = "colorscheme";
+    let is_name = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.');
+    let mut found = None;
+    for (start, line) in lines_with_offsets(init) {
+        let code = line.split("--").next().unwrap_or_default();
+        for (at, _) in code.match_indices(KEYWORD) {
+            let before = code[..at].chars().next_back();
+            if before.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+                continue;
+            }
+            let rest = &code[at + KEYWORD.len()..];
+            let name = rest.trim_start_matches([' ', '\t', '(', '"', '\'', '[']);
+            if name.len() == rest.len()
+                || !name.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+            {
+                continue;
+            }
+            let len = name.find(|c| !is_name(c)).unwrap_or(name.len());
+            let name_start = start + code.len() - name.len();
+            found = Some(ThemeSetting {
+                line: start..start + line.len(),
+                name: name_start..name_start + len,
+            });
+        }
+    }
+    found
+}
+
+/// Path of Neovim's `init.lua`.
+pub fn init_path() -> Result<PathBuf> {
+    Ok(config_home()?.join("nvim").join("init.lua"))
+}
+
+/// Where running Neovim instances put their server sockets.
+fn runtime_dir() -> Result<PathBuf> {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .context("XDG_RUNTIME_DIR is not set, so running Neovim instances cannot be found")
+}
+
+/// Build the plugin's files as `(relative path, content)` pairs.
+pub fn plugin_files(palette: &AnsiPalette, theme_name: &str) -> Vec<(PathBuf, String)> {
+    let safe_name = sanitize_name(theme_name);
+    let surface = derive_surface(palette);
+
+    vec![
+        (
+            Path::new("colors").join(format!("{}.lua", safe_name)),
+            plugin_loader(&safe_name),
+        ),
+        (
+            Path::new("lua").join(&safe_name).join("palette.lua"),
+            plugin_palette(&safe_name, palette, &surface),
+        ),
+        (
+            Path::new("lua").join(&safe_name).join("init.lua"),
+            plugin_init(&safe_name),
+        ),
+        (
+            Path::new("lua")
+                .join("lualine")
+                .join("themes")
+                .join(format!("{}.lua", safe_name)),
+            plugin_lualine(&safe_name),
+        ),
+    ]
+}
+
+/// Groups whose background is cleared when `transparent = true`.
+const TRANSPARENT_GROUPS: &[&str] = &[
+    "Normal",
+    "NormalFloat",
+    "FloatBorder",
+    "SignColumn",
+    "StatusLine",
+    "StatusLineNC",
+    "TabLineFill",
+];
+
+fn write_plugin_header(out: &mut String, safe_name: &str) {
+    out.push_str(&format!("-- nuri: {}\n", safe_name));
+    out.push_str("-- Generated by nuri\n");
+    out.push('\n');
+}
+
+/// `colors/<name>.lua`: defers to the plugin module so `:colorscheme` honors `setup()`.
+fn plugin_loader(safe_name: &str) -> String {
+    let mut out = String::new();
+    write_plugin_header(&mut out, safe_name);
+    out.push_str(&format!("require(\"{}\").load()\n", safe_name));
+    out
+}
+
+/// `lua/<name>/palette.lua`: the named color table.
+fn plugin_palette(safe_name: &str, palette: &AnsiPalette, surface: &Color) -> String {
+    let mut out = String::new();
+    write_plugin_header(&mut out, safe_name);
+    out.push_str("return {\n");
+    for (name, color) in &color_entries(palette, surface) {
+        out.push_str(&format!("  {} = \"{}\",\n", name, color.to_hex()));
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// `lua/<name>/init.lua`: `setup()` options and the highlight definitions.
+fn plugin_init(safe_name: &str) -> String {
+    let mut out = String::new();
+    write_plugin_header(&mut out, safe_name);
+
+    out.push_str("local M = {}\n");
+    out.push('\n');
+    out.push_str("M.config = {\n");
+    out.push_str("  transparent = false,\n");
+    out.push_str("  italic_comments = true,\n");
+    out.push_str("  -- Table of group specs, or a function(c) returning one\n");
+    out.push_str("  overrides = {},\n");
+    out.push_str("}\n");
+    out.push('\n');
+
+    out.push_str("function M.setup(opts)\n");
+    out.push_str("  M.config = vim.tbl_deep_extend(\"force\", M.config, opts or {})\n");
+    out.push_str("end\n");
+    out.push('\n');
+
+    out.push_str("function M.highlights(c)\n");
+    out.push_str("  return {\n");
+    let sections = [
+        ("Editor UI", EDITOR_GROUPS),
+        ("Diagnostics", DIAGNOSTIC_GROUPS),
+        ("Syntax", SYNTAX_GROUPS),
+        ("TreeSitter", TREESITTER_GROUPS),
+    ];
+    for (i, (title, groups)) in sections.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        out.push_str(&format!("    -- {}\n", title));
+        for (name, props) in *groups {
+            if name.starts_with('@') {
+                out.push_str(&format!("    [\"{}\"] = {},\n", name, props.to_lua()));
+            } else {
+                out.push_str(&format!("    {} = {},\n", name, props.to_lua()));
+            }
+        }
+    }
+    out.push_str("  }\n");
+    out.push_str("end\n");
+    out.push('\n');
+
+    out.push_str("function M.load()\n");
+    out.push_str("  vim.cmd(\"highlight clear\")\n");
+    out.push_str("  if vim.fn.exists(\"syntax_on\") then\n");
+    out.push_str("    vim.cmd(\"syntax reset\")\n");
+    out.push_str("  end\n");
+    out.push_str(&format!("  vim.g.colors_name = \"{}\"\n", safe_name));
+    out.push('\n');
+    out.push_str(&format!("  local c = require(\"{}.palette\")\n", safe_name));
+    out.push_str("  local groups = M.highlights(c)\n");
+    out.push('\n');
+    out.push_str("  if M.config.transparent then\n");
+    let transparent: Vec<String> = TRANSPARENT_GROUPS
+        .iter()
+        .map(|g| format!("\"{}\"", g))
+        .collect();
+    out.push_str(&format!(
+        "    for _, name in ipairs({{ {} }}) do\n",
+        transparent.join(", ")
+    ));
+    out.push_str("      groups[name].bg = \"NONE\"\n");
+    out.push_str("    end\n");
+    out.push_str("  end\n");
+    out.push_str("  groups.Comment.italic = M.config.italic_comments\n");
+    out.push('\n');
+    out.push_str("  local overrides = M.config.overrides\n");
+    out.push_str("  if type(overrides) == \"function\" then\n");
+    out.push_str("    overrides = overrides(c)\n");
+    out.push_str("  end\n");
+    out.push_str("  for name, spec in pairs(overrides) do\n");
+    out.push_str("    groups[name] = spec\n");
+    out.push_str("  end\n");
+    out.push('\n');
+    out.push_str("  for name, spec in pairs(groups) do\n");
+    out.push_str("    vim.api.nvim_set_hl(0, name, spec)\n");
+    out.push_str("  end\n");
+    out.push_str("end\n");
+    out.push('\n');
+    out.push_str("return M\n");
+    out
 }
 
-/// Sanitize a theme name for Neovim: only [a-z0-9_-] allowed.
-fn sanitize_name(name: &str) -> String {
+/// `lua/lualine/themes/<name>.lua`: mode colors follow the editor groups.
+fn plugin_lualine(safe_name: &str) -> String {
+    let mut out = String::new();
+    write_plugin_header(&mut out, safe_name);
+    out.push_str(&format!("local c = require(\"{}.palette\")\n", safe_name));
+    out.push('\n');
+    out.push_str("return {\n");
+    out.push_str("  normal = {\n");
+    out.push_str("    a = { fg = c.bg, bg = c.blue, gui = \"bold\" },\n");
+    out.push_str("    b = { fg = c.fg, bg = c.surface },\n");
+    out.push_str("    c = { fg = c.fg, bg = c.bg },\n");
+    out.push_str("  },\n");
+    for (mode, accent) in [
+        ("insert", "green"),
+        ("visual", "magenta"),
+        ("replace", "red"),
+        ("command", "yellow"),
+    ] {
+        out.push_str(&format!(
+            "  {} = {{ a = {{ fg = c.bg, bg = c.{}, gui = \"bold\" }} }},\n",
+            mode, accent
+        ));
+    }
+    out.push_str("  inactive = {\n");
+    out.push_str("    a = { fg = c.bright_black, bg = c.bg },\n");
+    out.push_str("    b = { fg = c.bright_black, bg = c.bg },\n");
+    out.push_str("    c = { fg = c.bright_black, bg = c.bg },\n");
+    out.push_str("  },\n");
+    out.push_str("}\n");
+    out
+}
+
+/// Sanitize a theme name for editor colorschemes: only [a-z0-9_-] allowed.
+pub fn sanitize_name(name: &str) -> String {
     let sanitized: String = name
         .to_lowercase()
         .chars()
@@ -96,7 +524,7 @@ fn sanitize_name(name: &str) -> String {
         .collect();
     if sanitized != name.to_lowercase() {
         eprintln!(
-            "warning: theme name sanitized for Neovim: '{}' -> '{}'",
+            "warning: theme name sanitized: '{}' -> '{}'",
             name, sanitized
         );
     }
@@ -105,7 +533,7 @@ fn sanitize_name(name: &str) -> String {
 
 /// Derive the "surface" color: background shifted slightly in Oklch lightness.
 /// Dark mode: L += 0.03, Light mode: L -= 0.03.
-fn derive_surface(palette: &AnsiPalette) -> Color {
+pub fn derive_surface<C: PaletteColor>(palette: &Palette<C>) -> C {
     let bg = palette.background.to_oklch();
     let is_dark = bg.l < 0.5;
     let l = if is_dark {
@@ -113,13 +541,12 @@ fn derive_surface(palette: &AnsiPalette) -> Color {
     } else {
         (bg.l - 0.03).max(0.0)
     };
-    Color::from_oklch(Oklch::new(l, bg.chroma, bg.hue))
+    C::from_oklch(Oklch::new(l, bg.chroma, bg.hue))
 }
 
-/// Write the `local c = { ... }` color table.
-fn write_color_table(out: &mut String, palette: &AnsiPalette, surface: &Color) {
-    out.push_str("local c = {\n");
-    let entries = [
+/// Named colors shared by the colorscheme's `c` table and the plugin palette.
+pub fn color_entries(palette: &AnsiPalette, surface: &Color) -> [(&'static str, Color); 21] {
+    [
         ("bg", palette.background),
         ("fg", palette.foreground),
         ("black", palette.slots[0]),
@@ -141,228 +568,360 @@ fn write_color_table(out: &mut String, palette: &AnsiPalette, surface: &Color) {
         ("selection", palette.selection_bg),
         ("cursor", palette.cursor_color),
         ("surface", *surface),
-    ];
-    for (name, color) in &entries {
+    ]
+}
+
+/// Write the `local c = { ... }` color table.
+fn write_color_table(out: &mut String, palette: &AnsiPalette, surface: &Color) {
+    out.push_str("local c = {\n");
+    for (name, color) in &color_entries(palette, surface) {
         out.push_str(&format!("  {} = \"{}\",\n", name, color.to_hex()));
     }
     out.push_str("}\n");
 }
 
+/// A highlight group definition in terms of color table names (`"fg"`, `"red"`, ...).
+///
+/// Shared with the backends that mirror Neovim's groups so every editor makes
+/// the same syntax mapping decisions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Highlight {
+    pub fg: Option<&'static str>,
+    pub bg: Option<&'static str>,
+    pub sp: Option<&'static str>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub undercurl: bool,
+    pub link: Option<&'static str>,
+}
+
+/// Start an empty highlight definition.
+const fn hl() -> Highlight {
+    Highlight {
+        fg: None,
+        bg: None,
+        sp: None,
+        bold: false,
+        italic: false,
+        underline: false,
+        undercurl: false,
+        link: None,
+    }
+}
+
+/// A highlight that links to another group.
+const fn link(group: &'static str) -> Highlight {
+    Highlight {
+        link: Some(group),
+        ..hl()
+    }
+}
+
+impl Highlight {
+    const fn fg(self, color: &'static str) -> Self {
+        Self {
+            fg: Some(color),
+            ..self
+        }
+    }
+
+    const fn bg(self, color: &'static str) -> Self {
+        Self {
+            bg: Some(color),
+            ..self
+        }
+    }
+
+    const fn sp(self, color: &'static str) -> Self {
+        Self {
+            sp: Some(color),
+            ..self
+        }
+    }
+
+    const fn bold(self) -> Self {
+        Self { bold: true, ..self }
+    }
+
+    const fn italic(self) -> Self {
+        Self {
+            italic: true,
+            ..self
+        }
+    }
+
+    const fn underline(self) -> Self {
+        Self {
+            underline: true,
+            ..self
+        }
+    }
+
+    const fn undercurl(self) -> Self {
+        Self {
+            undercurl: true,
+            ..self
+        }
+    }
+
+    /// Render as a Lua table literal for `nvim_set_hl`, e.g. `{ fg = c.fg, bold = true }`.
+    pub fn to_lua(&self) -> String {
+        if let Some(group) = self.link {
+            return format!("{{ link = \"{}\" }}", group);
+        }
+        let mut fields = Vec::new();
+        if let Some(fg) = self.fg {
+            fields.push(format!("fg = c.{}", fg));
+        }
+        if let Some(bg) = self.bg {
+            fields.push(format!("bg = c.{}", bg));
+        }
+        if self.undercurl {
+            fields.push("undercurl = true".to_string());
+        }
+        if let Some(sp) = self.sp {
+            fields.push(format!("sp = c.{}", sp));
+        }
+        if self.bold {
+            fields.push("bold = true".to_string());
+        }
+        if self.italic {
+            fields.push("italic = true".to_string());
+        }
+        if self.underline {
+            fields.push("underline = true".to_string());
+        }
+        format!("{{ {} }}", fields.join(", "))
+    }
+}
+
+/// Look up a group by name in the editor, diagnostic, and syntax tables.
+pub fn find_group(name: &str) -> Option<Highlight> {
+    EDITOR_GROUPS
+        .iter()
+        .chain(DIAGNOSTIC_GROUPS)
+        .chain(SYNTAX_GROUPS)
+        .find(|(group, _)| *group == name)
+        .map(|(_, highlight)| *highlight)
+}
+
+/// Look up a group, following links to the group that defines its colors.
+pub fn resolve_group(name: &str) -> Option<Highlight> {
+    let highlight = find_group(name)?;
+    match highlight.link {
+        Some(target) => resolve_group(target),
+        None => Some(highlight),
+    }
+}
+
+/// Editor UI highlight groups.
+pub const EDITOR_GROUPS: &[(&str, Highlight)] = &[
+    ("Normal", hl().fg("fg").bg("bg")),
+    ("NormalFloat", hl().fg("fg").bg("surface")),
+    ("FloatBorder", hl().fg("bright_black").bg("surface")),
+    ("Visual", hl().bg("selection")),
+    ("Cursor", hl().fg("bg").bg("cursor")),
+    ("CursorLine", hl().bg("surface")),
+    ("CursorLineNr", hl().fg("yellow").bg("surface")),
+    ("LineNr", hl().fg("bright_black")),
+    ("SignColumn", hl().bg("bg")),
+    ("StatusLine", hl().fg("fg").bg("surface")),
+    ("StatusLineNC", hl().fg("bright_black").bg("black")),
+    ("TabLine", hl().fg("bright_black").bg("black")),
+    ("TabLineSel", hl().fg("fg").bg("surface").bold()),
+    ("TabLineFill", hl().bg("black")),
+    ("WinSeparator", hl().fg("bright_black")),
+    ("Pmenu", hl().fg("fg").bg("surface")),
+    ("PmenuSel", hl().fg("bg").bg("blue")),
+    ("PmenuSbar", hl().bg("surface")),
+    ("PmenuThumb", hl().bg("bright_black")),
+    ("Search", hl().fg("bg").bg("yellow")),
+    ("IncSearch", hl().fg("bg").bg("bright_yellow")),
+    ("MatchParen", hl().fg("bright_cyan").bold()),
+    ("ErrorMsg", hl().fg("red")),
+    ("WarningMsg", hl().fg("yellow")),
+    ("Title", hl().fg("blue").bold()),
+    ("SpecialKey", hl().fg("bright_black")),
+    ("NonText", hl().fg("bright_black")),
+    ("Directory", hl().fg("blue")),
+    ("Question", hl().fg("green")),
+    ("MoreMsg", hl().fg("green")),
+    ("ModeMsg", hl().fg("fg").bold()),
+    ("WildMenu", hl().fg("bg").bg("blue")),
+];
+
 /// Write Editor UI highlight groups.
 fn write_editor_groups(out: &mut String) {
     out.push_str("-- Editor UI\n");
-    let groups: &[(&str, &str)] = &[
-        ("Normal", "{ fg = c.fg, bg = c.bg }"),
-        ("NormalFloat", "{ fg = c.fg, bg = c.surface }"),
-        ("FloatBorder", "{ fg = c.bright_black, bg = c.surface }"),
-        ("Visual", "{ bg = c.selection }"),
-        ("Cursor", "{ fg = c.bg, bg = c.cursor }"),
-        ("CursorLine", "{ bg = c.surface }"),
-        ("CursorLineNr", "{ fg = c.yellow, bg = c.surface }"),
-        ("LineNr", "{ fg = c.bright_black }"),
-        ("SignColumn", "{ bg = c.bg }"),
-        ("StatusLine", "{ fg = c.fg, bg = c.surface }"),
-        ("StatusLineNC", "{ fg = c.bright_black, bg = c.black }"),
-        ("TabLine", "{ fg = c.bright_black, bg = c.black }"),
-        ("TabLineSel", "{ fg = c.fg, bg = c.surface, bold = true }"),
-        ("TabLineFill", "{ bg = c.black }"),
-        ("WinSeparator", "{ fg = c.bright_black }"),
-        ("Pmenu", "{ fg = c.fg, bg = c.surface }"),
-        ("PmenuSel", "{ fg = c.bg, bg = c.blue }"),
-        ("PmenuSbar", "{ bg = c.surface }"),
-        ("PmenuThumb", "{ bg = c.bright_black }"),
-        ("Search", "{ fg = c.bg, bg = c.yellow }"),
-        ("IncSearch", "{ fg = c.bg, bg = c.bright_yellow }"),
-        ("MatchParen", "{ fg = c.bright_cyan, bold = true }"),
-        ("ErrorMsg", "{ fg = c.red }"),
-        ("WarningMsg", "{ fg = c.yellow }"),
-        ("Title", "{ fg = c.blue, bold = true }"),
-        ("SpecialKey", "{ fg = c.bright_black }"),
-        ("NonText", "{ fg = c.bright_black }"),
-        ("Directory", "{ fg = c.blue }"),
-        ("Question", "{ fg = c.green }"),
-        ("MoreMsg", "{ fg = c.green }"),
-        ("ModeMsg", "{ fg = c.fg, bold = true }"),
-        ("WildMenu", "{ fg = c.bg, bg = c.blue }"),
-    ];
-    for (name, props) in groups {
-        out.push_str(&format!("hl(0, \"{}\", {})\n", name, props));
+    for (name, props) in EDITOR_GROUPS {
+        out.push_str(&format!("hl(0, \"{}\", {})\n", name, props.to_lua()));
     }
     out.push('\n');
 }
 
+/// Diagnostic highlight groups.
+pub const DIAGNOSTIC_GROUPS: &[(&str, Highlight)] = &[
+    ("DiagnosticError", hl().fg("red")),
+    ("DiagnosticWarn", hl().fg("yellow")),
+    ("DiagnosticInfo", hl().fg("blue")),
+    ("DiagnosticHint", hl().fg("cyan")),
+    ("DiagnosticUnderlineError", hl().undercurl().sp("red")),
+    ("DiagnosticUnderlineWarn", hl().undercurl().sp("yellow")),
+    ("DiagnosticUnderlineInfo", hl().undercurl().sp("blue")),
+    ("DiagnosticUnderlineHint", hl().undercurl().sp("cyan")),
+];
+
 /// Write Diagnostic highlight groups.
 fn write_diagnostic_groups(out: &mut String) {
     out.push_str("-- Diagnostics\n");
-    let groups: &[(&str, &str)] = &[
-        ("DiagnosticError", "{ fg = c.red }"),
-        ("DiagnosticWarn", "{ fg = c.yellow }"),
-        ("DiagnosticInfo", "{ fg = c.blue }"),
-        ("DiagnosticHint", "{ fg = c.cyan }"),
-        (
-            "DiagnosticUnderlineError",
-            "{ undercurl = true, sp = c.red }",
-        ),
-        (
-            "DiagnosticUnderlineWarn",
-            "{ undercurl = true, sp = c.yellow }",
-        ),
-        (
-            "DiagnosticUnderlineInfo",
-            "{ undercurl = true, sp = c.blue }",
-        ),
-        (
-            "DiagnosticUnderlineHint",
-            "{ undercurl = true, sp = c.cyan }",
-        ),
-    ];
-    for (name, props) in groups {
-        out.push_str(&format!("hl(0, \"{}\", {})\n", name, props));
+    for (name, props) in DIAGNOSTIC_GROUPS {
+        out.push_str(&format!("hl(0, \"{}\", {})\n", name, props.to_lua()));
     }
     out.push('\n');
 }
 
+/// Vim standard syntax highlight groups.
+pub const SYNTAX_GROUPS: &[(&str, Highlight)] = &[
+    ("Comment", hl().fg("bright_black").italic()),
+    ("Constant", hl().fg("yellow")),
+    ("String", hl().fg("green")),
+    ("Character", hl().fg("green")),
+    ("Number", hl().fg("yellow")),
+    ("Boolean", hl().fg("yellow")),
+    ("Float", hl().fg("yellow")),
+    ("Identifier", hl().fg("fg")),
+    ("Function", hl().fg("blue")),
+    ("Statement", hl().fg("magenta")),
+    ("Conditional", hl().fg("magenta")),
+    ("Repeat", hl().fg("magenta")),
+    ("Label", hl().fg("magenta")),
+    ("Operator", hl().fg("cyan")),
+    ("Keyword", hl().fg("magenta")),
+    ("Exception", hl().fg("magenta")),
+    ("PreProc", hl().fg("cyan")),
+    ("Include", hl().fg("cyan")),
+    ("Define", hl().fg("magenta")),
+    ("Macro", hl().fg("magenta")),
+    ("Type", hl().fg("yellow")),
+    ("StorageClass", hl().fg("yellow")),
+    ("Structure", hl().fg("yellow")),
+    ("Typedef", hl().fg("yellow")),
+    ("Special", hl().fg("cyan")),
+    ("SpecialChar", hl().fg("cyan")),
+    ("Delimiter", hl().fg("fg")),
+    ("SpecialComment", hl().fg("bright_black").bold()),
+    ("Debug", hl().fg("red")),
+    ("Underlined", hl().fg("blue").underline()),
+    ("Error", hl().fg("red")),
+    ("Todo", hl().fg("yellow").bold().italic()),
+];
+
 /// Write Vim standard syntax highlight groups.
 fn write_syntax_groups(out: &mut String) {
     out.push_str("-- Syntax\n");
-    let groups: &[(&str, &str)] = &[
-        ("Comment", "{ fg = c.bright_black, italic = true }"),
-        ("Constant", "{ fg = c.yellow }"),
-        ("String", "{ fg = c.green }"),
-        ("Character", "{ fg = c.green }"),
-        ("Number", "{ fg = c.yellow }"),
-        ("Boolean", "{ fg = c.yellow }"),
-        ("Float", "{ fg = c.yellow }"),
-        ("Identifier", "{ fg = c.fg }"),
-        ("Function", "{ fg = c.blue }"),
-        ("Statement", "{ fg = c.magenta }"),
-        ("Conditional", "{ fg = c.magenta }"),
-        ("Repeat", "{ fg = c.magenta }"),
-        ("Label", "{ fg = c.magenta }"),
-        ("Operator", "{ fg = c.cyan }"),
-        ("Keyword", "{ fg = c.magenta }"),
-        ("Exception", "{ fg = c.magenta }"),
-        ("PreProc", "{ fg = c.cyan }"),
-        ("Include", "{ fg = c.cyan }"),
-        ("Define", "{ fg = c.magenta }"),
-        ("Macro", "{ fg = c.magenta }"),
-        ("Type", "{ fg = c.yellow }"),
-        ("StorageClass", "{ fg = c.yellow }"),
-        ("Structure", "{ fg = c.yellow }"),
-        ("Typedef", "{ fg = c.yellow }"),
-        ("Special", "{ fg = c.cyan }"),
-        ("SpecialChar", "{ fg = c.cyan }"),
-        ("Delimiter", "{ fg = c.fg }"),
-        ("SpecialComment", "{ fg = c.bright_black, bold = true }"),
-        ("Debug", "{ fg = c.red }"),
-        ("Underlined", "{ fg = c.blue, underline = true }"),
-        ("Error", "{ fg = c.red }"),
-        ("Todo", "{ fg = c.yellow, bold = true, italic = true }"),
-    ];
-    for (name, props) in groups {
-        out.push_str(&format!("hl(0, \"{}\", {})\n", name, props));
+    for (name, props) in SYNTAX_GROUPS {
+        out.push_str(&format!("hl(0, \"{}\", {})\n", name, props.to_lua()));
     }
     out.push('\n');
 }
 
+/// TreeSitter highlight groups.
+pub const TREESITTER_GROUPS: &[(&str, Highlight)] = &[
+    ("@variable", hl().fg("fg")),
+    ("@variable.builtin", hl().fg("red")),
+    ("@variable.parameter", hl().fg("bright_red")),
+    ("@variable.member", hl().fg("fg")),
+    ("@constant", link("Constant")),
+    ("@constant.builtin", hl().fg("yellow").bold()),
+    ("@string", link("String")),
+    ("@string.escape", hl().fg("bright_cyan")),
+    ("@string.regex", hl().fg("bright_green")),
+    ("@number", link("Number")),
+    ("@boolean", link("Boolean")),
+    ("@function", link("Function")),
+    ("@function.builtin", hl().fg("blue").bold()),
+    ("@function.call", hl().fg("blue")),
+    ("@function.macro", hl().fg("magenta")),
+    ("@method", hl().fg("blue")),
+    ("@method.call", hl().fg("blue")),
+    ("@constructor", hl().fg("yellow")),
+    ("@keyword", link("Keyword")),
+    ("@keyword.function", hl().fg("magenta")),
+    ("@keyword.return", hl().fg("magenta").bold()),
+    ("@keyword.operator", hl().fg("cyan")),
+    ("@keyword.conditional", link("Conditional")),
+    ("@keyword.repeat", link("Repeat")),
+    ("@keyword.import", hl().fg("cyan")),
+    ("@keyword.exception", link("Exception")),
+    ("@operator", link("Operator")),
+    ("@type", link("Type")),
+    ("@type.builtin", hl().fg("yellow").italic()),
+    ("@type.definition", hl().fg("yellow")),
+    ("@module", hl().fg("cyan")),
+    ("@property", hl().fg("fg")),
+    ("@attribute", hl().fg("cyan")),
+    ("@punctuation.delimiter", hl().fg("fg")),
+    ("@punctuation.bracket", hl().fg("fg")),
+    ("@punctuation.special", hl().fg("cyan")),
+    ("@comment", link("Comment")),
+    ("@comment.todo", link("Todo")),
+    ("@comment.error", hl().fg("red").bold()),
+    ("@comment.warning", hl().fg("yellow").bold()),
+    ("@comment.note", hl().fg("blue").bold()),
+    ("@markup.heading", hl().fg("blue").bold()),
+    ("@markup.strong", hl().bold()),
+    ("@markup.italic", hl().italic()),
+    ("@markup.link", hl().fg("blue").underline()),
+    ("@markup.raw", hl().fg("green")),
+    ("@tag", hl().fg("red")),
+    ("@tag.attribute", hl().fg("yellow")),
+    ("@tag.delimiter", hl().fg("fg")),
+];
+
 /// Write TreeSitter highlight groups.
 fn write_treesitter_groups(out: &mut String) {
     out.push_str("-- TreeSitter\n");
-    let groups: &[(&str, &str)] = &[
-        ("@variable", "{ fg = c.fg }"),
-        ("@variable.builtin", "{ fg = c.red }"),
-        ("@variable.parameter", "{ fg = c.bright_red }"),
-        ("@variable.member", "{ fg = c.fg }"),
-        ("@constant", "{ link = \"Constant\" }"),
-        ("@constant.builtin", "{ fg = c.yellow, bold = true }"),
-        ("@string", "{ link = \"String\" }"),
-        ("@string.escape", "{ fg = c.bright_cyan }"),
-        ("@string.regex", "{ fg = c.bright_green }"),
-        ("@number", "{ link = \"Number\" }"),
-        ("@boolean", "{ link = \"Boolean\" }"),
-        ("@function", "{ link = \"Function\" }"),
-        ("@function.builtin", "{ fg = c.blue, bold = true }"),
-        ("@function.call", "{ fg = c.blue }"),
-        ("@function.macro", "{ fg = c.magenta }"),
-        ("@method", "{ fg = c.blue }"),
-        ("@method.call", "{ fg = c.blue }"),
-        ("@constructor", "{ fg = c.yellow }"),
-        ("@keyword", "{ link = \"Keyword\" }"),
-        ("@keyword.function", "{ fg = c.magenta }"),
-        ("@keyword.return", "{ fg = c.magenta, bold = true }"),
-        ("@keyword.operator", "{ fg = c.cyan }"),
-        ("@keyword.conditional", "{ link = \"Conditional\" }"),
-        ("@keyword.repeat", "{ link = \"Repeat\" }"),
-        ("@keyword.import", "{ fg = c.cyan }"),
-        ("@keyword.exception", "{ link = \"Exception\" }"),
-        ("@operator", "{ link = \"Operator\" }"),
-        ("@type", "{ link = \"Type\" }"),
-        ("@type.builtin", "{ fg = c.yellow, italic = true }"),
-        ("@type.definition", "{ fg = c.yellow }"),
-        ("@module", "{ fg = c.cyan }"),
-        ("@property", "{ fg = c.fg }"),
-        ("@attribute", "{ fg = c.cyan }"),
-        ("@punctuation.delimiter", "{ fg = c.fg }"),
-        ("@punctuation.bracket", "{ fg = c.fg }"),
-        ("@punctuation.special", "{ fg = c.cyan }"),
-        ("@comment", "{ link = \"Comment\" }"),
-        ("@comment.todo", "{ link = \"Todo\" }"),
-        ("@comment.error", "{ fg = c.red, bold = true }"),
-        ("@comment.warning", "{ fg = c.yellow, bold = true }"),
-        ("@comment.note", "{ fg = c.blue, bold = true }"),
-        ("@markup.heading", "{ fg = c.blue, bold = true }"),
-        ("@markup.strong", "{ bold = true }"),
-        ("@markup.italic", "{ italic = true }"),
-        ("@markup.link", "{ fg = c.blue, underline = true }"),
-        ("@markup.raw", "{ fg = c.green }"),
-        ("@tag", "{ fg = c.red }"),
-        ("@tag.attribute", "{ fg = c.yellow }"),
-        ("@tag.delimiter", "{ fg = c.fg }"),
-    ];
-    for (name, props) in groups {
-        out.push_str(&format!("hl(0, \"{}\", {})\n", name, props));
+    for (name, props) in TREESITTER_GROUPS {
+        out.push_str(&format!("hl(0, \"{}\", {})\n", name, props.to_lua()));
     }
 }
 
 /// Resolve the Neovim plugins directory.
 fn colors_dir() -> Result<PathBuf> {
-    let config_home = std::env::var("XDG_CONFIG_HOME")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| {
-            let home = std::env::var("HOME").unwrap_or_else(|_| "~".to_string());
-            PathBuf::from(home).join(".config")
-        });
-    Ok(config_home.join("nvim").join("lua").join("plugins"))
+    Ok(config_home()?.join("nvim").join("lua").join("plugins"))
+}
+
+impl NeovimPluginBackend {
+    /// Install into the package directory under `data_home`.
+    fn install_in(
+        &self,
+        palette: &AnsiPalette,
+        theme_name: &str,
+        data_home: &Path,
+    ) -> Result<PathBuf> {
+        let safe_name = sanitize_name(theme_name);
+        let path = pack_dir_in(data_home).join(safe_name);
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+}
+
+/// The Neovim package directory under `data_home` that is loaded
+/// automatically on startup.
+fn pack_dir_in(data_home: &Path) -> PathBuf {
+    data_home
+        .join("nvim")
+        .join("site")
+        .join("pack")
+        .join("nuri")
+        .join("start")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::cli::ThemeMode;
-    use crate::pipeline::assign::assign_slots;
-    use crate::pipeline::extract::ExtractedColor;
-    use palette::Oklch;
-
-    fn make_extracted(l: f32, chroma: f32, hue: f32, weight: f32) -> ExtractedColor {
-        ExtractedColor {
-            color: Color::from_oklch(Oklch::new(l, chroma, hue)),
-            weight,
-        }
-    }
-
-    fn test_palette() -> AnsiPalette {
-        let colors = vec![
-            make_extracted(0.60, 0.20, 25.0, 0.12),
-            make_extracted(0.60, 0.20, 145.0, 0.12),
-            make_extracted(0.70, 0.20, 90.0, 0.12),
-            make_extracted(0.55, 0.20, 260.0, 0.12),
-            make_extracted(0.60, 0.20, 325.0, 0.12),
-            make_extracted(0.65, 0.20, 195.0, 0.10),
-            make_extracted(0.10, 0.01, 0.0, 0.15),
-            make_extracted(0.95, 0.01, 0.0, 0.15),
-        ];
-        assign_slots(&colors, ThemeMode::Dark)
-    }
+    use crate::pipeline::assign::test_support::test_palette;
 
     #[test]
     fn output_starts_with_header() {
@@ -513,4 +1072,242 @@ mod tests {
         std::fs::remove_dir_all(&temp_dir).unwrap();
         std::env::remove_var("XDG_CONFIG_HOME");
     }
+    #[test]
+    fn plugin_files_have_expected_layout() {
+        let files = plugin_files(&test_palette(), "mytheme");
+        let paths: Vec<PathBuf> = files.iter().map(|(p, _)| p.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("colors/mytheme.lua"),
+                PathBuf::from("lua/mytheme/palette.lua"),
+                PathBuf::from("lua/mytheme/init.lua"),
+                PathBuf::from("lua/lualine/themes/mytheme.lua"),
+            ]
+        );
+    }
+
+    #[test]
+    fn plugin_loader_requires_module() {
+        let files = plugin_files(&test_palette(), "mytheme");
+        assert!(files[0].1.contains(r#"require("mytheme").load()"#));
+    }
+
+    #[test]
+    fn plugin_init_exposes_setup_options() {
+        let files = plugin_files(&test_palette(), "mytheme");
+        let init = &files[2].1;
+        assert!(init.contains("function M.setup(opts)"));
+        assert!(init.contains("transparent = false"));
+        assert!(init.contains("italic_comments = true"));
+        assert!(init.contains("overrides = {}"));
+        assert!(init.contains(r#"vim.g.colors_name = "mytheme""#));
+        assert!(init.contains(r#"local c = require("mytheme.palette")"#));
+        assert!(init.contains(r#"["@variable"] = { fg = c.fg },"#));
+        assert!(init.contains("    Normal = { fg = c.fg, bg = c.bg },"));
+    }
+
+    #[test]
+    fn plugin_color_refs_are_defined_in_palette() {
+        let files = plugin_files(&test_palette(), "test");
+        let palette_lua = &files[1].1;
+
+        let key_re = regex::Regex::new(r##"^\s+(\w+)\s*=\s*"#"##).unwrap();
+        let keys: Vec<String> = palette_lua
+            .lines()
+            .filter_map(|line| key_re.captures(line).map(|cap| cap[1].to_string()))
+            .collect();
+
+        let color_ref_re = regex::Regex::new(r"c\.(\w+)").unwrap();
+        for (_, content) in &files[2..] {
+            for cap in color_ref_re.captures_iter(content) {
+                assert!(
+                    keys.contains(&cap[1].to_string()),
+                    "c.{} not defined",
+                    &cap[1]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn plugin_files_have_balanced_braces_and_parens() {
+        for (path, content) in plugin_files(&test_palette(), "test") {
+            assert_eq!(
+                content.matches('{').count(),
+                content.matches('}').count(),
+                "unbalanced braces in {}",
+                path.display()
+            );
+            assert_eq!(
+                content.matches('(').count(),
+                content.matches(')').count(),
+                "unbalanced parens in {}",
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn plugin_lualine_covers_modes() {
+        let files = plugin_files(&test_palette(), "test");
+        let lualine = &files[3].1;
+        for mode in [
+            "normal", "insert", "visual", "replace", "command", "inactive",
+        ] {
+            assert!(
+                lualine.contains(&format!("  {mode} = {{")),
+                "missing mode {mode}"
+            );
+        }
+    }
+
+    #[test]
+    fn plugin_write_to_creates_tree() {
+        let backend = NeovimPluginBackend;
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri_test_neovim_plugin_backend");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        backend.write_to(&palette, "test_theme", &dir).unwrap();
+
+        for (relative, content) in plugin_files(&palette, "test_theme") {
+            let written = std::fs::read_to_string(dir.join(&relative)).unwrap();
+            assert_eq!(written, content, "{} mismatch", relative.display());
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn plugin_install_creates_correct_path() {
+        let temp_dir = std::env::temp_dir().join("nuri_test_neovim_plugin_install");
+
+        let backend = NeovimPluginBackend;
+        let result = backend
+            .install_in(&test_palette(), "mytheme", &temp_dir)
+            .unwrap();
+
+        let expected = temp_dir.join("nvim/site/pack/nuri/start/mytheme");
+        assert_eq!(result, expected);
+        assert!(expected.join("colors/mytheme.lua").exists());
+        assert!(expected.join("lua/mytheme/init.lua").exists());
+
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    /// A stand-in Neovim on `socket` that answers `requests` requests,
+    /// sending a notification before each response. `error` is sent as the
+    /// error of every response. Returns the requests it received.
+    #[cfg(unix)]
+    fn stand_in_nvim(
+        socket: PathBuf,
+        requests: usize,
+        error: Value,
+    ) -> std::thread::JoinHandle<Vec<Vec<Value>>> {
+        let listener = std::os::unix::net::UnixListener::bind(socket).unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = Vec::new();
+            for _ in 0..requests {
+                let Value::Array(request) = msgpack::read(&mut stream).unwrap() else {
+                    panic!("request is not an array");
+                };
+                let notification = vec![Value::Int(2), "redraw".into(), Value::Array(vec![])];
+                msgpack::write(&mut stream, &Value::Array(notification)).unwrap();
+                let response = vec![Value::Int(1), request[1].clone(), error.clone(), Value::Nil];
+                msgpack::write(&mut stream, &Value::Array(response)).unwrap();
+                received.push(request);
+            }
+            received
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn broadcast_reaches_running_instances() {
+        let dir = std::env::temp_dir().join("nuri-test-nvim-broadcast");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let server = stand_in_nvim(dir.join("nvim.100.0"), 2, Value::Nil);
+        // Left behind by an exited instance: nothing listens on it
+        drop(std::os::unix::net::UnixListener::bind(dir.join("nvim.200.0")).unwrap());
+        std::fs::write(dir.join("other.sock"), "").unwrap();
+
+        let calls = [
+            (
+                "nvim_exec_lua",
+                vec!["return 1".into(), Value::Array(vec![])],
+            ),
+            ("nvim_command", vec!["colorscheme mytheme".into()]),
+        ];
+        let reloaded = broadcast(&dir, &calls).unwrap();
+        let received = server.join().unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(reloaded, 1);
+        assert_eq!(
+            received[1],
+            [
+                Value::Int(0),
+                Value::Int(2),
+                "nvim_command".into(),
+                Value::Array(vec!["colorscheme mytheme".into()])
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn broadcast_reports_neovim_errors() {
+        let dir = std::env::temp_dir().join("nuri-test-nvim-broadcast-error");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let error = Value::Array(vec![
+            Value::Int(0),
+            "Vim:E185: Cannot find color scheme 'nope'".into(),
+        ]);
+        let server = stand_in_nvim(dir.join("nvim.300.0"), 1, error);
+
+        let calls = [("nvim_command", vec!["colorscheme nope".into()])];
+        let err = broadcast(&dir, &calls).unwrap_err().to_string();
+        server.join().unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert!(err.contains("1 of 1 Neovim instance(s) failed"), "{err}");
+        assert!(err.contains("E185"), "{err}");
+    }
+
+    #[test]
+    fn finds_colorscheme_calls_in_every_form() {
+        for (line, name) in [
+            ("vim.cmd.colorscheme(\"tokyonight\")", "tokyonight"),
+            ("vim.cmd.colorscheme 'gruvbox'", "gruvbox"),
+            (
+                "vim.cmd(\"colorscheme catppuccin-mocha\")",
+                "catppuccin-mocha",
+            ),
+            ("  vim.cmd([[colorscheme kanagawa]])", "kanagawa"),
+            ("pcall(vim.cmd, 'colorscheme habamax')", "habamax"),
+        ] {
+            let init = format!("vim.opt.number = true\n{line}\n");
+            let setting = find_colorscheme(&init).unwrap();
+            assert_eq!(setting.value(&init), name, "{line}");
+            assert_eq!(
+                set_colorscheme(&init, "sunset"),
+                init.replace(name, "sunset")
+            );
+        }
+        for line in [
+            "-- vim.cmd.colorscheme(\"tokyonight\")",
+            "vim.cmd(\"colorscheme \" .. name)",
+            "vim.g.my_colorscheme = 1",
+        ] {
+            assert_eq!(find_colorscheme(line), None, "{line}");
+        }
+        assert_eq!(
+            set_colorscheme("vim.opt.number = true", "sunset"),
+            "vim.opt.number = true\nvim.cmd.colorscheme(\"sunset\")\n"
+        );
+    }
 }
@@ -0,0 +1,169 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::color::Color;
+use crate::pipeline::assign::AnsiPalette;
+
+use super::neovim::derive_surface;
+use super::{data_home, ThemeBackend};
+
+/// rofi theme backend (`.rasi` format).
+///
+/// Only the color properties of rofi's default theme are set, so the layout
+/// stays whatever the user's config already uses.
+pub struct RofiBackend;
+
+impl ThemeBackend for RofiBackend {
+    fn name(&self) -> &str {
+        "rofi"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        let p = palette;
+        let surface = derive_surface(p);
+        let mut out = String::new();
+
+        out.push_str(&format!("/* nuri: {} */\n", theme_name));
+        out.push_str("/* Generated by nuri */\n");
+        out.push('\n');
+
+        out.push_str("* {\n");
+        let colors: [(&str, Color); 8] = [
+            ("background", p.background),
+            ("foreground", p.foreground),
+            ("lightbg", surface),
+            ("lightfg", p.slots[7]),
+            ("red", p.slots[1]),
+            ("green", p.slots[2]),
+            ("blue", p.slots[4]),
+            ("selection", p.selection_bg),
+        ];
+        for (name, color) in colors {
+            push_property(&mut out, name, &color.to_hex());
+        }
+        out.push('\n');
+
+        let references = [
+            ("background-color", "@background"),
+            ("border-color", "@blue"),
+            ("separatorcolor", "@lightbg"),
+            ("normal-background", "@background"),
+            ("normal-foreground", "@foreground"),
+            ("alternate-normal-background", "@lightbg"),
+            ("alternate-normal-foreground", "@foreground"),
+            ("selected-normal-background", "@selection"),
+            ("selected-normal-foreground", "@foreground"),
+            ("active-background", "@background"),
+            ("active-foreground", "@blue"),
+            ("alternate-active-background", "@lightbg"),
+            ("alternate-active-foreground", "@blue"),
+            ("selected-active-background", "@blue"),
+            ("selected-active-foreground", "@background"),
+            ("urgent-background", "@background"),
+            ("urgent-foreground", "@red"),
+            ("alternate-urgent-background", "@lightbg"),
+            ("alternate-urgent-foreground", "@red"),
+            ("selected-urgent-background", "@red"),
+            ("selected-urgent-foreground", "@background"),
+        ];
+        for (name, value) in references {
+            push_property(&mut out, name, value);
+        }
+        out.push_str("}\n");
+
+        out
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        let dir = themes_dir()?;
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create themes directory: {}", dir.display()))?;
+
+        let path = dir.join(format!("{}.rasi", theme_name));
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ".rasi"
+    }
+}
+
+/// Append an aligned `name: value;` property line.
+fn push_property(out: &mut String, name: &str, value: &str) {
+    out.push_str(&format!("    {:<29} {};\n", format!("{name}:"), value));
+}
+
+/// Resolve the rofi user themes directory (listed by `rofi-theme-selector`).
+fn themes_dir() -> Result<PathBuf> {
+    Ok(data_home()?.join("rofi").join("themes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    #[test]
+    fn single_balanced_block() {
+        let output = RofiBackend.serialize(&test_palette(), "test");
+        assert_eq!(output.matches('{').count(), 1);
+        assert_eq!(output.matches('}').count(), 1);
+        assert!(output.contains("* {\n"));
+    }
+
+    #[test]
+    fn references_are_defined() {
+        let output = RofiBackend.serialize(&test_palette(), "test");
+        let ref_re = regex::Regex::new(r"@([a-z-]+);").unwrap();
+        for cap in ref_re.captures_iter(&output) {
+            let name = &cap[1];
+            assert!(
+                output.contains(&format!("    {name}: ")),
+                "@{name} is referenced but not defined"
+            );
+        }
+    }
+
+    #[test]
+    fn default_theme_properties_present() {
+        let palette = test_palette();
+        let output = RofiBackend.serialize(&palette, "test");
+        assert!(output.contains(&format!(
+            "    background:                   {};",
+            palette.background.to_hex()
+        )));
+        for name in [
+            "normal-background",
+            "selected-normal-background",
+            "urgent-foreground",
+            "active-foreground",
+            "border-color",
+        ] {
+            assert!(output.contains(&format!("    {name}: ")), "missing {name}");
+        }
+    }
+
+    #[test]
+    fn write_to_creates_file() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri-test-rofi-backend");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.rasi");
+
+        RofiBackend.write_to(&palette, "test", &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, RofiBackend.serialize(&palette, "test"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
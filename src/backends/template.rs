@@ -0,0 +1,670 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::color::Color;
+use crate::pipeline::assign::AnsiPalette;
+
+use super::neovim::derive_surface;
+use super::{config_home, home_dir, ThemeBackend};
+
+/// Colors a template can reference by name, in the order returned by `named_colors`.
+const COLOR_NAMES: [&str; 23] = [
+    "background",
+    "foreground",
+    "cursor",
+    "cursor_text",
+    "selection_background",
+    "selection_foreground",
+    "surface",
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "white",
+    "bright_black",
+    "bright_red",
+    "bright_green",
+    "bright_yellow",
+    "bright_blue",
+    "bright_magenta",
+    "bright_cyan",
+    "bright_white",
+];
+
+/// A user-defined theme template.
+///
+/// Templates live in `$XDG_CONFIG_HOME/nuri/templates/` and start with a
+/// header between `---` lines that declares where the theme is installed:
+///
+/// ```text
+/// ---
+/// output: rofi/themes/{{ name }}.rasi
+/// extension: .rasi
+/// ---
+/// * { background: {{ background.hex }}; accent: {{ blue | alpha(0.8) }}; }
+/// ```
+///
+/// Relative `output` paths are resolved against `$XDG_CONFIG_HOME`. If
+/// `extension` is omitted it is taken from `output`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    name: String,
+    output: Vec<Segment>,
+    extension: String,
+    body: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    Expr(Expr),
+}
+
+/// A `{{ ... }}` expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Name,
+    Mode,
+    Color {
+        color: ColorRef,
+        format: Format,
+        filters: Vec<Filter>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ColorRef {
+    /// Index into `COLOR_NAMES`.
+    Named(usize),
+    /// Index into `AnsiPalette::slots`.
+    Slot(usize),
+}
+
+/// How a color is written out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    /// `#rrggbb`, or `#rrggbbaa` when an alpha below 1 is set.
+    Hex,
+    /// `hex` without the leading `#`.
+    HexStripped,
+    /// `r, g, b` in decimal.
+    Rgb,
+    /// `r, g, b, a` with alpha in [0, 1].
+    Rgba,
+    R,
+    G,
+    B,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Filter {
+    Lighten(f32),
+    Darken(f32),
+    Saturate(f32),
+    Desaturate(f32),
+    Alpha(f32),
+}
+
+impl Template {
+    /// Load the template called `name` from the user template directory.
+    pub fn load(name: &str) -> Result<Self> {
+        Self::load_from(&templates_dir()?, name)
+    }
+
+    /// Load the template called `name` from `dir`. The file may be named
+    /// `<name>` or `<name>.<ext>`.
+    pub fn load_from(dir: &Path, name: &str) -> Result<Self> {
+        let path = find_template(dir, name)?;
+        let source = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read template {}", path.display()))?;
+        Self::parse(name, &source).with_context(|| format!("invalid template {}", path.display()))
+    }
+
+    /// Parse template source (header and body).
+    pub fn parse(name: &str, source: &str) -> Result<Self> {
+        let Some(rest) = source
+            .strip_prefix("---\n")
+            .or_else(|| source.strip_prefix("---\r\n"))
+        else {
+            bail!("missing header: a template must start with a '---' line");
+        };
+        let mut header = Vec::new();
+        let mut body = None;
+        let mut offset = 0;
+        for line in rest.split_inclusive('\n') {
+            offset += line.len();
+            if line.trim_end() == "---" {
+                body = Some(&rest[offset..]);
+                break;
+            }
+            header.push(line);
+        }
+        let Some(body) = body else {
+            bail!("unterminated header: expected a closing '---' line");
+        };
+
+        let mut output = None;
+        let mut extension = None;
+        for line in header
+            .iter()
+            .map(|l| l.trim_end())
+            .filter(|l| !l.is_empty())
+        {
+            let Some((key, value)) = line.split_once(':') else {
+                bail!("invalid header line '{line}': expected 'key: value'");
+            };
+            match key.trim() {
+                "output" => output = Some(value.trim().to_string()),
+                "extension" => extension = Some(value.trim().to_string()),
+                other => bail!("unknown header key '{other}'"),
+            }
+        }
+
+        let Some(output) = output else {
+            bail!("header is missing 'output'");
+        };
+        let extension = extension.unwrap_or_else(|| {
+            Path::new(&output)
+                .extension()
+                .map(|ext| format!(".{}", ext.to_string_lossy()))
+                .unwrap_or_default()
+        });
+
+        Ok(Self {
+            name: name.to_string(),
+            output: parse_segments(&output).context("invalid output path")?,
+            extension,
+            body: parse_segments(body)?,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Render the template body for a palette.
+    pub fn render(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        render_segments(&self.body, palette, theme_name)
+    }
+
+    /// Resolve the install path declared in the header.
+    pub fn output_path(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        let rendered = render_segments(&self.output, palette, theme_name);
+        if let Some(rest) = rendered.strip_prefix("~/") {
+            return Ok(home_dir()?.join(rest));
+        }
+        let path = PathBuf::from(rendered);
+        if path.is_absolute() {
+            Ok(path)
+        } else {
+            Ok(config_home()?.join(path))
+        }
+    }
+}
+
+/// Theme backend that renders a user `Template`.
+pub struct TemplateBackend {
+    template: Template,
+}
+
+impl TemplateBackend {
+    pub fn new(template: Template) -> Self {
+        Self { template }
+    }
+}
+
+impl ThemeBackend for TemplateBackend {
+    fn name(&self) -> &str {
+        self.template.name()
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        self.template.render(palette, theme_name)
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        let path = self.template.output_path(palette, theme_name)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create directory: {}", dir.display()))?;
+        }
+
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        &self.template.extension
+    }
+}
+
+/// Split text into literal runs and `{{ ... }}` expressions.
+fn parse_segments(source: &str) -> Result<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut rest = source;
+
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            segments.push(Segment::Text(rest[..start].to_string()));
+        }
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            let line = source[..source.len() - rest.len() + start]
+                .lines()
+                .count()
+                .max(1);
+            bail!("unterminated '{{{{' on line {line}");
+        };
+        let expr = after[..end].trim();
+        segments.push(Segment::Expr(
+            parse_expr(expr).with_context(|| format!("in '{{{{ {expr} }}}}'"))?,
+        ));
+        rest = &after[end + 2..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest.to_string()));
+    }
+
+    Ok(segments)
+}
+
+/// Parse `path[.format] [| filter(arg)]*`.
+fn parse_expr(expr: &str) -> Result<Expr> {
+    let mut parts = expr.split('|').map(str::trim);
+    let path = parts.next().unwrap_or_default();
+    let filters = parts.map(parse_filter).collect::<Result<Vec<_>>>()?;
+
+    match path {
+        "name" | "mode" if !filters.is_empty() => bail!("filters only apply to colors"),
+        "name" => return Ok(Expr::Name),
+        "mode" => return Ok(Expr::Mode),
+        _ => {}
+    }
+
+    let (color, format) = match path.strip_prefix("slots[") {
+        Some(rest) => {
+            let Some((index, rest)) = rest.split_once(']') else {
+                bail!("expected ']' after slot index");
+            };
+            let index: usize = index
+                .trim()
+                .parse()
+                .with_context(|| format!("invalid slot index '{index}'"))?;
+            if index >= 16 {
+                bail!("slot index {index} out of range (0-15)");
+            }
+            (ColorRef::Slot(index), rest.strip_prefix('.'))
+        }
+        None => {
+            let (name, format) = match path.split_once('.') {
+                Some((name, format)) => (name, Some(format)),
+                None => (path, None),
+            };
+            let Some(index) = COLOR_NAMES.iter().position(|n| *n == name) else {
+                bail!("unknown variable '{name}'");
+            };
+            (ColorRef::Named(index), format)
+        }
+    };
+
+    let format = match format {
+        None => Format::Hex,
+        Some("hex") => Format::Hex,
+        Some("hex_stripped") => Format::HexStripped,
+        Some("rgb") => Format::Rgb,
+        Some("rgba") => Format::Rgba,
+        Some("r") => Format::R,
+        Some("g") => Format::G,
+        Some("b") => Format::B,
+        Some(other) => bail!("unknown color format '{other}'"),
+    };
+    if matches!(format, Format::Rgb | Format::R | Format::G | Format::B)
+        && filters.iter().any(|f| matches!(f, Filter::Alpha(_)))
+    {
+        bail!("alpha() has no effect on this color format; use hex, hex_stripped, or rgba");
+    }
+
+    Ok(Expr::Color {
+        color,
+        format,
+        filters,
+    })
+}
+
+/// Parse a filter call such as `lighten(0.1)`.
+fn parse_filter(filter: &str) -> Result<Filter> {
+    let Some((name, arg)) = filter.strip_suffix(')').and_then(|f| f.split_once('(')) else {
+        bail!("invalid filter '{filter}': expected name(value)");
+    };
+    let amount: f32 = arg
+        .trim()
+        .parse()
+        .with_context(|| format!("invalid argument to {}: '{arg}'", name.trim()))?;
+    if !(0.0..=1.0).contains(&amount) {
+        bail!("argument to {} must be between 0 and 1", name.trim());
+    }
+
+    match name.trim() {
+        "lighten" => Ok(Filter::Lighten(amount)),
+        "darken" => Ok(Filter::Darken(amount)),
+        "saturate" => Ok(Filter::Saturate(amount)),
+        "desaturate" => Ok(Filter::Desaturate(amount)),
+        "alpha" => Ok(Filter::Alpha(amount)),
+        other => bail!("unknown filter '{other}'"),
+    }
+}
+
+fn render_segments(segments: &[Segment], palette: &AnsiPalette, theme_name: &str) -> String {
+    let colors = named_colors(palette);
+    let mut out = String::new();
+
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => out.push_str(text),
+            Segment::Expr(Expr::Name) => out.push_str(theme_name),
+            Segment::Expr(Expr::Mode) => {
+                let is_dark = palette.background.to_oklch().l < 0.5;
+                out.push_str(if is_dark { "dark" } else { "light" });
+            }
+            Segment::Expr(Expr::Color {
+                color,
+                format,
+                filters,
+            }) => {
+                let base = match *color {
+                    ColorRef::Named(index) => colors[index],
+                    ColorRef::Slot(index) => palette.slots[index],
+                };
+                let (color, alpha) = apply_filters(base, filters);
+                out.push_str(&format_color(color, alpha, *format));
+            }
+        }
+    }
+
+    out
+}
+
+/// Colors in `COLOR_NAMES` order.
+fn named_colors(palette: &AnsiPalette) -> [Color; 23] {
+    let p = palette;
+    let mut colors = [Color::new(0, 0, 0); 23];
+    colors[..7].copy_from_slice(&[
+        p.background,
+        p.foreground,
+        p.cursor_color,
+        p.cursor_text,
+        p.selection_bg,
+        p.selection_fg,
+        derive_surface(p),
+    ]);
+    colors[7..].copy_from_slice(&p.slots);
+    colors
+}
+
+/// Apply filters in order, returning the color and its alpha.
+fn apply_filters(color: Color, filters: &[Filter]) -> (Color, f32) {
+    filters
+        .iter()
+        .fold((color, 1.0), |(color, alpha), filter| match *filter {
+            Filter::Lighten(amount) => (color.adjust_lightness(amount), alpha),
+            Filter::Darken(amount) => (color.adjust_lightness(-amount), alpha),
+            Filter::Saturate(amount) => (color.adjust_chroma(amount), alpha),
+            Filter::Desaturate(amount) => (color.adjust_chroma(-amount), alpha),
+            Filter::Alpha(amount) => (color, amount),
+        })
+}
+
+fn format_color(color: Color, alpha: f32, format: Format) -> String {
+    let hex = if alpha < 1.0 {
+        format!("{}{:02x}", color.to_hex(), (alpha * 255.0).round() as u8)
+    } else {
+        color.to_hex()
+    };
+    match format {
+        Format::Hex => hex,
+        Format::HexStripped => hex.trim_start_matches('#').to_string(),
+        Format::Rgb => format!("{}, {}, {}", color.r, color.g, color.b),
+        Format::Rgba => format!("{}, {}, {}, {}", color.r, color.g, color.b, alpha),
+        Format::R => color.r.to_string(),
+        Format::G => color.g.to_string(),
+        Format::B => color.b.to_string(),
+    }
+}
+
+/// Find `<name>` or `<name>.<ext>` in `dir`.
+fn find_template(dir: &Path, name: &str) -> Result<PathBuf> {
+    if !dir.is_dir() {
+        bail!(
+            "no template named '{}': {} does not exist",
+            name,
+            dir.display()
+        );
+    }
+    let exact = dir.join(name);
+    if exact.is_file() {
+        return Ok(exact);
+    }
+
+    let entries = std::fs::read_dir(dir)
+        .with_context(|| format!("failed to read template directory {}", dir.display()))?;
+    let mut matches: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && path.file_stem().is_some_and(|stem| stem == name))
+        .collect();
+    matches.sort();
+
+    match matches.len() {
+        0 => bail!("no template named '{}' in {}", name, dir.display()),
+        1 => Ok(matches.remove(0)),
+        _ => bail!(
+            "template name '{}' is ambiguous in {}: {}",
+            name,
+            dir.display(),
+            matches
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Resolve the user template directory.
+fn templates_dir() -> Result<PathBuf> {
+    Ok(config_home()?.join("nuri").join("templates"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    fn render(body: &str) -> String {
+        let source = format!("---\noutput: /tmp/out\n---\n{body}");
+        Template::parse("test", &source)
+            .unwrap()
+            .render(&test_palette(), "my-theme")
+    }
+
+    #[test]
+    fn header_is_stripped_from_output() {
+        assert_eq!(render("plain text\n"), "plain text\n");
+    }
+
+    #[test]
+    fn crlf_header_is_accepted() {
+        let source = "---\r\noutput: /tmp/out\r\n---\r\n{{ name }}\r\n";
+        let rendered = Template::parse("test", source)
+            .unwrap()
+            .render(&test_palette(), "my-theme");
+        assert_eq!(rendered, "my-theme\r\n");
+    }
+
+    #[test]
+    fn color_formats() {
+        let palette = test_palette();
+        let red = palette.slots[1];
+
+        assert_eq!(render("{{ red }}"), red.to_hex());
+        assert_eq!(render("{{ red.hex }}"), red.to_hex());
+        assert_eq!(render("{{ red.hex_stripped }}"), &red.to_hex()[1..]);
+        assert_eq!(
+            render("{{ red.rgb }}"),
+            format!("{}, {}, {}", red.r, red.g, red.b)
+        );
+        assert_eq!(
+            render("{{red.r}}/{{red.g}}/{{red.b}}"),
+            format!("{}/{}/{}", red.r, red.g, red.b)
+        );
+    }
+
+    #[test]
+    fn named_and_slot_variables() {
+        let palette = test_palette();
+        assert_eq!(render("{{ background.hex }}"), palette.background.to_hex());
+        assert_eq!(render("{{ slots[4].hex }}"), palette.slots[4].to_hex());
+        assert_eq!(
+            render("{{ slots[12].hex_stripped }}"),
+            &palette.slots[12].to_hex()[1..]
+        );
+        assert_eq!(render("{{ surface }}"), derive_surface(&palette).to_hex());
+        assert_eq!(render("{{ bright_white }}"), palette.slots[15].to_hex());
+        assert_eq!(render("{{ name }} is {{ mode }}"), "my-theme is dark");
+    }
+
+    #[test]
+    fn filters_apply_in_order() {
+        let palette = test_palette();
+        let blue = palette.slots[4];
+
+        assert_eq!(
+            render("{{ blue | lighten(0.1) }}"),
+            blue.adjust_lightness(0.1).to_hex()
+        );
+        assert_eq!(
+            render("{{ blue | darken(0.1) | desaturate(0.05) }}"),
+            blue.adjust_lightness(-0.1).adjust_chroma(-0.05).to_hex()
+        );
+        assert_eq!(
+            render("{{ blue | alpha(0.8) }}"),
+            format!("{}cc", blue.to_hex())
+        );
+        assert_eq!(
+            render("{{ blue.rgba | alpha(0.5) }}"),
+            format!("{}, {}, {}, 0.5", blue.r, blue.g, blue.b)
+        );
+    }
+
+    #[test]
+    fn extension_defaults_to_output_extension() {
+        let template =
+            Template::parse("rofi", "---\noutput: rofi/themes/{{ name }}.rasi\n---\n").unwrap();
+        assert_eq!(TemplateBackend::new(template.clone()).extension(), ".rasi");
+
+        let explicit =
+            Template::parse("x", "---\noutput: x/{{ name }}\nextension: .conf\n---\n").unwrap();
+        assert_eq!(TemplateBackend::new(explicit).extension(), ".conf");
+    }
+
+    #[test]
+    fn output_path_renders_variables() {
+        let template =
+            Template::parse("rofi", "---\noutput: /tmp/rofi/{{ name }}.rasi\n---\n").unwrap();
+        let path = template.output_path(&test_palette(), "sunset").unwrap();
+        assert_eq!(path, PathBuf::from("/tmp/rofi/sunset.rasi"));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let cases = [
+            ("no header", "missing header"),
+            ("---\noutput: x\n", "unterminated header"),
+            ("---\nextension: .x\n---\n", "missing 'output'"),
+            ("---\noutput: x\ncolor: red\n---\n", "unknown header key"),
+            ("---\noutput: x\n---\n{{ red", "unterminated"),
+            (
+                "---\noutput: x\n---\n{{ purple }}",
+                "unknown variable 'purple'",
+            ),
+            ("---\noutput: x\n---\n{{ red.hsl }}", "unknown color format"),
+            ("---\noutput: x\n---\n{{ slots[16] }}", "out of range"),
+            (
+                "---\noutput: x\n---\n{{ red | blur(0.1) }}",
+                "unknown filter",
+            ),
+            (
+                "---\noutput: x\n---\n{{ red | alpha(2) }}",
+                "between 0 and 1",
+            ),
+            (
+                "---\noutput: x\n---\n{{ name | alpha(0.5) }}",
+                "only apply to colors",
+            ),
+            (
+                "---\noutput: x\n---\n{{ red.rgb | alpha(0.5) }}",
+                "alpha() has no effect",
+            ),
+            (
+                "---\noutput: x\n---\n{{ red.g | alpha(0.5) }}",
+                "alpha() has no effect",
+            ),
+        ];
+        for (source, expected) in cases {
+            let err = format!("{:#}", Template::parse("t", source).unwrap_err());
+            assert!(
+                err.contains(expected),
+                "expected '{expected}' in error for {source:?}, got '{err}'"
+            );
+        }
+    }
+
+    #[test]
+    fn load_from_matches_file_stem() {
+        let dir = std::env::temp_dir().join("nuri-test-template-load");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("rofi.rasi"),
+            "---\noutput: rofi/{{ name }}.rasi\n---\nbg: {{ background }};\n",
+        )
+        .unwrap();
+
+        let template = Template::load_from(&dir, "rofi").unwrap();
+        assert_eq!(template.name(), "rofi");
+        assert!(Template::load_from(&dir, "dunst").is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn install_writes_to_declared_path() {
+        let dir = std::env::temp_dir().join("nuri-test-template-install");
+        let _ = std::fs::remove_dir_all(&dir);
+        let source = format!(
+            "---\noutput: {}/themes/{{{{ name }}}}.rasi\n---\nfg: {{{{ foreground }}}};\n",
+            dir.display()
+        );
+        let backend = TemplateBackend::new(Template::parse("rofi", &source).unwrap());
+        let palette = test_palette();
+
+        let path = backend.install(&palette, "sunset").unwrap();
+        assert_eq!(path, dir.join("themes").join("sunset.rasi"));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("fg: {};\n", palette.foreground.to_hex())
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
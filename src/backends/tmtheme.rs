@@ -0,0 +1,296 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::color::Color;
+use crate::pipeline::assign::AnsiPalette;
+
+use super::neovim::{color_entries, derive_surface, resolve_group, Highlight};
+use super::{config_home, ThemeBackend};
+
+/// TextMate `.tmTheme` backend (plist XML), used by `bat` and `delta`.
+pub struct TmThemeBackend;
+
+/// TextMate scopes and the Neovim syntax group each one takes its style from,
+/// so `bat` highlights code the same way the Neovim colorscheme does.
+const SCOPES: &[(&str, &str)] = &[
+    ("comment, punctuation.definition.comment", "Comment"),
+    ("string", "String"),
+    ("constant.character", "Character"),
+    ("constant.character.escape", "SpecialChar"),
+    ("constant.numeric", "Number"),
+    ("constant.language", "Boolean"),
+    ("constant.other, variable.other.constant", "Constant"),
+    ("variable", "Identifier"),
+    (
+        "entity.name.function, support.function, meta.function-call",
+        "Function",
+    ),
+    ("keyword", "Keyword"),
+    ("keyword.control", "Conditional"),
+    ("keyword.operator", "Operator"),
+    (
+        "keyword.control.import, keyword.control.directive",
+        "Include",
+    ),
+    ("meta.preprocessor", "PreProc"),
+    (
+        "entity.name.function.macro, support.function.macro",
+        "Macro",
+    ),
+    ("storage.type, entity.name.type, support.type", "Type"),
+    ("storage.modifier", "StorageClass"),
+    (
+        "entity.name.struct, entity.name.enum, entity.name.union",
+        "Structure",
+    ),
+    ("support.constant, entity.name.tag", "Special"),
+    ("punctuation", "Delimiter"),
+    ("comment.block.documentation", "SpecialComment"),
+    ("markup.underline.link", "Underlined"),
+    ("invalid", "Error"),
+];
+
+impl ThemeBackend for TmThemeBackend {
+    fn name(&self) -> &str {
+        "tmTheme"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        let p = palette;
+        let surface = derive_surface(p);
+        let colors = color_entries(p, &surface);
+        let mut out = String::new();
+
+        out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n");
+        out.push_str("<!-- Generated by nuri -->\n");
+        out.push_str("<plist version=\"1.0\">\n");
+        out.push_str("<dict>\n");
+        push_entry(&mut out, 1, "name", &escape(theme_name));
+        out.push_str("\t<key>settings</key>\n");
+        out.push_str("\t<array>\n");
+
+        // Global settings
+        out.push_str("\t\t<dict>\n");
+        out.push_str("\t\t\t<key>settings</key>\n");
+        out.push_str("\t\t\t<dict>\n");
+        let globals = [
+            ("background", p.background),
+            ("foreground", p.foreground),
+            ("caret", p.cursor_color),
+            ("selection", p.selection_bg),
+            ("selectionForeground", p.selection_fg),
+            ("lineHighlight", surface),
+            ("gutter", p.background),
+            ("gutterForeground", p.slots[8]),
+            ("findHighlight", p.slots[3]),
+            ("invisibles", p.slots[8]),
+        ];
+        for (key, color) in globals {
+            push_entry(&mut out, 4, key, &color.to_hex());
+        }
+        out.push_str("\t\t\t</dict>\n");
+        out.push_str("\t\t</dict>\n");
+
+        // Syntax scopes
+        for (scope, group) in SCOPES {
+            if let Some(highlight) = resolve_group(group) {
+                push_rule(&mut out, group, scope, &highlight, &colors);
+            }
+        }
+
+        // Diffs, for bat's diff syntax and delta
+        let diffs = [
+            ("Inserted", "markup.inserted", p.slots[2]),
+            ("Deleted", "markup.deleted", p.slots[1]),
+            ("Changed", "markup.changed", p.slots[3]),
+        ];
+        for (name, scope, color) in diffs {
+            out.push_str("\t\t<dict>\n");
+            push_entry(&mut out, 3, "name", name);
+            push_entry(&mut out, 3, "scope", scope);
+            out.push_str("\t\t\t<key>settings</key>\n");
+            out.push_str("\t\t\t<dict>\n");
+            push_entry(&mut out, 4, "foreground", &color.to_hex());
+            out.push_str("\t\t\t</dict>\n");
+            out.push_str("\t\t</dict>\n");
+        }
+
+        out.push_str("\t</array>\n");
+        out.push_str("</dict>\n");
+        out.push_str("</plist>\n");
+
+        out
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        let dir = themes_dir()?;
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create themes directory: {}", dir.display()))?;
+
+        let path = dir.join(format!("{}.tmTheme", theme_name));
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ".tmTheme"
+    }
+}
+
+/// Append a `<key>`/`<string>` pair at the given tab depth.
+fn push_entry(out: &mut String, depth: usize, key: &str, value: &str) {
+    let indent = "\t".repeat(depth);
+    out.push_str(&format!("{indent}<key>{key}</key>\n"));
+    out.push_str(&format!("{indent}<string>{value}</string>\n"));
+}
+
+/// Append a scope rule for one highlight group.
+fn push_rule(
+    out: &mut String,
+    group: &str,
+    scope: &str,
+    highlight: &Highlight,
+    colors: &[(&str, Color)],
+) {
+    let lookup = |key: &str| {
+        colors
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, color)| *color)
+    };
+
+    out.push_str("\t\t<dict>\n");
+    push_entry(out, 3, "name", group);
+    push_entry(out, 3, "scope", scope);
+    out.push_str("\t\t\t<key>settings</key>\n");
+    out.push_str("\t\t\t<dict>\n");
+    if let Some(fg) = highlight.fg.and_then(lookup) {
+        push_entry(out, 4, "foreground", &fg.to_hex());
+    }
+    if let Some(bg) = highlight.bg.and_then(lookup) {
+        push_entry(out, 4, "background", &bg.to_hex());
+    }
+    let mut styles = Vec::new();
+    if highlight.bold {
+        styles.push("bold");
+    }
+    if highlight.italic {
+        styles.push("italic");
+    }
+    if highlight.underline || highlight.undercurl {
+        styles.push("underline");
+    }
+    if !styles.is_empty() {
+        push_entry(out, 4, "fontStyle", &styles.join(" "));
+    }
+    out.push_str("\t\t\t</dict>\n");
+    out.push_str("\t\t</dict>\n");
+}
+
+/// Escape text for an XML `<string>`.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Resolve the bat themes directory (run `bat cache --build` after installing).
+fn themes_dir() -> Result<PathBuf> {
+    Ok(config_home()?.join("bat").join("themes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    /// Return the settings block of the rule with the given name.
+    fn rule<'a>(output: &'a str, name: &str) -> &'a str {
+        let marker = format!("<key>name</key>\n\t\t\t<string>{name}</string>");
+        let start = output.find(&marker).unwrap();
+        let end = start + output[start..].find("\t\t</dict>").unwrap();
+        &output[start..end]
+    }
+
+    #[test]
+    fn plist_is_well_formed() {
+        let output = TmThemeBackend.serialize(&test_palette(), "test");
+        assert!(output.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+        assert!(output.ends_with("</plist>\n"));
+        for tag in ["dict", "array", "key", "string"] {
+            assert_eq!(
+                output.matches(&format!("<{tag}>")).count(),
+                output.matches(&format!("</{tag}>")).count(),
+                "unbalanced <{tag}>"
+            );
+        }
+    }
+
+    #[test]
+    fn scopes_follow_neovim_syntax_groups() {
+        let palette = test_palette();
+        let output = TmThemeBackend.serialize(&palette, "test");
+
+        let string = rule(&output, "String");
+        assert!(string.contains("<string>string</string>"));
+        assert!(string.contains(&palette.slots[2].to_hex()));
+
+        let function = rule(&output, "Function");
+        assert!(function.contains(&palette.slots[4].to_hex()));
+
+        let comment = rule(&output, "Comment");
+        assert!(comment.contains(&palette.slots[8].to_hex()));
+        assert!(comment.contains("<key>fontStyle</key>\n\t\t\t\t<string>italic</string>"));
+    }
+
+    #[test]
+    fn every_scope_group_is_a_syntax_group() {
+        for (_, group) in SCOPES {
+            assert!(resolve_group(group).is_some(), "unknown group: {group}");
+        }
+    }
+
+    #[test]
+    fn global_settings_use_palette() {
+        let palette = test_palette();
+        let output = TmThemeBackend.serialize(&palette, "test");
+        assert!(output.contains(&format!(
+            "<key>background</key>\n\t\t\t\t<string>{}</string>",
+            palette.background.to_hex()
+        )));
+        assert!(output.contains(&format!(
+            "<key>selection</key>\n\t\t\t\t<string>{}</string>",
+            palette.selection_bg.to_hex()
+        )));
+    }
+
+    #[test]
+    fn theme_name_is_escaped() {
+        let output = TmThemeBackend.serialize(&test_palette(), "a<b>&c");
+        assert!(output.contains("<string>a&lt;b&gt;&amp;c</string>"));
+    }
+
+    #[test]
+    fn write_to_creates_file() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri-test-tmtheme-backend");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.tmTheme");
+
+        TmThemeBackend.write_to(&palette, "test", &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, TmThemeBackend.serialize(&palette, "test"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
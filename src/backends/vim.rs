@@ -0,0 +1,301 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::color::Color;
+use crate::pipeline::assign::AnsiPalette;
+
+use super::neovim::{
+    color_entries, derive_surface, sanitize_name, Highlight, DIAGNOSTIC_GROUPS, EDITOR_GROUPS,
+    SYNTAX_GROUPS,
+};
+use super::{home_dir, ThemeBackend};
+
+/// Classic Vim colorscheme backend (Vimscript format).
+///
+/// Mirrors the editor, diagnostic, and syntax groups of the Neovim backend with
+/// `gui*` colors for `termguicolors` and nearest xterm-256 `cterm*` fallbacks.
+pub struct VimBackend;
+
+impl ThemeBackend for VimBackend {
+    fn name(&self) -> &str {
+        "Vim"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        let safe_name = sanitize_name(theme_name);
+        let surface = derive_surface(palette);
+        let colors = color_entries(palette, &surface);
+        let is_dark = palette.background.to_oklch().l < 0.5;
+
+        let mut out = String::new();
+
+        // Header
+        out.push_str(&format!("\" nuri: {}\n", safe_name));
+        out.push_str("\" Generated by nuri\n");
+        out.push('\n');
+
+        // Boilerplate
+        out.push_str(&format!(
+            "set background={}\n",
+            if is_dark { "dark" } else { "light" }
+        ));
+        out.push_str("hi clear\n");
+        out.push_str("if exists(\"syntax_on\")\n");
+        out.push_str("  syntax reset\n");
+        out.push_str("endif\n");
+        out.push_str(&format!("let g:colors_name = \"{}\"\n", safe_name));
+        out.push('\n');
+
+        // Terminal colors for :terminal
+        out.push_str("let g:terminal_ansi_colors = [\n");
+        for chunk in palette.slots.chunks(4) {
+            let quoted: Vec<String> = chunk.iter().map(|c| format!("'{}'", c.to_hex())).collect();
+            out.push_str(&format!("      \\ {},\n", quoted.join(", ")));
+        }
+        out.push_str("      \\ ]\n");
+        out.push('\n');
+
+        let sections = [
+            ("Editor UI", EDITOR_GROUPS),
+            ("Diagnostics", DIAGNOSTIC_GROUPS),
+            ("Syntax", SYNTAX_GROUPS),
+        ];
+        for (i, (title, groups)) in sections.iter().enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            out.push_str(&format!("\" {}\n", title));
+            for (name, highlight) in *groups {
+                out.push_str(&hi_command(name, highlight, &colors));
+                out.push('\n');
+            }
+        }
+
+        out
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        self.install_in(palette, theme_name, &home_dir()?)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ".vim"
+    }
+}
+
+/// Build a `hi` (or `hi! link`) command for one group.
+fn hi_command(name: &str, highlight: &Highlight, colors: &[(&str, Color)]) -> String {
+    if let Some(target) = highlight.link {
+        return format!("hi! link {} {}", name, target);
+    }
+
+    let lookup = |key: &str| {
+        colors
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, color)| *color)
+    };
+
+    let mut parts = vec![format!("hi {}", name)];
+    if let Some(fg) = highlight.fg.and_then(lookup) {
+        parts.push(format!("guifg={}", fg.to_hex()));
+    }
+    if let Some(bg) = highlight.bg.and_then(lookup) {
+        parts.push(format!("guibg={}", bg.to_hex()));
+    }
+    if let Some(sp) = highlight.sp.and_then(lookup) {
+        parts.push(format!("guisp={}", sp.to_hex()));
+    }
+    parts.push(format!("gui={}", attributes(highlight, false)));
+    if let Some(fg) = highlight.fg.and_then(lookup) {
+        parts.push(format!("ctermfg={}", fg.to_ansi256()));
+    }
+    if let Some(bg) = highlight.bg.and_then(lookup) {
+        parts.push(format!("ctermbg={}", bg.to_ansi256()));
+    }
+    parts.push(format!("cterm={}", attributes(highlight, true)));
+    parts.join(" ")
+}
+
+/// Attribute list for `gui=`/`cterm=`. Undercurl degrades to underline in
+/// terminals, where Vim 8 often cannot draw it.
+fn attributes(highlight: &Highlight, cterm: bool) -> String {
+    let mut attrs = Vec::new();
+    if highlight.bold {
+        attrs.push("bold");
+    }
+    if highlight.italic {
+        attrs.push("italic");
+    }
+    if highlight.underline || (cterm && highlight.undercurl) {
+        attrs.push("underline");
+    }
+    if highlight.undercurl && !cterm {
+        attrs.push("undercurl");
+    }
+    if attrs.is_empty() {
+        "NONE".to_string()
+    } else {
+        attrs.join(",")
+    }
+}
+
+impl VimBackend {
+    /// Install into `home/.vim/colors`, creating it if needed.
+    fn install_in(&self, palette: &AnsiPalette, theme_name: &str, home: &Path) -> Result<PathBuf> {
+        let safe_name = sanitize_name(theme_name);
+        let dir = home.join(".vim").join("colors");
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create colors directory: {}", dir.display()))?;
+
+        let path = dir.join(format!("{}.vim", safe_name));
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    #[test]
+    fn output_starts_with_header() {
+        let output = VimBackend.serialize(&test_palette(), "test");
+        assert!(output.starts_with("\" nuri: test\n"));
+    }
+
+    #[test]
+    fn colors_name_and_background_are_set() {
+        let output = VimBackend.serialize(&test_palette(), "my_theme");
+        assert!(output.contains("let g:colors_name = \"my_theme\""));
+        assert!(output.contains("set background=dark"));
+    }
+
+    #[test]
+    fn terminal_ansi_colors_has_16_entries() {
+        let palette = test_palette();
+        let output = VimBackend.serialize(&palette, "test");
+        let start = output.find("let g:terminal_ansi_colors").unwrap();
+        let end = start + output[start..].find("\\ ]").unwrap();
+        let block = &output[start..end];
+
+        let hex_re = regex::Regex::new(r"'#[0-9a-f]{6}'").unwrap();
+        let entries: Vec<&str> = hex_re.find_iter(block).map(|m| m.as_str()).collect();
+        assert_eq!(entries.len(), 16);
+        assert_eq!(entries[1], format!("'{}'", palette.slots[1].to_hex()));
+    }
+
+    #[test]
+    fn all_neovim_groups_present() {
+        let output = VimBackend.serialize(&test_palette(), "test");
+        for (name, _) in EDITOR_GROUPS
+            .iter()
+            .chain(DIAGNOSTIC_GROUPS)
+            .chain(SYNTAX_GROUPS)
+        {
+            assert!(
+                output.contains(&format!("hi {name} ")),
+                "missing highlight group: {name}"
+            );
+        }
+    }
+
+    #[test]
+    fn gui_colors_have_cterm_fallbacks() {
+        let output = VimBackend.serialize(&test_palette(), "test");
+        let cterm_re = regex::Regex::new(r"cterm(fg|bg)=(\d+)").unwrap();
+        for line in output.lines().filter(|l| l.starts_with("hi ")) {
+            assert_eq!(
+                line.contains("guifg="),
+                line.contains("ctermfg="),
+                "fg fallback mismatch: {line}"
+            );
+            assert_eq!(
+                line.contains("guibg="),
+                line.contains("ctermbg="),
+                "bg fallback mismatch: {line}"
+            );
+            for cap in cterm_re.captures_iter(line) {
+                let index: u16 = cap[2].parse().unwrap();
+                assert!((16..=255).contains(&index), "bad cterm index: {line}");
+            }
+        }
+    }
+
+    #[test]
+    fn normal_uses_palette_colors() {
+        let palette = test_palette();
+        let output = VimBackend.serialize(&palette, "test");
+        let expected = format!(
+            "hi Normal guifg={} guibg={} gui=NONE ctermfg={} ctermbg={} cterm=NONE",
+            palette.foreground.to_hex(),
+            palette.background.to_hex(),
+            palette.foreground.to_ansi256(),
+            palette.background.to_ansi256()
+        );
+        assert!(
+            output.contains(&expected),
+            "Normal line missing: {expected}"
+        );
+    }
+
+    #[test]
+    fn undercurl_degrades_to_underline_in_cterm() {
+        let output = VimBackend.serialize(&test_palette(), "test");
+        let line = output
+            .lines()
+            .find(|l| l.starts_with("hi DiagnosticUnderlineError "))
+            .unwrap();
+        assert!(line.contains("gui=undercurl"));
+        assert!(line.contains("guisp=#"));
+        assert!(line.contains("cterm=underline"));
+    }
+
+    #[test]
+    fn comment_attributes_combined() {
+        let output = VimBackend.serialize(&test_palette(), "test");
+        let line = output.lines().find(|l| l.starts_with("hi Todo ")).unwrap();
+        assert!(line.contains("gui=bold,italic"));
+        assert!(line.contains("cterm=bold,italic"));
+    }
+
+    #[test]
+    fn write_to_creates_file() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri_test_vim_backend");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test_theme.vim");
+
+        VimBackend.write_to(&palette, "test_theme", &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, VimBackend.serialize(&palette, "test_theme"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn install_creates_correct_path() {
+        let temp_dir = std::env::temp_dir().join("nuri_test_vim_install");
+
+        let palette = test_palette();
+        let result = VimBackend
+            .install_in(&palette, "mytheme", &temp_dir)
+            .unwrap();
+
+        let expected_path = temp_dir.join(".vim").join("colors").join("mytheme.vim");
+        assert_eq!(result, expected_path);
+        assert!(expected_path.exists());
+
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+    }
+}
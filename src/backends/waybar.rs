@@ -0,0 +1,113 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::pipeline::assign::AnsiPalette;
+
+use super::neovim::{color_entries, derive_surface};
+use super::{config_home, ThemeBackend};
+
+/// Waybar `colors.css` backend, meant to be `@import`ed from `style.css`.
+///
+/// There is no Display P3 output: Waybar styles with GTK 3, whose CSS parser
+/// rejects `color(display-p3 …)` values, so `--gamut display-p3` falls back
+/// to sRGB here.
+pub struct WaybarBackend;
+
+impl ThemeBackend for WaybarBackend {
+    fn name(&self) -> &str {
+        "Waybar"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        let surface = derive_surface(palette);
+        let mut out = String::new();
+
+        out.push_str(&format!("/* nuri: {} */\n", theme_name));
+        out.push_str("/* Generated by nuri */\n");
+        out.push('\n');
+
+        for (name, color) in color_entries(palette, &surface) {
+            out.push_str(&format!("@define-color {} {};\n", name, color.to_hex()));
+        }
+
+        out
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        let dir = waybar_dir()?;
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create Waybar directory: {}", dir.display()))?;
+
+        let path = dir.join("colors.css");
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ".css"
+    }
+}
+
+/// Resolve the Waybar config directory.
+fn waybar_dir() -> Result<PathBuf> {
+    Ok(config_home()?.join("waybar"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    #[test]
+    fn defines_all_named_colors() {
+        let output = WaybarBackend.serialize(&test_palette(), "test");
+        let count = output
+            .lines()
+            .filter(|l| l.starts_with("@define-color "))
+            .count();
+        assert_eq!(count, 21);
+        for name in ["bg", "fg", "red", "bright_white", "surface"] {
+            assert!(
+                output.contains(&format!("@define-color {name} #")),
+                "missing {name}"
+            );
+        }
+    }
+
+    #[test]
+    fn colors_match_palette() {
+        let palette = test_palette();
+        let output = WaybarBackend.serialize(&palette, "test");
+        assert!(output.contains(&format!(
+            "@define-color bg {};\n",
+            palette.background.to_hex()
+        )));
+        assert!(output.contains(&format!(
+            "@define-color blue {};\n",
+            palette.slots[4].to_hex()
+        )));
+    }
+
+    #[test]
+    fn write_to_creates_file() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri-test-waybar-backend");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("colors.css");
+
+        WaybarBackend.write_to(&palette, "test", &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, WaybarBackend.serialize(&palette, "test"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,127 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::pipeline::assign::AnsiPalette;
+
+use super::{home_dir, ThemeBackend};
+
+/// X resources backend for xterm, urxvt, and st.
+pub struct XresourcesBackend;
+
+impl ThemeBackend for XresourcesBackend {
+    fn name(&self) -> &str {
+        "Xresources"
+    }
+
+    fn serialize(&self, palette: &AnsiPalette, theme_name: &str) -> String {
+        let p = palette;
+        let mut out = String::new();
+
+        out.push_str(&format!("! nuri: {}\n", theme_name));
+        out.push_str("! Generated by nuri\n");
+        out.push('\n');
+
+        out.push_str(&format!("*.foreground: {}\n", p.foreground.to_hex()));
+        out.push_str(&format!("*.background: {}\n", p.background.to_hex()));
+        out.push_str(&format!("*.cursorColor: {}\n", p.cursor_color.to_hex()));
+        out.push('\n');
+
+        for (i, color) in p.slots.iter().enumerate() {
+            out.push_str(&format!("*.color{}: {}\n", i, color.to_hex()));
+        }
+
+        out
+    }
+
+    fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
+        let dir = resources_dir()?;
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create resources directory: {}", dir.display()))?;
+
+        let path = dir.join(format!("{}.Xresources", theme_name));
+        self.write_to(palette, theme_name, &path)?;
+        Ok(path)
+    }
+
+    fn write_to(&self, palette: &AnsiPalette, theme_name: &str, path: &Path) -> Result<()> {
+        let content = self.serialize(palette, theme_name);
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write theme to {}", path.display()))?;
+        Ok(())
+    }
+
+    fn extension(&self) -> &str {
+        ".Xresources"
+    }
+}
+
+/// Resolve the directory for X resource fragments, meant to be `#include`d
+/// from `~/.Xresources`.
+fn resources_dir() -> Result<PathBuf> {
+    Ok(home_dir()?.join(".Xresources.d"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    #[test]
+    fn special_colors_present() {
+        let palette = test_palette();
+        let output = XresourcesBackend.serialize(&palette, "test");
+        assert!(output.contains(&format!("*.foreground: {}\n", palette.foreground.to_hex())));
+        assert!(output.contains(&format!("*.background: {}\n", palette.background.to_hex())));
+        assert!(output.contains(&format!(
+            "*.cursorColor: {}\n",
+            palette.cursor_color.to_hex()
+        )));
+    }
+
+    #[test]
+    fn all_16_colors_in_order() {
+        let palette = test_palette();
+        let output = XresourcesBackend.serialize(&palette, "test");
+        let color_lines: Vec<&str> = output
+            .lines()
+            .filter(|l| l.starts_with("*.color"))
+            .collect();
+
+        assert_eq!(color_lines.len(), 16);
+        for (i, line) in color_lines.iter().enumerate() {
+            assert_eq!(
+                *line,
+                format!("*.color{}: {}", i, palette.slots[i].to_hex())
+            );
+        }
+    }
+
+    #[test]
+    fn comments_use_bang() {
+        let output = XresourcesBackend.serialize(&test_palette(), "test");
+        for line in output.lines().filter(|l| !l.is_empty()) {
+            assert!(
+                line.starts_with('!') || line.starts_with("*."),
+                "unexpected line: '{line}'"
+            );
+        }
+    }
+
+    #[test]
+    fn write_to_creates_file() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri-test-xresources-backend");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test-theme.Xresources");
+
+        XresourcesBackend
+            .write_to(&palette, "test-theme", &path)
+            .unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, XresourcesBackend.serialize(&palette, "test-theme"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
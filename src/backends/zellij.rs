@@ -1,12 +1,14 @@
 use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use palette::Oklch;
 
 use crate::color::Color;
-use crate::pipeline::assign::AnsiPalette;
+use crate::pipeline::assign::{complete_palette, AnsiPalette};
+use crate::pipeline::contrast::{enforce_contrast, DEFAULT_ACCENT_CONTRAST};
 
-use super::ThemeBackend;
+use super::{append_line, config_home, lines_with_offsets, ThemeBackend, ThemeSetting};
 
 /// Zellij terminal multiplexer theme backend (KDL format).
 pub struct ZellijBackend;
@@ -54,6 +56,64 @@ impl ThemeBackend for ZellijBackend {
         out
     }
 
+    /// Read the first theme in a Zellij `themes { ... }` block.
+    ///
+    /// Zellij only stores the eight base colors, so the bright row and the
+    /// cursor and selection colors are derived the way nuri generates them,
+    /// contrast enforcement included. The stored colors are kept as written.
+    fn parse(&self, content: &str) -> Result<AnsiPalette> {
+        let entries = theme_entries(content)?;
+        let get = |key: &str| {
+            entries
+                .iter()
+                .find(|e| e.key == key)
+                .map(|e| e.color)
+                .with_context(|| format!("missing '{key}' in Zellij theme"))
+        };
+        let mut base = [Color::new(0, 0, 0); 8];
+        for (slot, name) in base.iter_mut().zip(BASE_NAMES) {
+            *slot = get(name)?;
+        }
+        let stored = complete_palette(base, get("bg")?, get("fg")?);
+        let mut palette = stored.clone();
+        enforce_contrast(&mut palette, DEFAULT_ACCENT_CONTRAST);
+        palette.slots[..8].copy_from_slice(&stored.slots[..8]);
+        palette.slots[15] = stored.slots[15];
+        palette.foreground = stored.foreground;
+        palette.cursor_color = stored.cursor_color;
+        palette.selection_fg = stored.selection_fg;
+        Ok(palette)
+    }
+
+    /// Rewrite the changed colors of the first theme, keeping everything else
+    /// (other themes, comments, `orange`) as it was.
+    fn update(&self, content: &str, palette: &AnsiPalette, _theme_name: &str) -> Result<String> {
+        let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+        for entry in theme_entries(content)? {
+            let color = match entry.key.as_str() {
+                "fg" => palette.foreground,
+                "bg" => palette.background,
+                key => match BASE_NAMES.iter().position(|name| *name == key) {
+                    Some(slot) => palette.slots[slot],
+                    None => continue,
+                },
+            };
+            if color == entry.color {
+                continue;
+            }
+            let line = &lines[entry.line];
+            let indent = &line[..line.len() - line.trim_start().len()];
+            let comment = line.find("//").map(|i| &line[i..]).unwrap_or("");
+            let mut updated = format!("{indent}{} \"{}\"", entry.key, color.to_hex());
+            if !comment.is_empty() {
+                updated.push(' ');
+                updated.push_str(comment);
+            }
+            lines[entry.line] = updated;
+        }
+        Ok(lines.iter().map(|l| format!("{l}\n")).collect())
+    }
+
     fn install(&self, palette: &AnsiPalette, theme_name: &str) -> Result<PathBuf> {
         let dir = themes_dir()?;
         std::fs::create_dir_all(&dir)
@@ -74,11 +134,81 @@ impl ThemeBackend for ZellijBackend {
     fn extension(&self) -> &str {
         ".kdl"
     }
+
+    /// Switch to the theme with `zellij options --theme`.
+    fn reload(&self, theme_name: &str, _path: &Path) -> Result<Option<String>> {
+        let output = Command::new("zellij")
+            .args(["options", "--theme", theme_name])
+            .stdin(Stdio::null())
+            .output()
+            .context("failed to run zellij")?;
+        if !output.status.success() {
+            bail!(
+                "zellij options --theme failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(Some(format!("ran zellij options --theme {theme_name}")))
+    }
+}
+
+/// Zellij color keys for ANSI slots 0-7.
+const BASE_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// A color entry in a Zellij theme file.
+struct Entry {
+    /// Zero-based line number.
+    line: usize,
+    key: String,
+    color: Color,
+}
+
+/// Read the color entries of the first theme in a `themes { ... }` block.
+/// Colors may be hex strings or `r g b` triples.
+fn theme_entries(content: &str) -> Result<Vec<Entry>> {
+    let mut depth = 0;
+    let mut entries = Vec::new();
+    for (n, line) in content.lines().enumerate() {
+        let line = line.split("//").next().unwrap_or("").trim();
+        if line.ends_with('{') {
+            depth += 1;
+            continue;
+        }
+        if line == "}" {
+            depth -= 1;
+            // Only the first theme is read
+            if depth == 1 && !entries.is_empty() {
+                break;
+            }
+            continue;
+        }
+        if depth != 2 || line.is_empty() {
+            continue;
+        }
+
+        let mut words = line.split_whitespace();
+        let key = words.next().unwrap_or_default();
+        let values: Vec<&str> = words.map(|w| w.trim_matches('"')).collect();
+        let color = match values.as_slice() {
+            [hex] => Color::from_hex(hex),
+            [r, g, b] => (|| Ok(Color::new(r.parse()?, g.parse()?, b.parse()?)))(),
+            _ => bail!("line {}: expected a color for '{key}'", n + 1),
+        }
+        .with_context(|| format!("line {}: invalid color for '{key}'", n + 1))?;
+        entries.push(Entry {
+            line: n,
+            key: key.to_string(),
+            color,
+        });
+    }
+    Ok(entries)
 }
 
 /// Derive the Zellij-specific "orange" color by interpolating between
 /// slot 1 (red) and slot 3 (yellow) in Oklch space, targeting hue ~55°.
-fn derive_orange(palette: &AnsiPalette) -> Color {
+pub fn derive_orange(palette: &AnsiPalette) -> Color {
     let red = palette.slots[1].to_oklch();
     let yellow = palette.slots[3].to_oklch();
 
@@ -89,45 +219,59 @@ fn derive_orange(palette: &AnsiPalette) -> Color {
     Color::from_oklch(Oklch::new(l, chroma, hue))
 }
 
+/// Select `theme_name` in a Zellij `config.kdl`, keeping everything else.
+/// Without a top-level `theme` node, one is appended.
+pub fn set_config_theme(config: &str, theme_name: &str) -> String {
+    match find_config_theme(config) {
+        Some(setting) => setting.replace(config, theme_name),
+        None => append_line(config, &format!("theme \"{theme_name}\"")),
+    }
+}
+
+/// The last top-level `theme "…"` node of a `config.kdl`. Commented-out
+/// nodes and nodes inside blocks are skipped.
+pub fn find_config_theme(config: &str) -> Option<ThemeSetting> {
+    let mut depth = 0usize;
+    let mut found = None;
+    for (start, line) in lines_with_offsets(config) {
+        let code = line.split("//").next().unwrap_or_default();
+        if depth == 0 {
+            let rest = code.trim_start().strip_prefix("theme").unwrap_or_default();
+            let quoted = rest.trim_start();
+            if quoted.len() < rest.len() && quoted.starts_with('"') {
+                if let Some(len) = quoted[1..].find('"') {
+                    let name_start = start + code.len() - quoted.len() + 1;
+                    found = Some(ThemeSetting {
+                        line: start..start + line.len(),
+                        name: name_start..name_start + len,
+                    });
+                }
+            }
+        }
+        depth = (depth + code.matches('{').count()).saturating_sub(code.matches('}').count());
+    }
+    found
+}
+
+/// Path of the Zellij config file.
+pub fn config_path() -> Result<PathBuf> {
+    Ok(config_dir()?.join("config.kdl"))
+}
+
+/// Resolve the Zellij config directory.
+fn config_dir() -> Result<PathBuf> {
+    Ok(config_home()?.join("zellij"))
+}
+
 /// Resolve the Zellij themes directory.
 fn themes_dir() -> Result<PathBuf> {
-    let config_home = std::env::var("XDG_CONFIG_HOME")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| {
-            let home = std::env::var("HOME").unwrap_or_else(|_| "~".to_string());
-            PathBuf::from(home).join(".config")
-        });
-    Ok(config_home.join("zellij").join("themes"))
+    Ok(config_dir()?.join("themes"))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::cli::ThemeMode;
-    use crate::pipeline::assign::assign_slots;
-    use crate::pipeline::extract::ExtractedColor;
-    use palette::Oklch;
-
-    fn make_extracted(l: f32, chroma: f32, hue: f32, weight: f32) -> ExtractedColor {
-        ExtractedColor {
-            color: Color::from_oklch(Oklch::new(l, chroma, hue)),
-            weight,
-        }
-    }
-
-    fn test_palette() -> AnsiPalette {
-        let colors = vec![
-            make_extracted(0.60, 0.20, 25.0, 0.12),
-            make_extracted(0.60, 0.20, 145.0, 0.12),
-            make_extracted(0.70, 0.20, 90.0, 0.12),
-            make_extracted(0.55, 0.20, 260.0, 0.12),
-            make_extracted(0.60, 0.20, 325.0, 0.12),
-            make_extracted(0.65, 0.20, 195.0, 0.10),
-            make_extracted(0.10, 0.01, 0.0, 0.15),
-            make_extracted(0.95, 0.01, 0.0, 0.15),
-        ];
-        assign_slots(&colors, ThemeMode::Dark)
-    }
+    use crate::pipeline::assign::test_support::test_palette;
 
     #[test]
     fn serialization_contains_all_color_keys() {
@@ -218,6 +362,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_reads_base_colors() {
+        let palette = test_palette();
+        let parsed = ZellijBackend
+            .parse(&ZellijBackend.serialize(&palette, "test"))
+            .unwrap();
+        assert_eq!(parsed.slots[..8], palette.slots[..8]);
+        assert_eq!(parsed.background, palette.background);
+        assert_eq!(parsed.foreground, palette.foreground);
+        assert_eq!(parsed.slots[9..15], palette.slots[9..15]);
+    }
+
+    #[test]
+    fn parse_accepts_rgb_triples_and_comments() {
+        let content = "themes {\n    // hand-written\n    mine {\n        fg 200 200 200\n        bg 10 10 10\n        black 0 0 0\n        red \"#cc0000\"\n        green 0 204 0\n        yellow 204 204 0\n        blue 0 0 204\n        magenta 204 0 204\n        cyan 0 204 204\n        white 220 220 220 // bright-ish\n    }\n    other {\n        fg 1 2 3\n    }\n}\n";
+        let parsed = ZellijBackend.parse(content).unwrap();
+        assert_eq!(parsed.foreground, Color::new(200, 200, 200));
+        assert_eq!(parsed.slots[1], Color::new(0xcc, 0, 0));
+        assert_eq!(parsed.slots[7], Color::new(220, 220, 220));
+    }
+
+    #[test]
+    fn parse_reports_missing_colors() {
+        let err = ZellijBackend
+            .parse("themes {\n    t {\n        fg \"#ffffff\"\n    }\n}\n")
+            .unwrap_err();
+        assert_eq!(err.to_string(), "missing 'black' in Zellij theme");
+    }
+
+    #[test]
+    fn update_rewrites_only_changed_colors() {
+        let content = "// my theme\nthemes {\n    mine {\n        fg 200 200 200\n        bg \"#0a0a0a\"\n        black 0 0 0\n        red \"#cc0000\" // warm\n        green 0 204 0\n        yellow 204 204 0\n        blue 0 0 204\n        magenta 204 0 204\n        cyan 0 204 204\n        white 220 220 220\n        orange 255 128 0\n    }\n}\n";
+        let mut palette = ZellijBackend.parse(content).unwrap();
+        palette.slots[1] = Color::new(0xff, 0x44, 0x44);
+
+        let updated = ZellijBackend.update(content, &palette, "ignored").unwrap();
+        assert_eq!(
+            updated,
+            content.replace("red \"#cc0000\" // warm", "red \"#ff4444\" // warm")
+        );
+    }
+
     #[test]
     fn write_to_creates_file() {
         let backend = ZellijBackend;
@@ -253,4 +439,23 @@ mod tests {
         std::fs::remove_dir_all(&temp_dir).unwrap();
         std::env::remove_var("XDG_CONFIG_HOME");
     }
+
+    #[test]
+    fn config_theme_is_the_top_level_node() {
+        let config = "// theme \"dracula\"\nthemes {\n    theme \"nested\" {\n    }\n}\ntheme  \"nord\" // mine\nsimplified_ui true\n";
+        let setting = find_config_theme(config).unwrap();
+        assert_eq!(setting.value(config), "nord");
+        assert_eq!(
+            set_config_theme(config, "sunset"),
+            config.replace("\"nord\"", "\"sunset\"")
+        );
+        assert_eq!(
+            setting.remove(config),
+            "// theme \"dracula\"\nthemes {\n    theme \"nested\" {\n    }\n}\nsimplified_ui true\n"
+        );
+        assert_eq!(
+            set_config_theme("// theme \"dracula\"", "sunset"),
+            "// theme \"dracula\"\ntheme \"sunset\"\n"
+        );
+    }
 }
@@ -0,0 +1,500 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{bail, Context, Result};
+
+use crate::backends::json::{escape, palette_from_fields, parse_json, write_palette_fields, Value};
+use crate::backends::{default_theme_name, get_backend_with, BackendOptions, Target};
+use crate::cli::{mode_name, GalleryFormat, ThemeMode};
+use crate::pipeline::assign::AnsiPalette;
+use crate::pipeline::extract::list_images;
+use crate::pipeline::{generate, theme_key, PipelineOptions};
+use crate::swatch;
+
+/// File name of the JSON manifest in the output directory.
+pub const MANIFEST: &str = "index.json";
+
+/// Subdirectory of the output directory holding the swatch cards.
+const SWATCH_DIR: &str = "swatches";
+
+/// Settings shared by every image in a batch.
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    pub targets: Vec<Target>,
+    pub backend: BackendOptions,
+    /// Install themes instead of writing them under `output_dir`.
+    pub install: bool,
+    pub output_dir: PathBuf,
+    pub skip_existing: bool,
+    pub gallery: GalleryFormat,
+    /// Images processed at once.
+    pub jobs: usize,
+    pub pipeline: PipelineOptions,
+}
+
+/// A generated theme, as listed in the manifest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub name: String,
+    pub image: PathBuf,
+    pub mode: ThemeMode,
+    /// [`theme_key`] of the image and settings the theme was generated from.
+    pub key: String,
+    pub palette: AnsiPalette,
+    /// Written or installed theme files, by backend name.
+    pub files: Vec<(String, PathBuf)>,
+}
+
+/// What a batch run did with each image.
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    pub generated: Vec<Entry>,
+    /// Themes kept from the previous run (`skip_existing`).
+    pub skipped: Vec<Entry>,
+    /// Images that failed, with the reason.
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+/// Generate themes for every image in `dir`, then write the manifest and
+/// gallery to the output directory. A failing image is reported and skipped
+/// rather than stopping the batch.
+pub fn run(dir: &Path, options: &BatchOptions) -> Result<BatchReport> {
+    let images = list_images(dir)?;
+    let swatch_dir = options.output_dir.join(SWATCH_DIR);
+    std::fs::create_dir_all(&swatch_dir)
+        .with_context(|| format!("failed to create {}", swatch_dir.display()))?;
+
+    let manifest_path = options.output_dir.join(MANIFEST);
+    let previous = if options.skip_existing && manifest_path.exists() {
+        read_manifest(&manifest_path)?
+    } else {
+        Vec::new()
+    };
+    let backend_names: Vec<String> = options
+        .targets
+        .iter()
+        .map(|t| {
+            get_backend_with(t.clone(), &options.backend)
+                .name()
+                .to_string()
+        })
+        .collect();
+
+    let mut report = BatchReport::default();
+    let mut todo = Vec::new();
+    let mut names: HashMap<String, PathBuf> = HashMap::new();
+    for image in images {
+        let name = default_theme_name(&image);
+        if let Some(first) = names.get(&name) {
+            let reason = format!(
+                "theme name '{name}' is already taken by {}",
+                first.display()
+            );
+            report.failed.push((image, reason));
+            continue;
+        }
+        names.insert(name.clone(), image.clone());
+        let key = match theme_key(&[(image.clone(), 1.0)], &options.pipeline) {
+            Ok(key) => key,
+            Err(e) => {
+                report.failed.push((image, format!("{e:#}")));
+                continue;
+            }
+        };
+        match previous
+            .iter()
+            .find(|e| e.name == name && e.key == key && has_all_files(e, &backend_names))
+        {
+            Some(entry) => report.skipped.push(entry.clone()),
+            None => todo.push(Job { image, name, key }),
+        }
+    }
+
+    let results = generate_all(&todo, options);
+    for (Job { image, .. }, result) in todo.into_iter().zip(results) {
+        match result {
+            Ok(entry) => report.generated.push(entry),
+            Err(e) => report.failed.push((image, format!("{e:#}"))),
+        }
+    }
+
+    let mut entries: Vec<&Entry> = report.generated.iter().chain(&report.skipped).collect();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    std::fs::write(&manifest_path, manifest(&entries))
+        .with_context(|| format!("failed to write {}", manifest_path.display()))?;
+    let (file, gallery) = match options.gallery {
+        GalleryFormat::Html => ("index.html", html_gallery(&entries)),
+        GalleryFormat::Markdown => ("index.md", markdown_gallery(&entries)),
+    };
+    let gallery_path = options.output_dir.join(file);
+    std::fs::write(&gallery_path, gallery)
+        .with_context(|| format!("failed to write {}", gallery_path.display()))?;
+
+    Ok(report)
+}
+
+/// An image to generate themes for.
+struct Job {
+    image: PathBuf,
+    name: String,
+    key: String,
+}
+
+/// Run [`generate_themes`] for each job on `options.jobs` threads. Results come
+/// back in input order.
+fn generate_all(todo: &[Job], options: &BatchOptions) -> Vec<Result<Entry>> {
+    let next = AtomicUsize::new(0);
+    let finished = AtomicUsize::new(0);
+    let jobs = options.jobs.clamp(1, todo.len().max(1));
+
+    let mut results: Vec<(usize, Result<Entry>)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(job) = todo.get(i) else {
+                            break;
+                        };
+                        let name = &job.name;
+                        let result = generate_themes(job, options);
+                        let n = finished.fetch_add(1, Ordering::Relaxed) + 1;
+                        match &result {
+                            Ok(_) => eprintln!("[{n}/{}] {name}", todo.len()),
+                            Err(e) => eprintln!("[{n}/{}] {name}: error: {e:#}", todo.len()),
+                        }
+                        results.push((i, result));
+                    }
+                    results
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("batch worker panicked"))
+            .collect()
+    });
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Run the full pipeline for one image, write or install its themes, and
+/// render its swatch card.
+fn generate_themes(job: &Job, options: &BatchOptions) -> Result<Entry> {
+    let (image_path, name) = (job.image.as_path(), job.name.as_str());
+    let (palette, mode) = generate(image_path, &options.pipeline)?;
+
+    let mut files = Vec::new();
+    for target in &options.targets {
+        let backend = get_backend_with(target.clone(), &options.backend);
+        let path = if options.install {
+            backend.install(&palette, name)?
+        } else {
+            let dir = options.output_dir.join(target_dir(backend.name()));
+            std::fs::create_dir_all(&dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+            let path = dir.join(format!("{name}{}", backend.extension()));
+            backend.write_to(&palette, name, &path)?;
+            path
+        };
+        files.push((backend.name().to_string(), path));
+    }
+
+    let thumbnail = swatch::load_thumbnail(image_path)?;
+    swatch::write_swatch(
+        &options.output_dir.join(swatch_path(name)),
+        &palette,
+        Some(&thumbnail),
+    )?;
+
+    Ok(Entry {
+        name: name.to_string(),
+        image: image_path.to_path_buf(),
+        mode,
+        key: job.key.clone(),
+        palette,
+        files,
+    })
+}
+
+/// Whether the entry has a file for every backend and all of them still exist.
+fn has_all_files(entry: &Entry, backend_names: &[String]) -> bool {
+    backend_names.iter().all(|backend| {
+        entry
+            .files
+            .iter()
+            .any(|(name, path)| name == backend && path.exists())
+    })
+}
+
+/// Output subdirectory for a backend's themes, e.g. "Neovim" -> "neovim".
+fn target_dir(backend_name: &str) -> String {
+    backend_name.to_lowercase().replace(' ', "-")
+}
+
+/// Swatch card location, relative to the output directory.
+fn swatch_path(name: &str) -> PathBuf {
+    Path::new(SWATCH_DIR).join(format!("{name}.png"))
+}
+
+// ---------------------------------------------------------------------------
+// Manifest
+// ---------------------------------------------------------------------------
+
+/// The JSON manifest: one object per theme with its image, mode, swatch,
+/// files by backend, and colors in the same keys as `--target json`.
+fn manifest(entries: &[&Entry]) -> String {
+    let mut out = String::from("{\n  \"themes\": [");
+    for (i, entry) in entries.iter().enumerate() {
+        out.push_str(if i == 0 { "\n" } else { ",\n" });
+        out.push_str("    {\n");
+        let mut field = |key: &str, value: &str| {
+            out.push_str(&format!("      \"{key}\": \"{}\",\n", escape(value)));
+        };
+        field("name", &entry.name);
+        field("image", &entry.image.to_string_lossy());
+        field("mode", mode_name(entry.mode));
+        field("key", &entry.key);
+        field("swatch", &swatch_path(&entry.name).to_string_lossy());
+        out.push_str("      \"files\": {");
+        for (j, (backend, path)) in entry.files.iter().enumerate() {
+            out.push_str(if j == 0 { "\n" } else { ",\n" });
+            out.push_str(&format!(
+                "        \"{}\": \"{}\"",
+                escape(backend),
+                escape(&path.to_string_lossy())
+            ));
+        }
+        out.push_str("\n      },\n");
+        write_palette_fields(&mut out, &entry.palette, "      ");
+        out.push_str("    }");
+    }
+    out.push_str(if entries.is_empty() {
+        "]\n}\n"
+    } else {
+        "\n  ]\n}\n"
+    });
+    out
+}
+
+/// Read the entries of an existing manifest.
+fn read_manifest(path: &Path) -> Result<Vec<Entry>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let parse = || -> Result<Vec<Entry>> {
+        let Value::Object(fields) = parse_json(&content)? else {
+            bail!("expected a JSON object");
+        };
+        let Some((_, Value::Array(themes))) = fields.iter().find(|(key, _)| key == "themes") else {
+            bail!("missing \"themes\" array");
+        };
+        themes.iter().map(entry_from_value).collect()
+    };
+    parse().with_context(|| {
+        format!(
+            "invalid manifest {}; delete it or run without --skip-existing",
+            path.display()
+        )
+    })
+}
+
+fn entry_from_value(value: &Value) -> Result<Entry> {
+    let Value::Object(fields) = value else {
+        bail!("theme entries must be objects");
+    };
+    let get = |key: &str| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+    let string = |key: &str| -> Result<String> {
+        match get(key) {
+            Some(Value::String(s)) => Ok(s.clone()),
+            _ => bail!("missing \"{key}\""),
+        }
+    };
+
+    let mode = match string("mode")?.as_str() {
+        "dark" => ThemeMode::Dark,
+        "light" => ThemeMode::Light,
+        other => bail!("unknown mode '{other}'"),
+    };
+    let Some(Value::Object(files)) = get("files") else {
+        bail!("missing \"files\" object");
+    };
+    let files = files
+        .iter()
+        .map(|(backend, path)| match path {
+            Value::String(path) => Ok((backend.clone(), PathBuf::from(path))),
+            _ => bail!("file for \"{backend}\" must be a path string"),
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Entry {
+        name: string("name")?,
+        image: PathBuf::from(string("image")?),
+        mode,
+        key: string("key")?,
+        palette: palette_from_fields(fields)?,
+        files,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Galleries
+// ---------------------------------------------------------------------------
+
+const HTML_HEAD: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>nuri themes</title>
+<style>
+body { margin: 2rem; background: #111; color: #ddd; font-family: sans-serif; }
+main { display: grid; grid-template-columns: repeat(auto-fill, minmax(420px, 1fr)); gap: 2rem; }
+figure { margin: 0; }
+img { width: 100%; border-radius: 6px; }
+figcaption { margin-top: 0.5rem; }
+</style>
+</head>
+<body>
+<h1>nuri themes</h1>
+<main>
+"#;
+
+/// A page with every swatch card in a grid.
+fn html_gallery(entries: &[&Entry]) -> String {
+    let mut out = String::from(HTML_HEAD);
+    for entry in entries {
+        let name = html_escape(&entry.name);
+        out.push_str(&format!(
+            "<figure>\n  <img src=\"{}\" alt=\"{name}\">\n  \
+             <figcaption><strong>{name}</strong> · {} · {}</figcaption>\n</figure>\n",
+            url_path(&swatch_path(&entry.name)),
+            mode_name(entry.mode),
+            html_escape(&image_file_name(entry)),
+        ));
+    }
+    out.push_str("</main>\n</body>\n</html>\n");
+    out
+}
+
+/// A Markdown page with one section per theme.
+fn markdown_gallery(entries: &[&Entry]) -> String {
+    let mut out = String::from("# nuri themes\n");
+    for entry in entries {
+        out.push_str(&format!(
+            "\n## {name}\n\n{} · `{}`\n\n![{name}]({})\n",
+            mode_name(entry.mode),
+            image_file_name(entry),
+            url_path(&swatch_path(&entry.name)),
+            name = entry.name,
+        ));
+    }
+    out
+}
+
+fn image_file_name(entry: &Entry) -> String {
+    entry
+        .image
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Percent-encode a relative path for use as a link.
+fn url_path(path: &Path) -> String {
+    let mut out = String::new();
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    fn test_entry(name: &str) -> Entry {
+        let mut slots = [Color::new(0x20, 0x20, 0x20); 16];
+        slots[1] = Color::new(0xe0, 0x40, 0x40);
+        Entry {
+            name: name.to_string(),
+            image: PathBuf::from(format!("/walls/{name}.jpg")),
+            mode: ThemeMode::Dark,
+            key: "0123456789abcdef".to_string(),
+            palette: AnsiPalette {
+                slots,
+                background: Color::new(0x10, 0x10, 0x10),
+                foreground: Color::new(0xf0, 0xf0, 0xf0),
+                cursor_color: Color::new(0xf0, 0xf0, 0xf0),
+                cursor_text: Color::new(0x10, 0x10, 0x10),
+                selection_bg: Color::new(0x40, 0x40, 0x40),
+                selection_fg: Color::new(0xf0, 0xf0, 0xf0),
+            },
+            files: vec![
+                ("Ghostty".to_string(), PathBuf::from("/themes/ghostty/a")),
+                ("Zellij".to_string(), PathBuf::from("/themes/zellij/a.kdl")),
+            ],
+        }
+    }
+
+    #[test]
+    fn manifest_round_trips() {
+        let entries = [test_entry("sunset"), test_entry("rain \"city\"")];
+        let path = std::env::temp_dir().join("nuri_test_batch_manifest.json");
+        std::fs::write(&path, manifest(&entries.iter().collect::<Vec<_>>())).unwrap();
+        let read = read_manifest(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read, entries);
+    }
+
+    #[test]
+    fn empty_manifest_is_valid_json() {
+        let path = std::env::temp_dir().join("nuri_test_batch_empty_manifest.json");
+        std::fs::write(&path, manifest(&[])).unwrap();
+        let read = read_manifest(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(read.is_empty());
+    }
+
+    #[test]
+    fn galleries_link_encoded_swatches() {
+        let entry = test_entry("blue hour");
+        let html = html_gallery(&[&entry]);
+        assert!(html.contains("<img src=\"swatches/blue%20hour.png\" alt=\"blue hour\">"));
+        assert!(html.contains("<strong>blue hour</strong> · dark · blue hour.jpg"));
+        let markdown = markdown_gallery(&[&entry]);
+        assert!(markdown.contains("## blue hour\n\ndark · `blue hour.jpg`"));
+        assert!(markdown.contains("![blue hour](swatches/blue%20hour.png)"));
+    }
+
+    #[test]
+    fn complete_entries_need_every_backend_file() {
+        let dir = std::env::temp_dir().join("nuri_test_batch_complete");
+        std::fs::create_dir_all(&dir).unwrap();
+        let theme = dir.join("a");
+        std::fs::write(&theme, "").unwrap();
+        let mut entry = test_entry("a");
+        entry.files = vec![("Ghostty".to_string(), theme)];
+
+        assert!(has_all_files(&entry, &["Ghostty".to_string()]));
+        assert!(!has_all_files(
+            &entry,
+            &["Ghostty".to_string(), "Zellij".to_string()]
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(!has_all_files(&entry, &["Ghostty".to_string()]));
+    }
+}
@@ -0,0 +1,273 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use palette::Lab;
+
+use crate::backends::cache_home;
+use crate::cli::{mode_name, ExtractorKind, PixelWeight, ThemeMode};
+use crate::pipeline::extract::{ExtractedColor, MAX_DIM, RESIZE_FILTER};
+use crate::pipeline::frames::FrameSelection;
+use crate::pipeline::region::Region;
+
+/// Bumped whenever extraction changes in a way that makes old entries wrong.
+const FORMAT_VERSION: u32 = 2;
+
+/// Extension of cache entry files.
+const EXTENSION: &str = "colors";
+
+/// What extraction produced for an image: the colors, and the mode detected
+/// from its pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extraction {
+    pub colors: Vec<ExtractedColor>,
+    pub mode: ThemeMode,
+}
+
+/// Extraction settings that go into the cache key besides the image content.
+#[derive(Debug, Clone, Copy)]
+pub struct KeySettings<'a> {
+    pub k: usize,
+    pub seed: u64,
+    pub extractor: ExtractorKind,
+    pub weight: &'a [PixelWeight],
+    pub crop: Option<&'a Region>,
+    pub exclude: &'a [Region],
+    pub frames: FrameSelection,
+}
+
+/// Cache key for extracting from `images` (blended by weight) with
+/// `settings`. Reads every image, since the key covers their content rather
+/// than their names.
+pub fn key(images: &[(PathBuf, f32)], settings: &KeySettings) -> Result<String> {
+    let mut description = format!(
+        "v{FORMAT_VERSION} k={} seed={} extractor={:?} weight={:?} crop={:?} exclude={:?} \
+         frames={:?} resize={MAX_DIM}/{RESIZE_FILTER:?}",
+        settings.k,
+        settings.seed,
+        settings.extractor,
+        settings.weight,
+        settings.crop,
+        settings.exclude,
+        settings.frames,
+    );
+    for (path, weight) in images {
+        let bytes =
+            std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        description.push_str(&format!(" {:016x}*{weight}", content_hash(&bytes)));
+    }
+    Ok(format!("{:016x}", content_hash(description.as_bytes())))
+}
+
+/// FNV-1a: cheap, and plenty to tell images apart.
+pub fn content_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Resolve the cache directory, `$XDG_CACHE_HOME/nuri`.
+pub fn cache_dir() -> Result<PathBuf> {
+    Ok(cache_home()?.join("nuri"))
+}
+
+/// The cached extraction for `key`, if any. An unreadable entry counts as a
+/// miss and is overwritten by the next [`store`].
+pub fn load(key: &str) -> Option<Extraction> {
+    load_from(&cache_dir().ok()?, key)
+}
+
+/// Save an extraction under `key`.
+pub fn store(key: &str, extraction: &Extraction) -> Result<()> {
+    store_in(&cache_dir()?, key, extraction)
+}
+
+/// The cached extraction for `key`, or else the result of `extract`, saved
+/// for next time. Without a key (caching is off) this just runs `extract`.
+/// Failing to save only warns.
+pub fn get_or_extract(
+    key: Option<&str>,
+    extract: impl FnOnce() -> Result<Extraction>,
+) -> Result<Extraction> {
+    if let Some(hit) = key.and_then(load) {
+        return Ok(hit);
+    }
+    let extraction = extract()?;
+    if let Some(key) = key {
+        if let Err(e) = store(key, &extraction) {
+            eprintln!("warning: failed to cache extraction: {e:#}");
+        }
+    }
+    Ok(extraction)
+}
+
+/// Remove every cache entry. Returns how many there were.
+pub fn clear() -> Result<usize> {
+    clear_dir(&cache_dir()?)
+}
+
+fn entry_path(dir: &Path, key: &str) -> PathBuf {
+    dir.join(format!("{key}.{EXTENSION}"))
+}
+
+fn load_from(dir: &Path, key: &str) -> Option<Extraction> {
+    let content = std::fs::read_to_string(entry_path(dir, key)).ok()?;
+    parse(&content).ok()
+}
+
+fn store_in(dir: &Path, key: &str, extraction: &Extraction) -> Result<()> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("failed to create cache directory: {}", dir.display()))?;
+    let path = entry_path(dir, key);
+    std::fs::write(&path, serialize(extraction))
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn clear_dir(dir: &Path) -> Result<usize> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e).with_context(|| format!("failed to list {}", dir.display())),
+    };
+    let mut removed = 0;
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == EXTENSION) {
+            std::fs::remove_file(&path)
+                .with_context(|| format!("failed to remove {}", path.display()))?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// `mode <dark|light>`, then one `<L> <a> <b> <weight>` line per color, with
+/// the unrounded centroid in CIELAB.
+fn serialize(extraction: &Extraction) -> String {
+    let mut out = format!("mode {}\n", mode_name(extraction.mode));
+    for c in &extraction.colors {
+        let Lab { l, a, b, .. } = c.lab;
+        out.push_str(&format!("{l} {a} {b} {}\n", c.weight));
+    }
+    out
+}
+
+fn parse(content: &str) -> Result<Extraction> {
+    let mut lines = content.lines();
+    let mode = match lines.next() {
+        Some("mode dark") => ThemeMode::Dark,
+        Some("mode light") => ThemeMode::Light,
+        _ => bail!("missing mode"),
+    };
+    let colors = lines
+        .map(|line| {
+            let fields = line
+                .split(' ')
+                .map(str::parse)
+                .collect::<Result<Vec<f32>, _>>()?;
+            let [l, a, b, weight] = fields[..] else {
+                bail!("expected L, a, b, and weight");
+            };
+            Ok(ExtractedColor::new(Lab::new(l, a, b), weight))
+        })
+        .collect::<Result<_>>()?;
+    Ok(Extraction { colors, mode })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> KeySettings<'static> {
+        KeySettings {
+            k: 16,
+            seed: 42,
+            extractor: ExtractorKind::Kmeans,
+            weight: &[],
+            crop: None,
+            exclude: &[],
+            frames: FrameSelection::Evenly(8),
+        }
+    }
+
+    #[test]
+    fn key_covers_content_and_settings() {
+        let dir = std::env::temp_dir().join("nuri-test-cache-key");
+        std::fs::create_dir_all(&dir).unwrap();
+        let (a, b, copy) = (dir.join("a.png"), dir.join("b.png"), dir.join("copy.png"));
+        std::fs::write(&a, "image a").unwrap();
+        std::fs::write(&b, "image b").unwrap();
+        std::fs::write(&copy, "image a").unwrap();
+        let key_of = |path: &Path, settings: &KeySettings| {
+            key(&[(path.to_path_buf(), 1.0)], settings).unwrap()
+        };
+
+        let base = key_of(&a, &settings());
+        assert_eq!(key_of(&copy, &settings()), base, "same bytes, other name");
+        assert_ne!(key_of(&b, &settings()), base);
+        let crop: Region = "0,0,50%,50%".parse().unwrap();
+        for changed in [
+            KeySettings { k: 8, ..settings() },
+            KeySettings {
+                seed: 43,
+                ..settings()
+            },
+            KeySettings {
+                extractor: ExtractorKind::Octree,
+                ..settings()
+            },
+            KeySettings {
+                weight: &[PixelWeight::Center],
+                ..settings()
+            },
+            KeySettings {
+                crop: Some(&crop),
+                ..settings()
+            },
+            KeySettings {
+                exclude: std::slice::from_ref(&crop),
+                ..settings()
+            },
+            KeySettings {
+                frames: FrameSelection::All,
+                ..settings()
+            },
+        ] {
+            assert_ne!(key_of(&a, &changed), base, "{changed:?}");
+        }
+        let blended = key(&[(a.clone(), 1.0), (b.clone(), 1.0)], &settings()).unwrap();
+        let reweighted = key(&[(a.clone(), 1.0), (b.clone(), 2.0)], &settings()).unwrap();
+        assert_ne!(blended, reweighted);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn entries_round_trip_and_clear() {
+        let dir = std::env::temp_dir().join("nuri-test-cache-entries");
+        let _ = std::fs::remove_dir_all(&dir);
+        let extraction = Extraction {
+            colors: vec![
+                ExtractedColor::new(Lab::new(13.2, 3.5, -11.875), 0.6123457),
+                ExtractedColor::new(Lab::new(69.84517, 46.0001, 6.2), 0.0001),
+            ],
+            mode: ThemeMode::Light,
+        };
+        assert_eq!(load_from(&dir, "abc"), None);
+        store_in(&dir, "abc", &extraction).unwrap();
+        assert_eq!(load_from(&dir, "abc"), Some(extraction));
+
+        std::fs::write(entry_path(&dir, "bad"), "mode dusk\n").unwrap();
+        assert_eq!(load_from(&dir, "bad"), None);
+        std::fs::write(dir.join("keep.txt"), "").unwrap();
+        assert_eq!(clear_dir(&dir).unwrap(), 2);
+        assert!(dir.join("keep.txt").exists());
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(clear_dir(&dir).unwrap(), 0);
+    }
+
+    #[test]
+    fn content_hash_tells_files_apart() {
+        assert_eq!(content_hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(content_hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_ne!(content_hash(b"wallpaper 1"), content_hash(b"wallpaper 2"));
+    }
+}
@@ -1,55 +1,314 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
-use crate::backends::Target;
+use crate::backends::{target_names, Target};
+use crate::pipeline::frames::FrameSelection;
+use crate::pipeline::region::Region;
 
 /// Generate color themes from wallpaper images.
 #[derive(Parser, Debug)]
-#[command(name = "nuri", version, about)]
+#[command(
+    name = "nuri",
+    version,
+    about,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 pub struct Args {
-    /// Path to the input image
-    pub image: PathBuf,
+    #[command(subcommand)]
+    pub command: Option<Command>,
 
-    /// Theme name (defaults to image filename stem)
+    /// Input image(s). Several images, or a directory of them, are blended
+    /// into one palette
+    #[arg(required_unless_present = "deactivate", value_name = "IMAGE")]
+    pub images: Vec<PathBuf>,
+
+    /// How much each IMAGE argument counts when blending, comma-separated, in
+    /// argument order (default: all equal). Every image in a directory gets
+    /// that directory's weight
+    #[arg(long, value_delimiter = ',', value_name = "WEIGHTS")]
+    pub image_weight: Vec<f32>,
+
+    /// Theme name (defaults to the first image's filename stem)
     #[arg(short, long)]
     pub name: Option<String>,
 
-    /// Force dark or light mode (auto-detected if omitted)
-    #[arg(short, long, value_enum)]
-    pub mode: Option<ThemeMode>,
-
     /// Write theme to this file instead of stdout
     #[arg(short, long)]
     pub output: Option<PathBuf>,
 
-    /// Target theme format(s), comma-separated (e.g. ghostty,zellij)
-    #[arg(short = 't', long, value_enum, value_delimiter = ',')]
+    #[arg(
+        short = 't',
+        long,
+        value_delimiter = ',',
+        help = format!(
+            "Target theme format(s), comma-separated: {}, or template:<name>",
+            target_names()
+        )
+    )]
     pub target: Vec<Target>,
 
     /// Install theme to the target's standard config directory
     #[arg(long, conflicts_with = "output")]
     pub install: bool,
 
+    /// After installing, make running apps switch to the theme: Ghostty
+    /// (reloads its config; add --activate to select the theme there),
+    /// Zellij, and Neovim
+    #[arg(long, requires = "install")]
+    pub reload: bool,
+
+    /// After installing, select the theme in the app configs: Ghostty's
+    /// config, Zellij's config.kdl, and Neovim's init.lua (plugin layout
+    /// only). Each file is backed up first
+    #[arg(long, requires = "install")]
+    pub activate: bool,
+
+    /// Put back the themes the app configs selected before --activate, for
+    /// the apps given with -t (all by default), and exit
+    #[arg(long, conflicts_with_all = ["install", "output", "tui"])]
+    pub deactivate: bool,
+
     /// Print a colored terminal preview of the palette
     #[arg(long)]
     pub preview: bool,
 
+    /// Also render a swatch card of the palette to this .png or .svg file
+    #[arg(long, value_name = "PATH")]
+    pub swatch: Option<PathBuf>,
+
     /// Launch interactive TUI mode
     #[arg(long)]
     pub tui: bool,
 
-    /// Number of K-means clusters
+    #[command(flatten)]
+    pub pipeline: PipelineArgs,
+
+    /// Write one theme per sampled frame instead, named <NAME>-<FRAME>, with
+    /// --install or --output (the frame number is added to the file name)
+    #[arg(long, conflicts_with_all = ["tui", "activate", "reload"])]
+    pub per_frame: bool,
+
+    /// Only extract colors from this region: x,y,w,h in pixels or percentages
+    /// (e.g. 0,0,50%,100% for the left half)
+    #[arg(long, value_name = "X,Y,W,H")]
+    pub crop: Option<Region>,
+
+    /// Ignore this region during extraction (x,y,w,h like --crop, in source
+    /// image coordinates). Repeat to exclude several regions
+    #[arg(long, value_name = "X,Y,W,H")]
+    pub exclude: Vec<Region>,
+
+    /// Error instead of overwriting when installing an existing theme
+    #[arg(long)]
+    pub no_clobber: bool,
+
+    /// Neovim output layout: a single colorscheme file or a plugin directory
+    #[arg(long, value_enum, default_value_t = NeovimLayout::Single)]
+    pub neovim_layout: NeovimLayout,
+
+    /// Color gamut to generate for. Targets that cannot store Display P3
+    /// colors fall back to sRGB
+    #[arg(long, value_enum, default_value_t = Gamut::Srgb)]
+    pub gamut: Gamut,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Check an existing theme's contrast, color-blind safety, and hue roles;
+    /// exits nonzero if any check fails
+    Audit(AuditArgs),
+    /// Raise an existing theme's colors to the contrast minimums and write it
+    /// back in the same format
+    Fix(FixArgs),
+    /// Generate a theme for every image in a directory, in parallel, plus a
+    /// JSON manifest and a swatch gallery
+    Batch(BatchArgs),
+    /// Follow a wallpaper file or symlink, regenerating and installing the
+    /// themes whenever it changes
+    Watch(WatchArgs),
+    /// Manage the cache of extracted colors under $XDG_CACHE_HOME/nuri
+    Cache(CacheArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct AuditArgs {
+    /// Theme file: Ghostty, Zellij (.kdl), or nuri JSON (.json)
+    pub theme: PathBuf,
+
+    /// Theme format (guessed from the file extension if omitted)
+    #[arg(short = 't', long)]
+    pub target: Option<Target>,
+
+    /// Minimum accent contrast ratio against background
+    #[arg(long, default_value_t = 4.5)]
+    pub min_contrast: f32,
+
+    /// Only warn about confusable accents and hue drift; contrast still fails
+    #[arg(long)]
+    pub warn_only: bool,
+
+    /// With --warn-only, still fail when red/green or blue/magenta are
+    /// confusable under a simulated deficiency, as `--cvd-safe` generation
+    /// prevents
+    #[arg(long)]
+    pub cvd_safe: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct FixArgs {
+    /// Theme file: Ghostty, Zellij (.kdl), or nuri JSON (.json)
+    pub theme: PathBuf,
+
+    /// Theme format (guessed from the file extension if omitted)
+    #[arg(short = 't', long)]
+    pub target: Option<Target>,
+
+    /// Minimum accent contrast ratio against background
+    #[arg(long, default_value_t = 4.5)]
+    pub min_contrast: f32,
+
+    /// Write the fixed theme here instead of overwriting the input
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct BatchArgs {
+    /// Directory of wallpapers (not searched recursively)
+    pub dir: PathBuf,
+
+    /// Target theme format(s), comma-separated (see `nuri --help`) [default: ghostty]
+    #[arg(short = 't', long, value_delimiter = ',')]
+    pub target: Vec<Target>,
+
+    /// Install themes to each target's standard config directory instead of
+    /// writing them to the output directory
+    #[arg(long)]
+    pub install: bool,
+
+    /// Directory for the manifest, gallery, swatches, and (without --install)
+    /// the themes, one subdirectory per target
+    #[arg(short, long, value_name = "DIR", default_value = "nuri-themes")]
+    pub output_dir: PathBuf,
+
+    /// Skip images whose themes are listed in the existing manifest, were
+    /// generated from the same image content and settings, and whose files are
+    /// all still there
+    #[arg(long)]
+    pub skip_existing: bool,
+
+    /// Gallery format
+    #[arg(long, value_enum, default_value_t = GalleryFormat::Html)]
+    pub gallery: GalleryFormat,
+
+    /// Images processed at once [default: number of CPUs]
+    #[arg(short, long)]
+    pub jobs: Option<usize>,
+
+    #[command(flatten)]
+    pub pipeline: PipelineArgs,
+
+    /// Neovim output layout: a single colorscheme file or a plugin directory
+    #[arg(long, value_enum, default_value_t = NeovimLayout::Single)]
+    pub neovim_layout: NeovimLayout,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct WatchArgs {
+    /// Wallpaper file or symlink to follow, e.g. ~/.config/wallpaper/current
+    pub path: PathBuf,
+
+    /// Theme name, kept across wallpapers (defaults to the watched file's stem)
+    #[arg(short, long)]
+    pub name: Option<String>,
+
+    /// Target theme format(s) to install, comma-separated [default: ghostty]
+    #[arg(short = 't', long, value_delimiter = ',')]
+    pub target: Vec<Target>,
+
+    /// Shell command to run after each install (repeatable); gets
+    /// NURI_THEME, NURI_IMAGE, and NURI_MODE in its environment
+    #[arg(long, value_name = "CMD")]
+    pub hook: Vec<String>,
+
+    /// Milliseconds the wallpaper must stay unchanged before it is used
+    #[arg(long, value_name = "MS", default_value_t = 500)]
+    pub debounce: u64,
+
+    /// Milliseconds between checks when polling
+    #[arg(long, value_name = "MS", default_value_t = 1000)]
+    pub interval: u64,
+
+    /// Poll instead of using inotify
+    #[arg(long)]
+    pub poll: bool,
+
+    /// After each install, make running apps switch to the theme (see
+    /// `nuri --help`)
+    #[arg(long)]
+    pub reload: bool,
+
+    #[command(flatten)]
+    pub pipeline: PipelineArgs,
+
+    /// Neovim output layout: a single colorscheme file or a plugin directory
+    #[arg(long, value_enum, default_value_t = NeovimLayout::Single)]
+    pub neovim_layout: NeovimLayout,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct CacheArgs {
+    #[command(subcommand)]
+    pub command: CacheCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheCommand {
+    /// Remove every cached extraction
+    Clear,
+}
+
+/// Palette generation options shared by the main command, `batch`, and
+/// `watch`.
+#[derive(clap::Args, Debug, Clone)]
+pub struct PipelineArgs {
+    /// Force dark or light mode (auto-detected if omitted)
+    #[arg(short, long, value_enum)]
+    pub mode: Option<ThemeMode>,
+
+    /// Number of colors to extract
     #[arg(short = 'k', long = "colors", default_value_t = 16)]
     pub colors: usize,
 
+    /// Color extraction algorithm
+    #[arg(long, value_enum, default_value_t = ExtractorKind::Kmeans)]
+    pub extractor: ExtractorKind,
+
+    /// Weight pixels before extraction, comma-separated: center, edges, chroma.
+    /// Draws accents from the subject rather than large backgrounds
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub weight: Vec<PixelWeight>,
+
+    /// Frames of animated GIF, APNG, and WebP images whose pixels are
+    /// pooled: a count, evenly spaced over the animation, or `all`
+    #[arg(long, value_name = "N|all", default_value = "8")]
+    pub frames: FrameSelection,
+
     /// Minimum accent contrast ratio against background
     #[arg(long, default_value_t = 4.5)]
     pub min_contrast: f32,
 
-    /// Error instead of overwriting when installing an existing theme
+    /// Keep red/green and blue/magenta distinguishable under simulated
+    /// protanopia, deuteranopia, and tritanopia
     #[arg(long)]
-    pub no_clobber: bool,
+    pub cvd_safe: bool,
+
+    /// Extract again instead of reusing the cached colors and mode for these
+    /// images and settings (see `nuri cache`)
+    #[arg(long)]
+    pub no_cache: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
@@ -57,3 +316,61 @@ pub enum ThemeMode {
     Dark,
     Light,
 }
+
+/// Lowercase name of a mode, as `--mode` takes it.
+pub fn mode_name(mode: ThemeMode) -> &'static str {
+    match mode {
+        ThemeMode::Dark => "dark",
+        ThemeMode::Light => "light",
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExtractorKind {
+    /// K-means clustering in LAB
+    #[default]
+    Kmeans,
+    /// Median cut: split the widest color range in half until there are k boxes
+    MedianCut,
+    /// Octree quantization: merge the rarest RGB octree nodes until k remain
+    Octree,
+    /// Hue histogram peaks, weighted by chroma; keeps small vivid details
+    Histogram,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PixelWeight {
+    /// Favor the middle of the image
+    Center,
+    /// Favor detailed areas (Sobel edge density) over flat ones
+    Edges,
+    /// Favor saturated pixels
+    Chroma,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Gamut {
+    /// Standard sRGB, supported by every target
+    #[default]
+    Srgb,
+    /// The wider Display P3 gamut, for Ghostty and GTK
+    DisplayP3,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum GalleryFormat {
+    /// `index.html`, a grid of swatch cards
+    #[default]
+    Html,
+    /// `index.md`, one section per theme
+    Markdown,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum NeovimLayout {
+    /// `nvim/lua/plugins/<name>.lua` with all highlights inline
+    #[default]
+    Single,
+    /// Plugin directory with a `setup()` API and a lualine theme
+    Plugin,
+}
@@ -1,5 +1,7 @@
 use anyhow::{bail, Result};
-use palette::{FromColor, IntoColor, Lab, Oklch, Srgb};
+use palette::{
+    convert::FromColorUnclamped, FromColor, IntoColor, Lab, LinSrgb, Oklab, Oklch, Srgb,
+};
 
 /// Core color type used throughout the pipeline.
 /// Wraps sRGB u8 components and provides conversions to perceptual color spaces.
@@ -69,10 +71,15 @@ impl Color {
         srgb_f32.into_color()
     }
 
-    /// Create from Oklch.
+    /// Create from Oklch, mapping out-of-gamut colors into sRGB.
+    ///
+    /// Follows the CSS Color 4 gamut mapping algorithm: chroma is reduced at
+    /// constant lightness and hue until the clipped color is within a
+    /// just-noticeable difference of the unclipped one. Clipping channels
+    /// independently instead would shift the hue of saturated colors.
     pub fn from_oklch(oklch: Oklch) -> Self {
-        let srgb_f32: Srgb<f32> = Srgb::from_color(oklch);
-        Self::from_srgb_f32_clamped(srgb_f32)
+        let [r, g, b] = encode(gamut_map(oklch, RgbSpace::Srgb));
+        Self::from_srgb_f32_clamped(Srgb::new(r, g, b))
     }
 
     /// Clamp an Srgb<f32> to [0, 1] and convert to Color.
@@ -105,18 +112,13 @@ impl Color {
     ///
     /// Returns a value in [1, 21]. Higher means more contrast.
     pub fn contrast_ratio(c1: &Color, c2: &Color) -> f32 {
-        let l1 = c1.relative_luminance();
-        let l2 = c2.relative_luminance();
-        let (lighter, darker) = if l1 > l2 { (l1, l2) } else { (l2, l1) };
-        (lighter + 0.05) / (darker + 0.05)
+        <Color as PaletteColor>::contrast_ratio(c1, c2)
     }
 
     /// Adjust Oklch lightness by `delta`. Positive = lighter, negative = darker.
     /// Lightness is clamped to [0, 1].
     pub fn adjust_lightness(self, delta: f32) -> Color {
-        let mut oklch = self.to_oklch();
-        oklch.l = (oklch.l + delta).clamp(0.0, 1.0);
-        Color::from_oklch(oklch)
+        PaletteColor::adjust_lightness(self, delta)
     }
 
     /// Adjust Oklch chroma by `delta`. Positive = more saturated, negative = less.
@@ -126,6 +128,432 @@ impl Color {
         oklch.chroma = (oklch.chroma + delta).clamp(0.0, 0.4);
         Color::from_oklch(oklch)
     }
+
+    /// Interpolate toward `other` in Oklch. `t = 0` gives `self`, `t = 1` gives
+    /// `other`; hue takes the shorter way around the circle.
+    pub fn lerp_oklch(self, other: Color, t: f32) -> Color {
+        PaletteColor::lerp_oklch(self, other, t)
+    }
+
+    /// Nearest xterm-256 color index (16-255), by CIELAB distance.
+    ///
+    /// Only the 6x6x6 cube and the grayscale ramp are considered; indices 0-15
+    /// are terminal-defined and usually re-themed, so they make poor fallbacks.
+    pub fn to_ansi256(self) -> u8 {
+        const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+        let cube = (0..216u16).map(|i| {
+            let r = CUBE_LEVELS[(i / 36) as usize];
+            let g = CUBE_LEVELS[(i / 6 % 6) as usize];
+            let b = CUBE_LEVELS[(i % 6) as usize];
+            (16 + i as u8, Color::new(r, g, b))
+        });
+        let grays = (0..24u8).map(|i| {
+            let v = 8 + i * 10;
+            (232 + i, Color::new(v, v, v))
+        });
+
+        let lab = self.to_lab();
+        cube.chain(grays)
+            .min_by(|(_, a), (_, b)| {
+                let da = a.to_lab();
+                let db = b.to_lab();
+                let dist_a =
+                    (lab.l - da.l).powi(2) + (lab.a - da.a).powi(2) + (lab.b - da.b).powi(2);
+                let dist_b =
+                    (lab.l - db.l).powi(2) + (lab.a - db.a).powi(2) + (lab.b - db.b).powi(2);
+                dist_a.partial_cmp(&dist_b).unwrap()
+            })
+            .map(|(index, _)| index)
+            .unwrap_or(16)
+    }
+
+    /// Euclidean distance in Oklab, a rough measure of how distinguishable two
+    /// colors are (about 0.02 is a just-noticeable difference).
+    pub fn oklab_distance(a: &Color, b: &Color) -> f32 {
+        <Color as PaletteColor>::oklab_distance(a, b)
+    }
+
+    /// Simulate how this color appears with a color vision deficiency.
+    ///
+    /// Protanopia and deuteranopia use the single-plane projection of Viénot
+    /// et al. (1999); tritanopia uses the two half-planes of Brettel et al.
+    /// (1997), which Viénot's method handles poorly. Both work in linear sRGB.
+    pub fn simulate_cvd(self, cvd: Cvd) -> Color {
+        let srgb_f32: Srgb<f32> = self.to_srgb_u8().into_format();
+        let lin: LinSrgb = srgb_f32.into_linear();
+        let [r, g, b] = cvd.simulate([lin.red, lin.green, lin.blue]);
+        let simulated = LinSrgb::new(r.clamp(0.0, 1.0), g.clamp(0.0, 1.0), b.clamp(0.0, 1.0));
+        Self::from_srgb_f32_clamped(Srgb::from_linear(simulated))
+    }
+}
+
+/// Operations the palette pipeline needs from a color, so it can run on the
+/// 8-bit [`Color`] or on the unquantized [`WideColor`].
+pub trait PaletteColor: Copy + PartialEq + std::fmt::Debug {
+    /// Create from Oklch, mapping into the type's gamut.
+    fn from_oklch(oklch: Oklch) -> Self;
+
+    fn to_oklch(self) -> Oklch;
+
+    /// WCAG 2.0 relative luminance.
+    fn relative_luminance(self) -> f32;
+
+    /// Simulate how this color appears with a color vision deficiency.
+    fn simulate_cvd(self, cvd: Cvd) -> Self;
+
+    /// WCAG 2.0 contrast ratio between two colors, in [1, 21].
+    fn contrast_ratio(c1: &Self, c2: &Self) -> f32 {
+        let l1 = c1.relative_luminance();
+        let l2 = c2.relative_luminance();
+        let (lighter, darker) = if l1 > l2 { (l1, l2) } else { (l2, l1) };
+        (lighter + 0.05) / (darker + 0.05)
+    }
+
+    /// Adjust Oklch lightness by `delta`, clamped to [0, 1].
+    fn adjust_lightness(self, delta: f32) -> Self {
+        let mut oklch = self.to_oklch();
+        oklch.l = (oklch.l + delta).clamp(0.0, 1.0);
+        Self::from_oklch(oklch)
+    }
+
+    /// Euclidean distance in Oklab.
+    fn oklab_distance(a: &Self, b: &Self) -> f32 {
+        let (a, b) = (
+            Oklab::from_color_unclamped(a.to_oklch()),
+            Oklab::from_color_unclamped(b.to_oklch()),
+        );
+        ((a.l - b.l).powi(2) + (a.a - b.a).powi(2) + (a.b - b.b).powi(2)).sqrt()
+    }
+
+    /// Interpolate toward `other` in Oklch; hue takes the shorter way around.
+    fn lerp_oklch(self, other: Self, t: f32) -> Self {
+        let a = self.to_oklch();
+        let b = other.to_oklch();
+        let t = t.clamp(0.0, 1.0);
+
+        // A gray has no meaningful hue; borrow the other endpoint's.
+        let hue_a = a.hue.into_positive_degrees();
+        let hue_b = b.hue.into_positive_degrees();
+        let (hue_a, hue_b) = match (a.chroma < 0.01, b.chroma < 0.01) {
+            (true, false) => (hue_b, hue_b),
+            (false, true) => (hue_a, hue_a),
+            _ => (hue_a, hue_b),
+        };
+        let mut delta = hue_b - hue_a;
+        if delta > 180.0 {
+            delta -= 360.0;
+        } else if delta < -180.0 {
+            delta += 360.0;
+        }
+
+        Self::from_oklch(Oklch::new(
+            a.l + (b.l - a.l) * t,
+            a.chroma + (b.chroma - a.chroma) * t,
+            hue_a + delta * t,
+        ))
+    }
+}
+
+impl PaletteColor for Color {
+    fn from_oklch(oklch: Oklch) -> Self {
+        Color::from_oklch(oklch)
+    }
+
+    fn to_oklch(self) -> Oklch {
+        Color::to_oklch(self)
+    }
+
+    fn relative_luminance(self) -> f32 {
+        Color::relative_luminance(self)
+    }
+
+    fn simulate_cvd(self, cvd: Cvd) -> Self {
+        Color::simulate_cvd(self, cvd)
+    }
+}
+
+/// An unquantized color, kept in Oklch and within the Display P3 gamut.
+///
+/// Running the pipeline on these instead of [`Color`] keeps saturated colors
+/// that sRGB cannot show until the output format decides: sRGB backends get
+/// [`WideColor::to_srgb`], wide-gamut ones [`WideColor::to_display_p3`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WideColor {
+    oklch: Oklch,
+}
+
+impl WideColor {
+    /// Create from gamma-encoded Display P3 components in [0, 1].
+    pub fn from_display_p3(rgb: [f32; 3]) -> Self {
+        let oklab = RgbSpace::DisplayP3.to_oklab(decode(rgb));
+        Self::from_oklch(Oklch::from_color_unclamped(oklab))
+    }
+
+    /// Gamut-map into sRGB and quantize.
+    pub fn to_srgb(self) -> Color {
+        Color::from_oklch(self.oklch)
+    }
+
+    /// Gamma-encoded Display P3 components in [0, 1].
+    pub fn to_display_p3(self) -> [f32; 3] {
+        encode(gamut_map(self.oklch, RgbSpace::DisplayP3))
+    }
+
+    /// Display P3 components quantized to 8 bits.
+    pub fn to_display_p3_u8(self) -> [u8; 3] {
+        self.to_display_p3().map(|c| (c * 255.0).round() as u8)
+    }
+
+    /// Display P3 components as `#rrggbb`, for formats that declare their
+    /// colors to be in Display P3.
+    pub fn to_display_p3_hex(self) -> String {
+        let [r, g, b] = self.to_display_p3_u8();
+        Color::new(r, g, b).to_hex()
+    }
+
+    /// CSS Color 4 notation, e.g. `color(display-p3 0.9 0.1 0.2)`.
+    pub fn to_css_display_p3(self) -> String {
+        let [r, g, b] = self.to_display_p3();
+        format!("color(display-p3 {r:.4} {g:.4} {b:.4})")
+    }
+}
+
+impl From<Color> for WideColor {
+    fn from(color: Color) -> Self {
+        Self {
+            oklch: color.to_oklch(),
+        }
+    }
+}
+
+impl PaletteColor for WideColor {
+    fn from_oklch(oklch: Oklch) -> Self {
+        let oklab = RgbSpace::DisplayP3.to_oklab(gamut_map(oklch, RgbSpace::DisplayP3));
+        Self {
+            oklch: Oklch::from_color_unclamped(oklab),
+        }
+    }
+
+    fn to_oklch(self) -> Oklch {
+        self.oklch
+    }
+
+    fn relative_luminance(self) -> f32 {
+        // Luminance is linear, so out-of-sRGB components still add up correctly
+        let [r, g, b] = RgbSpace::Srgb.linear(self.oklch);
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    }
+
+    fn simulate_cvd(self, cvd: Cvd) -> Self {
+        let [r, g, b] = cvd.simulate(RgbSpace::Srgb.linear(self.oklch));
+        Self::from_oklch(Oklch::from_color_unclamped(LinSrgb::new(r, g, b)))
+    }
+}
+
+/// Oklab distance below which a clipped color is indistinguishable from the
+/// unclipped one (CSS Color 4 gamut mapping).
+const GAMUT_JND: f32 = 0.02;
+
+/// Chroma precision of the gamut mapping binary search.
+const GAMUT_EPSILON: f32 = 0.0001;
+
+/// RGB spaces a color can be gamut-mapped into. Both use the sRGB transfer
+/// function, so only their primaries differ.
+#[derive(Debug, Clone, Copy)]
+enum RgbSpace {
+    Srgb,
+    DisplayP3,
+}
+
+impl RgbSpace {
+    /// Unclamped linear components of an Oklch color in this space.
+    fn linear(self, oklch: Oklch) -> [f32; 3] {
+        let lin = LinSrgb::from_color_unclamped(oklch);
+        let rgb = [lin.red, lin.green, lin.blue];
+        match self {
+            RgbSpace::Srgb => rgb,
+            RgbSpace::DisplayP3 => mul(&P3_FROM_LINEAR_SRGB, rgb),
+        }
+    }
+
+    fn to_oklab(self, linear: [f32; 3]) -> Oklab {
+        let [r, g, b] = match self {
+            RgbSpace::Srgb => linear,
+            RgbSpace::DisplayP3 => mul(&LINEAR_SRGB_FROM_P3, linear),
+        };
+        Oklab::from_color_unclamped(LinSrgb::new(r, g, b))
+    }
+}
+
+/// Linear sRGB to linear Display P3.
+const P3_FROM_LINEAR_SRGB: [[f32; 3]; 3] = [
+    [0.822_462, 0.177_538, 0.0],
+    [0.033_194, 0.966_806, 0.0],
+    [0.017_083, 0.072_397, 0.910_520],
+];
+
+/// Inverse of [`P3_FROM_LINEAR_SRGB`].
+const LINEAR_SRGB_FROM_P3: [[f32; 3]; 3] = [
+    [1.224_94, -0.224_94, 0.0],
+    [-0.042_057, 1.042_057, 0.0],
+    [-0.019_638, -0.078_636, 1.098_274],
+];
+
+/// Apply the sRGB transfer function to linear components.
+fn encode(linear: [f32; 3]) -> [f32; 3] {
+    let srgb: Srgb<f32> = Srgb::from_linear(LinSrgb::new(linear[0], linear[1], linear[2]));
+    [srgb.red, srgb.green, srgb.blue]
+}
+
+/// Undo the sRGB transfer function.
+fn decode(encoded: [f32; 3]) -> [f32; 3] {
+    let lin: LinSrgb = Srgb::new(encoded[0], encoded[1], encoded[2]).into_linear();
+    [lin.red, lin.green, lin.blue]
+}
+
+/// Map an Oklch color into `space` (CSS Color 4, section 13.2), returning
+/// clipped linear components.
+///
+/// Works on unclamped values: palette's `FromColor` clamps, which would hide
+/// out-of-gamut colors.
+fn gamut_map(oklch: Oklch, space: RgbSpace) -> [f32; 3] {
+    if oklch.l >= 1.0 {
+        return [1.0; 3];
+    }
+    if oklch.l <= 0.0 {
+        return [0.0; 3];
+    }
+
+    let distance = |a: Oklch, b: [f32; 3]| {
+        let (a, b) = (Oklab::from_color_unclamped(a), space.to_oklab(b));
+        ((a.l - b.l).powi(2) + (a.a - b.a).powi(2) + (a.b - b.b).powi(2)).sqrt()
+    };
+
+    let origin = space.linear(oklch);
+    if in_gamut(origin) {
+        return clip(origin);
+    }
+    let clipped = clip(origin);
+    if distance(oklch, clipped) < GAMUT_JND {
+        return clipped;
+    }
+
+    let mut current = oklch;
+    let mut min = 0.0;
+    let mut max = oklch.chroma;
+    let mut min_in_gamut = true;
+    while max - min > GAMUT_EPSILON {
+        current.chroma = (min + max) / 2.0;
+        let linear = space.linear(current);
+        if min_in_gamut && in_gamut(linear) {
+            min = current.chroma;
+            continue;
+        }
+        let clipped = clip(linear);
+        let e = distance(current, clipped);
+        if e < GAMUT_JND {
+            if GAMUT_JND - e < GAMUT_EPSILON {
+                return clipped;
+            }
+            min_in_gamut = false;
+            min = current.chroma;
+        } else {
+            max = current.chroma;
+        }
+    }
+    clip(space.linear(current))
+}
+
+fn in_gamut(linear: [f32; 3]) -> bool {
+    // Small tolerance so colors that round-trip from u8 count as in gamut
+    linear.iter().all(|c| (-1e-4..=1.0 + 1e-4).contains(c))
+}
+
+fn clip(linear: [f32; 3]) -> [f32; 3] {
+    linear.map(|c| c.clamp(0.0, 1.0))
+}
+
+/// Linear sRGB to LMS cone responses (Viénot et al. 1999).
+const LMS_FROM_LINEAR_RGB: [[f32; 3]; 3] = [
+    [0.178_82, 0.435_16, 0.041_19],
+    [0.034_56, 0.271_55, 0.038_67],
+    [0.000_30, 0.001_84, 0.014_67],
+];
+
+/// Inverse of [`LMS_FROM_LINEAR_RGB`].
+const LINEAR_RGB_FROM_LMS: [[f32; 3]; 3] = [
+    [8.094_445, -13.050_434, 11.672_058],
+    [-1.024_853, 5.401_933, -11.361_472],
+    [-0.036_530, -0.412_164, 69.351_325],
+];
+
+/// Tritanopia projections onto the two half-planes of Brettel et al. (1997),
+/// folded into linear-sRGB matrices.
+const TRITAN_PLANE_1: [[f32; 3]; 3] = [
+    [1.012_77, 0.135_48, -0.148_26],
+    [-0.012_43, 0.868_12, 0.144_31],
+    [0.075_89, 0.805_00, 0.119_11],
+];
+const TRITAN_PLANE_2: [[f32; 3]; 3] = [
+    [0.936_78, 0.189_79, -0.126_57],
+    [0.061_54, 0.815_26, 0.123_20],
+    [-0.375_62, 1.127_67, 0.247_96],
+];
+
+/// Normal of the plane (through the neutral axis) that decides which tritan
+/// half-plane a color projects onto.
+const TRITAN_SEPARATION: [f32; 3] = [0.039_01, -0.027_88, -0.011_13];
+
+fn mul(m: &[[f32; 3]; 3], v: [f32; 3]) -> [f32; 3] {
+    m.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+}
+
+/// Dichromatic color vision deficiencies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cvd {
+    /// No working L cones (red-blind).
+    Protanopia,
+    /// No working M cones (green-blind), the most common form.
+    Deuteranopia,
+    /// No working S cones (blue-blind).
+    Tritanopia,
+}
+
+impl Cvd {
+    pub const ALL: [Cvd; 3] = [Cvd::Protanopia, Cvd::Deuteranopia, Cvd::Tritanopia];
+
+    /// Simulate on linear sRGB components. The result is not clamped.
+    fn simulate(self, rgb: [f32; 3]) -> [f32; 3] {
+        match self {
+            Cvd::Protanopia | Cvd::Deuteranopia => {
+                let mut lms = mul(&LMS_FROM_LINEAR_RGB, rgb);
+                match self {
+                    Cvd::Protanopia => lms[0] = 2.023_444 * lms[1] - 2.525_804 * lms[2],
+                    _ => lms[1] = 0.494_207 * lms[0] + 1.248_27 * lms[2],
+                }
+                mul(&LINEAR_RGB_FROM_LMS, lms)
+            }
+            Cvd::Tritanopia => {
+                let side: f32 = TRITAN_SEPARATION.iter().zip(rgb).map(|(n, c)| n * c).sum();
+                let plane = if side >= 0.0 {
+                    &TRITAN_PLANE_1
+                } else {
+                    &TRITAN_PLANE_2
+                };
+                mul(plane, rgb)
+            }
+        }
+    }
+
+    /// Lowercase name, e.g. `"deuteranopia"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Cvd::Protanopia => "protanopia",
+            Cvd::Deuteranopia => "deuteranopia",
+            Cvd::Tritanopia => "tritanopia",
+        }
+    }
 }
 
 impl std::fmt::Display for Color {
@@ -212,6 +640,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn from_oklch_reduces_chroma_instead_of_clipping() {
+        // A dark yellow far outside sRGB; clipping each channel turns it red
+        let mapped = Color::from_oklch(Oklch::new(0.23, 0.36, 99.0)).to_oklch();
+        let hue = mapped.hue.into_positive_degrees();
+        assert!((hue - 99.0).abs() < 10.0, "hue drifted to {hue}");
+        assert!(
+            (mapped.l - 0.23).abs() < 0.02,
+            "lightness drifted to {}",
+            mapped.l
+        );
+    }
+
+    #[test]
+    fn from_oklch_maps_lightness_extremes_to_black_and_white() {
+        assert_eq!(Color::from_oklch(Oklch::new(1.2, 0.2, 140.0)), WHITE);
+        assert_eq!(Color::from_oklch(Oklch::new(-0.1, 0.2, 140.0)), BLACK);
+    }
+
     #[test]
     fn srgb_to_oklch_round_trip() {
         let colors = [
@@ -318,6 +765,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::manual_range_contains)]
     fn adjust_chroma_preserves_approximate_hue() {
         let color = Color::new(200, 50, 50); // reddish
         let desaturated = color.adjust_chroma(-0.05);
@@ -333,9 +781,188 @@ mod tests {
         );
     }
 
+    #[test]
+    fn ansi256_exact_cube_colors() {
+        assert_eq!(BLACK.to_ansi256(), 16);
+        assert_eq!(WHITE.to_ansi256(), 231);
+        assert_eq!(Color::new(255, 0, 0).to_ansi256(), 196);
+        assert_eq!(Color::new(0, 135, 255).to_ansi256(), 33);
+    }
+
+    #[test]
+    fn ansi256_prefers_gray_ramp_for_grays() {
+        assert_eq!(Color::new(128, 128, 128).to_ansi256(), 244);
+        assert_eq!(Color::new(30, 30, 30).to_ansi256(), 234);
+    }
+
+    #[test]
+    fn ansi256_never_returns_base_16() {
+        for color in [
+            Color::new(200, 60, 60),
+            Color::new(12, 14, 20),
+            Color::new(250, 240, 200),
+        ] {
+            assert!(color.to_ansi256() >= 16);
+        }
+    }
+
+    #[test]
+    fn lerp_oklch_endpoints() {
+        let a = Color::new(200, 60, 60);
+        let b = Color::new(60, 90, 200);
+        assert_eq!(a.lerp_oklch(b, 0.0), a);
+        assert_eq!(a.lerp_oklch(b, 1.0), b);
+    }
+
+    #[test]
+    fn lerp_oklch_takes_shorter_hue_path() {
+        let red = Color::from_oklch(Oklch::new(0.6, 0.15, 350.0));
+        let orange = Color::from_oklch(Oklch::new(0.6, 0.15, 30.0));
+        let mid = red.lerp_oklch(orange, 0.5).to_oklch();
+        let hue = mid.hue.into_positive_degrees();
+        assert!(
+            !(15.0..=345.0).contains(&hue),
+            "hue went the long way: {hue}"
+        );
+    }
+
+    #[test]
+    fn lerp_oklch_from_gray_keeps_hue() {
+        let gray = Color::new(128, 128, 128);
+        let blue = Color::from_oklch(Oklch::new(0.6, 0.15, 260.0));
+        let mid = gray.lerp_oklch(blue, 0.5).to_oklch();
+        assert!((mid.hue.into_positive_degrees() - 260.0).abs() < 5.0);
+    }
+
     #[test]
     fn display_matches_to_hex() {
         let color = Color::new(171, 205, 239);
         assert_eq!(format!("{color}"), color.to_hex());
     }
+
+    #[test]
+    fn oklab_distance_is_zero_for_same_color() {
+        let color = Color::new(12, 200, 99);
+        assert_eq!(Color::oklab_distance(&color, &color), 0.0);
+        assert!((Color::oklab_distance(&BLACK, &WHITE) - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn cvd_simulation_keeps_grays() {
+        for cvd in Cvd::ALL {
+            for gray in [BLACK, WHITE, Color::new(128, 128, 128)] {
+                let simulated = gray.simulate_cvd(cvd);
+                assert!(
+                    Color::oklab_distance(&gray, &simulated) < 0.01,
+                    "{} changed {gray} to {simulated}",
+                    cvd.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn deuteranopia_confuses_red_and_green() {
+        let red = Color::new(200, 60, 40);
+        let green = Color::new(90, 150, 40);
+        let before = Color::oklab_distance(&red, &green);
+        let after = Color::oklab_distance(
+            &red.simulate_cvd(Cvd::Deuteranopia),
+            &green.simulate_cvd(Cvd::Deuteranopia),
+        );
+        assert!(after < before / 2.0, "before {before}, after {after}");
+    }
+
+    #[test]
+    fn protanopia_confuses_red_and_green() {
+        // Red looks darker without L cones, so it matches a darker green
+        let red = Color::new(200, 60, 40);
+        let green = Color::new(60, 100, 30);
+        let before = Color::oklab_distance(&red, &green);
+        let after = Color::oklab_distance(
+            &red.simulate_cvd(Cvd::Protanopia),
+            &green.simulate_cvd(Cvd::Protanopia),
+        );
+        assert!(after < before / 2.0, "before {before}, after {after}");
+    }
+
+    #[test]
+    fn tritanopia_confuses_blue_and_green() {
+        let blue = Color::new(40, 110, 200);
+        let green = Color::new(40, 160, 120);
+        let before = Color::oklab_distance(&blue, &green);
+        let after = Color::oklab_distance(
+            &blue.simulate_cvd(Cvd::Tritanopia),
+            &green.simulate_cvd(Cvd::Tritanopia),
+        );
+        assert!(after < before / 2.0, "before {before}, after {after}");
+    }
+
+    #[test]
+    fn tritanopia_keeps_red_and_green_apart() {
+        let red = Color::new(200, 60, 40);
+        let green = Color::new(90, 150, 40);
+        let after = Color::oklab_distance(
+            &red.simulate_cvd(Cvd::Tritanopia),
+            &green.simulate_cvd(Cvd::Tritanopia),
+        );
+        assert!(after > 0.1, "distance {after}");
+    }
+
+    #[test]
+    fn wide_color_keeps_chroma_beyond_srgb() {
+        let vivid = Oklch::new(0.65, 0.3, 145.0);
+        let srgb = Color::from_oklch(vivid).to_oklch();
+        let wide = <WideColor as PaletteColor>::from_oklch(vivid).to_oklch();
+        assert!(
+            wide.chroma > srgb.chroma + 0.02,
+            "P3 chroma {} vs sRGB chroma {}",
+            wide.chroma,
+            srgb.chroma
+        );
+    }
+
+    #[test]
+    fn wide_color_round_trips_srgb_colors() {
+        for color in [
+            Color::new(0, 0, 0),
+            Color::new(255, 255, 255),
+            Color::new(220, 50, 47),
+            Color::new(38, 139, 210),
+        ] {
+            let back = WideColor::from(color).to_srgb();
+            for (a, b) in [(color.r, back.r), (color.g, back.g), (color.b, back.b)] {
+                assert!(a.abs_diff(b) <= 1, "{color} came back as {back}");
+            }
+        }
+    }
+
+    #[test]
+    fn display_p3_encoding_of_srgb_red() {
+        let [r, g, b] = WideColor::from(Color::new(255, 0, 0)).to_display_p3();
+        assert!((r - 0.9176).abs() < 0.002, "r = {r}");
+        assert!((g - 0.2003).abs() < 0.002, "g = {g}");
+        assert!((b - 0.1386).abs() < 0.002, "b = {b}");
+        assert_eq!(
+            WideColor::from(Color::new(255, 0, 0)).to_css_display_p3(),
+            "color(display-p3 0.9175 0.2003 0.1386)"
+        );
+    }
+
+    #[test]
+    fn display_p3_round_trips() {
+        let p3 = [0.1, 0.8, 0.3];
+        let back = WideColor::from_display_p3(p3).to_display_p3();
+        for (a, b) in p3.iter().zip(back) {
+            assert!((a - b).abs() < 1e-3, "{p3:?} came back as {back:?}");
+        }
+    }
+
+    #[test]
+    fn wide_contrast_matches_srgb_for_srgb_colors() {
+        let (fg, bg) = (Color::new(200, 80, 60), Color::new(20, 24, 30));
+        let srgb = Color::contrast_ratio(&fg, &bg);
+        let wide = WideColor::contrast_ratio(&fg.into(), &bg.into());
+        assert!((srgb - wide).abs() < 0.01, "sRGB {srgb}, wide {wide}");
+    }
 }
@@ -1,6 +1,13 @@
+pub mod activate;
+pub mod audit;
 pub mod backends;
+pub mod batch;
+pub mod cache;
 pub mod cli;
 pub mod color;
+pub mod msgpack;
 pub mod pipeline;
 pub mod preview;
+pub mod swatch;
 pub mod tui;
+pub mod watch;
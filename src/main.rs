@@ -1,61 +1,130 @@
-use anyhow::{bail, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
 use clap::Parser;
 
-use nuri::backends::{get_backend, ghostty, Target, ThemeBackend};
-use nuri::cli::Args;
-use nuri::pipeline::assign::assign_slots;
-use nuri::pipeline::contrast::enforce_contrast;
-use nuri::pipeline::detect::detect_mode;
-use nuri::pipeline::extract::{extract_colors, load_and_prepare};
-use nuri::{preview, tui};
+use nuri::activate::{self, App, Deactivation};
+use nuri::backends::{
+    default_theme_name, get_backend, get_backend_with, ghostty, guess_target, reload_installed,
+    BackendOptions, Target, ThemeBackend,
+};
+use nuri::batch::{self, BatchOptions};
+use nuri::cache;
+use nuri::cli::{
+    Args, AuditArgs, BatchArgs, CacheArgs, CacheCommand, Command, FixArgs, Gamut, PipelineArgs,
+    WatchArgs,
+};
+use nuri::pipeline::assign::AnsiPalette;
+use nuri::pipeline::contrast::repair_contrast;
+use nuri::pipeline::extract::{list_images, load_frames_with};
+use nuri::pipeline::{self, palette_for, Generated, PipelineOptions};
+use nuri::watch::{self, WatchOptions};
+use nuri::{audit, preview, swatch, tui};
 
 fn main() -> Result<()> {
     let args = Args::parse();
 
-    // Validate --min-contrast
-    let min_contrast = validate_min_contrast(args.min_contrast);
-
-    // 1. Load and prepare image pixels
-    let pixels = load_and_prepare(&args.image)?;
-
-    // Warn on tiny images
-    if pixels.len() < 16 {
-        eprintln!(
-            "warning: very small image ({} pixels). Theme quality may be limited.",
-            pixels.len()
-        );
+    match args.command {
+        Some(Command::Audit(audit_args)) => return run_audit(&audit_args),
+        Some(Command::Fix(fix_args)) => return run_fix(&fix_args),
+        Some(Command::Batch(batch_args)) => return run_batch(&batch_args),
+        Some(Command::Watch(watch_args)) => return run_watch(&watch_args),
+        Some(Command::Cache(cache_args)) => return run_cache(&cache_args),
+        None => {}
     }
-
-    // 2. Extract dominant colors via K-means
-    let colors = extract_colors(&pixels, args.colors);
-
-    // Warn on few extracted colors
-    if colors.len() < 6 {
-        eprintln!(
-            "warning: only {} distinct colors extracted (expected ≥ 6). \
-             Some palette slots will be synthesized.",
-            colors.len()
-        );
+    if args.deactivate {
+        return run_deactivate(&args.target);
     }
+    let options = PipelineOptions {
+        crop: args.crop,
+        exclude: args.exclude.clone(),
+        gamut: args.gamut,
+        ..pipeline_options(&args.pipeline)
+    };
 
-    // 3. Detect dark/light mode (respect --mode override)
-    let mode = args.mode.unwrap_or_else(|| detect_mode(&pixels));
-
-    // 4. Assign colors to ANSI palette slots
-    let mut palette = assign_slots(&colors, mode);
+    // Several images are blended by their --image-weight
+    let inputs = expand_inputs(&args.images, &args.image_weight)?;
+    // The first image stands in for the rest in the TUI and swatch card
+    let image = inputs[0].0.clone();
+    if args.tui && inputs.len() > 1 {
+        bail!("--tui works on a single image; got {}", inputs.len());
+    }
+    if args.per_frame && inputs.len() > 1 {
+        bail!("--per-frame works on a single image; got {}", inputs.len());
+    }
+    if args.per_frame && !args.install && args.output.is_none() {
+        bail!("--per-frame writes several themes; use --install or --output");
+    }
+    if inputs.len() > 1 {
+        eprintln!("Blending {} images", inputs.len());
+    }
 
-    // 5. Enforce WCAG contrast minimums
-    enforce_contrast(&mut palette, min_contrast);
+    // 1-5. Load, extract, detect the mode, assign slots, and enforce contrast
+    let Generated {
+        colors,
+        mode,
+        palette,
+        wide,
+        frames: loaded,
+    } = pipeline::run(&inputs, &options)?;
 
     // 6. Derive theme name
-    let name = args.name.unwrap_or_else(|| default_theme_name(&args.image));
+    let name = args
+        .name
+        .unwrap_or_else(|| default_theme_name(&args.images[0]));
+
+    // The first image's frames and, for an animation, a palette per frame in
+    // the pooled palette's mode, for the TUI and --per-frame
+    let frames = if args.tui || args.per_frame {
+        match loaded.into_iter().next() {
+            Some(frames) => frames,
+            None => load_frames_with(
+                &image,
+                options.crop.as_ref(),
+                &options.exclude,
+                options.frames,
+            )?,
+        }
+    } else {
+        Vec::new()
+    };
+    let frame_palettes: Vec<AnsiPalette> = if frames.len() > 1 {
+        let options = PipelineOptions {
+            mode: Some(mode),
+            cache: false,
+            ..options.clone()
+        };
+        frames
+            .iter()
+            .map(|(frame, _)| palette_for(frame, &options, mode))
+            .collect()
+    } else {
+        Vec::new()
+    };
 
     // 7. TUI mode: launch interactive editor
     if args.tui {
+        if wide.is_some() {
+            eprintln!("warning: the TUI exports sRGB themes; ignoring --gamut display-p3");
+        }
         let targets = args.target.clone();
-        let mut tui_app =
-            tui::TuiApp::new(palette, colors, args.image, mode, name, pixels, args.colors);
+        let animation: Vec<tui::AnimationFrame> = frames
+            .iter()
+            .zip(frame_palettes)
+            .map(|((image, delay), palette)| tui::AnimationFrame {
+                image: image.clone(),
+                palette,
+                delay: *delay,
+            })
+            .collect();
+        let (prepared, _) = frames.into_iter().next().expect("at least one frame");
+        let mut tui_app = tui::TuiApp::new(palette, colors, image, mode, name, prepared, options.k);
+        tui_app.set_weight(options.weight.clone());
         tui_app.set_targets(targets);
+        tui_app.set_cvd_safe(options.cvd_safe);
+        tui_app.set_extractor(options.extractor);
+        tui_app.set_animation(animation);
         return tui::run(tui_app);
     }
 
@@ -66,12 +135,46 @@ fn main() -> Result<()> {
     } else {
         args.target.clone()
     };
-    let backends: Vec<Box<dyn ThemeBackend>> = targets.iter().map(|t| get_backend(*t)).collect();
+    let options = BackendOptions {
+        neovim_layout: args.neovim_layout,
+    };
+    let backends: Vec<Box<dyn ThemeBackend>> = targets
+        .iter()
+        .map(|t| get_backend_with(t.clone(), &options))
+        .collect();
+    if wide.is_some() {
+        for backend in backends.iter().filter(|b| b.display_p3().is_none()) {
+            eprintln!(
+                "warning: {} themes cannot store Display P3 colors; writing sRGB",
+                backend.name()
+            );
+        }
+    }
 
     if args.preview {
         preview::print_preview(&palette);
     }
 
+    if let Some(ref path) = args.swatch {
+        let thumbnail = swatch::load_thumbnail(&image)?;
+        swatch::write_swatch(path, &palette, Some(&thumbnail))?;
+        eprintln!("Wrote swatch to {}", path.display());
+    }
+
+    if args.per_frame {
+        if frame_palettes.is_empty() {
+            eprintln!(
+                "note: {} is not animated; writing a single theme",
+                image.display()
+            );
+        } else {
+            if wide.is_some() {
+                eprintln!("warning: --per-frame writes sRGB themes; ignoring --gamut display-p3");
+            }
+            return write_frame_themes(&backends, &frame_palettes, &name, args.output.as_deref());
+        }
+    }
+
     if args.install {
         // Check --no-clobber for Ghostty targets
         if args.no_clobber && targets.contains(&Target::Ghostty) {
@@ -84,19 +187,31 @@ fn main() -> Result<()> {
                 );
             }
         }
-        for backend in &backends {
-            let installed_path = backend.install(&palette, &name)?;
+        for (target, backend) in targets.iter().zip(&backends) {
+            let installed_path = match (&wide, backend.display_p3()) {
+                (Some(wide), Some(p3)) => p3.install_display_p3(wide, &name)?,
+                _ => backend.install(&palette, &name)?,
+            };
             eprintln!(
                 "Installed {} theme '{name}' to {}",
                 backend.name(),
                 installed_path.display()
             );
+            if args.activate {
+                activate_installed(target, &options, &name, &installed_path)?;
+            }
+            if args.reload {
+                reload_installed(backend.as_ref(), &name, &installed_path);
+            }
         }
     } else if let Some(ref path) = args.output {
         if backends.len() > 1 {
             bail!("cannot use --output with multiple targets; use --install instead");
         }
-        backends[0].write_to(&palette, &name, path)?;
+        match (&wide, backends[0].display_p3()) {
+            (Some(wide), Some(p3)) => p3.write_display_p3_to(wide, &name, path)?,
+            _ => backends[0].write_to(&palette, &name, path)?,
+        }
         eprintln!("Wrote theme to {}", path.display());
     } else {
         if backends.len() > 1 {
@@ -104,12 +219,297 @@ fn main() -> Result<()> {
                 "cannot output multiple targets to stdout; use --install or specify a single --target"
             );
         }
-        print!("{}", backends[0].serialize(&palette, &name));
+        match (&wide, backends[0].display_p3()) {
+            (Some(wide), Some(p3)) => print!("{}", p3.serialize_display_p3(wide, &name)),
+            _ => print!("{}", backends[0].serialize(&palette, &name)),
+        }
     }
 
     Ok(())
 }
 
+/// Select an installed theme in its app's config, if it has one.
+fn activate_installed(
+    target: &Target,
+    options: &BackendOptions,
+    name: &str,
+    installed: &Path,
+) -> Result<()> {
+    let Some(app) = App::for_target(target, options) else {
+        match App::from_target(target) {
+            Some(app) => eprintln!(
+                "note: activating {} themes needs --neovim-layout plugin",
+                app.name()
+            ),
+            None => eprintln!(
+                "note: {} themes can't be activated",
+                get_backend_with(target.clone(), options).name()
+            ),
+        }
+        return Ok(());
+    };
+    let activation = activate::activate(app, name, installed)?;
+    let before = match &activation.previous {
+        Some(previous) => format!("was '{previous}'"),
+        None => "no theme set before".to_string(),
+    };
+    eprintln!(
+        "Activated {} theme '{}' in {} ({before})",
+        app.name(),
+        activation.theme,
+        activation.config.display()
+    );
+    Ok(())
+}
+
+/// Undo `--activate` for the given targets, or for every app.
+fn run_deactivate(targets: &[Target]) -> Result<()> {
+    let mut apps = Vec::new();
+    for target in targets {
+        match App::from_target(target) {
+            Some(app) => apps.push(app),
+            None => bail!(
+                "{} themes are never activated",
+                get_backend(target.clone()).name()
+            ),
+        }
+    }
+    let done = activate::deactivate(&apps)?;
+    if done.is_empty() {
+        eprintln!("Nothing to deactivate");
+    }
+    for deactivation in done {
+        match deactivation {
+            Deactivation::Restored(a) => match &a.previous {
+                Some(previous) => eprintln!(
+                    "Restored {} theme '{previous}' in {}",
+                    a.app.name(),
+                    a.config.display()
+                ),
+                None => eprintln!(
+                    "Removed {} theme '{}' from {}",
+                    a.app.name(),
+                    a.theme,
+                    a.config.display()
+                ),
+            },
+            Deactivation::Changed(a, current) => eprintln!(
+                "warning: {} no longer selects '{}' ({}); left as is",
+                a.config.display(),
+                a.theme,
+                current.map_or("no theme set".to_string(), |c| format!("now '{c}'"))
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Audit an existing theme file and fail if any check does.
+fn run_audit(args: &AuditArgs) -> Result<()> {
+    let palette = audit::load_theme(&args.theme, args.target.clone())?;
+    let report = audit::audit(
+        &palette,
+        validate_min_contrast(args.min_contrast),
+        args.cvd_safe,
+        args.warn_only,
+    );
+    print!("{report}");
+    match report.failures() {
+        0 => Ok(()),
+        n => bail!("{} failed {n} audit check(s)", args.theme.display()),
+    }
+}
+
+/// Repair an existing theme's contrast and write it back in its own format.
+fn run_fix(args: &FixArgs) -> Result<()> {
+    let target = args
+        .target
+        .clone()
+        .unwrap_or_else(|| guess_target(&args.theme));
+    let backend = get_backend(target);
+    let content = std::fs::read_to_string(&args.theme)
+        .with_context(|| format!("failed to read theme: {}", args.theme.display()))?;
+    let palette = backend
+        .parse(&content)
+        .with_context(|| format!("failed to parse theme: {}", args.theme.display()))?;
+
+    let mut fixed = palette.clone();
+    repair_contrast(&mut fixed, validate_min_contrast(args.min_contrast));
+
+    // Report what changed
+    let specials = [
+        ("background", palette.background, fixed.background),
+        ("foreground", palette.foreground, fixed.foreground),
+        ("cursor-color", palette.cursor_color, fixed.cursor_color),
+        (
+            "selection-foreground",
+            palette.selection_fg,
+            fixed.selection_fg,
+        ),
+    ]
+    .map(|(name, before, after)| (name.to_string(), before, after));
+    let slots = (0..16).map(|i| (format!("palette {i}"), palette.slots[i], fixed.slots[i]));
+    for (name, before, after) in specials.into_iter().chain(slots) {
+        if before != after {
+            eprintln!("{name}: {before} -> {after}");
+        }
+    }
+
+    let name = default_theme_name(&args.theme);
+    let updated = backend.update(&content, &fixed, &name)?;
+    let path = args.output.as_ref().unwrap_or(&args.theme);
+    std::fs::write(path, updated)
+        .with_context(|| format!("failed to write theme to {}", path.display()))?;
+    eprintln!("Wrote fixed theme to {}", path.display());
+    Ok(())
+}
+
+/// Generate themes for every image in a directory, then report failures.
+fn run_batch(args: &BatchArgs) -> Result<()> {
+    let options = BatchOptions {
+        targets: if args.target.is_empty() {
+            vec![Target::Ghostty]
+        } else {
+            args.target.clone()
+        },
+        backend: BackendOptions {
+            neovim_layout: args.neovim_layout,
+        },
+        install: args.install,
+        output_dir: args.output_dir.clone(),
+        skip_existing: args.skip_existing,
+        gallery: args.gallery,
+        jobs: args
+            .jobs
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get())),
+        pipeline: pipeline_options(&args.pipeline),
+    };
+    let report = batch::run(&args.dir, &options)?;
+
+    eprintln!(
+        "Generated {} themes, skipped {}, {} failed; index in {}",
+        report.generated.len(),
+        report.skipped.len(),
+        report.failed.len(),
+        args.output_dir.display()
+    );
+    for (image, error) in &report.failed {
+        eprintln!("error: {}: {error}", image.display());
+    }
+    if !report.failed.is_empty() {
+        let total = report.generated.len() + report.skipped.len() + report.failed.len();
+        bail!("{} of {total} images failed", report.failed.len());
+    }
+    Ok(())
+}
+
+/// Regenerate the theme whenever the watched wallpaper changes, until
+/// interrupted.
+fn run_watch(args: &WatchArgs) -> Result<()> {
+    if args.interval == 0 {
+        bail!("--interval must be at least 1ms");
+    }
+    watch::run(&WatchOptions {
+        path: args.path.clone(),
+        name: args
+            .name
+            .clone()
+            .unwrap_or_else(|| default_theme_name(&args.path)),
+        targets: if args.target.is_empty() {
+            vec![Target::Ghostty]
+        } else {
+            args.target.clone()
+        },
+        backend: BackendOptions {
+            neovim_layout: args.neovim_layout,
+        },
+        pipeline: pipeline_options(&args.pipeline),
+        debounce: Duration::from_millis(args.debounce),
+        interval: Duration::from_millis(args.interval),
+        poll: args.poll,
+        reload: args.reload,
+        hooks: args.hook.clone(),
+    })
+}
+
+/// Write one theme per animation frame, named `<name>-<frame>`: installed,
+/// or to `--output` with the frame number added to the file name.
+fn write_frame_themes(
+    backends: &[Box<dyn ThemeBackend>],
+    palettes: &[AnsiPalette],
+    name: &str,
+    output: Option<&Path>,
+) -> Result<()> {
+    if output.is_some() && backends.len() > 1 {
+        bail!("cannot use --output with multiple targets; use --install instead");
+    }
+    let width = palettes.len().to_string().len();
+    for (i, palette) in palettes.iter().enumerate() {
+        let frame = format!("{:0width$}", i + 1);
+        let frame_name = format!("{name}-{frame}");
+        match output {
+            Some(path) => {
+                let path = frame_output_path(path, &frame);
+                backends[0].write_to(palette, &frame_name, &path)?;
+                eprintln!("Wrote theme to {}", path.display());
+            }
+            None => {
+                for backend in backends {
+                    let installed_path = backend.install(palette, &frame_name)?;
+                    eprintln!(
+                        "Installed {} theme '{frame_name}' to {}",
+                        backend.name(),
+                        installed_path.display()
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `path` with `-<frame>` added to the file stem, e.g. `theme-03.json`.
+fn frame_output_path(path: &Path, frame: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{stem}-{frame}.{}", ext.to_string_lossy()),
+        None => format!("{stem}-{frame}"),
+    };
+    path.with_file_name(name)
+}
+
+/// Manage the extraction cache (`nuri cache clear`).
+fn run_cache(args: &CacheArgs) -> Result<()> {
+    match args.command {
+        CacheCommand::Clear => {
+            let removed = cache::clear()?;
+            eprintln!(
+                "Removed {removed} cached extraction(s) from {}",
+                cache::cache_dir()?.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Pipeline settings from the flags shared by every mode. Only the main
+/// command takes --crop, --exclude, and --gamut.
+fn pipeline_options(args: &PipelineArgs) -> PipelineOptions {
+    PipelineOptions {
+        k: args.colors,
+        extractor: args.extractor,
+        weight: args.weight.clone(),
+        mode: args.mode,
+        min_contrast: validate_min_contrast(args.min_contrast),
+        cvd_safe: args.cvd_safe,
+        frames: args.frames,
+        crop: None,
+        exclude: Vec::new(),
+        gamut: Gamut::Srgb,
+        cache: !args.no_cache,
+    }
+}
+
 /// Validate and clamp --min-contrast to [1.0, 21.0].
 fn validate_min_contrast(value: f32) -> f32 {
     if value < 1.0 {
@@ -123,10 +523,28 @@ fn validate_min_contrast(value: f32) -> f32 {
     }
 }
 
-/// Derive a theme name from the image filename stem.
-fn default_theme_name(path: &std::path::Path) -> String {
-    path.file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("theme")
-        .to_string()
+/// The images to load with their blending weights. Directories are replaced
+/// by the images inside them, each with the directory's weight.
+fn expand_inputs(paths: &[PathBuf], weights: &[f32]) -> Result<Vec<(PathBuf, f32)>> {
+    if !weights.is_empty() && weights.len() != paths.len() {
+        bail!(
+            "--image-weight has {} values for {} images",
+            weights.len(),
+            paths.len()
+        );
+    }
+    if let Some(weight) = weights.iter().find(|w| !(w.is_finite() && **w > 0.0)) {
+        bail!("--image-weight values must be positive, got {weight}");
+    }
+
+    let mut inputs = Vec::new();
+    for (i, path) in paths.iter().enumerate() {
+        let weight = weights.get(i).copied().unwrap_or(1.0);
+        if path.is_dir() {
+            inputs.extend(list_images(path)?.into_iter().map(|image| (image, weight)));
+        } else {
+            inputs.push((path.clone(), weight));
+        }
+    }
+    Ok(inputs)
 }
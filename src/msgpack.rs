@@ -0,0 +1,289 @@
+//! Just enough MessagePack to talk to Neovim's RPC API.
+
+use std::io::{self, Read, Write};
+
+/// A decoded MessagePack value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Bin(Vec<u8>),
+    Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    /// Extension type, e.g. Neovim's buffer and window handles.
+    Ext(i8, Vec<u8>),
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Str(s.to_string())
+    }
+}
+
+/// Append the encoding of `value` to `out`, always in the smallest form.
+pub fn encode(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Nil => out.push(0xc0),
+        Value::Bool(b) => out.push(if *b { 0xc3 } else { 0xc2 }),
+        Value::Int(n) => encode_int(out, *n),
+        Value::Float(f) => {
+            out.push(0xcb);
+            out.extend_from_slice(&f.to_be_bytes());
+        }
+        Value::Str(s) => {
+            encode_len(out, s.len(), Some(0xa0), [0xd9, 0xda, 0xdb]);
+            out.extend_from_slice(s.as_bytes());
+        }
+        Value::Bin(bytes) => {
+            encode_len(out, bytes.len(), None, [0xc4, 0xc5, 0xc6]);
+            out.extend_from_slice(bytes);
+        }
+        Value::Array(items) => {
+            encode_collection(out, items.len(), 0x90, [0xdc, 0xdd]);
+            for item in items {
+                encode(out, item);
+            }
+        }
+        Value::Map(entries) => {
+            encode_collection(out, entries.len(), 0x80, [0xde, 0xdf]);
+            for (key, value) in entries {
+                encode(out, key);
+                encode(out, value);
+            }
+        }
+        Value::Ext(kind, data) => {
+            encode_len(out, data.len(), None, [0xc7, 0xc8, 0xc9]);
+            out.push(*kind as u8);
+            out.extend_from_slice(data);
+        }
+    }
+}
+
+fn encode_int(out: &mut Vec<u8>, n: i64) {
+    match n {
+        0..=0x7f => out.push(n as u8),
+        -32..=-1 => out.push(n as i8 as u8),
+        _ => {
+            out.push(0xd3);
+            out.extend_from_slice(&n.to_be_bytes());
+        }
+    }
+}
+
+/// Length prefix for strings, binary, and extension data: the fix form
+/// (strings under 32 bytes only), then 8, 16, and 32-bit lengths.
+fn encode_len(out: &mut Vec<u8>, len: usize, fix: Option<u8>, markers: [u8; 3]) {
+    match (len, fix) {
+        (0..=31, Some(fix)) => out.push(fix | len as u8),
+        (0..=0xff, _) => out.extend_from_slice(&[markers[0], len as u8]),
+        (0..=0xffff, _) => {
+            out.push(markers[1]);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        _ => {
+            out.push(markers[2]);
+            out.extend_from_slice(&(len as u32).to_be_bytes());
+        }
+    }
+}
+
+fn encode_collection(out: &mut Vec<u8>, len: usize, fix: u8, markers: [u8; 2]) {
+    match len {
+        0..=15 => out.push(fix | len as u8),
+        16..=0xffff => {
+            out.push(markers[0]);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        _ => {
+            out.push(markers[1]);
+            out.extend_from_slice(&(len as u32).to_be_bytes());
+        }
+    }
+}
+
+/// Write one encoded value and flush.
+pub fn write(writer: &mut impl Write, value: &Value) -> io::Result<()> {
+    let mut out = Vec::new();
+    encode(&mut out, value);
+    writer.write_all(&out)?;
+    writer.flush()
+}
+
+/// Read one value.
+pub fn read(reader: &mut impl Read) -> io::Result<Value> {
+    let marker = read_u8(reader)?;
+    Ok(match marker {
+        0x00..=0x7f => Value::Int(i64::from(marker)),
+        0x80..=0x8f => read_map(reader, usize::from(marker & 0x0f))?,
+        0x90..=0x9f => read_array(reader, usize::from(marker & 0x0f))?,
+        0xa0..=0xbf => read_str(reader, usize::from(marker & 0x1f))?,
+        0xc0 => Value::Nil,
+        0xc2 => Value::Bool(false),
+        0xc3 => Value::Bool(true),
+        0xc4..=0xc6 => {
+            let len = read_len(reader, marker - 0xc4)?;
+            Value::Bin(read_bytes(reader, len)?)
+        }
+        0xc7..=0xc9 => {
+            let len = read_len(reader, marker - 0xc7)?;
+            read_ext(reader, len)?
+        }
+        0xca => Value::Float(f64::from(f32::from_be_bytes(read_array_of(reader)?))),
+        0xcb => Value::Float(f64::from_be_bytes(read_array_of(reader)?)),
+        0xcc => Value::Int(i64::from(read_u8(reader)?)),
+        0xcd => Value::Int(i64::from(u16::from_be_bytes(read_array_of(reader)?))),
+        0xce => Value::Int(i64::from(u32::from_be_bytes(read_array_of(reader)?))),
+        // Values past i64::MAX don't occur in Neovim's API; wrap rather than fail
+        0xcf => Value::Int(u64::from_be_bytes(read_array_of(reader)?) as i64),
+        0xd0 => Value::Int(i64::from(read_u8(reader)? as i8)),
+        0xd1 => Value::Int(i64::from(i16::from_be_bytes(read_array_of(reader)?))),
+        0xd2 => Value::Int(i64::from(i32::from_be_bytes(read_array_of(reader)?))),
+        0xd3 => Value::Int(i64::from_be_bytes(read_array_of(reader)?)),
+        0xd4..=0xd8 => read_ext(reader, 1 << (marker - 0xd4))?,
+        0xd9..=0xdb => {
+            let len = read_len(reader, marker - 0xd9)?;
+            read_str(reader, len)?
+        }
+        0xdc | 0xdd => {
+            let len = read_len(reader, marker - 0xdc + 1)?;
+            read_array(reader, len)?
+        }
+        0xde | 0xdf => {
+            let len = read_len(reader, marker - 0xde + 1)?;
+            read_map(reader, len)?
+        }
+        0xe0..=0xff => Value::Int(i64::from(marker as i8)),
+        0xc1 => return Err(invalid("reserved marker 0xc1")),
+    })
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    Ok(read_array_of::<1>(reader)?[0])
+}
+
+fn read_array_of<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// A length of 1, 2, or 4 bytes (`size` 0, 1, or 2).
+fn read_len(reader: &mut impl Read, size: u8) -> io::Result<usize> {
+    Ok(match size {
+        0 => usize::from(read_u8(reader)?),
+        1 => usize::from(u16::from_be_bytes(read_array_of(reader)?)),
+        _ => u32::from_be_bytes(read_array_of(reader)?) as usize,
+    })
+}
+
+fn read_bytes(reader: &mut impl Read, len: usize) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    reader.take(len as u64).read_to_end(&mut buf)?;
+    if buf.len() < len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(buf)
+}
+
+fn read_str(reader: &mut impl Read, len: usize) -> io::Result<Value> {
+    String::from_utf8(read_bytes(reader, len)?)
+        .map(Value::Str)
+        .map_err(|_| invalid("string is not UTF-8"))
+}
+
+fn read_ext(reader: &mut impl Read, len: usize) -> io::Result<Value> {
+    let kind = read_u8(reader)? as i8;
+    Ok(Value::Ext(kind, read_bytes(reader, len)?))
+}
+
+fn read_array(reader: &mut impl Read, len: usize) -> io::Result<Value> {
+    (0..len)
+        .map(|_| read(reader))
+        .collect::<io::Result<_>>()
+        .map(Value::Array)
+}
+
+fn read_map(reader: &mut impl Read, len: usize) -> io::Result<Value> {
+    (0..len)
+        .map(|_| Ok((read(reader)?, read(reader)?)))
+        .collect::<io::Result<_>>()
+        .map(Value::Map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(value: &Value) -> Vec<u8> {
+        let mut out = Vec::new();
+        encode(&mut out, value);
+        out
+    }
+
+    #[test]
+    fn encodes_rpc_requests_compactly() {
+        let request = Value::Array(vec![
+            Value::Int(0),
+            Value::Int(1),
+            "nvim_command".into(),
+            Value::Array(vec!["colorscheme x".into()]),
+        ]);
+        let mut expected = vec![0x94, 0x00, 0x01, 0xac];
+        expected.extend_from_slice(b"nvim_command");
+        expected.extend_from_slice(&[0x91, 0xad]);
+        expected.extend_from_slice(b"colorscheme x");
+        assert_eq!(encoded(&request), expected);
+    }
+
+    #[test]
+    fn round_trips_every_kind() {
+        let long = "x".repeat(300);
+        for value in [
+            Value::Nil,
+            Value::Bool(true),
+            Value::Int(-1),
+            Value::Int(-33),
+            Value::Int(200),
+            Value::Int(i64::MIN),
+            Value::Float(1.5),
+            Value::Str(long.clone()),
+            Value::Bin(vec![1, 2, 3]),
+            Value::Array((0..20).map(Value::Int).collect()),
+            Value::Map(vec![("k".into(), Value::Nil)]),
+            Value::Ext(1, vec![7]),
+        ] {
+            let bytes = encoded(&value);
+            assert_eq!(read(&mut bytes.as_slice()).unwrap(), value, "{bytes:x?}");
+        }
+    }
+
+    #[test]
+    fn decodes_other_integer_widths() {
+        for (bytes, n) in [
+            (&[0xcc, 0xff][..], 255),
+            (&[0xcd, 0x01, 0x00][..], 256),
+            (&[0xd0, 0x80][..], -128),
+            (&[0xd2, 0xff, 0xff, 0xff, 0xfe][..], -2),
+            (&[0xe0][..], -32),
+        ] {
+            assert_eq!(read(&mut &bytes[..]).unwrap(), Value::Int(n));
+        }
+        assert!(read(&mut &[0xa5, b'a'][..]).is_err());
+    }
+}
@@ -1,24 +1,75 @@
 use palette::Oklch;
 
 use crate::cli::ThemeMode;
-use crate::color::Color;
+use crate::color::{Color, PaletteColor, WideColor};
 use crate::pipeline::extract::ExtractedColor;
 
 /// The full ANSI palette plus special Ghostty theme colors.
-#[derive(Debug, Clone)]
-pub struct AnsiPalette {
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette<C> {
     /// ANSI colors 0-15.
-    pub slots: [Color; 16],
-    pub background: Color,
-    pub foreground: Color,
-    pub cursor_color: Color,
-    pub cursor_text: Color,
-    pub selection_bg: Color,
-    pub selection_fg: Color,
+    pub slots: [C; 16],
+    pub background: C,
+    pub foreground: C,
+    pub cursor_color: C,
+    pub cursor_text: C,
+    pub selection_bg: C,
+    pub selection_fg: C,
+}
+
+/// ANSI slot names, in slot order.
+pub const SLOT_NAMES: [&str; 16] = [
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "white",
+    "bright black",
+    "bright red",
+    "bright green",
+    "bright yellow",
+    "bright blue",
+    "bright magenta",
+    "bright cyan",
+    "bright white",
+];
+
+/// A palette of 8-bit sRGB colors, as every backend writes them.
+pub type AnsiPalette = Palette<Color>;
+
+/// A palette kept in the Display P3 gamut, for wide-gamut output.
+pub type WidePalette = Palette<WideColor>;
+
+impl<C: Copy> Palette<C> {
+    /// Apply `f` to every color.
+    pub fn map<D>(&self, f: impl Fn(C) -> D) -> Palette<D> {
+        Palette {
+            slots: self.slots.map(&f),
+            background: f(self.background),
+            foreground: f(self.foreground),
+            cursor_color: f(self.cursor_color),
+            cursor_text: f(self.cursor_text),
+            selection_bg: f(self.selection_bg),
+            selection_fg: f(self.selection_fg),
+        }
+    }
+}
+
+impl WidePalette {
+    /// Gamut-map every color into sRGB.
+    ///
+    /// Contrast was enforced on the wide colors, so callers should enforce it
+    /// again: mapping can shift luminance slightly.
+    pub fn to_srgb(&self) -> AnsiPalette {
+        self.map(WideColor::to_srgb)
+    }
 }
 
 /// Target Oklch hue angles (degrees) for the six ANSI accent slots.
-const TARGET_HUES: [(usize, f32); 6] = [
+pub const TARGET_HUES: [(usize, f32); 6] = [
     (1, 25.0),  // Red
     (2, 145.0), // Green
     (3, 90.0),  // Yellow
@@ -28,11 +79,15 @@ const TARGET_HUES: [(usize, f32); 6] = [
 ];
 
 /// Maximum hue distance (degrees) before we synthesize instead of using the candidate.
-const MAX_HUE_DISTANCE: f32 = 60.0;
+pub const MAX_HUE_DISTANCE: f32 = 60.0;
 
 /// Oklch lightness increase for bright variants (slots 9-14).
 const BRIGHT_L_DELTA: f32 = 0.12;
 
+/// Upper Oklch lightness bound for bright variants. Gamut mapping turns
+/// anything at L = 1 into plain white, which would lose the accent's hue.
+const BRIGHT_MAX_L: f32 = 0.95;
+
 /// Minimum Oklch chroma to consider a candidate chromatic (not gray).
 const MIN_CHROMA: f32 = 0.02;
 
@@ -43,7 +98,7 @@ const BASE_MAX_CHROMA: f32 = 0.04;
 const TEXT_MAX_CHROMA: f32 = 0.02;
 
 /// Angular distance between two hue values, wrapped to [0, 180].
-fn hue_distance(a: f32, b: f32) -> f32 {
+pub fn hue_distance(a: f32, b: f32) -> f32 {
     let diff = (a - b).abs() % 360.0;
     if diff > 180.0 {
         360.0 - diff
@@ -54,21 +109,55 @@ fn hue_distance(a: f32, b: f32) -> f32 {
 
 /// Map extracted colors to the 16 ANSI palette slots plus special colors.
 pub fn assign_slots(colors: &[ExtractedColor], mode: ThemeMode) -> AnsiPalette {
-    let mut slots = [Color::new(0, 0, 0); 16];
-
     let oklch_colors: Vec<Oklch> = colors.iter().map(|ec| ec.color.to_oklch()).collect();
+    assign(&oklch_colors, mode)
+}
+
+/// Like [`assign_slots`], but keeps colors that sRGB cannot show. Starts from
+/// the unrounded centroids, where [`assign_slots`] starts from the sRGB colors
+/// the TUI shows.
+pub fn assign_slots_wide(colors: &[ExtractedColor], mode: ThemeMode) -> WidePalette {
+    let oklch_colors: Vec<Oklch> = colors.iter().map(ExtractedColor::to_oklch).collect();
+    assign(&oklch_colors, mode)
+}
+
+fn assign<C: PaletteColor>(oklch_colors: &[Oklch], mode: ThemeMode) -> Palette<C> {
+    let mut slots = [C::from_oklch(Oklch::new(0.0, 0.0, 0.0)); 16];
 
-    assign_accents(&oklch_colors, &mut slots);
-    assign_base_colors(&oklch_colors, mode, &mut slots);
+    assign_accents(oklch_colors, &mut slots);
+    assign_base_colors(oklch_colors, mode, &mut slots);
     assign_bright_variants(&mut slots);
     derive_special_colors(slots, mode)
 }
 
+/// Build a full palette from the eight base colors, for formats that store
+/// only those (e.g. Zellij).
+///
+/// Bright accents are derived as in [`assign_slots`], bright black sits halfway
+/// between black and white, and bright white is the foreground.
+pub fn complete_palette(base: [Color; 8], background: Color, foreground: Color) -> AnsiPalette {
+    let mut slots = [Color::new(0, 0, 0); 16];
+    slots[..8].copy_from_slice(&base);
+    slots[8] = base[0].lerp_oklch(base[7], 0.5);
+    slots[15] = foreground;
+    assign_bright_variants(&mut slots);
+
+    let mode = if background.relative_luminance() < 0.5 {
+        ThemeMode::Dark
+    } else {
+        ThemeMode::Light
+    };
+    let mut palette = derive_special_colors(slots, mode);
+    palette.background = background;
+    palette.cursor_text = background;
+    palette
+}
+
 /// Assign accent colors (slots 1-6) by hue proximity to target hues.
 ///
 /// If no candidate is within [`MAX_HUE_DISTANCE`] of a target, the nearest
 /// candidate's hue is rotated to the target in Oklch space (synthesis).
-fn assign_accents(candidates: &[Oklch], slots: &mut [Color; 16]) {
+fn assign_accents<C: PaletteColor>(candidates: &[Oklch], slots: &mut [C; 16]) {
     let chromatic: Vec<Oklch> = candidates
         .iter()
         .copied()
@@ -79,15 +168,15 @@ fn assign_accents(candidates: &[Oklch], slots: &mut [Color; 16]) {
         if let Some(best) = find_closest_by_hue(&chromatic, target_hue) {
             let dist = hue_distance(f32::from(best.hue), target_hue);
             if dist <= MAX_HUE_DISTANCE {
-                slots[slot] = Color::from_oklch(best);
+                slots[slot] = C::from_oklch(best);
             } else {
                 // Synthesize: rotate the nearest candidate's hue to the target
                 let synth = Oklch::new(best.l, best.chroma, target_hue);
-                slots[slot] = Color::from_oklch(synth);
+                slots[slot] = C::from_oklch(synth);
             }
         } else {
             // No chromatic candidates — fully synthetic fallback
-            slots[slot] = Color::from_oklch(Oklch::new(0.65, 0.15, target_hue));
+            slots[slot] = C::from_oklch(Oklch::new(0.65, 0.15, target_hue));
         }
     }
 }
@@ -105,7 +194,7 @@ fn find_closest_by_hue(candidates: &[Oklch], target_hue: f32) -> Option<Oklch> {
 ///
 /// Dark mode: slot 0 = darkest (L ≤ 0.15), slot 15 = lightest (L ~ 0.93).
 /// Light mode: inverted — slot 0 = lightest, slot 15 = darkest.
-fn assign_base_colors(candidates: &[Oklch], mode: ThemeMode, slots: &mut [Color; 16]) {
+fn assign_base_colors<C: PaletteColor>(candidates: &[Oklch], mode: ThemeMode, slots: &mut [C; 16]) {
     let darkest = candidates
         .iter()
         .copied()
@@ -121,25 +210,25 @@ fn assign_base_colors(candidates: &[Oklch], mode: ThemeMode, slots: &mut [Color;
     match mode {
         ThemeMode::Dark => {
             // Slot 0 (black): darkest candidate, clamped to L ≤ 0.15
-            slots[0] = Color::from_oklch(Oklch::new(
+            slots[0] = C::from_oklch(Oklch::new(
                 dark_base.l.min(0.15),
                 dark_base.chroma.min(BASE_MAX_CHROMA),
                 dark_base.hue,
             ));
             // Slot 7 (white): light text, L ~ 0.85
-            slots[7] = Color::from_oklch(Oklch::new(
+            slots[7] = C::from_oklch(Oklch::new(
                 0.85,
                 light_base.chroma.min(TEXT_MAX_CHROMA),
                 light_base.hue,
             ));
             // Slot 8 (bright black): dim text / comments, L ~ 0.40
-            slots[8] = Color::from_oklch(Oklch::new(
+            slots[8] = C::from_oklch(Oklch::new(
                 0.40,
                 dark_base.chroma.min(BASE_MAX_CHROMA),
                 dark_base.hue,
             ));
             // Slot 15 (bright white): brightest text, L ~ 0.93
-            slots[15] = Color::from_oklch(Oklch::new(
+            slots[15] = C::from_oklch(Oklch::new(
                 0.93,
                 light_base.chroma.min(TEXT_MAX_CHROMA),
                 light_base.hue,
@@ -147,22 +236,22 @@ fn assign_base_colors(candidates: &[Oklch], mode: ThemeMode, slots: &mut [Color;
         }
         ThemeMode::Light => {
             // Inverted: slot 0 = lightest (background), slot 15 = darkest (foreground)
-            slots[0] = Color::from_oklch(Oklch::new(
+            slots[0] = C::from_oklch(Oklch::new(
                 light_base.l.max(0.93),
                 light_base.chroma.min(TEXT_MAX_CHROMA),
                 light_base.hue,
             ));
-            slots[7] = Color::from_oklch(Oklch::new(
+            slots[7] = C::from_oklch(Oklch::new(
                 0.20,
                 dark_base.chroma.min(TEXT_MAX_CHROMA),
                 dark_base.hue,
             ));
-            slots[8] = Color::from_oklch(Oklch::new(
+            slots[8] = C::from_oklch(Oklch::new(
                 0.60,
                 light_base.chroma.min(BASE_MAX_CHROMA),
                 light_base.hue,
             ));
-            slots[15] = Color::from_oklch(Oklch::new(
+            slots[15] = C::from_oklch(Oklch::new(
                 dark_base.l.min(0.15),
                 dark_base.chroma.min(TEXT_MAX_CHROMA),
                 dark_base.hue,
@@ -172,9 +261,11 @@ fn assign_base_colors(candidates: &[Oklch], mode: ThemeMode, slots: &mut [Color;
 }
 
 /// Generate bright variants (slots 9-14) from normal accents (slots 1-6).
-fn assign_bright_variants(slots: &mut [Color; 16]) {
+fn assign_bright_variants<C: PaletteColor>(slots: &mut [C; 16]) {
     for i in 1..=6 {
-        slots[i + 8] = slots[i].adjust_lightness(BRIGHT_L_DELTA);
+        let mut oklch = slots[i].to_oklch();
+        oklch.l = (oklch.l + BRIGHT_L_DELTA).min(BRIGHT_MAX_L.max(oklch.l));
+        slots[i + 8] = C::from_oklch(oklch);
     }
 }
 
@@ -182,7 +273,7 @@ fn assign_bright_variants(slots: &mut [Color; 16]) {
 ///
 /// Background = slot 0, foreground = slot 15 in both modes. The base color
 /// inversion ensures slot 0 is dark in dark mode and light in light mode.
-fn derive_special_colors(slots: [Color; 16], mode: ThemeMode) -> AnsiPalette {
+fn derive_special_colors<C: PaletteColor>(slots: [C; 16], mode: ThemeMode) -> Palette<C> {
     let background = slots[0];
     let foreground = slots[15];
     let cursor_color = foreground;
@@ -194,10 +285,10 @@ fn derive_special_colors(slots: [Color; 16], mode: ThemeMode) -> AnsiPalette {
         ThemeMode::Dark => (sel.l + 0.1).min(1.0),
         ThemeMode::Light => (sel.l - 0.1).max(0.0),
     };
-    let selection_bg = Color::from_oklch(Oklch::new(sel_l, (sel.chroma * 0.6).max(0.01), sel.hue));
+    let selection_bg = C::from_oklch(Oklch::new(sel_l, (sel.chroma * 0.6).max(0.01), sel.hue));
     let selection_fg = foreground;
 
-    AnsiPalette {
+    Palette {
         slots,
         background,
         foreground,
@@ -208,16 +299,40 @@ fn derive_special_colors(slots: [Color; 16], mode: ThemeMode) -> AnsiPalette {
     }
 }
 
+/// Candidates and palettes shared by the unit tests.
 #[cfg(test)]
-mod tests {
+pub(crate) mod test_support {
     use super::*;
 
-    fn make_extracted(l: f32, chroma: f32, hue: f32, weight: f32) -> ExtractedColor {
-        ExtractedColor {
-            color: Color::from_oklch(Oklch::new(l, chroma, hue)),
+    pub fn make_extracted(l: f32, chroma: f32, hue: f32, weight: f32) -> ExtractedColor {
+        ExtractedColor::new(
+            Color::from_oklch(Oklch::new(l, chroma, hue)).to_lab(),
             weight,
-        }
+        )
+    }
+
+    /// A dark palette from six saturated hues and two near-neutral bases,
+    /// before contrast enforcement.
+    pub fn test_palette() -> AnsiPalette {
+        let colors = vec![
+            make_extracted(0.60, 0.20, 25.0, 0.12),
+            make_extracted(0.60, 0.20, 145.0, 0.12),
+            make_extracted(0.70, 0.20, 90.0, 0.12),
+            make_extracted(0.55, 0.20, 260.0, 0.12),
+            make_extracted(0.60, 0.20, 325.0, 0.12),
+            make_extracted(0.65, 0.20, 195.0, 0.10),
+            make_extracted(0.10, 0.01, 0.0, 0.15),
+            make_extracted(0.95, 0.01, 0.0, 0.15),
+        ];
+        assign_slots(&colors, ThemeMode::Dark)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::make_extracted;
+    use super::*;
+    use palette::{IntoColor, Lab};
 
     fn diverse_candidates() -> Vec<ExtractedColor> {
         vec![
@@ -382,4 +497,61 @@ mod tests {
             let _ = format!("slot {i}: {color}");
         }
     }
+
+    #[test]
+    fn complete_palette_fills_bright_row() {
+        let full = assign_slots(&diverse_candidates(), ThemeMode::Dark);
+        let base: [Color; 8] = full.slots[..8].try_into().unwrap();
+        let palette = complete_palette(base, full.background, full.foreground);
+
+        assert_eq!(palette.slots[..8], full.slots[..8]);
+        assert_eq!(palette.slots[9..15], full.slots[9..15]);
+        assert_eq!(palette.slots[15], full.foreground);
+        assert_eq!(palette.selection_bg, full.selection_bg);
+        let l8 = palette.slots[8].to_oklch().l;
+        assert!(l8 > base[0].to_oklch().l && l8 < base[7].to_oklch().l);
+    }
+
+    #[test]
+    fn wide_assignment_agrees_with_srgb_inside_the_gamut() {
+        let srgb = assign_slots(&diverse_candidates(), ThemeMode::Dark);
+        let wide = assign_slots_wide(&diverse_candidates(), ThemeMode::Dark).to_srgb();
+        // Accents and base colors come straight from sRGB candidates
+        for slot in 0..=8 {
+            let (a, b) = (srgb.slots[slot], wide.slots[slot]);
+            for (x, y) in [(a.r, b.r), (a.g, b.g), (a.b, b.b)] {
+                assert!(x.abs_diff(y) <= 1, "slot {slot}: {a} vs {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn wide_bright_variants_keep_more_chroma() {
+        let srgb = assign_slots(&diverse_candidates(), ThemeMode::Dark);
+        let wide = assign_slots_wide(&diverse_candidates(), ThemeMode::Dark);
+        let srgb_chroma: f32 = (9..=14).map(|i| srgb.slots[i].to_oklch().chroma).sum();
+        let wide_chroma: f32 = (9..=14).map(|i| wide.slots[i].to_oklch().chroma).sum();
+        assert!(
+            wide_chroma > srgb_chroma,
+            "wide {wide_chroma} vs sRGB {srgb_chroma}"
+        );
+    }
+
+    #[test]
+    fn wide_assignment_starts_from_unrounded_centroids() {
+        // A green centroid beyond sRGB, which rounding would clip
+        let lab: Lab = Oklch::new(0.70, 0.30, 145.0).into_color();
+        let mut candidates = diverse_candidates();
+        candidates[1] = ExtractedColor::new(lab, 0.12);
+        let srgb = assign_slots(&candidates, ThemeMode::Dark);
+        let wide = assign_slots_wide(&candidates, ThemeMode::Dark);
+        let (srgb_chroma, wide_chroma) = (
+            srgb.slots[2].to_oklch().chroma,
+            wide.slots[2].to_oklch().chroma,
+        );
+        assert!(
+            wide_chroma > srgb_chroma + 0.02,
+            "wide {wide_chroma} vs sRGB {srgb_chroma}"
+        );
+    }
 }
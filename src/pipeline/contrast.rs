@@ -1,5 +1,5 @@
-use crate::color::Color;
-use crate::pipeline::assign::AnsiPalette;
+use crate::color::PaletteColor;
+use crate::pipeline::assign::{AnsiPalette, Palette};
 
 /// Minimum contrast ratio for accent colors (slots 1-6, 9-14) vs background.
 const ACCENT_MIN_CONTRAST: f32 = 4.5;
@@ -10,6 +10,10 @@ const FOREGROUND_MIN_CONTRAST: f32 = 7.0;
 /// Minimum contrast ratio for bright black (slot 8) vs background.
 const BRIGHT_BLACK_MIN_CONTRAST: f32 = 3.0;
 
+/// Minimum contrast ratio for selection text vs selection background (WCAG
+/// large-text / UI component level).
+pub const SELECTION_MIN_CONTRAST: f32 = 3.0;
+
 /// Oklch lightness adjustment step per iteration.
 const L_STEP: f32 = 0.01;
 
@@ -26,13 +30,19 @@ pub const DEFAULT_ACCENT_CONTRAST: f32 = ACCENT_MIN_CONTRAST;
 /// darken for light themes.
 ///
 /// `accent_min` overrides the accent contrast threshold (default 4.5:1).
-pub fn enforce_contrast(palette: &mut AnsiPalette, accent_min: f32) {
+pub fn enforce_contrast<C: PaletteColor>(palette: &mut Palette<C>, accent_min: f32) {
+    enforce_slot_contrast(palette, accent_min);
+    palette.foreground = palette.slots[15];
+    palette.cursor_color = palette.foreground;
+    palette.selection_fg = palette.foreground;
+    enforce_selection_contrast(palette);
+}
+
+/// The slot pass shared by [`enforce_contrast`] and [`repair_contrast`]:
+/// accents, slot 15, and bright black against the background.
+fn enforce_slot_contrast<C: PaletteColor>(palette: &mut Palette<C>, accent_min: f32) {
     let bg = palette.background;
-    let l_direction = if bg.relative_luminance() < 0.5 {
-        L_STEP
-    } else {
-        -L_STEP
-    };
+    let l_direction = lightness_direction(bg);
 
     // Accent colors (slots 1-6, 9-14) vs background: ≥ accent_min
     for slot in (1..=6).chain(9..=14) {
@@ -42,20 +52,72 @@ pub fn enforce_contrast(palette: &mut AnsiPalette, accent_min: f32) {
     // Foreground (slot 15) vs background: ≥ 7:1
     palette.slots[15] =
         adjust_to_contrast(palette.slots[15], bg, FOREGROUND_MIN_CONTRAST, l_direction);
-    palette.foreground = palette.slots[15];
-    palette.cursor_color = palette.foreground;
-    palette.selection_fg = palette.foreground;
 
     // Bright black (slot 8) vs background: ≥ 3:1
     palette.slots[8] =
         adjust_to_contrast(palette.slots[8], bg, BRIGHT_BLACK_MIN_CONTRAST, l_direction);
 }
 
+/// Lighten on dark backgrounds, darken on light ones.
+fn lightness_direction<C: PaletteColor>(background: C) -> f32 {
+    if background.relative_luminance() < 0.5 {
+        L_STEP
+    } else {
+        -L_STEP
+    }
+}
+
+/// Selection text vs selection background: ≥ 3:1. The selection background
+/// moves away from the text, toward the theme background, so the text keeps
+/// the contrast it has everywhere else.
+fn enforce_selection_contrast<C: PaletteColor>(palette: &mut Palette<C>) {
+    let l_direction =
+        if palette.selection_fg.relative_luminance() > palette.selection_bg.relative_luminance() {
+            -L_STEP
+        } else {
+            L_STEP
+        };
+    palette.selection_bg = adjust_to_contrast(
+        palette.selection_bg,
+        palette.selection_fg,
+        SELECTION_MIN_CONTRAST,
+        l_direction,
+    );
+}
+
+/// Variant of [`enforce_contrast`] for existing themes that nuri did not
+/// generate.
+///
+/// `enforce_contrast` ties the foreground, cursor, and selection text to
+/// slot 15, as generated palettes do. Imported themes often keep them apart, so
+/// here the foreground is checked on its own, and the cursor and selection text
+/// only follow it if they matched it to begin with. The selection background is
+/// then checked once, against the selection text the theme ends up with.
+pub fn repair_contrast(palette: &mut AnsiPalette, accent_min: f32) {
+    enforce_slot_contrast(palette, accent_min);
+
+    let original_foreground = palette.foreground;
+    let foreground = adjust_to_contrast(
+        original_foreground,
+        palette.background,
+        FOREGROUND_MIN_CONTRAST,
+        lightness_direction(palette.background),
+    );
+    palette.foreground = foreground;
+    if palette.cursor_color == original_foreground {
+        palette.cursor_color = foreground;
+    }
+    if palette.selection_fg == original_foreground {
+        palette.selection_fg = foreground;
+    }
+    enforce_selection_contrast(palette);
+}
+
 /// Iteratively adjust a color's Oklch lightness until it meets the contrast target.
-fn adjust_to_contrast(color: Color, background: Color, min_ratio: f32, l_step: f32) -> Color {
+fn adjust_to_contrast<C: PaletteColor>(color: C, background: C, min_ratio: f32, l_step: f32) -> C {
     let mut current = color;
     for _ in 0..MAX_ITERATIONS {
-        if Color::contrast_ratio(&current, &background) >= min_ratio {
+        if C::contrast_ratio(&current, &background) >= min_ratio {
             return current;
         }
         current = current.adjust_lightness(l_step);
@@ -67,16 +129,9 @@ fn adjust_to_contrast(color: Color, background: Color, min_ratio: f32, l_step: f
 mod tests {
     use super::*;
     use crate::cli::ThemeMode;
+    use crate::color::Color;
     use crate::pipeline::assign::assign_slots;
-    use crate::pipeline::extract::ExtractedColor;
-    use palette::Oklch;
-
-    fn make_extracted(l: f32, chroma: f32, hue: f32, weight: f32) -> ExtractedColor {
-        ExtractedColor {
-            color: Color::from_oklch(Oklch::new(l, chroma, hue)),
-            weight,
-        }
-    }
+    use crate::pipeline::assign::test_support::make_extracted;
 
     #[test]
     fn low_contrast_accent_gets_adjusted() {
@@ -257,4 +312,92 @@ mod tests {
             "cursor_color should be synced with foreground"
         );
     }
+
+    #[test]
+    fn selection_text_stays_readable() {
+        for mode in [ThemeMode::Dark, ThemeMode::Light] {
+            // A light blue selection under light text, and its light-mode twin
+            let colors = vec![
+                make_extracted(0.60, 0.20, 25.0, 0.12),
+                make_extracted(0.60, 0.20, 145.0, 0.12),
+                make_extracted(0.70, 0.20, 90.0, 0.12),
+                make_extracted(0.85, 0.10, 260.0, 0.12),
+                make_extracted(0.60, 0.20, 325.0, 0.12),
+                make_extracted(0.65, 0.20, 195.0, 0.10),
+                make_extracted(0.10, 0.01, 0.0, 0.15),
+                make_extracted(0.95, 0.01, 0.0, 0.15),
+            ];
+            let mut palette = assign_slots(&colors, mode);
+            palette.selection_bg = palette.foreground.adjust_lightness(match mode {
+                ThemeMode::Dark => -0.1,
+                ThemeMode::Light => 0.1,
+            });
+            assert!(Color::contrast_ratio(&palette.selection_fg, &palette.selection_bg) < 3.0);
+
+            enforce_contrast(&mut palette, ACCENT_MIN_CONTRAST);
+
+            let ratio = Color::contrast_ratio(&palette.selection_fg, &palette.selection_bg);
+            assert!(ratio >= SELECTION_MIN_CONTRAST, "{mode:?}: {ratio:.2}");
+        }
+    }
+
+    #[test]
+    fn repair_keeps_foreground_apart_from_slot_15() {
+        let colors = vec![
+            make_extracted(0.60, 0.20, 25.0, 0.12),
+            make_extracted(0.60, 0.20, 145.0, 0.12),
+            make_extracted(0.70, 0.20, 90.0, 0.12),
+            make_extracted(0.55, 0.20, 260.0, 0.12),
+            make_extracted(0.60, 0.20, 325.0, 0.12),
+            make_extracted(0.65, 0.20, 195.0, 0.10),
+            make_extracted(0.10, 0.01, 0.0, 0.15),
+            make_extracted(0.95, 0.01, 0.0, 0.15),
+        ];
+        let mut palette = assign_slots(&colors, ThemeMode::Dark);
+        // A hand-made theme: dim foreground, distinct cursor, unreadable comments
+        palette.foreground = Color::new(0x70, 0x70, 0x70);
+        palette.cursor_color = Color::new(0xff, 0x80, 0x00);
+        palette.selection_fg = palette.foreground;
+        palette.slots[8] = palette.background.adjust_lightness(0.05);
+
+        repair_contrast(&mut palette, ACCENT_MIN_CONTRAST);
+
+        assert_ne!(palette.foreground, palette.slots[15]);
+        assert!(Color::contrast_ratio(&palette.foreground, &palette.background) >= 7.0);
+        assert_eq!(palette.cursor_color, Color::new(0xff, 0x80, 0x00));
+        assert_eq!(palette.selection_fg, palette.foreground);
+        assert!(Color::contrast_ratio(&palette.slots[8], &palette.background) >= 3.0);
+        assert!(
+            Color::contrast_ratio(&palette.selection_fg, &palette.selection_bg)
+                >= SELECTION_MIN_CONTRAST
+        );
+    }
+
+    #[test]
+    fn repair_leaves_a_readable_selection_alone() {
+        let colors = vec![
+            make_extracted(0.60, 0.20, 25.0, 0.12),
+            make_extracted(0.60, 0.20, 145.0, 0.12),
+            make_extracted(0.70, 0.20, 90.0, 0.12),
+            make_extracted(0.55, 0.20, 260.0, 0.12),
+            make_extracted(0.60, 0.20, 325.0, 0.12),
+            make_extracted(0.65, 0.20, 195.0, 0.10),
+            make_extracted(0.10, 0.01, 0.0, 0.15),
+            make_extracted(0.95, 0.01, 0.0, 0.15),
+        ];
+        let mut palette = assign_slots(&colors, ThemeMode::Dark);
+        // Dark text on a light selection: readable, but slot 15 is light too,
+        // so checking the selection against slot 15 would move it
+        palette.selection_fg = Color::new(0x10, 0x10, 0x10);
+        palette.selection_bg = Color::new(0xc0, 0xc0, 0x60);
+        assert!(
+            Color::contrast_ratio(&palette.slots[15], &palette.selection_bg)
+                < SELECTION_MIN_CONTRAST
+        );
+        let selection = (palette.selection_fg, palette.selection_bg);
+
+        repair_contrast(&mut palette, ACCENT_MIN_CONTRAST);
+
+        assert_eq!((palette.selection_fg, palette.selection_bg), selection);
+    }
 }
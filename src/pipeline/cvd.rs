@@ -0,0 +1,230 @@
+use crate::color::{Cvd, PaletteColor};
+use crate::pipeline::assign::{AnsiPalette, Palette};
+
+/// Accents closer than this in Oklab under a simulated deficiency are
+/// considered confusable.
+pub const CVD_MIN_DISTANCE: f32 = 0.03;
+
+/// Accent pairs that must stay apart: red/green (diff colors) and
+/// blue/magenta, in both the normal and the bright row.
+pub const CVD_PAIRS: [(usize, usize); 4] = [(1, 2), (4, 5), (9, 10), (12, 13)];
+
+/// Oklch lightness adjustment step per iteration.
+const L_STEP: f32 = 0.01;
+
+/// Maximum adjustment iterations per pair.
+const MAX_ITERATIONS: usize = 100;
+
+/// Smallest Oklab distance between two colors across all simulated
+/// deficiencies, with the deficiency it occurs under.
+pub fn min_cvd_distance<C: PaletteColor>(a: &C, b: &C) -> (Cvd, f32) {
+    Cvd::ALL
+        .into_iter()
+        .map(|cvd| {
+            let distance = C::oklab_distance(&a.simulate_cvd(cvd), &b.simulate_cvd(cvd));
+            (cvd, distance)
+        })
+        .min_by(|x, y| x.1.total_cmp(&y.1))
+        .expect("Cvd::ALL is not empty")
+}
+
+/// The palette as seen with the given deficiency, for previewing.
+pub fn simulate_palette(palette: &AnsiPalette, cvd: Cvd) -> AnsiPalette {
+    palette.map(|c| c.simulate_cvd(cvd))
+}
+
+/// Nudge accents until red/green and blue/magenta stay distinguishable under
+/// every simulated deficiency.
+///
+/// Meant to run after [`enforce_contrast`](super::contrast::enforce_contrast).
+/// Lightness is the one cue all three deficiencies keep, so only Oklch
+/// lightness is adjusted: the accent that already stands out more from the
+/// background moves further away from it. If it runs out of range, the other
+/// accent moves toward the background, but only while it still meets
+/// `accent_min`.
+///
+/// Returns the pairs that could not be separated, each with the deficiency
+/// it stays confusable under.
+pub fn enforce_cvd_safety<C: PaletteColor>(
+    palette: &mut Palette<C>,
+    accent_min: f32,
+) -> Vec<(Cvd, usize, usize)> {
+    let mut unresolved = Vec::new();
+    let bg = palette.background;
+    let away = if bg.relative_luminance() < 0.5 {
+        L_STEP
+    } else {
+        -L_STEP
+    };
+
+    for (a, b) in CVD_PAIRS {
+        let (far, near) = if C::contrast_ratio(&palette.slots[a], &bg)
+            >= C::contrast_ratio(&palette.slots[b], &bg)
+        {
+            (a, b)
+        } else {
+            (b, a)
+        };
+
+        for _ in 0..MAX_ITERATIONS {
+            let (_, distance) = min_cvd_distance(&palette.slots[a], &palette.slots[b]);
+            if distance >= CVD_MIN_DISTANCE {
+                break;
+            }
+
+            let pushed = palette.slots[far].adjust_lightness(away);
+            if pushed != palette.slots[far] {
+                palette.slots[far] = pushed;
+                continue;
+            }
+
+            let pulled = palette.slots[near].adjust_lightness(-away);
+            if pulled == palette.slots[near] || C::contrast_ratio(&pulled, &bg) < accent_min {
+                break;
+            }
+            palette.slots[near] = pulled;
+        }
+
+        let (cvd, distance) = min_cvd_distance(&palette.slots[a], &palette.slots[b]);
+        if distance < CVD_MIN_DISTANCE {
+            unresolved.push((cvd, a, b));
+        }
+    }
+    unresolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::ThemeMode;
+    use crate::color::Color;
+    use crate::pipeline::assign::assign_slots;
+    use crate::pipeline::assign::test_support::make_extracted;
+    use crate::pipeline::contrast::{enforce_contrast, DEFAULT_ACCENT_CONTRAST};
+
+    fn test_palette(mode: ThemeMode) -> AnsiPalette {
+        let colors = vec![
+            make_extracted(0.70, 0.20, 25.0, 0.12),
+            make_extracted(0.70, 0.20, 145.0, 0.12),
+            make_extracted(0.80, 0.20, 90.0, 0.12),
+            make_extracted(0.65, 0.20, 260.0, 0.12),
+            make_extracted(0.70, 0.20, 325.0, 0.12),
+            make_extracted(0.75, 0.20, 195.0, 0.10),
+            make_extracted(0.05, 0.01, 0.0, 0.15),
+            make_extracted(0.97, 0.01, 0.0, 0.15),
+        ];
+        let mut palette = assign_slots(&colors, mode);
+        enforce_contrast(&mut palette, DEFAULT_ACCENT_CONTRAST);
+        palette
+    }
+
+    #[test]
+    fn confusable_pairs_are_pulled_apart() {
+        for mode in [ThemeMode::Dark, ThemeMode::Light] {
+            let mut palette = test_palette(mode);
+            // Identical for deuteranopes and protanopes respectively
+            palette.slots[1] = Color::new(220, 90, 70);
+            palette.slots[2] = Color::new(130, 150, 60);
+            palette.slots[4] = Color::new(80, 130, 230);
+            palette.slots[5] = Color::new(180, 70, 230);
+            assert!(min_cvd_distance(&palette.slots[1], &palette.slots[2]).1 < CVD_MIN_DISTANCE);
+            assert!(min_cvd_distance(&palette.slots[4], &palette.slots[5]).1 < CVD_MIN_DISTANCE);
+            let before = palette.slots;
+
+            let unresolved = enforce_cvd_safety(&mut palette, DEFAULT_ACCENT_CONTRAST);
+
+            assert!(unresolved.is_empty(), "{mode:?}: {unresolved:?}");
+            for (a, b) in CVD_PAIRS {
+                let (cvd, distance) = min_cvd_distance(&palette.slots[a], &palette.slots[b]);
+                assert!(
+                    distance >= CVD_MIN_DISTANCE,
+                    "{mode:?}: slots {a} and {b} still confusable under {} ({distance:.3})",
+                    cvd.name()
+                );
+            }
+            // Contrast only drops where there was room above the minimum
+            for slot in (1..=6).chain(9..=14) {
+                let ratio = Color::contrast_ratio(&palette.slots[slot], &palette.background);
+                let ratio_before = Color::contrast_ratio(&before[slot], &palette.background);
+                assert!(
+                    ratio >= ratio_before.min(DEFAULT_ACCENT_CONTRAST),
+                    "{mode:?}: slot {slot} dropped from {ratio_before:.2}:1 to {ratio:.2}:1"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn inseparable_pairs_are_reported() {
+        let mut palette = test_palette(ThemeMode::Dark);
+        // The same color twice, already as light as it gets, with no room to
+        // pull either accent toward the background
+        palette.slots[1] = Color::new(255, 255, 255);
+        palette.slots[2] = Color::new(255, 255, 255);
+
+        let unresolved = enforce_cvd_safety(&mut palette, 21.0);
+
+        assert!(unresolved.iter().any(|&(_, a, b)| (a, b) == (1, 2)));
+        for (cvd, a, b) in unresolved {
+            let (_, distance) = min_cvd_distance(&palette.slots[a], &palette.slots[b]);
+            assert!(distance < CVD_MIN_DISTANCE, "{} {a}/{b}", cvd.name());
+        }
+    }
+
+    #[test]
+    fn hue_preserved_when_nudging() {
+        let mut palette = test_palette(ThemeMode::Dark);
+        palette.slots[1] = Color::new(220, 90, 70);
+        palette.slots[2] = Color::new(130, 150, 60);
+        let hues_before = [1, 2].map(|s| palette.slots[s].to_oklch().hue.into_positive_degrees());
+
+        enforce_cvd_safety(&mut palette, DEFAULT_ACCENT_CONTRAST);
+
+        for (slot, before) in [1, 2].into_iter().zip(hues_before) {
+            let after = palette.slots[slot].to_oklch().hue.into_positive_degrees();
+            let diff = (after - before).abs().min(360.0 - (after - before).abs());
+            assert!(
+                diff < 10.0,
+                "slot {slot} hue moved {before:.1} -> {after:.1}"
+            );
+        }
+    }
+
+    #[test]
+    fn distinguishable_palette_unchanged() {
+        let mut palette = test_palette(ThemeMode::Dark);
+        palette.slots[1] = Color::new(230, 80, 70);
+        palette.slots[2] = Color::new(90, 220, 120);
+        palette.slots[4] = Color::new(80, 130, 230);
+        palette.slots[5] = Color::new(230, 110, 200);
+        palette.slots[9] = palette.slots[1];
+        palette.slots[10] = palette.slots[2];
+        palette.slots[12] = palette.slots[4];
+        palette.slots[13] = palette.slots[5];
+        for (a, b) in CVD_PAIRS {
+            assert!(min_cvd_distance(&palette.slots[a], &palette.slots[b]).1 >= CVD_MIN_DISTANCE);
+        }
+        let before = palette.slots;
+
+        enforce_cvd_safety(&mut palette, DEFAULT_ACCENT_CONTRAST);
+
+        assert_eq!(palette.slots, before);
+    }
+
+    #[test]
+    fn simulated_palette_covers_every_color() {
+        let palette = test_palette(ThemeMode::Dark);
+        let simulated = simulate_palette(&palette, Cvd::Deuteranopia);
+        for (slot, color) in simulated.slots.iter().enumerate() {
+            assert_eq!(*color, palette.slots[slot].simulate_cvd(Cvd::Deuteranopia));
+        }
+        assert_eq!(
+            simulated.selection_bg,
+            palette.selection_bg.simulate_cvd(Cvd::Deuteranopia)
+        );
+        assert!(
+            Color::oklab_distance(&simulated.slots[1], &simulated.slots[2])
+                < Color::oklab_distance(&palette.slots[1], &palette.slots[2])
+        );
+    }
+}
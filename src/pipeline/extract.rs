@@ -1,28 +1,159 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use image::imageops::FilterType;
+use image::DynamicImage;
 use kmeans_colors::get_kmeans_hamerly;
-use palette::{IntoColor, Lab, Srgb};
+use palette::{IntoColor, Lab, Oklch, Srgb};
 
+use crate::cli::ExtractorKind;
 use crate::color::Color;
+use crate::pipeline::frames::{decode_animation, FrameSelection};
+use crate::pipeline::histogram::HueHistogram;
+use crate::pipeline::median_cut::MedianCut;
+use crate::pipeline::octree::Octree;
+use crate::pipeline::region::{PixelRect, Region};
 
 /// A color extracted from the image with its cluster weight.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ExtractedColor {
+    /// The cluster centroid as computed, before rounding to 8-bit sRGB.
+    /// Wide-gamut palettes start from this.
+    pub lab: Lab,
+    /// The centroid as an sRGB color.
     pub color: Color,
     pub weight: f32,
 }
 
-const MAX_DIM: u32 = 256;
+impl ExtractedColor {
+    /// A color with centroid `lab` standing for `weight` of the pixels.
+    pub fn new(lab: Lab, weight: f32) -> Self {
+        ExtractedColor {
+            lab,
+            color: Color::from_lab(lab),
+            weight,
+        }
+    }
+
+    /// The unrounded centroid in Oklch.
+    pub fn to_oklch(&self) -> Oklch {
+        self.lab.into_color()
+    }
+}
+
+/// Images are resized to fit within MAX_DIM x MAX_DIM with RESIZE_FILTER.
+pub const MAX_DIM: u32 = 256;
+pub const RESIZE_FILTER: FilterType = FilterType::Lanczos3;
 const MAX_ITER: usize = 20;
 const CONVERGE: f32 = 5.0;
 const DEDUP_THRESHOLD: f32 = 25.0; // ΔE² < 25 means ΔE < 5
 
+/// Extensions of the files [`list_images`] picks up.
+const IMAGE_EXTENSIONS: [&str; 8] = ["png", "jpg", "jpeg", "webp", "bmp", "tif", "tiff", "gif"];
+
+/// Seed for the first extraction; the TUI increments it to regenerate.
+pub const DEFAULT_SEED: u64 = 42;
+
+/// An image prepared for extraction: resized, with pixels in CIELAB, row by row.
+#[derive(Debug, Clone)]
+pub struct PreparedImage {
+    pub pixels: Vec<Lab>,
+    pub width: usize,
+    pub height: usize,
+    /// Whether each pixel takes part in extraction; `false` inside `--exclude`
+    /// regions.
+    pub included: Vec<bool>,
+}
+
+impl PreparedImage {
+    /// An image with every pixel included.
+    pub fn new(pixels: Vec<Lab>, width: usize, height: usize) -> Self {
+        let included = vec![true; pixels.len()];
+        PreparedImage {
+            pixels,
+            width,
+            height,
+            included,
+        }
+    }
+
+    /// The pixels that take part in extraction.
+    pub fn included_pixels(&self) -> Vec<Lab> {
+        self.pixels
+            .iter()
+            .zip(&self.included)
+            .filter(|(_, &included)| included)
+            .map(|(lab, _)| *lab)
+            .collect()
+    }
+
+    /// The part of the image inside `rect`, which must lie within the image.
+    /// Used by the TUI to crop live without reloading.
+    pub fn crop(&self, rect: PixelRect) -> PreparedImage {
+        let (x, y) = (rect.x as usize, rect.y as usize);
+        let (width, height) = (rect.width as usize, rect.height as usize);
+        let mut pixels = Vec::with_capacity(width * height);
+        let mut included = Vec::with_capacity(width * height);
+        for row in y..y + height {
+            let start = row * self.width + x;
+            pixels.extend_from_slice(&self.pixels[start..start + width]);
+            included.extend_from_slice(&self.included[start..start + width]);
+        }
+        PreparedImage {
+            pixels,
+            width,
+            height,
+            included,
+        }
+    }
+}
+
 /// Load an image, resize to fit within 256x256 (preserving aspect ratio),
 /// and convert all pixels to CIELAB space.
 pub fn load_and_prepare(path: &Path) -> Result<Vec<Lab>> {
-    let img = image::open(path).with_context(|| {
+    Ok(load_image(path)?.pixels)
+}
+
+/// Like [`load_and_prepare`], but keeps the image dimensions for spatial
+/// weighting.
+pub fn load_image(path: &Path) -> Result<PreparedImage> {
+    load_image_with(path, None, &[])
+}
+
+/// Like [`load_image`], restricted to a region of interest. The image is
+/// cropped to `crop` before resizing, so a small crop keeps its detail.
+/// Pixels inside any `exclude` region (in source image coordinates) are
+/// marked as not included.
+pub fn load_image_with(
+    path: &Path,
+    crop: Option<&Region>,
+    exclude: &[Region],
+) -> Result<PreparedImage> {
+    prepare(open_image(path)?, crop, exclude)
+}
+
+/// Like [`load_image_with`], but animated GIF, APNG, and WebP images give the
+/// frames picked by `selection`, each with how long it shows. Still images
+/// give one frame.
+pub fn load_frames_with(
+    path: &Path,
+    crop: Option<&Region>,
+    exclude: &[Region],
+    selection: FrameSelection,
+) -> Result<Vec<(PreparedImage, Duration)>> {
+    match decode_animation(path, selection, |img| prepare(img, crop, exclude))? {
+        Some(frames) => Ok(frames),
+        None => Ok(vec![(
+            load_image_with(path, crop, exclude)?,
+            Duration::ZERO,
+        )]),
+    }
+}
+
+/// Decode the (first frame of the) image, explaining why if it can't be read.
+fn open_image(path: &Path) -> Result<DynamicImage> {
+    image::open(path).with_context(|| {
         if !path.exists() {
             format!("file not found: {}", path.display())
         } else if path.metadata().map(|m| m.permissions().readonly()).unwrap_or(false)
@@ -38,14 +169,41 @@ pub fn load_and_prepare(path: &Path) -> Result<Vec<Lab>> {
                 path.display()
             )
         }
-    })?;
+    })
+}
 
+/// Crop, resize, and convert a decoded image for extraction.
+fn prepare(img: DynamicImage, crop: Option<&Region>, exclude: &[Region]) -> Result<PreparedImage> {
+    let (full_width, full_height) = (img.width(), img.height());
+    let area = match crop {
+        Some(region) => region
+            .resolve(full_width, full_height)
+            .context("invalid --crop")?,
+        None => PixelRect {
+            x: 0,
+            y: 0,
+            width: full_width,
+            height: full_height,
+        },
+    };
+    let exclude = exclude
+        .iter()
+        .map(|region| region.resolve(full_width, full_height))
+        .collect::<Result<Vec<_>>>()
+        .context("invalid --exclude")?;
+
+    let img = if crop.is_some() {
+        img.crop_imm(area.x, area.y, area.width, area.height)
+    } else {
+        img
+    };
     let img = if img.width() > MAX_DIM || img.height() > MAX_DIM {
-        img.resize(MAX_DIM, MAX_DIM, FilterType::Lanczos3)
+        img.resize(MAX_DIM, MAX_DIM, RESIZE_FILTER)
     } else {
         img
     };
     let rgb_img = img.to_rgb8();
+    let (width, height) = (rgb_img.width(), rgb_img.height());
 
     let pixels: Vec<Lab> = rgb_img
         .pixels()
@@ -55,7 +213,89 @@ pub fn load_and_prepare(path: &Path) -> Result<Vec<Lab>> {
         })
         .collect();
 
-    Ok(pixels)
+    // A pixel is excluded when its center, mapped back to the source image,
+    // falls inside an excluded region
+    let scale_x = area.width as f32 / width as f32;
+    let scale_y = area.height as f32 / height as f32;
+    let included: Vec<bool> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let sx = area.x as f32 + (x as f32 + 0.5) * scale_x;
+            let sy = area.y as f32 + (y as f32 + 0.5) * scale_y;
+            !exclude.iter().any(|rect| rect.contains(sx, sy))
+        })
+        .collect();
+    if !included.contains(&true) {
+        bail!("--exclude leaves no pixels to extract colors from");
+    }
+
+    Ok(PreparedImage {
+        pixels,
+        width: width as usize,
+        height: height as usize,
+        included,
+    })
+}
+
+/// The images directly inside `dir`, sorted by file name. Files are picked by
+/// extension; subdirectories are not searched.
+pub fn list_images(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = std::fs::read_dir(dir)
+        .with_context(|| format!("failed to read directory {}", dir.display()))?;
+    let mut images = Vec::new();
+    for entry in entries {
+        let path = entry
+            .with_context(|| format!("failed to read directory {}", dir.display()))?
+            .path();
+        let is_image = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()));
+        if is_image && path.is_file() {
+            images.push(path);
+        }
+    }
+    if images.is_empty() {
+        bail!("no images found in {}", dir.display());
+    }
+    images.sort();
+    Ok(images)
+}
+
+/// An algorithm that finds the dominant colors of an image.
+pub trait Extractor {
+    /// Human-readable name shown in the TUI (e.g., "K-means").
+    fn name(&self) -> &str;
+
+    /// Extract at most `k` colors from LAB pixels, deduplicated and sorted by
+    /// weight (descending). Weights are the share of pixels each color stands
+    /// for. `seed` varies randomized algorithms; deterministic ones ignore it.
+    fn extract(&self, pixels: &[Lab], k: usize, seed: u64) -> Vec<ExtractedColor>;
+}
+
+/// Get the extractor for the given `--extractor` value.
+pub fn get_extractor(kind: ExtractorKind) -> Box<dyn Extractor> {
+    match kind {
+        ExtractorKind::Kmeans => Box::new(KMeans),
+        ExtractorKind::MedianCut => Box::new(MedianCut),
+        ExtractorKind::Octree => Box::new(Octree),
+        ExtractorKind::Histogram => Box::new(HueHistogram),
+    }
+}
+
+/// K-means clustering in LAB, using Hamerly's algorithm with K-means++
+/// initialization. Good all-round, but small details get averaged into
+/// their surroundings.
+pub struct KMeans;
+
+impl Extractor for KMeans {
+    fn name(&self) -> &str {
+        "K-means"
+    }
+
+    fn extract(&self, pixels: &[Lab], k: usize, seed: u64) -> Vec<ExtractedColor> {
+        extract_colors_with_seed(pixels, k, seed)
+    }
 }
 
 /// Run K-means on LAB pixels to extract dominant colors.
@@ -63,7 +303,7 @@ pub fn load_and_prepare(path: &Path) -> Result<Vec<Lab>> {
 /// Returns deduplicated colors sorted by weight (descending).
 /// Uses Hamerly's algorithm with K-means++ initialization.
 pub fn extract_colors(pixels: &[Lab], k: usize) -> Vec<ExtractedColor> {
-    extract_colors_with_seed(pixels, k, 42)
+    extract_colors_with_seed(pixels, k, DEFAULT_SEED)
 }
 
 /// Run K-means with an explicit seed (for TUI regeneration).
@@ -78,18 +318,21 @@ pub fn extract_colors_with_seed(pixels: &[Lab], k: usize, seed: u64) -> Vec<Extr
         counts[idx as usize] += 1;
     }
 
-    let mut colors: Vec<ExtractedColor> = result
+    let colors: Vec<ExtractedColor> = result
         .centroids
         .iter()
         .enumerate()
         .filter(|(i, _)| counts[*i] > 0)
-        .map(|(i, lab)| ExtractedColor {
-            color: Color::from_lab(*lab),
-            weight: counts[i] as f32 / total,
-        })
+        .map(|(i, lab)| ExtractedColor::new(*lab, counts[i] as f32 / total))
         .collect();
 
-    // Deduplicate centroids with ΔE < 5 (squared distance < 25)
+    dedup_and_sort(colors)
+}
+
+/// Merge near-identical colors and sort by weight (descending). Every
+/// extractor finishes with this.
+pub(crate) fn dedup_and_sort(mut colors: Vec<ExtractedColor>) -> Vec<ExtractedColor> {
+    // Deduplicate colors with ΔE < 5 (squared distance < 25)
     deduplicate(&mut colors);
 
     // Sort by weight descending
@@ -98,6 +341,19 @@ pub fn extract_colors_with_seed(pixels: &[Lab], k: usize, seed: u64) -> Vec<Extr
     colors
 }
 
+/// Mean of LAB pixels. `pixels` must not be empty.
+pub(crate) fn mean_lab<'a>(pixels: impl IntoIterator<Item = &'a Lab>) -> Lab {
+    let (mut sum, mut n) = ([0.0f32; 3], 0usize);
+    for lab in pixels {
+        sum[0] += lab.l;
+        sum[1] += lab.a;
+        sum[2] += lab.b;
+        n += 1;
+    }
+    let n = n as f32;
+    Lab::new(sum[0] / n, sum[1] / n, sum[2] / n)
+}
+
 /// Merge colors that are too similar (ΔE < 5 in LAB space).
 /// Keeps the first color and accumulates the weight.
 fn deduplicate(colors: &mut Vec<ExtractedColor>) {
@@ -105,8 +361,7 @@ fn deduplicate(colors: &mut Vec<ExtractedColor>) {
     while i < colors.len() {
         let mut j = i + 1;
         while j < colors.len() {
-            let lab_i = colors[i].color.to_lab();
-            let lab_j = colors[j].color.to_lab();
+            let (lab_i, lab_j) = (colors[i].lab, colors[j].lab);
             let delta_e_sq = (lab_i.l - lab_j.l).powi(2)
                 + (lab_i.a - lab_j.a).powi(2)
                 + (lab_i.b - lab_j.b).powi(2);
@@ -197,6 +452,68 @@ mod tests {
         }
     }
 
+    #[test]
+    fn crop_happens_before_resizing() {
+        let path = fixture_path("512x512_crop_test.png");
+        create_test_image_solid(&path, 512, 512, [128, 128, 128]);
+
+        let crop: Region = "0,0,128,25%".parse().unwrap();
+        let image = load_image_with(&path, Some(&crop), &[]).unwrap();
+        assert_eq!((image.width, image.height), (128, 128));
+        assert!(image.included.iter().all(|&i| i));
+    }
+
+    #[test]
+    fn exclude_marks_pixels_in_source_coordinates() {
+        let path = fixture_path("512x256_exclude_test.png");
+        create_test_image_solid(&path, 512, 256, [128, 128, 128]);
+
+        // The left quarter of the source is the left 64 columns after resizing
+        let exclude: Region = "0,0,128,256".parse().unwrap();
+        let image = load_image_with(&path, None, &[exclude]).unwrap();
+        assert_eq!((image.width, image.height), (256, 128));
+        assert_eq!(image.included_pixels().len(), 192 * 128);
+        assert!(!image.included[63]);
+        assert!(image.included[64]);
+
+        let everything: Region = "0,0,100%,100%".parse().unwrap();
+        let err = load_image_with(&path, None, &[everything]).unwrap_err();
+        assert!(err.to_string().contains("no pixels"), "{err}");
+    }
+
+    #[test]
+    fn prepared_image_crop_keeps_rows_and_mask() {
+        let pixels = (0..16).map(|i| Lab::new(i as f32, 0.0, 0.0)).collect();
+        let mut image = PreparedImage::new(pixels, 4, 4);
+        image.included[6] = false;
+
+        let cropped = image.crop(PixelRect {
+            x: 1,
+            y: 1,
+            width: 2,
+            height: 2,
+        });
+        let lightness: Vec<f32> = cropped.pixels.iter().map(|p| p.l).collect();
+        assert_eq!(lightness, [5.0, 6.0, 9.0, 10.0]);
+        assert_eq!(cropped.included, [true, false, true, true]);
+    }
+
+    #[test]
+    fn list_images_filters_and_sorts() {
+        let dir = fixture_path("list_images_test");
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        create_test_image_solid(&dir.join("b.PNG"), 2, 2, [0, 0, 0]);
+        create_test_image_solid(&dir.join("a.png"), 2, 2, [0, 0, 0]);
+        std::fs::write(dir.join("notes.txt"), "not an image").unwrap();
+
+        let images = list_images(&dir).unwrap();
+        let names: Vec<_> = images.iter().map(|p| p.file_name().unwrap()).collect();
+        assert_eq!(names, ["a.png", "b.PNG"]);
+
+        let err = list_images(&dir.join("nested")).unwrap_err().to_string();
+        assert!(err.contains("no images found"), "{err}");
+    }
+
     // --- extract_colors tests ---
 
     #[test]
@@ -307,6 +624,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn animations_give_evenly_spaced_frames() {
+        let path = fixture_path("frames_test.gif");
+        let colors = [[0, 0, 0], [200, 0, 0], [0, 200, 0], [0, 0, 200]];
+        create_test_animation(&path, 8, &colors, 50);
+
+        let red_and_blue = load_frames_with(&path, None, &[], FrameSelection::Evenly(2)).unwrap();
+        assert_eq!(red_and_blue.len(), 2);
+        for ((image, delay), rgb) in red_and_blue.iter().zip([colors[1], colors[3]]) {
+            assert_eq!(*delay, Duration::from_millis(50));
+            let expected: Lab = Srgb::new(rgb[0], rgb[1], rgb[2]).into_format().into_color();
+            assert!((image.pixels[0].a - expected.a).abs() < 1.0, "{rgb:?}");
+            assert!((image.pixels[0].b - expected.b).abs() < 1.0, "{rgb:?}");
+        }
+        let all = load_frames_with(&path, None, &[], FrameSelection::All).unwrap();
+        assert_eq!(all.len(), 4);
+
+        let still = fixture_path("frames_still_test.png");
+        create_test_image_solid(&still, 4, 4, [128, 128, 128]);
+        let frames = load_frames_with(&still, None, &[], FrameSelection::All).unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].1, Duration::ZERO);
+    }
+
+    #[test]
+    fn long_animations_are_decoded_once_and_thinned() {
+        let path = fixture_path("frames_long_test.gif");
+        let colors: Vec<[u8; 3]> = (0..40).map(|i| [i * 6, 0, 0]).collect();
+        create_test_animation(&path, 4, &colors, 50);
+
+        // Evenly spaced frames 5, 15, 25, 35 snap to 8, 16, 24, 32 once every
+        // eighth frame is kept
+        let frames = load_frames_with(&path, None, &[], FrameSelection::Evenly(4)).unwrap();
+        assert_eq!(frames.len(), 4);
+        for ((image, _), frame) in frames.iter().zip([8, 16, 24, 32]) {
+            let expected: Lab = Srgb::new(colors[frame][0], 0, 0).into_format().into_color();
+            assert!(
+                (image.pixels[0].l - expected.l).abs() < 1.0,
+                "frame {frame}: {:?}",
+                image.pixels[0]
+            );
+        }
+        assert_eq!(
+            load_frames_with(&path, None, &[], FrameSelection::All)
+                .unwrap()
+                .len(),
+            40
+        );
+    }
+
     // --- test helpers ---
 
     fn create_test_image_solid(path: &Path, width: u32, height: u32, rgb: [u8; 3]) {
@@ -328,4 +695,19 @@ mod tests {
         }
         img.save(path).unwrap();
     }
+
+    fn create_test_animation(path: &Path, size: u32, colors: &[[u8; 3]], delay_ms: u32) {
+        use image::codecs::gif::GifEncoder;
+        use image::{Delay, Frame, Rgba, RgbaImage};
+
+        let frames = colors.iter().map(|&[r, g, b]| {
+            let buffer = RgbaImage::from_pixel(size, size, Rgba([r, g, b, 255]));
+            Frame::from_parts(buffer, 0, 0, Delay::from_numer_denom_ms(delay_ms, 1))
+        });
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        let file = std::fs::File::create(path).unwrap();
+        GifEncoder::new(file).encode_frames(frames).unwrap();
+    }
 }
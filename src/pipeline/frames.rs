@@ -0,0 +1,197 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::{AnimationDecoder, DynamicImage, Frame, Frames, ImageFormat, ImageReader};
+
+/// Delay used for frames that give none. Browsers play such GIFs at 10 fps.
+const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+
+/// Which frames of an animated image to use (`--frames`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameSelection {
+    /// Every frame.
+    All,
+    /// At most this many, evenly spaced over the animation.
+    Evenly(usize),
+}
+
+impl FromStr for FrameSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "all" => Ok(FrameSelection::All),
+            n => match n.parse() {
+                Ok(0) => Err("frame count must be at least 1".to_string()),
+                Ok(n) => Ok(FrameSelection::Evenly(n)),
+                Err(_) => Err(format!("expected a frame count or 'all', got '{s}'")),
+            },
+        }
+    }
+}
+
+impl FrameSelection {
+    /// Indices of the frames to use out of `total`. Evenly spaced frames sit
+    /// in the middle of equal stretches of the animation, so the first frame,
+    /// often a fade-in from black, is skipped unless every frame is used.
+    pub fn indices(self, total: usize) -> Vec<usize> {
+        match self {
+            FrameSelection::Evenly(n) if n < total => {
+                (0..n).map(|i| (2 * i + 1) * total / (2 * n)).collect()
+            }
+            _ => (0..total).collect(),
+        }
+    }
+}
+
+/// The frames of an animated GIF, APNG, or WebP image picked by `selection`,
+/// each with how long it shows. Frames are passed through `prepare` as soon
+/// as they are decoded, so a long animation is never held at full size.
+///
+/// Returns `None` for still images and other formats, and for files that
+/// can't be decoded at all; those are left to [`image::open`] to load (or
+/// report).
+pub fn decode_animation<T>(
+    path: &Path,
+    selection: FrameSelection,
+    mut prepare: impl FnMut(DynamicImage) -> Result<T>,
+) -> Result<Option<Vec<(T, Duration)>>> {
+    let Some(frames) = animation_frames(path) else {
+        return Ok(None);
+    };
+    // Frames are decoded once, before the total is known. Every `stride`-th
+    // one is kept, and the stride doubles whenever more than twice the wanted
+    // number pile up; the frames nearest the evenly spaced ones are picked at
+    // the end
+    let cap = match selection {
+        FrameSelection::All => usize::MAX,
+        FrameSelection::Evenly(n) => 2 * n,
+    };
+    let mut stride = 1;
+    let mut kept: Vec<(usize, T, Duration)> = Vec::new();
+    let mut total = 0;
+    for (i, frame) in frames.enumerate() {
+        let frame = frame
+            .with_context(|| format!("failed to decode frame {} of {}", i + 1, path.display()))?;
+        total = i + 1;
+        if i % stride != 0 {
+            continue;
+        }
+        let delay = frame_delay(&frame);
+        kept.push((
+            i,
+            prepare(DynamicImage::ImageRgba8(frame.into_buffer()))?,
+            delay,
+        ));
+        if kept.len() > cap {
+            stride *= 2;
+            kept.retain(|(i, _, _)| i % stride == 0);
+        }
+    }
+    // A GIF with a single frame is just a still image
+    if total < 2 {
+        return Ok(None);
+    }
+
+    let mut wanted: Vec<usize> = selection
+        .indices(total)
+        .into_iter()
+        .map(|i| nearest_multiple(i, stride, total))
+        .collect();
+    wanted.dedup();
+    Ok(Some(
+        kept.into_iter()
+            .filter(|(i, _, _)| wanted.contains(i))
+            .map(|(_, frame, delay)| (frame, delay))
+            .collect(),
+    ))
+}
+
+/// How long a frame shows.
+fn frame_delay(frame: &Frame) -> Duration {
+    let (numer, denom) = frame.delay().numer_denom_ms();
+    let delay = Duration::from_micros(u64::from(numer) * 1000 / u64::from(denom.max(1)));
+    if delay.is_zero() {
+        DEFAULT_DELAY
+    } else {
+        delay
+    }
+}
+
+/// The multiple of `stride` below `total` closest to `index`.
+fn nearest_multiple(index: usize, stride: usize, total: usize) -> usize {
+    let nearest = (index + stride / 2) / stride * stride;
+    if nearest >= total {
+        nearest - stride
+    } else {
+        nearest
+    }
+}
+
+/// A frame iterator for `path`, if it's an animation.
+fn animation_frames(path: &Path) -> Option<Frames<'static>> {
+    let format = ImageReader::open(path)
+        .ok()?
+        .with_guessed_format()
+        .ok()?
+        .format()?;
+    let reader = BufReader::new(File::open(path).ok()?);
+    match format {
+        ImageFormat::Gif => Some(GifDecoder::new(reader).ok()?.into_frames()),
+        ImageFormat::Png => {
+            let decoder = PngDecoder::new(reader).ok()?;
+            if !decoder.is_apng().ok()? {
+                return None;
+            }
+            Some(decoder.apng().ok()?.into_frames())
+        }
+        ImageFormat::WebP => {
+            let decoder = WebPDecoder::new(reader).ok()?;
+            if !decoder.has_animation() {
+                return None;
+            }
+            Some(decoder.into_frames())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_frame_selection() {
+        assert_eq!("all".parse(), Ok(FrameSelection::All));
+        assert_eq!("12".parse(), Ok(FrameSelection::Evenly(12)));
+        assert!("0".parse::<FrameSelection>().is_err());
+        assert!("some".parse::<FrameSelection>().is_err());
+    }
+
+    #[test]
+    fn evenly_spaced_frames_skip_the_first() {
+        assert_eq!(FrameSelection::Evenly(2).indices(10), vec![2, 7]);
+        assert_eq!(FrameSelection::Evenly(4).indices(8), vec![1, 3, 5, 7]);
+        assert_eq!(FrameSelection::Evenly(1).indices(5), vec![2]);
+        assert_eq!(FrameSelection::Evenly(8).indices(3), vec![0, 1, 2]);
+        assert_eq!(FrameSelection::All.indices(3), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn thinned_frames_snap_to_the_nearest_kept() {
+        assert_eq!(nearest_multiple(5, 1, 10), 5);
+        assert_eq!(nearest_multiple(5, 4, 10), 4);
+        assert_eq!(nearest_multiple(6, 4, 10), 8);
+        // The last kept frame when rounding up runs past the end
+        assert_eq!(nearest_multiple(9, 4, 10), 8);
+        assert_eq!(nearest_multiple(31, 8, 33), 32);
+        assert_eq!(nearest_multiple(30, 8, 31), 24);
+    }
+}
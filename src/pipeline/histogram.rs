@@ -0,0 +1,223 @@
+use palette::{FromColor, Lab, Oklch};
+
+use crate::pipeline::assign::hue_distance;
+use crate::pipeline::extract::{dedup_and_sort, mean_lab, ExtractedColor, Extractor};
+
+/// Number of hue bins (10° each).
+const HUE_BINS: usize = 36;
+
+/// Minimum Oklch chroma for a pixel to count toward the hue histogram.
+const MIN_CHROMA: f32 = 0.04;
+
+/// Share of all pixels a hue peak needs, so that noise is not picked up.
+/// Small on purpose: a sign covering 1% of the image should still count.
+const MIN_PEAK_SHARE: f32 = 0.002;
+
+/// Pixels further than this from every peak's hue (degrees) are left out.
+const MAX_PEAK_DISTANCE: f32 = 30.0;
+
+/// Lightness bands the low-chroma pixels are split into.
+const NEUTRAL_BANDS: usize = 4;
+
+/// Hue histogram peak finder.
+///
+/// Chromatic pixels vote for their hue bin with their chroma, so a small
+/// vivid region outvotes a large dull one. Each peak of the smoothed
+/// histogram becomes the chroma-weighted mean of the pixels near it, which
+/// keeps it saturated instead of averaging it into its surroundings.
+/// Low-chroma pixels are summarized per lightness band to provide
+/// backgrounds and text colors. Deterministic, so the seed is ignored.
+pub struct HueHistogram;
+
+impl Extractor for HueHistogram {
+    fn name(&self) -> &str {
+        "hue histogram"
+    }
+
+    fn extract(&self, pixels: &[Lab], k: usize, _seed: u64) -> Vec<ExtractedColor> {
+        if pixels.is_empty() || k == 0 {
+            return Vec::new();
+        }
+        let total = pixels.len() as f32;
+
+        let oklch: Vec<Oklch> = pixels.iter().map(|&lab| Oklch::from_color(lab)).collect();
+        let (chromatic, neutral): (Vec<usize>, Vec<usize>) =
+            (0..pixels.len()).partition(|&i| oklch[i].chroma > MIN_CHROMA);
+
+        let mut votes = [0.0f32; HUE_BINS];
+        let mut counts = [0usize; HUE_BINS];
+        for &i in &chromatic {
+            let bin = hue_bin(oklch[i].hue.into_positive_degrees());
+            votes[bin] += oklch[i].chroma;
+            counts[bin] += 1;
+        }
+        let smoothed: Vec<f32> = (0..HUE_BINS)
+            .map(|i| {
+                let prev = votes[(i + HUE_BINS - 1) % HUE_BINS];
+                let next = votes[(i + 1) % HUE_BINS];
+                0.25 * prev + 0.5 * votes[i] + 0.25 * next
+            })
+            .collect();
+
+        let mut peaks: Vec<usize> = (0..HUE_BINS)
+            .filter(|&i| {
+                let prev = smoothed[(i + HUE_BINS - 1) % HUE_BINS];
+                let next = smoothed[(i + 1) % HUE_BINS];
+                let share = (i + HUE_BINS - 1..=i + HUE_BINS + 1)
+                    .map(|j| counts[j % HUE_BINS])
+                    .sum::<usize>() as f32
+                    / total;
+                smoothed[i] > prev && smoothed[i] >= next && share >= MIN_PEAK_SHARE
+            })
+            .collect();
+        peaks.sort_by(|&a, &b| smoothed[b].total_cmp(&smoothed[a]));
+
+        let mut bands: Vec<Vec<Lab>> = vec![Vec::new(); NEUTRAL_BANDS];
+        for &i in &neutral {
+            let band =
+                ((pixels[i].l / 100.0 * NEUTRAL_BANDS as f32) as usize).min(NEUTRAL_BANDS - 1);
+            bands[band].push(pixels[i]);
+        }
+        bands.retain(|b| !b.is_empty());
+        bands.sort_by_key(|b| std::cmp::Reverse(b.len()));
+
+        // Leave room for at least a dark and a light neutral when there are any
+        let neutral_budget = bands.len().min(k.saturating_sub(peaks.len()).max(2)).min(k);
+        bands.truncate(neutral_budget);
+        peaks.truncate(k - neutral_budget);
+
+        let peak_hues: Vec<f32> = peaks.iter().map(|&bin| bin_center(bin)).collect();
+        let mut members: Vec<Vec<usize>> = vec![Vec::new(); peaks.len()];
+        for &i in &chromatic {
+            let hue = oklch[i].hue.into_positive_degrees();
+            let nearest = peak_hues
+                .iter()
+                .enumerate()
+                .map(|(p, &peak)| (p, hue_distance(hue, peak)))
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((p, distance)) = nearest {
+                if distance <= MAX_PEAK_DISTANCE {
+                    members[p].push(i);
+                }
+            }
+        }
+
+        let mut colors: Vec<ExtractedColor> = members
+            .iter()
+            .filter(|m| !m.is_empty())
+            .map(|m| {
+                ExtractedColor::new(
+                    chroma_weighted_mean(m.iter().map(|&i| (pixels[i], oklch[i].chroma))),
+                    m.len() as f32 / total,
+                )
+            })
+            .collect();
+        colors.extend(
+            bands
+                .iter()
+                .map(|b| ExtractedColor::new(mean_lab(b), b.len() as f32 / total)),
+        );
+        dedup_and_sort(colors)
+    }
+}
+
+fn hue_bin(hue: f32) -> usize {
+    ((hue / 360.0 * HUE_BINS as f32) as usize).min(HUE_BINS - 1)
+}
+
+fn bin_center(bin: usize) -> f32 {
+    (bin as f32 + 0.5) * 360.0 / HUE_BINS as f32
+}
+
+/// Mean of LAB pixels, each weighted by its chroma.
+fn chroma_weighted_mean(pixels: impl Iterator<Item = (Lab, f32)>) -> Lab {
+    let (mut sum, mut weight) = ([0.0f32; 3], 0.0f32);
+    for (lab, chroma) in pixels {
+        sum[0] += lab.l * chroma;
+        sum[1] += lab.a * chroma;
+        sum[2] += lab.b * chroma;
+        weight += chroma;
+    }
+    Lab::new(sum[0] / weight, sum[1] / weight, sum[2] / weight)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use palette::{IntoColor, Srgb};
+
+    fn lab(r: u8, g: u8, b: u8) -> Lab {
+        Srgb::new(r, g, b).into_format::<f32>().into_color()
+    }
+
+    /// A gray city with a small red sign.
+    fn gray_with_red_detail() -> Vec<Lab> {
+        let mut pixels = Vec::new();
+        for i in 0..4950u32 {
+            let v = (90 + i % 60) as u8;
+            pixels.push(lab(v, v, v + 4));
+        }
+        pixels.extend(vec![lab(220, 30, 40); 50]);
+        pixels
+    }
+
+    #[test]
+    fn keeps_small_vivid_details() {
+        let pixels = gray_with_red_detail();
+
+        let colors = HueHistogram.extract(&pixels, 4, 0);
+
+        let red = colors
+            .iter()
+            .find(|c| c.color.to_oklch().chroma > 0.15)
+            .unwrap_or_else(|| panic!("no vivid color in {colors:?}"));
+        assert!(
+            hue_distance(red.color.to_oklch().hue.into_positive_degrees(), 25.0) < 15.0,
+            "{red:?}"
+        );
+        assert!((red.weight - 0.01).abs() < 1e-3, "{red:?}");
+    }
+
+    #[test]
+    fn separates_hues_and_neutrals() {
+        let mut pixels = vec![lab(200, 50, 50); 300];
+        pixels.extend(vec![lab(50, 50, 200); 300]);
+        pixels.extend(vec![lab(15, 15, 15); 200]);
+        pixels.extend(vec![lab(240, 240, 240); 200]);
+
+        let colors = HueHistogram.extract(&pixels, 8, 0);
+
+        assert_eq!(colors.len(), 4, "{colors:?}");
+        for expected in [
+            Color::new(200, 50, 50),
+            Color::new(50, 50, 200),
+            Color::new(15, 15, 15),
+            Color::new(240, 240, 240),
+        ] {
+            assert!(
+                colors
+                    .iter()
+                    .any(|c| Color::oklab_distance(&c.color, &expected) < 0.01),
+                "{expected} missing from {colors:?}"
+            );
+        }
+        let total: f32 = colors.iter().map(|c| c.weight).sum();
+        assert!((total - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn never_returns_more_than_k() {
+        let pixels: Vec<Lab> = (0..360u32)
+            .map(|h| {
+                let color = Color::from_oklch(Oklch::new(0.6, 0.15, h as f32));
+                color.to_lab()
+            })
+            .collect();
+        for k in [1, 3, 8] {
+            let colors = HueHistogram.extract(&pixels, k, 0);
+            assert!(!colors.is_empty());
+            assert!(colors.len() <= k, "k = {k}: got {}", colors.len());
+        }
+    }
+}
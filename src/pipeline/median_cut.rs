@@ -0,0 +1,124 @@
+use palette::Lab;
+
+use crate::pipeline::extract::{dedup_and_sort, mean_lab, ExtractedColor, Extractor};
+
+/// Median cut quantization in LAB.
+///
+/// Starts with one box holding every pixel and repeatedly splits the box with
+/// the largest channel range (scaled by its pixel count) at the median of that
+/// channel, until there are `k` boxes. The split moves to the nearest change in
+/// value, so runs of one color are never cut in two. Each box becomes its mean
+/// color. Deterministic, so the seed is ignored.
+pub struct MedianCut;
+
+impl Extractor for MedianCut {
+    fn name(&self) -> &str {
+        "median cut"
+    }
+
+    fn extract(&self, pixels: &[Lab], k: usize, _seed: u64) -> Vec<ExtractedColor> {
+        if pixels.is_empty() || k == 0 {
+            return Vec::new();
+        }
+
+        let mut boxes: Vec<Vec<Lab>> = vec![pixels.to_vec()];
+        while boxes.len() < k {
+            let widest = boxes
+                .iter()
+                .enumerate()
+                .map(|(i, b)| {
+                    let (channel, range) = widest_channel(b);
+                    (i, channel, range * b.len() as f32)
+                })
+                .filter(|&(_, _, score)| score > 0.0)
+                .max_by(|a, b| a.2.total_cmp(&b.2));
+            // Every box is a single color: nothing left to split
+            let Some((i, channel, _)) = widest else {
+                break;
+            };
+
+            let mut lower = boxes.swap_remove(i);
+            lower.sort_by(|a, b| channel_value(a, channel).total_cmp(&channel_value(b, channel)));
+            let mid = lower.len() / 2;
+            let split = (1..lower.len())
+                .filter(|&j| {
+                    channel_value(&lower[j - 1], channel) < channel_value(&lower[j], channel)
+                })
+                .min_by_key(|&j| j.abs_diff(mid))
+                .expect("a box with a nonzero range has a value change");
+            let upper = lower.split_off(split);
+            boxes.push(lower);
+            boxes.push(upper);
+        }
+
+        let total = pixels.len() as f32;
+        let colors = boxes
+            .iter()
+            .map(|b| ExtractedColor::new(mean_lab(b), b.len() as f32 / total))
+            .collect();
+        dedup_and_sort(colors)
+    }
+}
+
+/// The LAB channel (0 = L, 1 = a, 2 = b) with the largest range, and that range.
+fn widest_channel(pixels: &[Lab]) -> (usize, f32) {
+    (0..3)
+        .map(|channel| {
+            let (min, max) = pixels.iter().fold((f32::MAX, f32::MIN), |(min, max), lab| {
+                let v = channel_value(lab, channel);
+                (min.min(v), max.max(v))
+            });
+            (channel, max - min)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .expect("three channels")
+}
+
+fn channel_value(lab: &Lab, channel: usize) -> f32 {
+    match channel {
+        0 => lab.l,
+        1 => lab.a,
+        _ => lab.b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use palette::{IntoColor, Srgb};
+
+    fn lab(r: u8, g: u8, b: u8) -> Lab {
+        Srgb::new(r, g, b).into_format::<f32>().into_color()
+    }
+
+    #[test]
+    fn separates_distinct_colors() {
+        let mut pixels = vec![lab(200, 50, 50); 600];
+        pixels.extend(vec![lab(50, 50, 200); 300]);
+        pixels.extend(vec![lab(50, 200, 50); 100]);
+
+        let colors = MedianCut.extract(&pixels, 8, 0);
+
+        assert_eq!(colors.len(), 3, "{colors:?}");
+        assert_eq!(colors[0].color, Color::new(200, 50, 50));
+        assert!((colors[0].weight - 0.6).abs() < 1e-3);
+        let total: f32 = colors.iter().map(|c| c.weight).sum();
+        assert!((total - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn never_returns_more_than_k() {
+        let pixels: Vec<Lab> = (0..=255u8).map(|v| lab(v, 255 - v, v / 2)).collect();
+        for k in [1, 4, 16] {
+            let colors = MedianCut.extract(&pixels, k, 0);
+            assert!(!colors.is_empty());
+            assert!(colors.len() <= k, "k = {k}: got {}", colors.len());
+        }
+    }
+
+    #[test]
+    fn empty_input_gives_no_colors() {
+        assert!(MedianCut.extract(&[], 8, 0).is_empty());
+    }
+}
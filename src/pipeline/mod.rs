@@ -1,4 +1,253 @@
 pub mod assign;
 pub mod contrast;
+pub mod cvd;
 pub mod detect;
 pub mod extract;
+pub mod frames;
+pub mod histogram;
+pub mod median_cut;
+pub mod octree;
+pub mod region;
+pub mod weight;
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::cache::{self, Extraction, KeySettings};
+use crate::cli::{ExtractorKind, Gamut, PixelWeight, ThemeMode};
+use crate::color::PaletteColor;
+use crate::pipeline::assign::{
+    assign_slots, assign_slots_wide, AnsiPalette, Palette, WidePalette, SLOT_NAMES,
+};
+use crate::pipeline::contrast::enforce_contrast;
+use crate::pipeline::cvd::enforce_cvd_safety;
+use crate::pipeline::detect::detect_mode;
+use crate::pipeline::extract::{
+    get_extractor, load_frames_with, ExtractedColor, PreparedImage, DEFAULT_SEED,
+};
+use crate::pipeline::frames::FrameSelection;
+use crate::pipeline::region::Region;
+use crate::pipeline::weight::{blend, weighted_pixels};
+
+/// Settings for [`run`].
+#[derive(Debug, Clone)]
+pub struct PipelineOptions {
+    pub k: usize,
+    pub extractor: ExtractorKind,
+    pub weight: Vec<PixelWeight>,
+    /// Theme mode, detected from the image if `None`.
+    pub mode: Option<ThemeMode>,
+    pub min_contrast: f32,
+    pub cvd_safe: bool,
+    /// Frames of animated images whose pixels are pooled.
+    pub frames: FrameSelection,
+    /// Only extract from this region of each image.
+    pub crop: Option<Region>,
+    /// Ignore these regions of each image.
+    pub exclude: Vec<Region>,
+    /// Also build a Display P3 palette for [`Gamut::DisplayP3`].
+    pub gamut: Gamut,
+    /// Reuse and save extraction results in the cache.
+    pub cache: bool,
+}
+
+impl PipelineOptions {
+    /// The settings that go into the extraction cache key.
+    fn key_settings(&self) -> KeySettings<'_> {
+        KeySettings {
+            k: self.k,
+            seed: DEFAULT_SEED,
+            extractor: self.extractor,
+            weight: &self.weight,
+            crop: self.crop.as_ref(),
+            exclude: &self.exclude,
+            frames: self.frames,
+        }
+    }
+}
+
+/// What [`run`] produces.
+pub struct Generated {
+    /// The extracted colors the palette was assigned from.
+    pub colors: Vec<ExtractedColor>,
+    /// The forced or detected mode.
+    pub mode: ThemeMode,
+    /// The sRGB palette, for previews and sRGB-only targets.
+    pub palette: AnsiPalette,
+    /// The Display P3 palette, with [`Gamut::DisplayP3`].
+    pub wide: Option<WidePalette>,
+    /// The frames of each image, a still image having one. Empty when the
+    /// extraction came from the cache.
+    pub frames: Vec<Vec<(PreparedImage, Duration)>>,
+}
+
+/// The whole pipeline: load `images` (pooling the frames of animations),
+/// weight, extract colors from the images blended by their weights, detect
+/// the mode, assign slots, and enforce the constraints. Everything up to the
+/// mode comes from the cache when it can.
+pub fn run(images: &[(PathBuf, f32)], options: &PipelineOptions) -> Result<Generated> {
+    // Steps 1 to 3 are skipped when the cache already has their result for
+    // these images and settings
+    let key = if options.cache {
+        Some(cache::key(images, &options.key_settings())?)
+    } else {
+        None
+    };
+    // The frames of each input; a still image has one. Frames share their
+    // image's weight
+    let mut frames = Vec::new();
+    let extraction = cache::get_or_extract(key.as_deref(), || {
+        // 1. Load and prepare image pixels, limited to the crop and excluded
+        //    regions
+        for (path, _) in images {
+            let loaded = load_frames_with(
+                path,
+                options.crop.as_ref(),
+                &options.exclude,
+                options.frames,
+            )?;
+            if loaded.len() > 1 {
+                eprintln!("Pooling {} frames of {}", loaded.len(), path.display());
+            }
+            frames.push(loaded);
+        }
+        let prepared: Vec<(&PreparedImage, f32)> = frames
+            .iter()
+            .zip(images)
+            .flat_map(|(frames, (_, weight))| {
+                let weight = weight / frames.len() as f32;
+                frames.iter().map(move |(frame, _)| (frame, weight))
+            })
+            .collect();
+        let pixels = blend(
+            &prepared
+                .iter()
+                .map(|(image, weight)| (image.included_pixels(), *weight))
+                .collect::<Vec<_>>(),
+        );
+
+        // Warn on tiny images
+        if pixels.len() < 16 {
+            eprintln!(
+                "warning: very small image ({} pixels). Theme quality may be limited.",
+                pixels.len()
+            );
+        }
+
+        // 2. Extract dominant colors, from pixels resampled by the weights
+        let sampled = blend(
+            &prepared
+                .iter()
+                .map(|(image, weight)| (weighted_pixels(image, &options.weight), *weight))
+                .collect::<Vec<_>>(),
+        );
+        Ok(Extraction {
+            colors: get_extractor(options.extractor).extract(&sampled, options.k, DEFAULT_SEED),
+            // 3. Detect dark/light mode
+            mode: detect_mode(&pixels),
+        })
+    })?;
+    let colors = extraction.colors;
+
+    // Warn on few extracted colors
+    if colors.len() < 6 {
+        eprintln!(
+            "warning: only {} distinct colors extracted (expected ≥ 6). \
+             Some palette slots will be synthesized.",
+            colors.len()
+        );
+    }
+
+    let mode = options.mode.unwrap_or(extraction.mode);
+
+    // 4. Assign colors to ANSI palette slots and enforce the constraints. For
+    //    Display P3 this runs on wide colors; the sRGB palette is mapped from
+    //    them and checked again
+    let (palette, wide) = match options.gamut {
+        Gamut::Srgb => {
+            let mut palette = assign_slots(&colors, mode);
+            enforce_constraints(&mut palette, options.min_contrast, options.cvd_safe);
+            (palette, None)
+        }
+        Gamut::DisplayP3 => {
+            let mut wide = assign_slots_wide(&colors, mode);
+            enforce_constraints(&mut wide, options.min_contrast, options.cvd_safe);
+            // Pairs left confusable were reported for the wide palette
+            let mut palette = wide.to_srgb();
+            enforce_contrast(&mut palette, options.min_contrast);
+            if options.cvd_safe {
+                enforce_cvd_safety(&mut palette, options.min_contrast);
+            }
+            (palette, Some(wide))
+        }
+    };
+
+    Ok(Generated {
+        colors,
+        mode,
+        palette,
+        wide,
+        frames,
+    })
+}
+
+/// Key for the palette [`run`] produces from `images` with `options`: the
+/// extraction cache key plus the settings applied after extraction. Equal keys
+/// mean equal palettes, so batch mode can tell which themes are up to date.
+pub fn theme_key(images: &[(PathBuf, f32)], options: &PipelineOptions) -> Result<String> {
+    let description = format!(
+        "{} mode={:?} min_contrast={} cvd_safe={} gamut={:?}",
+        cache::key(images, &options.key_settings())?,
+        options.mode,
+        options.min_contrast,
+        options.cvd_safe,
+        options.gamut,
+    );
+    Ok(format!(
+        "{:016x}",
+        cache::content_hash(description.as_bytes())
+    ))
+}
+
+/// [`run`] for a single image file, for batch and watch mode.
+pub fn generate(path: &Path, options: &PipelineOptions) -> Result<(AnsiPalette, ThemeMode)> {
+    let generated = run(&[(path.to_path_buf(), 1.0)], options)?;
+    Ok((generated.palette, generated.mode))
+}
+
+/// The palette for one prepared image (e.g. a frame of an animation) in
+/// `mode`, without the cache.
+pub fn palette_for(
+    image: &PreparedImage,
+    options: &PipelineOptions,
+    mode: ThemeMode,
+) -> AnsiPalette {
+    let sampled = weighted_pixels(image, &options.weight);
+    let colors = get_extractor(options.extractor).extract(&sampled, options.k, DEFAULT_SEED);
+    let mut palette = assign_slots(&colors, mode);
+    enforce_constraints(&mut palette, options.min_contrast, options.cvd_safe);
+    palette
+}
+
+/// Enforce WCAG contrast minimums, then keep accents apart for color-blind
+/// users if asked, warning about pairs that can't be.
+pub fn enforce_constraints<C: PaletteColor>(
+    palette: &mut Palette<C>,
+    min_contrast: f32,
+    cvd_safe: bool,
+) {
+    enforce_contrast(palette, min_contrast);
+    if cvd_safe {
+        for (cvd, a, b) in enforce_cvd_safety(palette, min_contrast) {
+            eprintln!(
+                "warning: --cvd-safe could not keep {} and {} apart for {}; \
+                 they stay confusable at --min-contrast {min_contrast}",
+                SLOT_NAMES[a],
+                SLOT_NAMES[b],
+                cvd.name()
+            );
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use palette::Lab;
+
+use crate::color::Color;
+use crate::pipeline::extract::{dedup_and_sort, ExtractedColor, Extractor};
+
+/// Depth of a full octree over 8-bit RGB.
+const MAX_DEPTH: u32 = 8;
+
+/// Octree quantization.
+///
+/// Each pixel is filed under the path its RGB bits take through an octree,
+/// then the least-populated nodes at the deepest level are folded into their
+/// parents until at most `k` leaves remain. Rare colors go first, so large
+/// areas keep their detail. Deterministic, so the seed is ignored.
+pub struct Octree;
+
+/// Pixels gathered under one octree node, summed in LAB.
+#[derive(Debug, Clone, Copy, Default)]
+struct Node {
+    sum: [f32; 3],
+    count: usize,
+}
+
+impl Node {
+    fn merge(&mut self, other: &Node) {
+        for (s, o) in self.sum.iter_mut().zip(other.sum) {
+            *s += o;
+        }
+        self.count += other.count;
+    }
+
+    fn mean(&self) -> Lab {
+        let n = self.count as f32;
+        Lab::new(self.sum[0] / n, self.sum[1] / n, self.sum[2] / n)
+    }
+}
+
+impl Extractor for Octree {
+    fn name(&self) -> &str {
+        "octree"
+    }
+
+    fn extract(&self, pixels: &[Lab], k: usize, _seed: u64) -> Vec<ExtractedColor> {
+        if pixels.is_empty() || k == 0 {
+            return Vec::new();
+        }
+
+        let mut leaves: HashMap<u32, Node> = HashMap::new();
+        for lab in pixels {
+            let node = leaves.entry(path(Color::from_lab(*lab))).or_default();
+            node.merge(&Node {
+                sum: [lab.l, lab.a, lab.b],
+                count: 1,
+            });
+        }
+
+        // All leaves sit at the same depth until a level is partly reduced,
+        // which only happens on the last pass
+        let mut reduced = Vec::new();
+        let mut depth = MAX_DEPTH;
+        while leaves.len() > k && depth > 0 {
+            let mut parents: HashMap<u32, (Node, Vec<u32>)> = HashMap::new();
+            for (&key, node) in &leaves {
+                let (parent, children) = parents.entry(key >> 3).or_default();
+                parent.merge(node);
+                children.push(key);
+            }
+            let mut parents: Vec<(u32, (Node, Vec<u32>))> = parents.into_iter().collect();
+            parents.sort_by_key(|(key, (node, _))| (node.count, *key));
+
+            let mut remaining = leaves.len();
+            let mut next = HashMap::new();
+            for (key, (parent, children)) in parents {
+                if remaining <= k {
+                    break;
+                }
+                remaining -= children.len() - 1;
+                for child in children {
+                    leaves.remove(&child);
+                }
+                next.insert(key, parent);
+            }
+
+            if remaining <= k {
+                reduced.extend(next.into_values());
+                break;
+            }
+            leaves = next;
+            depth -= 1;
+        }
+        reduced.extend(leaves.into_values());
+
+        let total = pixels.len() as f32;
+        let colors = reduced
+            .iter()
+            .map(|node| ExtractedColor::new(node.mean(), node.count as f32 / total))
+            .collect();
+        dedup_and_sort(colors)
+    }
+}
+
+/// The color's path from the root to a leaf: three bits (one per channel)
+/// per level, most significant bit first.
+fn path(color: Color) -> u32 {
+    (0..MAX_DEPTH).fold(0, |key, level| {
+        let bit = 7 - level;
+        let r = u32::from(color.r >> bit) & 1;
+        let g = u32::from(color.g >> bit) & 1;
+        let b = u32::from(color.b >> bit) & 1;
+        (key << 3) | (r << 2) | (g << 1) | b
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use palette::{IntoColor, Srgb};
+
+    fn lab(r: u8, g: u8, b: u8) -> Lab {
+        Srgb::new(r, g, b).into_format::<f32>().into_color()
+    }
+
+    #[test]
+    fn separates_distinct_colors() {
+        let mut pixels = vec![lab(200, 50, 50); 600];
+        pixels.extend(vec![lab(50, 50, 200); 300]);
+        pixels.extend(vec![lab(50, 200, 50); 100]);
+
+        let colors = Octree.extract(&pixels, 8, 0);
+
+        assert_eq!(colors.len(), 3, "{colors:?}");
+        assert_eq!(colors[0].color, Color::new(200, 50, 50));
+        assert!((colors[0].weight - 0.6).abs() < 1e-3);
+    }
+
+    #[test]
+    fn never_returns_more_than_k() {
+        let pixels: Vec<Lab> = (0..4096u32)
+            .map(|i| {
+                lab(
+                    (i % 16 * 16) as u8,
+                    (i / 16 % 16 * 16) as u8,
+                    (i / 256 * 16) as u8,
+                )
+            })
+            .collect();
+        for k in [1, 5, 16] {
+            let colors = Octree.extract(&pixels, k, 0);
+            assert!(!colors.is_empty());
+            assert!(colors.len() <= k, "k = {k}: got {}", colors.len());
+            let total: f32 = colors.iter().map(|c| c.weight).sum();
+            assert!(
+                (total - 1.0).abs() < 1e-3,
+                "k = {k}: weights sum to {total}"
+            );
+        }
+    }
+
+    #[test]
+    fn rare_colors_are_merged_first() {
+        let mut pixels = vec![lab(30, 30, 30); 900];
+        pixels.extend(vec![lab(220, 220, 220); 90]);
+        pixels.extend(vec![lab(34, 30, 30); 10]);
+
+        let colors = Octree.extract(&pixels, 2, 0);
+
+        assert_eq!(colors.len(), 2);
+        assert!((colors[1].weight - 0.09).abs() < 1e-3, "{colors:?}");
+    }
+
+    #[test]
+    fn paths_share_prefixes_with_similar_colors() {
+        let a = path(Color::new(0x80, 0x40, 0x20));
+        let b = path(Color::new(0x81, 0x40, 0x20));
+        assert_ne!(a, b);
+        assert_eq!(a >> 3, b >> 3);
+        assert_eq!(path(Color::new(255, 255, 255)), (1 << 24) - 1);
+    }
+}
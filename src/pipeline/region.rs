@@ -0,0 +1,164 @@
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+
+/// One coordinate of a [`Region`]: pixels, or a percentage of the image size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Extent {
+    Pixels(u32),
+    Percent(f32),
+}
+
+impl Extent {
+    /// Pixels along an image side of length `full`.
+    fn resolve(self, full: u32) -> u32 {
+        match self {
+            Extent::Pixels(px) => px,
+            Extent::Percent(pct) => (pct / 100.0 * full as f32).round() as u32,
+        }
+    }
+}
+
+impl FromStr for Extent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(pct) = s.strip_suffix('%') {
+            let pct: f32 = pct
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid percentage '{s}'"))?;
+            if !(0.0..=100.0).contains(&pct) {
+                return Err(format!("percentage '{s}' must be between 0% and 100%"));
+            }
+            return Ok(Extent::Percent(pct));
+        }
+        s.parse()
+            .map(Extent::Pixels)
+            .map_err(|_| format!("invalid value '{s}': expected pixels or a percentage"))
+    }
+}
+
+/// A rectangle of the source image, given as `x,y,w,h` in pixels or
+/// percentages (e.g. `0,0,50%,100%` for the left half).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Region {
+    pub x: Extent,
+    pub y: Extent,
+    pub width: Extent,
+    pub height: Extent,
+}
+
+impl Region {
+    /// Resolve against an image of the given size, clipping to its bounds.
+    /// Errors when nothing of the region lies inside the image.
+    pub fn resolve(&self, width: u32, height: u32) -> Result<PixelRect> {
+        let x = self.x.resolve(width);
+        let y = self.y.resolve(height);
+        let right = x.saturating_add(self.width.resolve(width)).min(width);
+        let bottom = y.saturating_add(self.height.resolve(height)).min(height);
+        if x >= right || y >= bottom {
+            bail!("region lies outside the {width}x{height} image");
+        }
+        Ok(PixelRect {
+            x,
+            y,
+            width: right - x,
+            height: bottom - y,
+        })
+    }
+}
+
+impl FromStr for Region {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(',').collect();
+        let [x, y, width, height] = parts[..] else {
+            return Err(format!("expected x,y,w,h, got '{s}'"));
+        };
+        Ok(Region {
+            x: x.parse()?,
+            y: y.parse()?,
+            width: width.parse()?,
+            height: height.parse()?,
+        })
+    }
+}
+
+/// A rectangle in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PixelRect {
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x as f32
+            && y >= self.y as f32
+            && x < (self.x + self.width) as f32
+            && y < (self.y + self.height) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_pixels_and_percentages() {
+        let region: Region = "10,0,50%,100%".parse().unwrap();
+        assert_eq!(
+            region,
+            Region {
+                x: Extent::Pixels(10),
+                y: Extent::Pixels(0),
+                width: Extent::Percent(50.0),
+                height: Extent::Percent(100.0),
+            }
+        );
+        assert_eq!(
+            region.resolve(400, 300).unwrap(),
+            PixelRect {
+                x: 10,
+                y: 0,
+                width: 200,
+                height: 300
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_regions() {
+        for (input, message) in [
+            ("1,2,3", "expected x,y,w,h"),
+            ("1,2,3,4,5", "expected x,y,w,h"),
+            ("a,0,10,10", "invalid value 'a'"),
+            ("0,0,150%,10", "between 0% and 100%"),
+            ("0,0,x%,10", "invalid percentage"),
+        ] {
+            let err = input.parse::<Region>().unwrap_err();
+            assert!(err.contains(message), "{input}: {err}");
+        }
+    }
+
+    #[test]
+    fn clips_to_the_image() {
+        let region: Region = "300,250,500,500".parse().unwrap();
+        assert_eq!(
+            region.resolve(400, 300).unwrap(),
+            PixelRect {
+                x: 300,
+                y: 250,
+                width: 100,
+                height: 50
+            }
+        );
+        let outside: Region = "400,0,10,10".parse().unwrap();
+        assert!(outside.resolve(400, 300).is_err());
+    }
+}
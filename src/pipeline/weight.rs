@@ -0,0 +1,302 @@
+use palette::Lab;
+
+use crate::cli::PixelWeight;
+use crate::pipeline::extract::PreparedImage;
+
+/// Weight of the image corners relative to its center with center bias.
+const CENTER_FLOOR: f32 = 0.25;
+
+/// Standard deviation of the center falloff, as a fraction of the image size.
+const CENTER_SIGMA: f32 = 0.3;
+
+/// Weight of flat areas relative to the most detailed ones with edge weighting.
+const EDGE_FLOOR: f32 = 0.25;
+
+/// Radius (pixels) of the window edge magnitudes are averaged over, so that
+/// whole detailed regions count rather than single outlines.
+const EDGE_RADIUS: usize = 4;
+
+/// LAB chroma at which chroma boosting doubles a pixel's weight.
+const CHROMA_SCALE: f32 = 25.0;
+
+/// Per-pixel weights for the selected terms, multiplied together. Every
+/// included pixel has a positive weight, so no part of the image is ignored
+/// entirely; excluded pixels weigh 0.
+pub fn pixel_weights(image: &PreparedImage, terms: &[PixelWeight]) -> Vec<f32> {
+    let mut weights: Vec<f32> = image
+        .included
+        .iter()
+        .map(|&included| if included { 1.0 } else { 0.0 })
+        .collect();
+    for term in terms {
+        let factors = match term {
+            PixelWeight::Center => center_bias(image.width, image.height),
+            PixelWeight::Edges => edge_density(image),
+            PixelWeight::Chroma => image
+                .pixels
+                .iter()
+                .map(|lab| 1.0 + lab.a.hypot(lab.b) / CHROMA_SCALE)
+                .collect(),
+        };
+        for (w, f) in weights.iter_mut().zip(factors) {
+            *w *= f;
+        }
+    }
+    weights
+}
+
+/// Pixels to cluster, resampled so each appears in proportion to its weight.
+///
+/// None of the extractors take per-pixel weights, so weighted pixels are
+/// repeated and light ones dropped instead (systematic resampling). The
+/// result has one pixel per included pixel, and cluster weights in
+/// [`ExtractedColor`](super::extract::ExtractedColor) reflect the weighting.
+/// Returns the included pixels unchanged when no terms are selected.
+pub fn weighted_pixels(image: &PreparedImage, terms: &[PixelWeight]) -> Vec<Lab> {
+    if terms.is_empty() {
+        return image.included_pixels();
+    }
+    let count = image.included.iter().filter(|&&included| included).count();
+    resample(&image.pixels, &pixel_weights(image, terms), count)
+}
+
+/// Pool the pixels of several images into one set. Each image takes a share
+/// of the result proportional to its weight, however many pixels it has, so
+/// a small image counts as much as a large one of the same weight. A single
+/// image is returned as is.
+pub fn blend(sets: &[(Vec<Lab>, f32)]) -> Vec<Lab> {
+    if let [(pixels, _)] = sets {
+        return pixels.clone();
+    }
+    let mut pixels = Vec::new();
+    let mut weights = Vec::new();
+    for (set, weight) in sets {
+        pixels.extend_from_slice(set);
+        weights.extend(std::iter::repeat_n(weight / set.len() as f32, set.len()));
+    }
+    let count = pixels.len();
+    resample(&pixels, &weights, count)
+}
+
+/// Systematic resampling: walk the cumulative weights in `count` equal steps
+/// and take the pixel each step lands on. Deterministic, unlike random
+/// sampling.
+fn resample(pixels: &[Lab], weights: &[f32], count: usize) -> Vec<Lab> {
+    let total: f32 = weights.iter().sum();
+    if pixels.is_empty() || total <= 0.0 {
+        return Vec::new();
+    }
+    let step = total / count as f32;
+
+    let mut out = Vec::with_capacity(count);
+    let mut next = step / 2.0;
+    let mut cumulative = 0.0;
+    let mut last = pixels[0];
+    for (pixel, &weight) in pixels.iter().zip(weights) {
+        if weight <= 0.0 {
+            continue;
+        }
+        last = *pixel;
+        cumulative += weight;
+        while next < cumulative && out.len() < count {
+            out.push(*pixel);
+            next += step;
+        }
+    }
+    // Float rounding can leave the last step just past the end
+    while out.len() < count {
+        out.push(last);
+    }
+    out
+}
+
+/// Gaussian falloff from the image center, from 1 down to [`CENTER_FLOOR`].
+fn center_bias(width: usize, height: usize) -> Vec<f32> {
+    let mut factors = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let dx = (x as f32 + 0.5) / width as f32 - 0.5;
+            let dy = (y as f32 + 0.5) / height as f32 - 0.5;
+            let falloff = (-(dx * dx + dy * dy) / (2.0 * CENTER_SIGMA * CENTER_SIGMA)).exp();
+            factors.push(CENTER_FLOOR + (1.0 - CENTER_FLOOR) * falloff);
+        }
+    }
+    factors
+}
+
+/// Sobel gradient magnitude in LAB, averaged over a window and scaled to
+/// [[`EDGE_FLOOR`], 1]. All three channels count, so edges between colors of
+/// equal lightness are found too.
+fn edge_density(image: &PreparedImage) -> Vec<f32> {
+    let (w, h) = (image.width, image.height);
+    let channels = |x: usize, y: usize| {
+        let lab = image.pixels[y * w + x];
+        [lab.l, lab.a, lab.b]
+    };
+
+    let mut magnitude = vec![0.0f32; w * h];
+    for y in 0..h {
+        for x in 0..w {
+            // Clamp at the borders
+            let (x0, x1) = (x.saturating_sub(1), (x + 1).min(w - 1));
+            let (y0, y1) = (y.saturating_sub(1), (y + 1).min(h - 1));
+            let mut squared = 0.0;
+            for c in 0..3 {
+                let v = |x, y| channels(x, y)[c];
+                let gx = (v(x1, y0) + 2.0 * v(x1, y) + v(x1, y1))
+                    - (v(x0, y0) + 2.0 * v(x0, y) + v(x0, y1));
+                let gy = (v(x0, y1) + 2.0 * v(x, y1) + v(x1, y1))
+                    - (v(x0, y0) + 2.0 * v(x, y0) + v(x1, y0));
+                squared += gx * gx + gy * gy;
+            }
+            magnitude[y * w + x] = squared.sqrt();
+        }
+    }
+
+    let density = box_blur(&magnitude, w, h, EDGE_RADIUS);
+    let max = density.iter().copied().fold(0.0f32, f32::max);
+    if max <= 0.0 {
+        return vec![1.0; w * h];
+    }
+    density
+        .iter()
+        .map(|d| EDGE_FLOOR + (1.0 - EDGE_FLOOR) * d / max)
+        .collect()
+}
+
+/// Mean over a (2 * radius + 1)² window, using a summed-area table.
+fn box_blur(values: &[f32], w: usize, h: usize, radius: usize) -> Vec<f32> {
+    let mut sums = vec![0.0f64; (w + 1) * (h + 1)];
+    for y in 0..h {
+        for x in 0..w {
+            sums[(y + 1) * (w + 1) + x + 1] = f64::from(values[y * w + x])
+                + sums[y * (w + 1) + x + 1]
+                + sums[(y + 1) * (w + 1) + x]
+                - sums[y * (w + 1) + x];
+        }
+    }
+
+    let mut out = Vec::with_capacity(w * h);
+    for y in 0..h {
+        for x in 0..w {
+            let (x0, x1) = (x.saturating_sub(radius), (x + radius + 1).min(w));
+            let (y0, y1) = (y.saturating_sub(radius), (y + radius + 1).min(h));
+            let sum = sums[y1 * (w + 1) + x1] - sums[y0 * (w + 1) + x1] - sums[y1 * (w + 1) + x0]
+                + sums[y0 * (w + 1) + x0];
+            out.push((sum / ((x1 - x0) * (y1 - y0)) as f64) as f32);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A flat gray image with a vivid red square in the middle.
+    fn gray_with_red_center(size: usize, square: usize) -> PreparedImage {
+        let gray = Lab::new(50.0, 0.0, 0.0);
+        let red = Lab::new(50.0, 70.0, 50.0);
+        let lo = (size - square) / 2;
+        let inside = |v: usize| (lo..lo + square).contains(&v);
+        let pixels = (0..size * size)
+            .map(|i| {
+                if inside(i % size) && inside(i / size) {
+                    red
+                } else {
+                    gray
+                }
+            })
+            .collect();
+        PreparedImage::new(pixels, size, size)
+    }
+
+    fn red_share(pixels: &[Lab]) -> f32 {
+        pixels.iter().filter(|p| p.a > 35.0).count() as f32 / pixels.len() as f32
+    }
+
+    #[test]
+    fn no_terms_leaves_pixels_unchanged() {
+        let image = gray_with_red_center(32, 8);
+        assert_eq!(weighted_pixels(&image, &[]), image.pixels);
+    }
+
+    #[test]
+    fn every_term_favors_the_red_square() {
+        let image = gray_with_red_center(64, 12);
+        let before = red_share(&image.pixels);
+        for term in [PixelWeight::Center, PixelWeight::Edges, PixelWeight::Chroma] {
+            let pixels = weighted_pixels(&image, &[term]);
+            assert_eq!(pixels.len(), image.pixels.len());
+            let after = red_share(&pixels);
+            assert!(after > before * 1.3, "{term:?}: {before} -> {after}");
+        }
+    }
+
+    #[test]
+    fn terms_combine() {
+        let image = gray_with_red_center(64, 12);
+        let one = red_share(&weighted_pixels(&image, &[PixelWeight::Chroma]));
+        let all = red_share(&weighted_pixels(
+            &image,
+            &[PixelWeight::Center, PixelWeight::Edges, PixelWeight::Chroma],
+        ));
+        assert!(all > one, "chroma alone {one}, all terms {all}");
+    }
+
+    #[test]
+    fn excluded_pixels_are_never_sampled() {
+        let mut image = gray_with_red_center(32, 8);
+        for (included, lab) in image.included.iter_mut().zip(&image.pixels) {
+            *included = lab.a < 35.0;
+        }
+        let gray = image.included.iter().filter(|&&i| i).count();
+        for terms in [&[][..], &[PixelWeight::Center, PixelWeight::Chroma][..]] {
+            let pixels = weighted_pixels(&image, terms);
+            assert_eq!(pixels.len(), gray);
+            assert_eq!(red_share(&pixels), 0.0, "{terms:?}");
+        }
+    }
+
+    #[test]
+    fn blend_follows_image_weights_not_sizes() {
+        let dark = vec![Lab::new(10.0, 0.0, 0.0); 300];
+        let light = vec![Lab::new(90.0, 0.0, 0.0); 100];
+
+        let even = blend(&[(dark.clone(), 1.0), (light.clone(), 1.0)]);
+        assert_eq!(even.len(), 400);
+        assert_eq!(even.iter().filter(|p| p.l > 50.0).count(), 200);
+
+        let favored = blend(&[(dark.clone(), 1.0), (light, 3.0)]);
+        assert_eq!(favored.iter().filter(|p| p.l > 50.0).count(), 300);
+
+        assert_eq!(blend(&[(dark.clone(), 2.0)]), dark);
+    }
+
+    #[test]
+    fn center_bias_peaks_in_the_middle() {
+        let factors = center_bias(9, 5);
+        let center = factors[2 * 9 + 4];
+        let corner = factors[0];
+        assert!(center > 0.95, "{center}");
+        assert!((CENTER_FLOOR..0.6).contains(&corner), "{corner}");
+    }
+
+    #[test]
+    fn flat_image_has_uniform_edge_density() {
+        let image = PreparedImage::new(vec![Lab::new(40.0, 5.0, 5.0); 100], 10, 10);
+        assert!(edge_density(&image).iter().all(|&d| d == 1.0));
+    }
+
+    #[test]
+    fn resample_follows_weights() {
+        let mut pixels = vec![Lab::new(10.0, 0.0, 0.0); 50];
+        pixels.extend(vec![Lab::new(90.0, 0.0, 0.0); 50]);
+        let mut weights = vec![1.0; 50];
+        weights.extend(vec![3.0; 50]);
+        let out = resample(&pixels, &weights, 100);
+        let light = out.iter().filter(|p| p.l > 50.0).count();
+        assert_eq!(out.len(), 100);
+        assert_eq!(light, 75);
+    }
+}
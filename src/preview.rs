@@ -1,5 +1,7 @@
+use crate::audit::audit;
 use crate::color::Color;
 use crate::pipeline::assign::AnsiPalette;
+use crate::pipeline::contrast::DEFAULT_ACCENT_CONTRAST;
 
 const RESET: &str = "\x1b[0m";
 
@@ -24,6 +26,30 @@ fn contrast_fg(bg: &Color) -> &'static str {
     }
 }
 
+/// One-line summary of the accent pairs that become confusable under any
+/// simulated color vision deficiency.
+fn cvd_summary(palette: &AnsiPalette) -> String {
+    let mut pairs: Vec<(usize, usize)> = audit(palette, DEFAULT_ACCENT_CONTRAST, false, false)
+        .confusable
+        .iter()
+        .map(|c| (c.a, c.b))
+        .collect();
+    pairs.sort_unstable();
+    pairs.dedup();
+    if pairs.is_empty() {
+        return "all accents distinguishable".to_string();
+    }
+    let names: Vec<String> = pairs
+        .iter()
+        .map(|&(a, b)| format!("{}/{}", SLOT_NAMES[a], SLOT_NAMES[b]))
+        .collect();
+    format!(
+        "{} pairs confusable (see `nuri audit`): {}",
+        pairs.len(),
+        names.join(", ")
+    )
+}
+
 /// Print a colored terminal preview of the generated palette.
 pub fn print_preview(palette: &AnsiPalette) {
     println!();
@@ -72,5 +98,6 @@ pub fn print_preview(palette: &AnsiPalette) {
 
     println!("  Foreground contrast: {fg_ratio:.1}:1");
     println!("  Dimmest accent:      {min_accent_ratio:.1}:1");
+    println!("  Color vision:        {}", cvd_summary(palette));
     println!();
 }
@@ -0,0 +1,422 @@
+use std::io::Cursor;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use image::{ImageFormat, Rgb, RgbImage};
+
+use crate::color::Color;
+use crate::pipeline::assign::AnsiPalette;
+
+/// Outer margin of the card, in pixels.
+const PAD: u32 = 16;
+/// Side length of a color cell.
+const CELL: u32 = 96;
+/// Space between cells.
+const GAP: u32 = 8;
+/// Card width: eight cells per row.
+pub const WIDTH: u32 = 2 * PAD + 8 * CELL + 7 * GAP;
+/// Tallest the wallpaper thumbnail is allowed to be.
+const MAX_THUMBNAIL_HEIGHT: u32 = 320;
+
+/// Glyph scale factor for the built-in 5x7 font.
+const SCALE: u32 = 2;
+/// Line height of the built-in font at `SCALE`.
+const LINE: u32 = 7 * SCALE + 4;
+
+/// An outlined rectangle with up to two lines of text in its lower-left corner.
+#[derive(Debug, Clone, PartialEq)]
+struct Cell {
+    x: u32,
+    y: u32,
+    color: Color,
+    text_color: Color,
+    lines: Vec<String>,
+}
+
+/// Positions of everything on the card, shared by the PNG and SVG renderers.
+struct Layout {
+    height: u32,
+    thumbnail: Option<(u32, u32)>,
+    cells: Vec<Cell>,
+}
+
+/// Load the image and scale it down to fit the card.
+pub fn load_thumbnail(path: &Path) -> Result<RgbImage> {
+    let img =
+        image::open(path).with_context(|| format!("failed to open image: {}", path.display()))?;
+    Ok(img
+        .thumbnail(WIDTH - 2 * PAD, MAX_THUMBNAIL_HEIGHT)
+        .to_rgb8())
+}
+
+/// Write a swatch card to `path`; the extension (`.png` or `.svg`) picks the format.
+pub fn write_swatch(
+    path: &Path,
+    palette: &AnsiPalette,
+    thumbnail: Option<&RgbImage>,
+) -> Result<()> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    match ext.as_deref() {
+        Some("png") => render_png(palette, thumbnail)
+            .save_with_format(path, ImageFormat::Png)
+            .with_context(|| format!("failed to write swatch to {}", path.display())),
+        Some("svg") => std::fs::write(path, render_svg(palette, thumbnail)?)
+            .with_context(|| format!("failed to write swatch to {}", path.display())),
+        _ => bail!(
+            "unsupported swatch format: {} (expected .png or .svg)",
+            path.display()
+        ),
+    }
+}
+
+/// Render the swatch card as a raster image.
+pub fn render_png(palette: &AnsiPalette, thumbnail: Option<&RgbImage>) -> RgbImage {
+    let layout = layout(palette, thumbnail.map(|t| t.dimensions()));
+    let mut img = RgbImage::from_pixel(WIDTH, layout.height, rgb(&palette.background));
+
+    if let (Some(thumb), Some((x, y))) = (thumbnail, layout.thumbnail) {
+        image::imageops::replace(&mut img, thumb, x as i64, y as i64);
+    }
+
+    for cell in &layout.cells {
+        fill_rect(&mut img, cell.x, cell.y, CELL, CELL, &palette.slots[8]);
+        fill_rect(
+            &mut img,
+            cell.x + 1,
+            cell.y + 1,
+            CELL - 2,
+            CELL - 2,
+            &cell.color,
+        );
+        for (i, line) in cell.lines.iter().enumerate() {
+            let (tx, ty) = text_origin(cell, i);
+            draw_text(&mut img, tx, ty, line, &cell.text_color);
+        }
+    }
+
+    img
+}
+
+/// Render the swatch card as SVG, with the thumbnail embedded as a PNG data URI.
+pub fn render_svg(palette: &AnsiPalette, thumbnail: Option<&RgbImage>) -> Result<String> {
+    let layout = layout(palette, thumbnail.map(|t| t.dimensions()));
+    let mut out = String::new();
+
+    out.push_str(&format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+        w = WIDTH,
+        h = layout.height
+    ));
+    out.push_str("<!-- Generated by nuri -->\n");
+    out.push_str(&format!(
+        "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n",
+        palette.background.to_hex()
+    ));
+
+    if let (Some(thumb), Some((x, y))) = (thumbnail, layout.thumbnail) {
+        let mut png = Vec::new();
+        thumb
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .context("failed to encode swatch thumbnail")?;
+        out.push_str(&format!(
+            "<image x=\"{x}\" y=\"{y}\" width=\"{}\" height=\"{}\" href=\"data:image/png;base64,{}\"/>\n",
+            thumb.width(),
+            thumb.height(),
+            base64(&png)
+        ));
+    }
+
+    for cell in &layout.cells {
+        out.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"{}\"/>\n",
+            cell.x as f32 + 0.5,
+            cell.y as f32 + 0.5,
+            CELL - 1,
+            CELL - 1,
+            cell.color.to_hex(),
+            palette.slots[8].to_hex()
+        ));
+        for (i, line) in cell.lines.iter().enumerate() {
+            let (tx, ty) = text_origin(cell, i);
+            out.push_str(&format!(
+                "<text x=\"{tx}\" y=\"{}\" font-family=\"monospace\" font-size=\"14\" fill=\"{}\">{line}</text>\n",
+                ty + 7 * SCALE,
+                cell.text_color.to_hex()
+            ));
+        }
+    }
+
+    out.push_str("</svg>\n");
+    Ok(out)
+}
+
+/// Lay out the thumbnail, a row of special colors, and two rows of ANSI slots.
+fn layout(palette: &AnsiPalette, thumbnail: Option<(u32, u32)>) -> Layout {
+    let p = palette;
+    let mut y = PAD;
+    let mut cells = Vec::new();
+
+    let thumbnail = thumbnail.map(|(w, h)| {
+        let pos = (PAD + (WIDTH - 2 * PAD - w) / 2, y);
+        y += h + 2 * GAP;
+        pos
+    });
+
+    // Special colors, each labeled with its contrast against what it is read on
+    let specials = [
+        (p.background, None),
+        (p.foreground, Some(p.background)),
+        (p.cursor_color, Some(p.background)),
+        (p.cursor_text, Some(p.cursor_color)),
+        (p.selection_bg, Some(p.background)),
+        (p.selection_fg, Some(p.selection_bg)),
+    ];
+    for (i, (color, against)) in specials.into_iter().enumerate() {
+        cells.push(cell(p, PAD + i as u32 * (CELL + GAP), y, color, against));
+    }
+    y += CELL + 2 * GAP;
+
+    // ANSI slots, normal row then bright row, contrast against the background
+    for row in 0..2 {
+        for col in 0..8 {
+            let color = p.slots[row * 8 + col];
+            cells.push(cell(
+                p,
+                PAD + col as u32 * (CELL + GAP),
+                y,
+                color,
+                Some(p.background),
+            ));
+        }
+        y += CELL + GAP;
+    }
+
+    Layout {
+        height: y - GAP + PAD,
+        thumbnail,
+        cells,
+    }
+}
+
+/// Build a cell showing the color's hex and, if given, its contrast ratio.
+fn cell(palette: &AnsiPalette, x: u32, y: u32, color: Color, against: Option<Color>) -> Cell {
+    let mut lines = vec![color.to_hex()];
+    if let Some(other) = against {
+        lines.push(format!("{:.1}:1", Color::contrast_ratio(&color, &other)));
+    }
+    // Label with whichever of background/foreground reads better on the cell
+    let text_color = if Color::contrast_ratio(&color, &palette.foreground)
+        >= Color::contrast_ratio(&color, &palette.background)
+    {
+        palette.foreground
+    } else {
+        palette.background
+    };
+    Cell {
+        x,
+        y,
+        color,
+        text_color,
+        lines,
+    }
+}
+
+/// Top-left corner of text line `i`, stacked upward from the bottom of the cell.
+fn text_origin(cell: &Cell, i: usize) -> (u32, u32) {
+    let from_bottom = (cell.lines.len() - i) as u32;
+    (cell.x + 6, cell.y + CELL - from_bottom * LINE)
+}
+
+fn rgb(color: &Color) -> Rgb<u8> {
+    Rgb([color.r, color.g, color.b])
+}
+
+fn fill_rect(img: &mut RgbImage, x: u32, y: u32, w: u32, h: u32, color: &Color) {
+    for py in y..(y + h).min(img.height()) {
+        for px in x..(x + w).min(img.width()) {
+            img.put_pixel(px, py, rgb(color));
+        }
+    }
+}
+
+/// Draw text with the built-in font; characters without a glyph are left blank.
+fn draw_text(img: &mut RgbImage, x: u32, y: u32, text: &str, color: &Color) {
+    for (i, c) in text.chars().enumerate() {
+        let gx = x + i as u32 * (5 * SCALE + SCALE);
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..5 {
+                if bits & (0x10 >> col) != 0 {
+                    fill_rect(
+                        img,
+                        gx + col * SCALE,
+                        y + row as u32 * SCALE,
+                        SCALE,
+                        SCALE,
+                        color,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// 5x7 bitmaps for the characters used in hex codes and contrast ratios.
+fn glyph(c: char) -> [u8; 7] {
+    match c {
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        'a' => [0x00, 0x00, 0x0E, 0x01, 0x0F, 0x11, 0x0F],
+        'b' => [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1E],
+        'c' => [0x00, 0x00, 0x0E, 0x10, 0x10, 0x11, 0x0E],
+        'd' => [0x01, 0x01, 0x0D, 0x13, 0x11, 0x11, 0x0F],
+        'e' => [0x00, 0x00, 0x0E, 0x11, 0x1F, 0x10, 0x0E],
+        'f' => [0x06, 0x09, 0x08, 0x1C, 0x08, 0x08, 0x08],
+        '#' => [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        _ => [0; 7],
+    }
+}
+
+/// Standard base64 with padding.
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::assign::test_support::test_palette;
+
+    fn pixel(img: &RgbImage, x: u32, y: u32) -> Color {
+        let Rgb([r, g, b]) = *img.get_pixel(x, y);
+        Color::new(r, g, b)
+    }
+
+    #[test]
+    fn layout_has_specials_and_all_slots() {
+        let palette = test_palette();
+        let layout = layout(&palette, None);
+        assert_eq!(layout.cells.len(), 6 + 16);
+        for (i, slot) in palette.slots.iter().enumerate() {
+            assert_eq!(layout.cells[6 + i].color, *slot);
+        }
+        let last = layout.cells.last().unwrap();
+        assert_eq!(last.x + CELL + PAD, WIDTH);
+        assert_eq!(last.y + CELL + PAD, layout.height);
+    }
+
+    #[test]
+    fn cells_show_hex_and_contrast() {
+        let palette = test_palette();
+        let layout = layout(&palette, None);
+        let red = &layout.cells[6 + 1];
+        let ratio = Color::contrast_ratio(&palette.slots[1], &palette.background);
+        assert_eq!(
+            red.lines,
+            vec![palette.slots[1].to_hex(), format!("{ratio:.1}:1")]
+        );
+        // The background has nothing to be measured against
+        assert_eq!(layout.cells[0].lines, vec![palette.background.to_hex()]);
+    }
+
+    #[test]
+    fn png_cells_are_filled_with_palette_colors() {
+        let palette = test_palette();
+        let img = render_png(&palette, None);
+        let layout = layout(&palette, None);
+        assert_eq!(img.dimensions(), (WIDTH, layout.height));
+        for cell in &layout.cells {
+            // Top-left corner is clear of text
+            assert_eq!(pixel(&img, cell.x + 2, cell.y + 2), cell.color);
+        }
+        assert_eq!(pixel(&img, 0, 0), palette.background);
+    }
+
+    #[test]
+    fn png_thumbnail_pushes_cells_down() {
+        let palette = test_palette();
+        let thumb = RgbImage::from_pixel(200, 100, Rgb([255, 0, 0]));
+        let img = render_png(&palette, Some(&thumb));
+        let without = render_png(&palette, None);
+        assert_eq!(img.height(), without.height() + 100 + 2 * GAP);
+
+        let x = PAD + (WIDTH - 2 * PAD - 200) / 2;
+        assert_eq!(pixel(&img, x + 10, PAD + 10), Color::new(255, 0, 0));
+    }
+
+    #[test]
+    fn svg_is_well_formed() {
+        let palette = test_palette();
+        let thumb = RgbImage::from_pixel(4, 4, Rgb([0, 128, 255]));
+        let svg = render_svg(&palette, Some(&thumb)).unwrap();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(
+            svg.matches("<text ").count(),
+            svg.matches("</text>").count()
+        );
+        assert!(svg.contains("href=\"data:image/png;base64,iVBORw0KGgo"));
+        for slot in &palette.slots {
+            assert!(svg.contains(&format!("fill=\"{}\"", slot.to_hex())));
+        }
+    }
+
+    #[test]
+    fn base64_matches_rfc_4648_vectors() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn write_swatch_rejects_unknown_extension() {
+        let path = std::env::temp_dir().join("nuri-test-swatch.jpg");
+        let err = write_swatch(&path, &test_palette(), None).unwrap_err();
+        assert!(err.to_string().contains("unsupported swatch format"));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn write_swatch_png_round_trips() {
+        let palette = test_palette();
+        let dir = std::env::temp_dir().join("nuri-test-swatch");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("card.png");
+
+        write_swatch(&path, &palette, None).unwrap();
+
+        let loaded = image::open(&path).unwrap().to_rgb8();
+        assert_eq!(loaded, render_png(&palette, None));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
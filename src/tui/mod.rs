@@ -2,7 +2,7 @@ pub mod widgets;
 
 use std::io::{self, stdout};
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use crossterm::event::{self, Event, KeyCode, KeyEventKind};
@@ -10,17 +10,29 @@ use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
 };
 use crossterm::ExecutableCommand;
-use palette::Lab;
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Clear, Paragraph};
 
-use crate::backends::{get_backend, Target};
-use crate::cli::ThemeMode;
-use crate::pipeline::assign::{assign_slots, AnsiPalette};
+use crate::backends::{get_backend, home_dir, Target};
+use crate::cli::{ExtractorKind, PixelWeight, ThemeMode};
+use crate::color::Cvd;
+use crate::pipeline::assign::{assign_slots, AnsiPalette, SLOT_NAMES};
 use crate::pipeline::contrast::{enforce_contrast, DEFAULT_ACCENT_CONTRAST};
-use crate::pipeline::extract::{extract_colors_with_seed, ExtractedColor};
+use crate::pipeline::cvd::{enforce_cvd_safety, simulate_palette};
+use crate::pipeline::extract::{get_extractor, ExtractedColor, PreparedImage, DEFAULT_SEED};
+use crate::pipeline::region::PixelRect;
+use crate::pipeline::weight::weighted_pixels;
 
-use self::widgets::{PaletteWidget, PreviewWidget};
+use self::widgets::{ImageWidget, PaletteWidget, PreviewWidget};
+
+/// Smallest crop side, in prepared image pixels.
+const MIN_CROP: u32 = 4;
+
+/// Crop moves and resizes in steps of this fraction of the image size.
+const CROP_STEPS: u32 = 20;
+
+/// Longest wait for input before redrawing.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 /// Input mode for the TUI.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -30,6 +42,21 @@ enum InputMode {
     NameInput,
     ConfirmQuit,
     ConfirmOverwrite,
+    Crop,
+}
+
+/// One frame of an animated image, with the palette extracted from it alone.
+pub struct AnimationFrame {
+    pub image: PreparedImage,
+    pub palette: AnsiPalette,
+    /// How long the frame shows.
+    pub delay: Duration,
+}
+
+/// The frame being played and when it appeared.
+struct Playback {
+    frame: usize,
+    shown_at: Instant,
 }
 
 /// State for the interactive TUI application.
@@ -43,15 +70,30 @@ pub struct TuiApp {
     pub show_help: bool,
     pub dirty: bool,
     pub status_message: Option<String>,
+    /// Deficiency the palette and preview are currently rendered through.
+    pub simulation: Option<Cvd>,
     input_mode: InputMode,
     name_input_buf: String,
-    pixels: Vec<Lab>,
+    image: PreparedImage,
+    /// Pixel weighting applied before extraction (--weight).
+    weight: Vec<PixelWeight>,
+    /// Region of `image` colors are extracted from (None = whole image).
+    crop: Option<PixelRect>,
+    /// Crop to restore when crop mode is cancelled.
+    crop_before: Option<PixelRect>,
     k: usize,
     seed: u64,
+    /// Extraction algorithm used when regenerating (--extractor).
+    extractor: ExtractorKind,
     /// Targets passed via --target CLI flag (empty = show picker).
     cli_targets: Vec<Target>,
     /// Backend selection state for the picker popup.
     selected_backends: [bool; 3],
+    /// Keep accents distinguishable for color-blind users (--cvd-safe).
+    cvd_safe: bool,
+    /// Frames of an animated image, played back with their palettes.
+    animation: Vec<AnimationFrame>,
+    playback: Option<Playback>,
 }
 
 impl TuiApp {
@@ -61,7 +103,7 @@ impl TuiApp {
         image_path: PathBuf,
         mode: ThemeMode,
         theme_name: String,
-        pixels: Vec<Lab>,
+        image: PreparedImage,
         k: usize,
     ) -> Self {
         Self {
@@ -74,13 +116,21 @@ impl TuiApp {
             show_help: false,
             dirty: false,
             status_message: None,
+            simulation: None,
             input_mode: InputMode::Normal,
             name_input_buf: format!("~/{theme_name}"),
-            pixels,
+            image,
+            weight: Vec::new(),
+            crop: None,
+            crop_before: None,
             k,
-            seed: 42,
+            seed: DEFAULT_SEED,
+            extractor: ExtractorKind::default(),
             cli_targets: Vec::new(),
             selected_backends: [true, false, false],
+            cvd_safe: false,
+            animation: Vec::new(),
+            playback: None,
         }
     }
 
@@ -88,6 +138,27 @@ impl TuiApp {
     pub fn set_targets(&mut self, targets: Vec<Target>) {
         self.cli_targets = targets;
     }
+
+    /// Run the color-blind separation stage after every contrast pass.
+    pub fn set_cvd_safe(&mut self, cvd_safe: bool) {
+        self.cvd_safe = cvd_safe;
+    }
+
+    /// Weight pixels as chosen via --weight whenever colors are re-extracted.
+    pub fn set_weight(&mut self, weight: Vec<PixelWeight>) {
+        self.weight = weight;
+    }
+
+    /// Regenerate with the extractor chosen via --extractor.
+    pub fn set_extractor(&mut self, extractor: ExtractorKind) {
+        self.extractor = extractor;
+    }
+
+    /// Frames of an animated image to play with `p`, each showing the
+    /// palette extracted from it.
+    pub fn set_animation(&mut self, animation: Vec<AnimationFrame>) {
+        self.animation = animation;
+    }
 }
 
 /// Launch the TUI application.
@@ -112,9 +183,10 @@ fn run_event_loop(
     app: &mut TuiApp,
 ) -> Result<()> {
     loop {
+        advance_animation(app);
         terminal.draw(|f| draw(f, app))?;
 
-        if event::poll(Duration::from_millis(100))? {
+        if event::poll(poll_timeout(app))? {
             if let Event::Key(key) = event::read()? {
                 if key.kind == KeyEventKind::Press {
                     match app.input_mode {
@@ -129,6 +201,7 @@ fn run_event_loop(
                         InputMode::ConfirmOverwrite => {
                             handle_confirm_overwrite(app, key.code);
                         }
+                        InputMode::Crop => handle_crop_input(app, key.code),
                         InputMode::Normal => {
                             if handle_normal_input(app, key.code) {
                                 return Ok(());
@@ -173,6 +246,11 @@ fn handle_confirm_overwrite(app: &mut TuiApp, code: KeyCode) {
 /// Handle key input in normal mode. Returns true if the app should quit.
 fn handle_normal_input(app: &mut TuiApp, code: KeyCode) -> bool {
     app.status_message = None;
+    // Playback only changes what is shown; anything but the simulation
+    // stops it and acts on the edited palette
+    if !matches!(code, KeyCode::Char('p' | 'c')) {
+        app.playback = None;
+    }
     match code {
         KeyCode::Char('q') => {
             if app.dirty {
@@ -197,6 +275,9 @@ fn handle_normal_input(app: &mut TuiApp, code: KeyCode) -> bool {
         KeyCode::Char('d') => switch_mode(app, ThemeMode::Dark),
         KeyCode::Char('l') => switch_mode(app, ThemeMode::Light),
         KeyCode::Char('r') => regenerate(app),
+        KeyCode::Char('c') => cycle_simulation(app),
+        KeyCode::Char('x') => enter_crop_mode(app),
+        KeyCode::Char('p') => toggle_playback(app),
         KeyCode::Char('+') | KeyCode::Char('=') => adjust_lightness(app, 0.02),
         KeyCode::Char('-') => adjust_lightness(app, -0.02),
         KeyCode::Char('s') => adjust_chroma(app, -0.02),
@@ -219,6 +300,47 @@ fn handle_normal_input(app: &mut TuiApp, code: KeyCode) -> bool {
     false
 }
 
+fn handle_crop_input(app: &mut TuiApp, code: KeyCode) {
+    app.status_message = None;
+    let step_x = (app.image.width as i64 / CROP_STEPS as i64).max(1);
+    let step_y = (app.image.height as i64 / CROP_STEPS as i64).max(1);
+    match code {
+        KeyCode::Left | KeyCode::Char('h') => move_crop(app, -step_x, 0),
+        KeyCode::Right | KeyCode::Char('l') => move_crop(app, step_x, 0),
+        KeyCode::Up | KeyCode::Char('k') => move_crop(app, 0, -step_y),
+        KeyCode::Down | KeyCode::Char('j') => move_crop(app, 0, step_y),
+        KeyCode::Char('H') => resize_crop(app, -step_x, 0),
+        KeyCode::Char('L') => resize_crop(app, step_x, 0),
+        KeyCode::Char('K') => resize_crop(app, 0, -step_y),
+        KeyCode::Char('J') => resize_crop(app, 0, step_y),
+        KeyCode::Char('+') | KeyCode::Char('=') => resize_crop(app, step_x, step_y),
+        KeyCode::Char('-') => resize_crop(app, -step_x, -step_y),
+        KeyCode::Char('f') => {
+            app.crop = Some(full_rect(&app.image));
+            reextract(app);
+        }
+        KeyCode::Enter => {
+            if app.crop == Some(full_rect(&app.image)) {
+                app.crop = None;
+            }
+            app.input_mode = InputMode::Normal;
+            app.status_message = Some(match app.crop {
+                Some(rect) => format!("Cropped to {}", describe_crop(rect)),
+                None => "Using the whole image".to_string(),
+            });
+        }
+        KeyCode::Esc => {
+            if app.crop != app.crop_before {
+                app.crop = app.crop_before;
+                reextract(app);
+            }
+            app.input_mode = InputMode::Normal;
+            app.status_message = Some("Crop cancelled".to_string());
+        }
+        _ => {}
+    }
+}
+
 fn handle_backend_select(app: &mut TuiApp, code: KeyCode) {
     match code {
         KeyCode::Char('g') => app.selected_backends[0] = !app.selected_backends[0],
@@ -269,7 +391,7 @@ fn switch_mode(app: &mut TuiApp, mode: ThemeMode) {
     }
     app.mode = mode;
     app.palette = assign_slots(&app.extracted_colors, app.mode);
-    enforce_contrast(&mut app.palette, DEFAULT_ACCENT_CONTRAST);
+    enforce_constraints(app);
     app.dirty = true;
     app.selected_slot = None;
     app.status_message = Some(format!("Switched to {mode:?} mode"));
@@ -277,12 +399,190 @@ fn switch_mode(app: &mut TuiApp, mode: ThemeMode) {
 
 fn regenerate(app: &mut TuiApp) {
     app.seed = app.seed.wrapping_add(1);
-    app.extracted_colors = extract_colors_with_seed(&app.pixels, app.k, app.seed);
+    if reextract(app) {
+        let name = get_extractor(app.extractor).name().to_string();
+        app.status_message = Some(format!("Regenerated palette ({name})"));
+    }
+}
+
+/// Extract colors from the cropped, weighted image and rebuild the palette.
+/// Keeps the current palette and returns false when the crop holds only
+/// excluded pixels.
+fn reextract(app: &mut TuiApp) -> bool {
+    let image = match app.crop {
+        Some(rect) => app.image.crop(rect),
+        None => app.image.clone(),
+    };
+    let pixels = weighted_pixels(&image, &app.weight);
+    if pixels.is_empty() {
+        app.status_message = Some("Crop covers only excluded pixels".to_string());
+        return false;
+    }
+    app.extracted_colors = get_extractor(app.extractor).extract(&pixels, app.k, app.seed);
     app.palette = assign_slots(&app.extracted_colors, app.mode);
-    enforce_contrast(&mut app.palette, DEFAULT_ACCENT_CONTRAST);
+    enforce_constraints(app);
     app.dirty = true;
     app.selected_slot = None;
-    app.status_message = Some("Regenerated palette".to_string());
+    true
+}
+
+// ---------------------------------------------------------------------------
+// Crop editing
+// ---------------------------------------------------------------------------
+
+fn full_rect(image: &PreparedImage) -> PixelRect {
+    PixelRect {
+        x: 0,
+        y: 0,
+        width: image.width as u32,
+        height: image.height as u32,
+    }
+}
+
+/// Start editing the crop; a fresh crop covers the middle half of the image.
+fn enter_crop_mode(app: &mut TuiApp) {
+    app.crop_before = app.crop;
+    if app.crop.is_none() {
+        let (w, h) = (app.image.width as i64, app.image.height as i64);
+        app.crop = Some(clamp_crop(&app.image, w / 4, h / 4, w / 2, h / 2));
+        reextract(app);
+    }
+    app.input_mode = InputMode::Crop;
+}
+
+/// The crop rectangle with the given position and size, shrunk and shifted as
+/// needed to stay inside the image.
+fn clamp_crop(image: &PreparedImage, x: i64, y: i64, width: i64, height: i64) -> PixelRect {
+    let (max_w, max_h) = (image.width as i64, image.height as i64);
+    let width = width.clamp(i64::from(MIN_CROP).min(max_w), max_w);
+    let height = height.clamp(i64::from(MIN_CROP).min(max_h), max_h);
+    PixelRect {
+        x: x.clamp(0, max_w - width) as u32,
+        y: y.clamp(0, max_h - height) as u32,
+        width: width as u32,
+        height: height as u32,
+    }
+}
+
+fn move_crop(app: &mut TuiApp, dx: i64, dy: i64) {
+    let rect = app.crop.unwrap_or_else(|| full_rect(&app.image));
+    let moved = clamp_crop(
+        &app.image,
+        i64::from(rect.x) + dx,
+        i64::from(rect.y) + dy,
+        i64::from(rect.width),
+        i64::from(rect.height),
+    );
+    update_crop(app, moved);
+}
+
+/// Grow or shrink the crop around its center.
+fn resize_crop(app: &mut TuiApp, dw: i64, dh: i64) {
+    let rect = app.crop.unwrap_or_else(|| full_rect(&app.image));
+    let resized = clamp_crop(
+        &app.image,
+        i64::from(rect.x) - dw / 2,
+        i64::from(rect.y) - dh / 2,
+        i64::from(rect.width) + dw,
+        i64::from(rect.height) + dh,
+    );
+    update_crop(app, resized);
+}
+
+/// Re-extract live whenever the crop actually changes.
+fn update_crop(app: &mut TuiApp, rect: PixelRect) {
+    if app.crop != Some(rect) {
+        app.crop = Some(rect);
+        reextract(app);
+    }
+}
+
+fn describe_crop(rect: PixelRect) -> String {
+    format!("{}x{} at {},{}", rect.width, rect.height, rect.x, rect.y)
+}
+
+// ---------------------------------------------------------------------------
+// Animation playback
+// ---------------------------------------------------------------------------
+
+fn toggle_playback(app: &mut TuiApp) {
+    if app.animation.len() < 2 {
+        app.status_message = Some("Not an animated image".to_string());
+        return;
+    }
+    app.playback = match app.playback {
+        Some(_) => None,
+        None => Some(Playback {
+            frame: 0,
+            shown_at: Instant::now(),
+        }),
+    };
+}
+
+/// Move on to the next frame, looping, once the current one has shown long
+/// enough.
+fn advance_animation(app: &mut TuiApp) {
+    let Some(playback) = &mut app.playback else {
+        return;
+    };
+    let delay = app.animation[playback.frame].delay;
+    if playback.shown_at.elapsed() >= delay {
+        playback.frame = (playback.frame + 1) % app.animation.len();
+        playback.shown_at += delay;
+        // Don't try to catch up after a stall
+        if playback.shown_at.elapsed() >= delay {
+            playback.shown_at = Instant::now();
+        }
+    }
+}
+
+/// Wait for input until the next frame is due, or [`POLL_INTERVAL`].
+fn poll_timeout(app: &TuiApp) -> Duration {
+    match &app.playback {
+        Some(playback) => app.animation[playback.frame]
+            .delay
+            .saturating_sub(playback.shown_at.elapsed())
+            .min(POLL_INTERVAL),
+        None => POLL_INTERVAL,
+    }
+}
+
+/// The frame being played, if any.
+fn playing_frame(app: &TuiApp) -> Option<(usize, &AnimationFrame)> {
+    let frame = app.playback.as_ref()?.frame;
+    Some((frame, &app.animation[frame]))
+}
+
+/// Step through the simulated deficiencies, then back to normal vision.
+fn cycle_simulation(app: &mut TuiApp) {
+    app.simulation = match app.simulation {
+        None => Some(Cvd::ALL[0]),
+        Some(cvd) => Cvd::ALL
+            .iter()
+            .position(|&c| c == cvd)
+            .and_then(|i| Cvd::ALL.get(i + 1))
+            .copied(),
+    };
+    app.status_message = Some(match app.simulation {
+        Some(cvd) => format!("Simulating {}", cvd.name()),
+        None => "Simulation off".to_string(),
+    });
+}
+
+/// Re-apply contrast minimums and, with --cvd-safe, color-blind separation.
+fn enforce_constraints(app: &mut TuiApp) {
+    enforce_contrast(&mut app.palette, DEFAULT_ACCENT_CONTRAST);
+    if app.cvd_safe {
+        let unresolved = enforce_cvd_safety(&mut app.palette, DEFAULT_ACCENT_CONTRAST);
+        if let Some((cvd, a, b)) = unresolved.first() {
+            app.status_message = Some(format!(
+                "{} and {} stay confusable for {}",
+                SLOT_NAMES[*a],
+                SLOT_NAMES[*b],
+                cvd.name()
+            ));
+        }
+    }
 }
 
 fn adjust_lightness(app: &mut TuiApp, delta: f32) {
@@ -345,7 +645,7 @@ fn cycle_candidate(app: &mut TuiApp, forward: bool) {
 fn recompute_after_tweak(app: &mut TuiApp) {
     app.palette.background = app.palette.slots[0];
     app.palette.cursor_text = app.palette.background;
-    enforce_contrast(&mut app.palette, DEFAULT_ACCENT_CONTRAST);
+    enforce_constraints(app);
     app.dirty = true;
 }
 
@@ -363,20 +663,18 @@ fn save_targets(app: &TuiApp) -> Vec<Target> {
         .iter()
         .zip(app.selected_backends.iter())
         .filter(|(_, &selected)| selected)
-        .map(|(&t, _)| t)
+        .map(|(t, _)| t.clone())
         .collect()
 }
 
 /// Expand a leading `~` to the user's home directory.
-fn expand_tilde(path: &str) -> PathBuf {
+fn expand_tilde(path: &str) -> Result<PathBuf> {
     if let Some(rest) = path.strip_prefix("~/") {
-        let home = std::env::var("HOME").unwrap_or_else(|_| "~".to_string());
-        PathBuf::from(home).join(rest)
+        Ok(home_dir()?.join(rest))
     } else if path == "~" {
-        let home = std::env::var("HOME").unwrap_or_else(|_| "~".to_string());
-        PathBuf::from(home)
+        home_dir()
     } else {
-        PathBuf::from(path)
+        Ok(PathBuf::from(path))
     }
 }
 
@@ -403,12 +701,12 @@ fn try_save(app: &mut TuiApp) -> Result<()> {
         return Ok(());
     }
 
-    let base = expand_tilde(&raw_path);
+    let base = expand_tilde(&raw_path)?;
     let targets = save_targets(app);
 
     // Check for existing files (overwrite confirmation)
     for target in &targets {
-        let backend = get_backend(*target);
+        let backend = get_backend(target.clone());
         let path = save_path_for_backend(&base, backend.extension());
         if path.exists() {
             app.input_mode = InputMode::ConfirmOverwrite;
@@ -421,7 +719,7 @@ fn try_save(app: &mut TuiApp) -> Result<()> {
 
 fn do_save(app: &mut TuiApp) -> Result<()> {
     let raw_path = app.name_input_buf.trim().to_string();
-    let base = expand_tilde(&raw_path);
+    let base = expand_tilde(&raw_path)?;
     let theme_name = base
         .file_stem()
         .and_then(|s| s.to_str())
@@ -432,7 +730,7 @@ fn do_save(app: &mut TuiApp) -> Result<()> {
     let mut errors = Vec::new();
 
     for target in &targets {
-        let backend = get_backend(*target);
+        let backend = get_backend(target.clone());
         let path = save_path_for_backend(&base, backend.extension());
 
         if let Some(parent) = path.parent() {
@@ -484,10 +782,19 @@ fn draw(f: &mut Frame, app: &TuiApp) {
     let top_layout = Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)])
         .split(main_layout[0]);
 
+    // Render the palette (or the playing frame's) and preview as the
+    // selected deficiency sees them
+    let shown = match playing_frame(app) {
+        Some((_, frame)) => &frame.palette,
+        None => &app.palette,
+    };
+    let simulated = app.simulation.map(|cvd| simulate_palette(shown, cvd));
+    let palette = simulated.as_ref().unwrap_or(shown);
+
     draw_image_pane(f, app, top_layout[0]);
-    draw_palette_pane(f, app, top_layout[1]);
+    draw_palette_pane(f, app, palette, top_layout[1]);
 
-    let preview = PreviewWidget::new(&app.palette);
+    let preview = PreviewWidget::new(palette);
     f.render_widget(preview, main_layout[1]);
 
     draw_status_bar(f, app, main_layout[2]);
@@ -505,11 +812,18 @@ fn draw(f: &mut Frame, app: &TuiApp) {
         InputMode::ConfirmOverwrite => {
             draw_confirm_overwrite_overlay(f, &app.name_input_buf);
         }
+        InputMode::Crop => {}
     }
 }
 
 fn draw_image_pane(f: &mut Frame, app: &TuiApp, area: Rect) {
-    let block = Block::bordered().title("Image");
+    let block = if app.input_mode == InputMode::Crop {
+        Block::bordered()
+            .title("Image (crop)")
+            .border_style(Style::default().fg(Color::Yellow))
+    } else {
+        Block::bordered().title("Image")
+    };
     let inner = block.inner(area);
     f.render_widget(block, area);
 
@@ -522,6 +836,32 @@ fn draw_image_pane(f: &mut Frame, app: &TuiApp, area: Rect) {
         Line::from(format!("  Colors: {}", app.extracted_colors.len())),
         Line::from(""),
     ];
+    if let Some(rect) = app.crop {
+        lines.insert(6, Line::from(format!("  Crop: {}", describe_crop(rect))));
+    }
+    match playing_frame(app) {
+        Some((i, _)) => lines.insert(
+            lines.len() - 1,
+            Line::from(Span::styled(
+                format!("  Playing frame {}/{}", i + 1, app.animation.len()),
+                Style::default().fg(Color::Cyan),
+            )),
+        ),
+        None if app.animation.len() > 1 => lines.insert(
+            lines.len() - 1,
+            Line::from(format!("  Frames: {}", app.animation.len())),
+        ),
+        None => {}
+    }
+    if let Some(cvd) = app.simulation {
+        lines.insert(
+            lines.len() - 1,
+            Line::from(Span::styled(
+                format!("  Simulating: {}", cvd.name()),
+                Style::default().fg(Color::Yellow),
+            )),
+        );
+    }
 
     // Show extracted color swatches
     let mut swatch_spans = vec![Span::raw("  ")];
@@ -540,21 +880,40 @@ fn draw_image_pane(f: &mut Frame, app: &TuiApp, area: Rect) {
         )));
     }
 
-    f.render_widget(Paragraph::new(lines), inner);
+    // The image itself fills whatever room the text leaves
+    let [text_area, image_area] = Layout::vertical([
+        Constraint::Length(lines.len() as u16 + 1),
+        Constraint::Min(0),
+    ])
+    .areas(inner);
+    f.render_widget(Paragraph::new(lines), text_area);
+    if image_area.height >= 3 {
+        let image_area = image_area.inner(Margin::new(2, 0));
+        let widget = match playing_frame(app) {
+            Some((_, frame)) => ImageWidget::new(&frame.image, app.crop),
+            None => ImageWidget::new(&app.image, app.crop),
+        };
+        f.render_widget(widget, image_area);
+    }
 }
 
-fn draw_palette_pane(f: &mut Frame, app: &TuiApp, area: Rect) {
-    let widget = PaletteWidget::new(&app.palette, app.selected_slot);
+fn draw_palette_pane(f: &mut Frame, app: &TuiApp, palette: &AnsiPalette, area: Rect) {
+    let widget = PaletteWidget::new(palette, app.selected_slot);
     f.render_widget(widget, area);
 }
 
 fn draw_status_bar(f: &mut Frame, app: &TuiApp, area: Rect) {
     let text = if let Some(msg) = &app.status_message {
         format!(" {msg}")
+    } else if app.playback.is_some() {
+        " p: Stop | c: CVD | any other key stops and edits the theme palette".to_string()
+    } else if app.input_mode == InputMode::Crop {
+        " Arrows/hjkl: Move | H/L J/K: Width/Height | +/-: Size | f: Full | Enter: Keep | Esc: Cancel"
+            .to_string()
     } else if app.selected_slot.is_some() {
         " +/-: Lightness | s/S: Chroma | Left/Right: Cycle | Enter: Save | q: Quit".to_string()
     } else {
-        " d/l: Mode | r: Regen | Tab: Cycle | 1-6: Select | Enter: Save | ?: Help | q: Quit"
+        " d/l: Mode | r: Regen | x: Crop | c: CVD | Tab: Cycle | 1-6: Select | Enter: Save | ?: Help | q: Quit"
             .to_string()
     };
     let bar = Paragraph::new(text).style(
@@ -566,7 +925,7 @@ fn draw_status_bar(f: &mut Frame, app: &TuiApp, area: Rect) {
 }
 
 fn draw_help_overlay(f: &mut Frame) {
-    let area = centered_rect(60, 70, f.area());
+    let area = centered_rect(60, 90, f.area());
     let lines = vec![
         Line::from(""),
         Line::from("  Keybindings:"),
@@ -579,6 +938,9 @@ fn draw_help_overlay(f: &mut Frame) {
         Line::from("  Esc           Deselect / close"),
         Line::from("  d / l         Switch to dark / light mode"),
         Line::from("  r             Regenerate palette (new seed)"),
+        Line::from("  c             Cycle color-blindness simulation"),
+        Line::from("  x             Crop the region colors come from"),
+        Line::from("  p             Play / stop each frame's palette (animations)"),
         Line::from("  Enter         Save theme"),
         Line::from(""),
         Line::from("  When a slot is selected:"),
@@ -586,6 +948,12 @@ fn draw_help_overlay(f: &mut Frame) {
         Line::from("  s / S         Adjust chroma"),
         Line::from("  Left / Right  Cycle through extracted colors"),
         Line::from(""),
+        Line::from("  While cropping (colors update live):"),
+        Line::from("  Arrows / hjkl Move the crop"),
+        Line::from("  H/L, K/J, +/- Narrower/wider, shorter/taller, resize"),
+        Line::from("  f             Whole image"),
+        Line::from("  Enter / Esc   Keep / cancel the crop"),
+        Line::from(""),
         Line::from("  Press ? or Esc to close"),
     ];
     let popup = Paragraph::new(lines)
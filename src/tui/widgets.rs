@@ -3,6 +3,8 @@ use ratatui::widgets::{Block, Paragraph, Widget};
 
 use crate::color::Color as AppColor;
 use crate::pipeline::assign::AnsiPalette;
+use crate::pipeline::extract::PreparedImage;
+use crate::pipeline::region::PixelRect;
 
 const SLOT_NAMES: [&str; 8] = ["Blk", "Red", "Grn", "Yel", "Blu", "Mag", "Cyn", "Wht"];
 
@@ -129,6 +131,71 @@ impl Widget for PaletteWidget<'_> {
     }
 }
 
+// ---------------------------------------------------------------------------
+// ImageWidget
+// ---------------------------------------------------------------------------
+
+/// Brightness of pixels outside the crop or inside excluded regions.
+const DIM: f32 = 0.3;
+
+/// A widget that renders the prepared image with half-block characters (two
+/// pixels per cell), scaled to fit and centered. Pixels that extraction
+/// ignores (outside the crop or excluded) are dimmed.
+pub struct ImageWidget<'a> {
+    image: &'a PreparedImage,
+    crop: Option<PixelRect>,
+}
+
+impl<'a> ImageWidget<'a> {
+    pub fn new(image: &'a PreparedImage, crop: Option<PixelRect>) -> Self {
+        Self { image, crop }
+    }
+
+    /// Terminal color of the pixel nearest to (`x`, `y`) in image coordinates.
+    fn pixel(&self, x: f32, y: f32) -> Color {
+        let px = (x as usize).min(self.image.width - 1);
+        let py = (y as usize).min(self.image.height - 1);
+        let i = py * self.image.width + px;
+        let c = AppColor::from_lab(self.image.pixels[i]);
+        let active = self.image.included[i]
+            && self
+                .crop
+                .is_none_or(|rect| rect.contains(px as f32 + 0.5, py as f32 + 0.5));
+        if active {
+            to_color(&c)
+        } else {
+            let dim = |v: u8| (f32::from(v) * DIM) as u8;
+            Color::Rgb(dim(c.r), dim(c.g), dim(c.b))
+        }
+    }
+}
+
+impl Widget for ImageWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let (w, h) = (self.image.width as f32, self.image.height as f32);
+        if area.is_empty() || w == 0.0 || h == 0.0 {
+            return;
+        }
+        // Cells are about twice as tall as wide, so each holds two pixel rows
+        let scale = (f32::from(area.width) / w).min(f32::from(area.height) * 2.0 / h);
+        let cols = ((w * scale) as u16).clamp(1, area.width);
+        let rows = ((h * scale / 2.0).ceil() as u16).clamp(1, area.height);
+        let left = area.x + (area.width - cols) / 2;
+        let top = area.y + (area.height - rows) / 2;
+
+        for row in 0..rows {
+            for col in 0..cols {
+                let x = (f32::from(col) + 0.5) / scale;
+                let upper = self.pixel(x, (f32::from(row) * 2.0 + 0.5) / scale);
+                let lower = self.pixel(x, (f32::from(row) * 2.0 + 1.5) / scale);
+                if let Some(cell) = buf.cell_mut((left + col, top + row)) {
+                    cell.set_char('\u{2580}').set_fg(upper).set_bg(lower);
+                }
+            }
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // PreviewWidget
 // ---------------------------------------------------------------------------
@@ -0,0 +1,411 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{bail, Context, Result};
+
+use crate::backends::{get_backend_with, reload_installed, BackendOptions, Target};
+use crate::cache::content_hash;
+use crate::cli::{mode_name, ThemeMode};
+use crate::pipeline::assign::AnsiPalette;
+use crate::pipeline::{generate, PipelineOptions};
+
+/// Settings for [`run`].
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    /// Wallpaper file, or a symlink to it.
+    pub path: PathBuf,
+    /// Name the themes are installed under, kept across wallpapers.
+    pub name: String,
+    pub targets: Vec<Target>,
+    pub backend: BackendOptions,
+    pub pipeline: PipelineOptions,
+    /// How long the wallpaper must stay unchanged before it is used.
+    pub debounce: Duration,
+    /// How often to check the wallpaper when not woken by inotify.
+    pub interval: Duration,
+    /// Poll even where inotify is available.
+    pub poll: bool,
+    /// Run each backend's reload hook after installing.
+    pub reload: bool,
+    /// Shell commands run after each install.
+    pub hooks: Vec<String>,
+}
+
+/// Follow `options.path`, regenerating and installing the themes each time
+/// it changes (including the first time it is seen). Only returns on error
+/// before watching starts; failures for a single wallpaper are reported and
+/// the watch goes on.
+pub fn run(options: &WatchOptions) -> Result<()> {
+    if signature(&options.path).is_none() {
+        bail!(
+            "{} does not exist or is a broken link",
+            options.path.display()
+        );
+    }
+    let mut waker = Waker::new(options.poll);
+    waker.follow(&options.path);
+    eprintln!(
+        "Watching {} ({})",
+        options.path.display(),
+        waker.describe(options.interval)
+    );
+
+    let mut debouncer = Debouncer::new(options.debounce);
+    let mut cache: HashMap<u64, (AnsiPalette, ThemeMode)> = HashMap::new();
+    let mut installed = None;
+    loop {
+        let now = Instant::now();
+        if let Some(changed) = debouncer.observe(signature(&options.path), now) {
+            waker.follow(&options.path);
+            if let Err(e) = apply(&changed.target, options, &mut cache, &mut installed) {
+                eprintln!("error: {}: {e:#}", changed.target.display());
+            }
+        }
+        let timeout = debouncer
+            .remaining(Instant::now())
+            .map_or(options.interval, |left| left.min(options.interval));
+        waker.wait(timeout);
+    }
+}
+
+/// Generate (or fetch from `cache`) the palette for `image`, install it, and
+/// run the hooks. Does nothing if the content is what was last installed, so
+/// touching the file or re-pointing a link at a copy costs nothing.
+fn apply(
+    image: &Path,
+    options: &WatchOptions,
+    cache: &mut HashMap<u64, (AnsiPalette, ThemeMode)>,
+    installed: &mut Option<u64>,
+) -> Result<()> {
+    let bytes =
+        std::fs::read(image).with_context(|| format!("failed to read {}", image.display()))?;
+    let hash = content_hash(&bytes);
+    if *installed == Some(hash) {
+        return Ok(());
+    }
+
+    let (palette, mode) = match cache.get(&hash) {
+        Some(hit) => {
+            eprintln!("Wallpaper changed: {} (cached)", image.display());
+            hit.clone()
+        }
+        None => {
+            eprintln!("Wallpaper changed: {}", image.display());
+            let result = generate(image, &options.pipeline)?;
+            cache.insert(hash, result.clone());
+            result
+        }
+    };
+
+    for target in &options.targets {
+        let backend = get_backend_with(target.clone(), &options.backend);
+        let path = backend.install(&palette, &options.name)?;
+        eprintln!(
+            "Installed {} theme '{}' to {}",
+            backend.name(),
+            options.name,
+            path.display()
+        );
+        if options.reload {
+            reload_installed(backend.as_ref(), &options.name, &path);
+        }
+    }
+    *installed = Some(hash);
+
+    for hook in &options.hooks {
+        run_hook(hook, image, &options.name, mode);
+    }
+    Ok(())
+}
+
+/// Run a post-install hook through `sh -c`. The theme name, wallpaper, and
+/// mode are passed as `NURI_THEME`, `NURI_IMAGE`, and `NURI_MODE`. A failing
+/// hook is reported but doesn't stop the watch.
+fn run_hook(hook: &str, image: &Path, name: &str, mode: ThemeMode) {
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(hook)
+        .env("NURI_THEME", name)
+        .env("NURI_IMAGE", image)
+        .env("NURI_MODE", mode_name(mode))
+        .status();
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => eprintln!("warning: hook '{hook}' exited with {status}"),
+        Err(e) => eprintln!("warning: failed to run hook '{hook}': {e}"),
+    }
+}
+
+/// What the watched path currently points at. Any difference counts as a
+/// change; whether the content really changed is settled by
+/// [`content_hash`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Signature {
+    /// The path with every symlink resolved.
+    target: PathBuf,
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+/// `None` if the path or the file it links to is missing.
+fn signature(path: &Path) -> Option<Signature> {
+    let target = std::fs::canonicalize(path).ok()?;
+    let meta = std::fs::metadata(&target).ok()?;
+    Some(Signature {
+        target,
+        modified: meta.modified().ok(),
+        len: meta.len(),
+    })
+}
+
+/// Reports a change only once the wallpaper has stayed the same for the
+/// debounce window, so a burst of events (or a file still being written) is
+/// handled once, with its final content.
+#[derive(Debug)]
+struct Debouncer {
+    delay: Duration,
+    seen: Option<Signature>,
+    /// When `seen` was first observed, while a change is pending.
+    since: Option<Instant>,
+}
+
+impl Debouncer {
+    fn new(delay: Duration) -> Self {
+        Debouncer {
+            delay,
+            seen: None,
+            since: None,
+        }
+    }
+
+    /// Record the current signature. Returns it once it has been stable for
+    /// the debounce window; a missing wallpaper is never returned.
+    fn observe(&mut self, current: Option<Signature>, now: Instant) -> Option<Signature> {
+        if current != self.seen {
+            self.seen = current;
+            self.since = Some(now);
+            return None;
+        }
+        if now.duration_since(self.since?) < self.delay {
+            return None;
+        }
+        self.since = None;
+        self.seen.clone()
+    }
+
+    /// Time left until a pending change settles.
+    fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.since
+            .map(|since| self.delay.saturating_sub(now.duration_since(since)))
+    }
+}
+
+/// Puts the watch loop to sleep until the next check. With inotify it wakes
+/// as soon as something changes in the directories holding the wallpaper and
+/// its link target; otherwise, or if inotify fails, it polls.
+struct Waker {
+    #[cfg(target_os = "linux")]
+    inotify: Option<(inotify::Inotify, Vec<PathBuf>)>,
+    poll: bool,
+}
+
+impl Waker {
+    fn new(poll: bool) -> Self {
+        Waker {
+            #[cfg(target_os = "linux")]
+            inotify: None,
+            poll: poll || cfg!(not(target_os = "linux")),
+        }
+    }
+
+    /// Watch the directories `path` currently depends on. Called again after
+    /// each change, since a re-pointed link may lead somewhere new.
+    #[cfg(target_os = "linux")]
+    fn follow(&mut self, path: &Path) {
+        if self.poll {
+            return;
+        }
+        let dirs = watched_dirs(path);
+        if matches!(&self.inotify, Some((_, watched)) if *watched == dirs) {
+            return;
+        }
+        match inotify::Inotify::new(&dirs) {
+            Ok(inotify) => self.inotify = Some((inotify, dirs)),
+            Err(e) => {
+                eprintln!("warning: inotify unavailable ({e}); polling instead");
+                self.inotify = None;
+                self.poll = true;
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn follow(&mut self, _path: &Path) {}
+
+    fn describe(&self, interval: Duration) -> String {
+        if self.poll {
+            format!("polling every {}ms", interval.as_millis())
+        } else {
+            "inotify".to_string()
+        }
+    }
+
+    fn wait(&self, timeout: Duration) {
+        #[cfg(target_os = "linux")]
+        if let Some((inotify, _)) = &self.inotify {
+            inotify.wait(timeout);
+            return;
+        }
+        std::thread::sleep(timeout);
+    }
+}
+
+/// The directory holding `path`, plus the one holding the file it links to
+/// if that is elsewhere. Watching directories rather than files catches
+/// links being replaced and files being renamed into place.
+#[cfg(target_os = "linux")]
+fn watched_dirs(path: &Path) -> Vec<PathBuf> {
+    let parent = |p: &Path| match p.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let mut dirs = vec![parent(path)];
+    if let Ok(target) = std::fs::canonicalize(path) {
+        let dir = parent(&target);
+        if std::fs::canonicalize(&dirs[0]).ok().as_ref() != Some(&dir) {
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
+
+#[cfg(target_os = "linux")]
+mod inotify {
+    use std::ffi::CString;
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    /// Events that can mean the wallpaper changed.
+    const MASK: u32 = libc::IN_CLOSE_WRITE
+        | libc::IN_MODIFY
+        | libc::IN_ATTRIB
+        | libc::IN_CREATE
+        | libc::IN_DELETE
+        | libc::IN_MOVED_FROM
+        | libc::IN_MOVED_TO;
+
+    /// An inotify instance watching a few directories. The events themselves
+    /// are discarded: the caller re-checks the wallpaper on every wake-up.
+    pub struct Inotify {
+        fd: OwnedFd,
+    }
+
+    impl Inotify {
+        pub fn new(dirs: &[PathBuf]) -> io::Result<Self> {
+            // SAFETY: plain syscall; the result is checked before use.
+            let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // SAFETY: `fd` is a freshly opened descriptor nothing else owns.
+            let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+            for dir in dirs {
+                let path = CString::new(dir.as_os_str().as_bytes())
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+                // SAFETY: `path` is a valid NUL-terminated string.
+                let wd = unsafe { libc::inotify_add_watch(fd.as_raw_fd(), path.as_ptr(), MASK) };
+                if wd < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(Inotify { fd })
+        }
+
+        /// Block until an event arrives or `timeout` passes, then drain the
+        /// queue.
+        pub fn wait(&self, timeout: Duration) {
+            let mut pollfd = libc::pollfd {
+                fd: self.fd.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let millis = timeout.as_millis().min(i32::MAX as u128) as i32;
+            // SAFETY: `pollfd` is a valid array of one element.
+            if unsafe { libc::poll(&mut pollfd, 1, millis) } <= 0 {
+                return;
+            }
+            let mut buf = [0u8; 4096];
+            // SAFETY: reads at most `buf.len()` bytes into `buf`; the fd is
+            // non-blocking, so this stops once the queue is empty.
+            while unsafe { libc::read(self.fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len()) } > 0
+            {
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sig(name: &str, len: u64) -> Option<Signature> {
+        Some(Signature {
+            target: PathBuf::from(name),
+            modified: None,
+            len,
+        })
+    }
+
+    #[test]
+    fn debouncer_waits_for_a_stable_signature() {
+        let start = Instant::now();
+        let ms = |n| start + Duration::from_millis(n);
+        let mut debouncer = Debouncer::new(Duration::from_millis(100));
+
+        assert_eq!(debouncer.observe(sig("a.png", 1), ms(0)), None);
+        assert_eq!(debouncer.remaining(ms(40)), Some(Duration::from_millis(60)));
+        // Still being written: the window starts over
+        assert_eq!(debouncer.observe(sig("a.png", 2), ms(50)), None);
+        assert_eq!(debouncer.observe(sig("a.png", 2), ms(120)), None);
+        assert_eq!(debouncer.observe(sig("a.png", 2), ms(150)), sig("a.png", 2));
+        // Reported once
+        assert_eq!(debouncer.observe(sig("a.png", 2), ms(400)), None);
+        assert_eq!(debouncer.remaining(ms(400)), None);
+
+        // A missing wallpaper is never reported
+        assert_eq!(debouncer.observe(None, ms(500)), None);
+        assert_eq!(debouncer.observe(None, ms(700)), None);
+        assert_eq!(debouncer.observe(sig("b.png", 1), ms(800)), None);
+        assert_eq!(debouncer.observe(sig("b.png", 1), ms(900)), sig("b.png", 1));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn signature_follows_symlinks() {
+        let dir = std::env::temp_dir().join("nuri-watch-signature");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let (a, b, link) = (dir.join("a.png"), dir.join("b.png"), dir.join("current"));
+        std::fs::write(&a, "aaaa").unwrap();
+        std::fs::write(&b, "bb").unwrap();
+
+        std::os::unix::fs::symlink(&a, &link).unwrap();
+        let first = signature(&link).unwrap();
+        assert_eq!(first.target, std::fs::canonicalize(&a).unwrap());
+        assert_eq!(first.len, 4);
+
+        std::fs::remove_file(&link).unwrap();
+        std::os::unix::fs::symlink(&b, &link).unwrap();
+        let second = signature(&link).unwrap();
+        assert_eq!(second.target, std::fs::canonicalize(&b).unwrap());
+
+        std::fs::remove_file(&b).unwrap();
+        assert_eq!(signature(&link), None);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
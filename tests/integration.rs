@@ -3,14 +3,21 @@ use std::process::Command;
 
 use nuri::backends::ghostty::GhosttyBackend;
 use nuri::backends::neovim::NeovimBackend;
+use nuri::backends::vim::VimBackend;
 use nuri::backends::zellij::ZellijBackend;
 use nuri::backends::{get_backend, Target, ThemeBackend};
-use nuri::cli::ThemeMode;
-use nuri::color::Color;
-use nuri::pipeline::assign::assign_slots;
+use nuri::cli::{ExtractorKind, PixelWeight, ThemeMode};
+use nuri::color::{Color, PaletteColor, WideColor};
+use nuri::pipeline::assign::{assign_slots, assign_slots_wide, hue_distance};
 use nuri::pipeline::contrast::{enforce_contrast, DEFAULT_ACCENT_CONTRAST};
+use nuri::pipeline::cvd::{enforce_cvd_safety, min_cvd_distance, CVD_MIN_DISTANCE};
 use nuri::pipeline::detect::detect_mode;
-use nuri::pipeline::extract::{extract_colors, load_and_prepare};
+use nuri::pipeline::extract::{
+    extract_colors, get_extractor, load_and_prepare, load_image, load_image_with,
+};
+use nuri::pipeline::region::Region;
+use nuri::pipeline::weight::{blend, weighted_pixels};
+use palette::Oklch;
 
 // ---------------------------------------------------------------------------
 // Helpers
@@ -271,6 +278,217 @@ fn contrast_ratios_met_for_all_fixtures() {
     }
 }
 
+#[test]
+fn cvd_safe_separates_diff_colors_for_all_fixtures() {
+    ensure_fixtures();
+    for fixture in &[
+        "dark-photo.png",
+        "light-photo.png",
+        "monochrome.png",
+        "colorful.png",
+    ] {
+        let path = fixture_dir().join(fixture);
+        let pixels = load_and_prepare(&path).unwrap();
+        let colors = extract_colors(&pixels, 16);
+        let mode = detect_mode(&pixels);
+        let mut palette = assign_slots(&colors, mode);
+        enforce_contrast(&mut palette, DEFAULT_ACCENT_CONTRAST);
+        enforce_cvd_safety(&mut palette, DEFAULT_ACCENT_CONTRAST);
+
+        for (a, b) in [(1, 2), (4, 5), (9, 10), (12, 13)] {
+            let (cvd, distance) = min_cvd_distance(&palette.slots[a], &palette.slots[b]);
+            assert!(
+                distance >= CVD_MIN_DISTANCE,
+                "{fixture}: slots {a} and {b} confusable under {} ({distance:.3})",
+                cvd.name()
+            );
+        }
+        for slot in (1..=6).chain(9..=14) {
+            let ratio = Color::contrast_ratio(&palette.slots[slot], &palette.background);
+            assert!(
+                ratio >= 4.5,
+                "{fixture}: slot {slot} contrast {ratio:.2} < 4.5"
+            );
+        }
+    }
+}
+
+#[test]
+fn every_extractor_produces_a_valid_palette() {
+    ensure_fixtures();
+    for kind in [
+        ExtractorKind::Kmeans,
+        ExtractorKind::MedianCut,
+        ExtractorKind::Octree,
+        ExtractorKind::Histogram,
+    ] {
+        let extractor = get_extractor(kind);
+        for fixture in &["dark-photo.png", "light-photo.png", "colorful.png"] {
+            let path = fixture_dir().join(fixture);
+            let pixels = load_and_prepare(&path).unwrap();
+            let colors = extractor.extract(&pixels, 16, 42);
+
+            let name = extractor.name();
+            assert!(!colors.is_empty(), "{name} on {fixture}: no colors");
+            assert!(colors.len() <= 16, "{name} on {fixture}: {}", colors.len());
+            let total: f32 = colors.iter().map(|c| c.weight).sum();
+            assert!(
+                total > 0.5 && total <= 1.0 + 1e-3,
+                "{name} on {fixture}: weights sum to {total}"
+            );
+            for pair in colors.windows(2) {
+                assert!(
+                    pair[0].weight >= pair[1].weight,
+                    "{name} on {fixture}: unsorted"
+                );
+            }
+
+            let mut palette = assign_slots(&colors, detect_mode(&pixels));
+            enforce_contrast(&mut palette, DEFAULT_ACCENT_CONTRAST);
+            for slot in (1..=6).chain(9..=14) {
+                let ratio = Color::contrast_ratio(&palette.slots[slot], &palette.background);
+                assert!(
+                    ratio >= 4.5,
+                    "{name} on {fixture}: slot {slot} contrast {ratio:.2} < 4.5"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn weighting_favors_the_subject_over_the_sky() {
+    // A dull sky gradient with a small orange subject in the middle
+    let path = std::env::temp_dir().join("nuri-test-subject.png");
+    image::RgbImage::from_fn(128, 128, |x, y| {
+        if (56..72).contains(&x) && (56..72).contains(&y) {
+            image::Rgb([230, 120, 30])
+        } else {
+            let v = 120 + (y / 4) as u8;
+            image::Rgb([v - 10, v, v + 20])
+        }
+    })
+    .save(&path)
+    .unwrap();
+    let image = load_image(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let orange = Color::new(230, 120, 30);
+    let subject_weight = |terms: &[PixelWeight]| {
+        let colors = extract_colors(&weighted_pixels(&image, terms), 8);
+        colors
+            .iter()
+            .filter(|c| Color::oklab_distance(&c.color, &orange) < 0.05)
+            .map(|c| c.weight)
+            .sum::<f32>()
+    };
+
+    let plain = subject_weight(&[]);
+    let weighted = subject_weight(&[PixelWeight::Center, PixelWeight::Edges, PixelWeight::Chroma]);
+    assert!(plain > 0.0, "subject not extracted at all");
+    assert!(
+        weighted > plain * 3.0,
+        "subject weight {plain:.3} -> {weighted:.3}"
+    );
+}
+
+#[test]
+fn crop_and_exclude_limit_extraction_to_a_region() {
+    // Red on the left half, blue on the right
+    let path = std::env::temp_dir().join("nuri-test-halves.png");
+    image::RgbImage::from_fn(400, 200, |x, _| {
+        if x < 200 {
+            image::Rgb([200, 40, 40])
+        } else {
+            image::Rgb([40, 40, 200])
+        }
+    })
+    .save(&path)
+    .unwrap();
+    let left: Region = "0,0,50%,100%".parse().unwrap();
+    let cropped = load_image_with(&path, Some(&left), &[]).unwrap();
+    let excluded = load_image_with(&path, None, &[left]).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let red = Color::new(200, 40, 40);
+    let blue = Color::new(40, 40, 200);
+    let dominant = |image| extract_colors(&weighted_pixels(image, &[]), 8)[0].color;
+    assert!(Color::oklab_distance(&dominant(&cropped), &red) < 0.02);
+    assert!(Color::oklab_distance(&dominant(&excluded), &blue) < 0.02);
+    // Resampling at the seam blends a column or two; nothing more
+    for (image, other) in [(&cropped, blue), (&excluded, red)] {
+        let colors = extract_colors(&weighted_pixels(image, &[]), 8);
+        let stray: f32 = colors
+            .iter()
+            .filter(|c| Color::oklab_distance(&c.color, &other) < 0.1)
+            .map(|c| c.weight)
+            .sum();
+        assert!(stray < 0.02, "{colors:?}");
+    }
+}
+
+#[test]
+fn blended_images_share_the_palette_by_weight() {
+    let dir = std::env::temp_dir().join("nuri-test-blend");
+    std::fs::create_dir_all(&dir).unwrap();
+    let red_path = dir.join("red.png");
+    let blue_path = dir.join("blue.png");
+    // Different sizes, so pixel counts alone would favor the red image
+    image::RgbImage::from_pixel(200, 200, image::Rgb([200, 40, 40]))
+        .save(&red_path)
+        .unwrap();
+    image::RgbImage::from_pixel(50, 50, image::Rgb([40, 40, 200]))
+        .save(&blue_path)
+        .unwrap();
+    let red = load_and_prepare(&red_path).unwrap();
+    let blue = load_and_prepare(&blue_path).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let share = |weights: [f32; 2]| {
+        let pixels = blend(&[(red.clone(), weights[0]), (blue.clone(), weights[1])]);
+        let colors = extract_colors(&pixels, 4);
+        colors
+            .iter()
+            .find(|c| Color::oklab_distance(&c.color, &Color::new(40, 40, 200)) < 0.02)
+            .map_or(0.0, |c| c.weight)
+    };
+    assert!((share([1.0, 1.0]) - 0.5).abs() < 0.01);
+    assert!((share([1.0, 3.0]) - 0.75).abs() < 0.01);
+}
+
+#[test]
+fn display_p3_pipeline_meets_contrast_for_all_fixtures() {
+    ensure_fixtures();
+    for fixture in &[
+        "dark-photo.png",
+        "light-photo.png",
+        "monochrome.png",
+        "colorful.png",
+    ] {
+        let path = fixture_dir().join(fixture);
+        let pixels = load_and_prepare(&path).unwrap();
+        let colors = extract_colors(&pixels, 16);
+        let mode = detect_mode(&pixels);
+        let mut wide = assign_slots_wide(&colors, mode);
+        enforce_contrast(&mut wide, DEFAULT_ACCENT_CONTRAST);
+        let mut srgb = wide.to_srgb();
+        enforce_contrast(&mut srgb, DEFAULT_ACCENT_CONTRAST);
+
+        for slot in (1..=6).chain(9..=14) {
+            let ratio = WideColor::contrast_ratio(&wide.slots[slot], &wide.background);
+            assert!(
+                ratio >= 4.5,
+                "{fixture}: P3 slot {slot} contrast {ratio:.2} < 4.5"
+            );
+            let ratio = Color::contrast_ratio(&srgb.slots[slot], &srgb.background);
+            assert!(
+                ratio >= 4.5,
+                "{fixture}: sRGB slot {slot} contrast {ratio:.2} < 4.5"
+            );
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Property tests
 // ---------------------------------------------------------------------------
@@ -298,6 +516,25 @@ mod property_tests {
             .collect()
     }
 
+    /// How far (in Oklab) rounding to 8 bits can move a color: a step in each
+    /// channel, which near black is several times a JND.
+    fn rounding_error(color: Color) -> f32 {
+        let (r, g, b) = (color.r, color.g, color.b);
+        let step = |up: Color, down: Color| {
+            Color::oklab_distance(&color, &up).max(Color::oklab_distance(&color, &down))
+        };
+        step(
+            Color::new(r.saturating_add(1), g, b),
+            Color::new(r.saturating_sub(1), g, b),
+        ) + step(
+            Color::new(r, g.saturating_add(1), b),
+            Color::new(r, g.saturating_sub(1), b),
+        ) + step(
+            Color::new(r, g, b.saturating_add(1)),
+            Color::new(r, g, b.saturating_sub(1)),
+        )
+    }
+
     proptest! {
         #[test]
         fn theme_always_has_22_lines(pixels in arb_pixel_buffer()) {
@@ -351,6 +588,56 @@ mod property_tests {
             let fg_ratio = Color::contrast_ratio(&palette.foreground, bg);
             prop_assert!(fg_ratio >= 7.0, "foreground contrast {:.2} < 7.0", fg_ratio);
         }
+
+        #[test]
+        fn gamut_mapping_preserves_hue(
+            l in 0.0f32..1.0,
+            chroma in 0.03f32..0.4,
+            hue in 0.0f32..360.0,
+        ) {
+            let color = Color::from_oklch(Oklch::new(l, chroma, hue));
+            let mapped = color.to_oklch();
+            // 8-bit rounding leaves near-grays without a meaningful hue
+            if mapped.chroma < 0.03 {
+                return Ok(());
+            }
+            // Gamut mapping may clip by up to a JND (0.02 in Oklab), and 8-bit
+            // rounding adds more near black; either is a wider angle at low
+            // chroma
+            let tolerance = ((0.021 + rounding_error(color)) / mapped.chroma).to_degrees();
+            let drift = hue_distance(mapped.hue.into_positive_degrees(), hue);
+            prop_assert!(
+                drift <= tolerance,
+                "hue {:.1} mapped to {:.1} (tolerance {:.1})",
+                hue,
+                mapped.hue.into_positive_degrees(),
+                tolerance
+            );
+        }
+
+        #[test]
+        fn gamut_mapping_preserves_lightness(
+            l in 0.0f32..1.0,
+            chroma in 0.0f32..0.4,
+            hue in 0.0f32..360.0,
+        ) {
+            let color = Color::from_oklch(Oklch::new(l, chroma, hue));
+            let mapped = color.to_oklch();
+            let tolerance = 0.025 + rounding_error(color);
+            prop_assert!(
+                (mapped.l - l).abs() < tolerance,
+                "L {:.3} mapped to {:.3} (tolerance {:.3})",
+                l,
+                mapped.l,
+                tolerance
+            );
+        }
+
+        #[test]
+        fn in_gamut_colors_round_trip(rgb in proptest::array::uniform3(0u8..=255u8)) {
+            let color = Color::new(rgb[0], rgb[1], rgb[2]);
+            prop_assert_eq!(Color::from_oklch(color.to_oklch()), color);
+        }
     }
 }
 
@@ -493,178 +780,1378 @@ fn cli_unsupported_format_error() {
     );
 }
 
-// ---------------------------------------------------------------------------
-// Multi-backend CLI tests
-// ---------------------------------------------------------------------------
-
 #[test]
-fn cli_default_target_is_ghostty() {
+fn cli_preview_reports_color_vision() {
     ensure_fixtures();
     let bin = cargo_bin();
+    let image = fixture_dir().join("colorful.png");
     let output = Command::new(&bin)
-        .arg(fixture_dir().join("dark-photo.png"))
+        .args([image.to_str().unwrap(), "--preview", "--cvd-safe"])
         .output()
         .expect("failed to run binary");
-
     assert!(output.status.success());
+
     let stdout = String::from_utf8_lossy(&output.stdout);
-    validate_theme_structure(&stdout);
+    let line = stdout
+        .lines()
+        .find(|l| l.contains("Color vision:"))
+        .unwrap_or_else(|| panic!("no color vision line in: {stdout}"));
+    assert!(!line.contains("Red/Grn"), "{line}");
+    assert!(!line.contains("Blu/Mag"), "{line}");
 }
 
 #[test]
-fn cli_target_ghostty_explicit() {
+fn cli_extractor_flag_selects_algorithm() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let image = fixture_dir().join("colorful.png");
+    let run = |extractor: &str| {
+        Command::new(&bin)
+            .args([image.to_str().unwrap(), "--extractor", extractor])
+            .output()
+            .expect("failed to run binary")
+    };
+
+    let output = run("median-cut");
+    assert!(output.status.success());
+    validate_theme_structure(&String::from_utf8_lossy(&output.stdout));
+
+    let output = run("bogus");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("histogram"), "{stderr}");
+}
+
+#[test]
+fn cli_weight_flag_accepts_terms() {
     ensure_fixtures();
     let bin = cargo_bin();
+    let image = fixture_dir().join("colorful.png");
     let output = Command::new(&bin)
-        .args([
-            fixture_dir().join("dark-photo.png").to_str().unwrap(),
-            "--target",
-            "ghostty",
-        ])
+        .args([image.to_str().unwrap(), "--weight", "center,edges,chroma"])
         .output()
         .expect("failed to run binary");
-
     assert!(output.status.success());
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    validate_theme_structure(&stdout);
+    validate_theme_structure(&String::from_utf8_lossy(&output.stdout));
 }
 
 #[test]
-fn cli_target_zellij_stdout() {
+fn cli_crop_and_exclude_flags_accept_regions() {
     ensure_fixtures();
     let bin = cargo_bin();
+    let image = fixture_dir().join("colorful.png");
     let output = Command::new(&bin)
         .args([
-            fixture_dir().join("dark-photo.png").to_str().unwrap(),
-            "--target",
-            "zellij",
+            image.to_str().unwrap(),
+            "--crop",
+            "10%,10%,80%,80%",
+            "--exclude",
+            "0,0,20,20",
+            "--exclude",
+            "50%,50%,10%,10%",
         ])
         .output()
         .expect("failed to run binary");
-
     assert!(output.status.success());
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    assert!(
-        stdout.contains("themes {"),
-        "zellij output should contain KDL themes block"
-    );
+    validate_theme_structure(&String::from_utf8_lossy(&output.stdout));
 }
 
 #[test]
-fn cli_target_neovim_stdout() {
+fn cli_rejects_invalid_regions() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let image = fixture_dir().join("colorful.png");
+    for (flag, region, message) in [
+        ("--crop", "1,2,3", "expected x,y,w,h"),
+        ("--exclude", "0,0,120%,10", "between 0% and 100%"),
+        ("--crop", "100000,0,10,10", "invalid --crop"),
+        ("--exclude", "0,0,100%,100%", "no pixels"),
+    ] {
+        let output = Command::new(&bin)
+            .args([image.to_str().unwrap(), flag, region])
+            .output()
+            .expect("failed to run binary");
+        assert!(!output.status.success(), "{flag} {region}");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains(message), "{flag} {region}: {stderr}");
+    }
+}
+
+#[test]
+fn cli_blends_images_and_directories() {
     ensure_fixtures();
     let bin = cargo_bin();
+    let dir = std::env::temp_dir().join("nuri-test-series");
+    std::fs::create_dir_all(&dir).unwrap();
+    for fixture in ["colorful.png", "dark-photo.png"] {
+        std::fs::copy(fixture_dir().join(fixture), dir.join(fixture)).unwrap();
+    }
+    let single = fixture_dir().join("light-photo.png");
+
     let output = Command::new(&bin)
-        .args([
-            fixture_dir().join("dark-photo.png").to_str().unwrap(),
-            "--target",
-            "neovim",
-        ])
+        .args([dir.to_str().unwrap(), single.to_str().unwrap()])
+        .args(["--image-weight", "1,2", "--target", "json"])
+        .output()
+        .expect("failed to run binary");
+    let mismatched = Command::new(&bin)
+        .args([dir.to_str().unwrap(), "--image-weight", "1,2"])
+        .output()
+        .expect("failed to run binary");
+    let tui = Command::new(&bin)
+        .args([dir.to_str().unwrap(), "--tui"])
         .output()
         .expect("failed to run binary");
+    std::fs::remove_dir_all(&dir).unwrap();
 
     assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Blending 3 images"), "{stderr}");
     let stdout = String::from_utf8_lossy(&output.stdout);
     assert!(
-        stdout.contains("vim.g.colors_name"),
-        "neovim output should contain vim.g.colors_name"
+        stdout.contains("\"name\": \"nuri-test-series\""),
+        "{stdout}"
     );
+
+    assert!(!mismatched.status.success());
+    let stderr = String::from_utf8_lossy(&mismatched.stderr);
+    assert!(stderr.contains("2 values for 1 images"), "{stderr}");
+
+    assert!(!tui.status.success());
+    let stderr = String::from_utf8_lossy(&tui.stderr);
+    assert!(stderr.contains("--tui works on a single image"), "{stderr}");
 }
 
 #[test]
-fn cli_multiple_targets_no_install_errors() {
+fn cli_batch_writes_themes_manifest_and_gallery() {
     ensure_fixtures();
     let bin = cargo_bin();
-    let output = Command::new(&bin)
-        .args([
-            fixture_dir().join("dark-photo.png").to_str().unwrap(),
-            "--target",
-            "ghostty,zellij",
-        ])
-        .output()
-        .expect("failed to run binary");
+    let root = std::env::temp_dir().join("nuri-test-batch");
+    let _ = std::fs::remove_dir_all(&root);
+    let walls = root.join("walls");
+    let out = root.join("out");
+    std::fs::create_dir_all(&walls).unwrap();
+    for fixture in ["colorful.png", "light-photo.png"] {
+        std::fs::copy(fixture_dir().join(fixture), walls.join(fixture)).unwrap();
+    }
+    let batch = |extra: &[&str]| {
+        Command::new(&bin)
+            .args([
+                "batch",
+                walls.to_str().unwrap(),
+                "-o",
+                out.to_str().unwrap(),
+            ])
+            .args(["-t", "ghostty,json", "-j", "2"])
+            .args(extra)
+            .output()
+            .expect("failed to run binary")
+    };
 
-    assert!(!output.status.success());
-    let stderr = String::from_utf8_lossy(&output.stderr);
+    let first = batch(&["--gallery", "markdown"]);
+    assert!(first.status.success(), "{first:?}");
+    let theme = std::fs::read_to_string(out.join("ghostty").join("colorful")).unwrap();
+    validate_theme_structure(&theme);
+    let json = std::fs::read_to_string(out.join("json").join("light-photo.json")).unwrap();
+    let palette = get_backend(Target::Json).parse(&json).unwrap();
+    assert!(Color::contrast_ratio(&palette.foreground, &palette.background) >= 7.0);
+    assert!(out.join("swatches").join("colorful.png").exists());
+    let manifest = std::fs::read_to_string(out.join("index.json")).unwrap();
+    assert!(manifest.contains("\"name\": \"colorful\""), "{manifest}");
+    assert!(manifest.contains("\"mode\": \"light\""), "{manifest}");
+    let gallery = std::fs::read_to_string(out.join("index.md")).unwrap();
+    assert!(gallery.contains("![colorful](swatches/colorful.png)"));
+
+    // Nothing new: both themes are kept from the first run
+    std::fs::write(walls.join("broken.png"), "not an image").unwrap();
+    let second = batch(&["--skip-existing"]);
+    let stderr = String::from_utf8_lossy(&second.stderr);
+    assert!(!second.status.success());
     assert!(
-        stderr.contains("cannot output multiple targets"),
-        "expected multi-target error, got: {stderr}"
+        stderr.contains("Generated 0 themes, skipped 2, 1 failed"),
+        "{stderr}"
     );
+    assert!(stderr.contains("1 of 3 images failed"), "{stderr}");
+
+    // A replaced wallpaper and changed settings both make themes stale
+    std::fs::remove_file(walls.join("broken.png")).unwrap();
+    std::fs::copy(
+        fixture_dir().join("dark-photo.png"),
+        walls.join("colorful.png"),
+    )
+    .unwrap();
+    let third = batch(&["--skip-existing"]);
+    let stderr = String::from_utf8_lossy(&third.stderr);
+    assert!(third.status.success(), "{stderr}");
+    assert!(stderr.contains("Generated 1 themes, skipped 1"), "{stderr}");
+    let fourth = batch(&["--skip-existing", "--min-contrast", "5"]);
+    let stderr = String::from_utf8_lossy(&fourth.stderr);
+    std::fs::remove_dir_all(&root).ok();
+    assert!(fourth.status.success(), "{stderr}");
+    assert!(stderr.contains("Generated 2 themes, skipped 0"), "{stderr}");
 }
 
 #[test]
-fn cli_multiple_targets_with_output_errors() {
+fn cli_watch_follows_a_wallpaper_symlink() {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
     ensure_fixtures();
     let bin = cargo_bin();
-    let tmp = std::env::temp_dir().join("nuri_test_multi_output");
-    std::fs::create_dir_all(&tmp).unwrap();
-    let out_path = tmp.join("out.txt");
-
-    let output = Command::new(&bin)
-        .args([
-            fixture_dir().join("dark-photo.png").to_str().unwrap(),
-            "--target",
-            "ghostty,zellij",
-            "--output",
-            out_path.to_str().unwrap(),
-        ])
-        .output()
+    let root = std::env::temp_dir().join("nuri-test-watch");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let (dark, light) = (root.join("dark.png"), root.join("light.png"));
+    std::fs::copy(fixture_dir().join("dark-photo.png"), &dark).unwrap();
+    std::fs::copy(fixture_dir().join("light-photo.png"), &light).unwrap();
+    let link = root.join("current");
+    let point_at = |image: &Path| {
+        let _ = std::fs::remove_file(&link);
+        std::os::unix::fs::symlink(image, &link).unwrap();
+    };
+    point_at(&dark);
+
+    let hook_log = root.join("hook.log");
+    let mut child = Command::new(&bin)
+        .args(["watch", link.to_str().unwrap(), "-t", "json"])
+        .args(["--debounce", "50", "--interval", "50"])
+        .arg("--hook")
+        .arg(format!(
+            "echo \"$NURI_THEME $NURI_MODE\" >> '{}'",
+            hook_log.display()
+        ))
+        .env("XDG_DATA_HOME", root.join("data"))
+        .stderr(Stdio::piped())
+        .spawn()
         .expect("failed to run binary");
+    let (tx, rx) = mpsc::channel();
+    let stderr = child.stderr.take().unwrap();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+            let _ = tx.send(line);
+        }
+    });
+    let mut seen = Vec::new();
+    let mut wait_for = |needle: &str| {
+        while let Ok(line) = rx.recv_timeout(Duration::from_secs(30)) {
+            seen.push(line);
+            if seen.last().unwrap().contains(needle) {
+                return;
+            }
+        }
+        panic!("no '{needle}' in output: {seen:#?}");
+    };
 
-    assert!(!output.status.success());
-    let stderr = String::from_utf8_lossy(&output.stderr);
+    wait_for("Installed JSON theme 'current'");
+    point_at(&light);
+    wait_for("Installed JSON theme 'current'");
+    let json = std::fs::read_to_string(root.join("data/nuri/palettes/current.json")).unwrap();
+    point_at(&dark);
+    wait_for("(cached)");
+    wait_for("Installed JSON theme 'current'");
+    // The hook runs just after the install message
+    let mut hooks = String::new();
+    for _ in 0..300 {
+        hooks = std::fs::read_to_string(&hook_log).unwrap_or_default();
+        if hooks.lines().count() >= 3 {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    child.kill().ok();
+    child.wait().ok();
+    std::fs::remove_dir_all(&root).ok();
+
+    let palette = get_backend(Target::Json).parse(&json).unwrap();
     assert!(
-        stderr.contains("cannot use --output with multiple targets"),
-        "expected multi-target output error, got: {stderr}"
+        palette.background.to_oklch().l > 0.5,
+        "light wallpaper: {json}"
     );
-
-    let _ = std::fs::remove_dir_all(&tmp);
+    assert_eq!(hooks, "current dark\ncurrent light\ncurrent dark\n");
 }
 
 #[test]
-fn cli_target_install_multiple() {
+fn cli_reload_switches_running_apps() {
+    use nuri::msgpack::{self, Value};
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::UnixListener;
+
     ensure_fixtures();
     let bin = cargo_bin();
-    let tmp = std::env::temp_dir().join("nuri_test_multi_install");
-    let _ = std::fs::remove_dir_all(&tmp);
+    let root = std::env::temp_dir().join("nuri-test-reload");
+    let _ = std::fs::remove_dir_all(&root);
+    let (config, runtime, path_dir) = (root.join("config"), root.join("run"), root.join("bin"));
+    for dir in [&config.join("ghostty"), &runtime, &path_dir] {
+        std::fs::create_dir_all(dir).unwrap();
+    }
+    std::fs::write(
+        config.join("ghostty/config"),
+        "# fonts\nfont-size = 12\ntheme = old\n",
+    )
+    .unwrap();
+    // Stand-in zellij that records its arguments
+    let zellij = path_dir.join("zellij");
+    let zellij_log = root.join("zellij.log");
+    std::fs::write(
+        &zellij,
+        format!("#!/bin/sh\necho \"$@\" > '{}'\n", zellij_log.display()),
+    )
+    .unwrap();
+    std::fs::set_permissions(&zellij, std::fs::Permissions::from_mode(0o755)).unwrap();
+    // Stand-in Neovim answering one request
+    let listener = UnixListener::bind(runtime.join("nvim.4242.0")).unwrap();
+    let nvim = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let Value::Array(request) = msgpack::read(&mut stream).unwrap() else {
+            panic!("request is not an array");
+        };
+        let response = vec![Value::Int(1), request[1].clone(), Value::Nil, Value::Nil];
+        msgpack::write(&mut stream, &Value::Array(response)).unwrap();
+        request
+    });
 
+    let image = fixture_dir().join("colorful.png");
+    let path = format!(
+        "{}:{}",
+        path_dir.display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
     let output = Command::new(&bin)
-        .env("XDG_CONFIG_HOME", &tmp)
-        .args([
-            fixture_dir().join("dark-photo.png").to_str().unwrap(),
-            "--target",
-            "ghostty,zellij,neovim",
-            "--install",
-        ])
+        .args([image.to_str().unwrap(), "-n", "sunset"])
+        .args(["-t", "ghostty,zellij,neovim,vim", "--install", "--reload"])
+        .env("XDG_CONFIG_HOME", &config)
+        .env("XDG_RUNTIME_DIR", &runtime)
+        .env("HOME", &root)
+        .env("PATH", path)
         .output()
         .expect("failed to run binary");
-
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    let request = nvim.join().unwrap();
+    let ghostty = std::fs::read_to_string(config.join("ghostty/config")).unwrap();
+    let zellij_args = std::fs::read_to_string(&zellij_log).unwrap();
+    std::fs::remove_dir_all(&root).ok();
+
+    assert!(output.status.success(), "{stderr}");
+    // Selecting the theme in the config is left to --activate
+    assert_eq!(ghostty, "# fonts\nfont-size = 12\ntheme = old\n");
     assert!(
-        output.status.success(),
-        "multi-install failed: {}",
-        String::from_utf8_lossy(&output.stderr)
+        stderr.contains("does not select 'sunset' (see --activate)"),
+        "{stderr}"
     );
+    assert_eq!(zellij_args, "options --theme sunset\n");
+    assert_eq!(request[2], Value::from("nvim_exec_lua"));
+    let Value::Array(args) = &request[3] else {
+        panic!("params are not an array");
+    };
+    let installed = config.join("nvim/lua/plugins/sunset.lua");
+    assert_eq!(
+        args[1],
+        Value::Array(vec![Value::from(installed.to_str().unwrap())])
+    );
+    assert!(stderr.contains("Reloaded Neovim: reloaded 1 running instance(s)"));
+    assert!(stderr.contains("note: Vim has no reload hook"), "{stderr}");
 
-    // Check all three files exist
-    assert!(tmp.join("ghostty/themes/dark-photo").exists());
-    assert!(tmp.join("zellij/themes/dark-photo.kdl").exists());
-    assert!(tmp.join("nvim/lua/plugins/dark-photo.lua").exists());
-
-    let _ = std::fs::remove_dir_all(&tmp);
+    let without_install = Command::new(&bin)
+        .args([image.to_str().unwrap(), "--reload"])
+        .output()
+        .expect("failed to run binary");
+    assert!(!without_install.status.success());
 }
 
-// ---------------------------------------------------------------------------
-// Zellij & Neovim snapshot tests
-// ---------------------------------------------------------------------------
-
-fn run_pipeline_with_backend(fixture_name: &str, backend: &dyn ThemeBackend) -> String {
+#[test]
+fn cli_activate_and_deactivate_edit_app_configs() {
     ensure_fixtures();
-    let path = fixture_dir().join(fixture_name);
-    let pixels = load_and_prepare(&path).unwrap();
-    let colors = extract_colors(&pixels, 16);
-    let detected_mode = detect_mode(&pixels);
-    let mut palette = assign_slots(&colors, detected_mode);
-    enforce_contrast(&mut palette, DEFAULT_ACCENT_CONTRAST);
+    let bin = cargo_bin();
+    let root = std::env::temp_dir().join("nuri-test-activate");
+    let _ = std::fs::remove_dir_all(&root);
+    let config = root.join("config");
+    let ghostty = config.join("ghostty/config");
+    let zellij = config.join("zellij/config.kdl");
+    let init = config.join("nvim/init.lua");
+    std::fs::create_dir_all(ghostty.parent().unwrap()).unwrap();
+    std::fs::create_dir_all(zellij.parent().unwrap()).unwrap();
+    let ghostty_before = "# Ghostty\nfont-size = 13\ntheme = catppuccin-mocha\n";
+    let zellij_before = "// Zellij\ntheme \"dracula\"\nsimplified_ui true\n";
+    std::fs::write(&ghostty, ghostty_before).unwrap();
+    std::fs::write(&zellij, zellij_before).unwrap();
+
+    let image = fixture_dir().join("colorful.png");
+    let nuri = |args: &[&str]| {
+        Command::new(&bin)
+            .args(args)
+            .env("XDG_CONFIG_HOME", &config)
+            .env("XDG_DATA_HOME", root.join("data"))
+            .env("XDG_STATE_HOME", root.join("state"))
+            .output()
+            .expect("failed to run binary")
+    };
+    let activate = |name: &str| {
+        nuri(&[
+            image.to_str().unwrap(),
+            "-n",
+            name,
+            "-t",
+            "ghostty,zellij,neovim,vim",
+            "--neovim-layout",
+            "plugin",
+            "--install",
+            "--activate",
+        ])
+    };
+
+    let first = activate("sunset");
+    let stderr = String::from_utf8_lossy(&first.stderr).to_string();
+    let ghostty_active = std::fs::read_to_string(&ghostty).unwrap();
+    let zellij_active = std::fs::read_to_string(&zellij).unwrap();
+    let init_active = std::fs::read_to_string(&init).unwrap();
+    let ghostty_backup = std::fs::read_to_string(config.join("ghostty/config.nuri.bak")).unwrap();
+    // A second activation still remembers the original themes
+    let second = activate("dusk");
+    let zellij_second = std::fs::read_to_string(&zellij).unwrap();
+    let deactivate = nuri(&["--deactivate"]);
+    let restored = (
+        std::fs::read_to_string(&ghostty).unwrap(),
+        std::fs::read_to_string(&zellij).unwrap(),
+        std::fs::read_to_string(&init).unwrap(),
+    );
+    let nothing_left = nuri(&["--deactivate", "-t", "zellij"]);
+    std::fs::remove_dir_all(&root).ok();
+
+    assert!(first.status.success(), "{stderr}");
+    assert!(
+        stderr.contains("Activated Zellij theme 'sunset'") && stderr.contains("(was 'dracula')"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("note: Vim themes can't be activated"),
+        "{stderr}"
+    );
+    assert_eq!(
+        ghostty_active,
+        "# Ghostty\nfont-size = 13\ntheme = sunset\n"
+    );
+    assert_eq!(
+        zellij_active,
+        "// Zellij\ntheme \"sunset\"\nsimplified_ui true\n"
+    );
+    assert_eq!(init_active, "vim.cmd.colorscheme(\"sunset\")\n");
+    assert_eq!(ghostty_backup, ghostty_before);
+
+    assert!(second.status.success());
+    assert!(zellij_second.contains("theme \"dusk\""));
+
+    let stderr = String::from_utf8_lossy(&deactivate.stderr);
+    assert!(deactivate.status.success(), "{stderr}");
+    assert!(
+        stderr.contains("Restored Zellij theme 'dracula'"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("Removed Neovim theme 'dusk'"), "{stderr}");
+    assert_eq!(
+        restored,
+        (
+            ghostty_before.to_string(),
+            zellij_before.to_string(),
+            String::new()
+        )
+    );
+    assert!(String::from_utf8_lossy(&nothing_left.stderr).contains("Nothing to deactivate"));
+}
+
+#[test]
+fn cli_caches_extractions() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let root = std::env::temp_dir().join("nuri-test-cache");
+    let _ = std::fs::remove_dir_all(&root);
+    let image = fixture_dir().join("colorful.png");
+    let run = |args: &[&str]| {
+        Command::new(&bin)
+            .arg(&image)
+            .args(["-t", "json"])
+            .args(args)
+            .env("XDG_CACHE_HOME", &root)
+            .output()
+            .expect("failed to run binary")
+    };
+
+    let first = run(&[]);
+    assert!(first.status.success());
+    let cache_dir = root.join("nuri");
+    let entries: Vec<PathBuf> = std::fs::read_dir(&cache_dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .collect();
+    assert_eq!(entries.len(), 1, "{entries:?}");
+    assert_eq!(run(&[]).stdout, first.stdout);
+
+    // A tampered entry shows the cache is read, and --no-cache skips it
+    std::fs::write(&entries[0], "mode light\n53.24 80.09 67.2 1\n").unwrap();
+    assert_ne!(run(&[]).stdout, first.stdout);
+    assert_eq!(run(&["--no-cache"]).stdout, first.stdout);
+    // Other settings get their own entry
+    run(&["-k", "8"]);
+
+    let clear = Command::new(&bin)
+        .args(["cache", "clear"])
+        .env("XDG_CACHE_HOME", &root)
+        .output()
+        .expect("failed to run binary");
+    let remaining = std::fs::read_dir(&cache_dir).unwrap().count();
+    std::fs::remove_dir_all(&root).unwrap();
+
+    assert!(clear.status.success());
+    let stderr = String::from_utf8_lossy(&clear.stderr);
+    assert!(
+        stderr.contains("Removed 2 cached extraction(s)"),
+        "{stderr}"
+    );
+    assert_eq!(remaining, 0);
+}
+
+#[test]
+fn cli_pools_animation_frames_and_writes_one_theme_per_frame() {
+    use image::codecs::gif::GifEncoder;
+    use image::{Delay, Frame, Rgba, RgbaImage};
+
+    let bin = cargo_bin();
+    let root = std::env::temp_dir().join("nuri-test-frames");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    // Fades in from black, then cycles through three hues
+    let animation = root.join("anim.gif");
+    let frames = [[0, 0, 0], [180, 40, 40], [40, 160, 60], [50, 70, 190]].map(|[r, g, b]| {
+        let buffer = RgbaImage::from_fn(32, 32, |x, _| {
+            let shade = (x * 2) as u8;
+            Rgba([r.max(shade), g.max(shade), b.max(shade), 255])
+        });
+        Frame::from_parts(buffer, 0, 0, Delay::from_numer_denom_ms(80, 1))
+    });
+    let file = std::fs::File::create(&animation).unwrap();
+    GifEncoder::new(file).encode_frames(frames).unwrap();
+    let run = |args: &[&str]| {
+        Command::new(&bin)
+            .arg(&animation)
+            .args(["-t", "json", "--no-cache"])
+            .args(args)
+            .output()
+            .expect("failed to run binary")
+    };
+
+    let pooled = run(&[]);
+    let single = run(&["--frames", "1"]);
+    let out = root.join("theme.json");
+    let per_frame = run(&[
+        "--frames",
+        "all",
+        "--per-frame",
+        "-o",
+        out.to_str().unwrap(),
+    ]);
+    let written: Vec<String> = (1..=4)
+        .map(|i| std::fs::read_to_string(root.join(format!("theme-{i}.json"))).unwrap())
+        .collect();
+    let to_stdout = run(&["--per-frame"]);
+    std::fs::remove_dir_all(&root).unwrap();
+
+    assert!(pooled.status.success());
+    let stderr = String::from_utf8_lossy(&pooled.stderr);
+    assert!(stderr.contains("Pooling 4 frames of"), "{stderr}");
+    assert!(single.status.success());
+    assert_ne!(pooled.stdout, single.stdout);
+
+    assert!(per_frame.status.success());
+    for (i, theme) in written.iter().enumerate() {
+        assert!(
+            theme.contains(&format!("\"name\": \"anim-{}\"", i + 1)),
+            "{theme}"
+        );
+    }
+    assert_ne!(written[1], written[2]);
+    assert_ne!(written[2], written[3]);
+
+    assert!(!to_stdout.status.success());
+    let stderr = String::from_utf8_lossy(&to_stdout.stderr);
+    assert!(stderr.contains("use --install or --output"), "{stderr}");
+}
+
+#[test]
+fn cli_display_p3_ghostty_declares_colorspace() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let image = fixture_dir().join("colorful.png");
+    let output = Command::new(&bin)
+        .args([image.to_str().unwrap(), "--gamut", "display-p3"])
+        .output()
+        .expect("failed to run binary");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("window-colorspace = display-p3\n"));
+    let palette = GhosttyBackend.parse(&stdout).unwrap();
+    assert!(Color::contrast_ratio(&palette.foreground, &palette.background) >= 7.0);
+}
+
+#[test]
+fn cli_display_p3_falls_back_to_srgb() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let image = fixture_dir().join("colorful.png");
+    let output = Command::new(&bin)
+        .args([
+            image.to_str().unwrap(),
+            "--target",
+            "vim",
+            "--gamut",
+            "display-p3",
+        ])
+        .output()
+        .expect("failed to run binary");
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Vim themes cannot store Display P3 colors"),
+        "{stderr}"
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("hi Normal guifg=#"), "{stdout}");
+}
+
+// ---------------------------------------------------------------------------
+// Multi-backend CLI tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn cli_default_target_is_ghostty() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let output = Command::new(&bin)
+        .arg(fixture_dir().join("dark-photo.png"))
+        .output()
+        .expect("failed to run binary");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    validate_theme_structure(&stdout);
+}
+
+#[test]
+fn cli_target_ghostty_explicit() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let output = Command::new(&bin)
+        .args([
+            fixture_dir().join("dark-photo.png").to_str().unwrap(),
+            "--target",
+            "ghostty",
+        ])
+        .output()
+        .expect("failed to run binary");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    validate_theme_structure(&stdout);
+}
+
+#[test]
+fn cli_target_zellij_stdout() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let output = Command::new(&bin)
+        .args([
+            fixture_dir().join("dark-photo.png").to_str().unwrap(),
+            "--target",
+            "zellij",
+        ])
+        .output()
+        .expect("failed to run binary");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("themes {"),
+        "zellij output should contain KDL themes block"
+    );
+}
+
+#[test]
+fn cli_target_neovim_stdout() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let output = Command::new(&bin)
+        .args([
+            fixture_dir().join("dark-photo.png").to_str().unwrap(),
+            "--target",
+            "neovim",
+        ])
+        .output()
+        .expect("failed to run binary");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("vim.g.colors_name"),
+        "neovim output should contain vim.g.colors_name"
+    );
+}
+
+#[test]
+fn cli_target_vim_stdout() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let output = Command::new(&bin)
+        .args([
+            fixture_dir().join("dark-photo.png").to_str().unwrap(),
+            "--target",
+            "vim",
+        ])
+        .output()
+        .expect("failed to run binary");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("let g:colors_name"),
+        "vim output should contain g:colors_name"
+    );
+    assert!(
+        stdout.contains("let g:terminal_ansi_colors"),
+        "vim output should contain g:terminal_ansi_colors"
+    );
+}
+
+#[test]
+fn cli_target_emacs_stdout() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let output = Command::new(&bin)
+        .args([
+            fixture_dir().join("dark-photo.png").to_str().unwrap(),
+            "--target",
+            "emacs",
+        ])
+        .output()
+        .expect("failed to run binary");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("(deftheme dark-photo"),
+        "emacs output should contain deftheme"
+    );
+}
+
+#[test]
+fn cli_target_foot_stdout() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let output = Command::new(&bin)
+        .args([
+            fixture_dir().join("dark-photo.png").to_str().unwrap(),
+            "--target",
+            "foot",
+        ])
+        .output()
+        .expect("failed to run binary");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("[colors]"),
+        "foot output should contain [colors]"
+    );
+    assert!(
+        stdout.lines().any(|l| l.starts_with("regular0=")),
+        "foot output should contain regular0"
+    );
+}
+
+#[test]
+fn cli_target_base16_stdout() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let output = Command::new(&bin)
+        .args([
+            fixture_dir().join("dark-photo.png").to_str().unwrap(),
+            "--target",
+            "base16",
+        ])
+        .output()
+        .expect("failed to run binary");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("system: \"base16\"\n"));
+    assert!(
+        stdout.contains("  base0F: \"#"),
+        "base16 output should contain base0F"
+    );
+}
+
+#[test]
+fn cli_target_ls_colors_stdout() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let output = Command::new(&bin)
+        .args([
+            fixture_dir().join("dark-photo.png").to_str().unwrap(),
+            "--target",
+            "ls-colors",
+        ])
+        .output()
+        .expect("failed to run binary");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let re = regex::Regex::new(r"export LS_COLORS='di=01;38;2;\d+;\d+;\d+:").unwrap();
+    assert!(
+        re.is_match(&stdout),
+        "unexpected LS_COLORS output: {stdout}"
+    );
+}
+
+#[test]
+fn cli_multiple_targets_no_install_errors() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let output = Command::new(&bin)
+        .args([
+            fixture_dir().join("dark-photo.png").to_str().unwrap(),
+            "--target",
+            "ghostty,zellij",
+        ])
+        .output()
+        .expect("failed to run binary");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("cannot output multiple targets"),
+        "expected multi-target error, got: {stderr}"
+    );
+}
+
+#[test]
+fn cli_multiple_targets_with_output_errors() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let tmp = std::env::temp_dir().join("nuri_test_multi_output");
+    std::fs::create_dir_all(&tmp).unwrap();
+    let out_path = tmp.join("out.txt");
+
+    let output = Command::new(&bin)
+        .args([
+            fixture_dir().join("dark-photo.png").to_str().unwrap(),
+            "--target",
+            "ghostty,zellij",
+            "--output",
+            out_path.to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to run binary");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("cannot use --output with multiple targets"),
+        "expected multi-target output error, got: {stderr}"
+    );
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn cli_target_install_multiple() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let tmp = std::env::temp_dir().join("nuri_test_multi_install");
+    let _ = std::fs::remove_dir_all(&tmp);
+
+    let output = Command::new(&bin)
+        .env("XDG_CONFIG_HOME", &tmp)
+        .args([
+            fixture_dir().join("dark-photo.png").to_str().unwrap(),
+            "--target",
+            "ghostty,zellij,neovim",
+            "--install",
+        ])
+        .output()
+        .expect("failed to run binary");
+
+    assert!(
+        output.status.success(),
+        "multi-install failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // Check all three files exist
+    assert!(tmp.join("ghostty/themes/dark-photo").exists());
+    assert!(tmp.join("zellij/themes/dark-photo.kdl").exists());
+    assert!(tmp.join("nvim/lua/plugins/dark-photo.lua").exists());
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn cli_desktop_targets_install() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let tmp = std::env::temp_dir().join("nuri_test_desktop_install");
+    let _ = std::fs::remove_dir_all(&tmp);
+
+    let output = Command::new(&bin)
+        .env("XDG_CONFIG_HOME", tmp.join("config"))
+        .env("XDG_DATA_HOME", tmp.join("data"))
+        .args([
+            fixture_dir().join("dark-photo.png").to_str().unwrap(),
+            "--target",
+            "ghostty,gtk,waybar,rofi,dunst,hyprland",
+            "--install",
+        ])
+        .output()
+        .expect("failed to run binary");
+
+    assert!(
+        output.status.success(),
+        "desktop install failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    assert!(tmp.join("config/ghostty/themes/dark-photo").exists());
+    assert!(tmp.join("config/gtk-4.0/colors.css").exists());
+    assert!(tmp.join("config/waybar/colors.css").exists());
+    assert!(tmp.join("data/rofi/themes/dark-photo.rasi").exists());
+    assert!(tmp.join("config/dunst/dunstrc.d/90-nuri.conf").exists());
+    assert!(tmp.join("config/hypr/nuri/dark-photo.conf").exists());
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn cli_swatch_png_and_svg() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let tmp = std::env::temp_dir().join("nuri_test_swatch_cli");
+    let _ = std::fs::remove_dir_all(&tmp);
+    std::fs::create_dir_all(&tmp).unwrap();
+
+    for ext in ["png", "svg"] {
+        let swatch = tmp.join(format!("card.{ext}"));
+        let output = Command::new(&bin)
+            .args([
+                fixture_dir().join("colorful.png").to_str().unwrap(),
+                "--swatch",
+                swatch.to_str().unwrap(),
+            ])
+            .output()
+            .expect("failed to run binary");
+
+        assert!(
+            output.status.success(),
+            "--swatch {ext} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        // The theme itself still goes to stdout
+        assert!(String::from_utf8_lossy(&output.stdout).contains("palette = 0="));
+        assert!(swatch.exists());
+    }
+
+    let png = image::open(tmp.join("card.png")).unwrap();
+    assert_eq!(png.width(), nuri::swatch::WIDTH);
+    let svg = std::fs::read_to_string(tmp.join("card.svg")).unwrap();
+    assert!(svg.contains("data:image/png;base64,"));
+
+    let output = Command::new(&bin)
+        .args([
+            fixture_dir().join("colorful.png").to_str().unwrap(),
+            "--swatch",
+            tmp.join("card.bmp").to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to run binary");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unsupported swatch format"));
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn cli_audit_reads_generated_themes() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let tmp = std::env::temp_dir().join("nuri_test_audit_cli");
+    let _ = std::fs::remove_dir_all(&tmp);
+    std::fs::create_dir_all(&tmp).unwrap();
+
+    for (target, file) in [
+        ("ghostty", "theme"),
+        ("zellij", "theme.kdl"),
+        ("json", "theme.json"),
+    ] {
+        let path = tmp.join(file);
+        let output = Command::new(&bin)
+            .args([
+                fixture_dir().join("colorful.png").to_str().unwrap(),
+                "--target",
+                target,
+                "-o",
+                path.to_str().unwrap(),
+            ])
+            .output()
+            .expect("failed to run binary");
+        assert!(output.status.success());
+
+        let output = Command::new(&bin)
+            .args(["audit", path.to_str().unwrap(), "--warn-only"])
+            .output()
+            .expect("failed to run binary");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("Contrast"), "{target}: {stdout}");
+        assert!(stdout.contains("Color vision"), "{target}: {stdout}");
+        assert!(stdout.contains("Hue roles"), "{target}: {stdout}");
+        // nuri's own output meets the contrast it enforces
+        assert!(output.status.success(), "{target}: {stdout}");
+        assert!(stdout.contains("All checks passed"), "{target}: {stdout}");
+
+        // Its confusable accents fail by default
+        let output = Command::new(&bin)
+            .args(["audit", path.to_str().unwrap()])
+            .output()
+            .expect("failed to run binary");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(!output.status.success(), "{target}: {stdout}");
+        assert!(stdout.contains("are confusable"), "{target}: {stdout}");
+    }
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn cli_generated_themes_pass_audit() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let tmp = std::env::temp_dir().join("nuri_test_audit_generated");
+    let _ = std::fs::remove_dir_all(&tmp);
+    std::fs::create_dir_all(&tmp).unwrap();
+
+    let path = tmp.join("theme");
+    for fixture in [
+        "colorful.png",
+        "dark-photo.png",
+        "light-photo.png",
+        "monochrome.png",
+    ] {
+        // With --warn-only confusable accents only warn, unless --cvd-safe
+        // asks for the pairs it keeps apart to stay apart
+        for flags in [&[][..], &["--cvd-safe"][..]] {
+            let output = Command::new(&bin)
+                .arg(fixture_dir().join(fixture))
+                .args(flags)
+                .arg("-o")
+                .arg(&path)
+                .output()
+                .expect("failed to run binary");
+            assert!(output.status.success(), "{fixture} {flags:?}");
+
+            let output = Command::new(&bin)
+                .args(["audit", "--warn-only"])
+                .arg(&path)
+                .args(flags)
+                .output()
+                .expect("failed to run binary");
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert!(output.status.success(), "{fixture} {flags:?}: {stdout}");
+        }
+    }
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn cli_audit_fails_low_contrast_theme() {
+    let bin = cargo_bin();
+    let tmp = std::env::temp_dir().join("nuri_test_audit_low_contrast");
+    let _ = std::fs::remove_dir_all(&tmp);
+    std::fs::create_dir_all(&tmp).unwrap();
+
+    // Every accent is a shade of the background
+    let mut theme = String::from("background = #101010\nforeground = #202020\n");
+    for i in 0..16 {
+        theme.push_str(&format!("palette = {i}=#1{i:x}1{i:x}1{i:x}\n"));
+    }
+    let path = tmp.join("dim");
+    std::fs::write(&path, theme).unwrap();
+
+    let output = Command::new(&bin)
+        .args(["audit", path.to_str().unwrap()])
+        .output()
+        .expect("failed to run binary");
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("too gray to read as its role !"),
+        "{stdout}"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("audit check(s)"));
+
+    // --warn-only lets hue drift pass, but not contrast
+    let output = Command::new(&bin)
+        .args(["audit", path.to_str().unwrap(), "--warn-only"])
+        .output()
+        .expect("failed to run binary");
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("too gray to read as its role\n"),
+        "{stdout}"
+    );
+
+    let output = Command::new(&bin)
+        .args(["audit", path.to_str().unwrap(), "--target", "vim"])
+        .output()
+        .expect("failed to run binary");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("reading Vim themes is not supported"));
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn cli_fix_repairs_theme_in_place() {
+    let bin = cargo_bin();
+    let tmp = std::env::temp_dir().join("nuri_test_fix_cli");
+    let _ = std::fs::remove_dir_all(&tmp);
+    std::fs::create_dir_all(&tmp).unwrap();
+
+    // A community-style theme whose bright black is unreadable as comment text
+    let mut theme = String::from(
+        "# keep me\nbackground = #1e1e2e\nforeground = #cdd6f4\nfont-family = Iosevka\n",
+    );
+    let slots = [
+        "45475a", "f38ba8", "a6e3a1", "f9e2af", "89b4fa", "f5c2e7", "94e2d5", "bac2de", "313244",
+        "f38ba8", "a6e3a1", "f9e2af", "89b4fa", "f5c2e7", "94e2d5", "a6adc8",
+    ];
+    for (i, hex) in slots.iter().enumerate() {
+        theme.push_str(&format!("palette = {i}=#{hex}\n"));
+    }
+    let path = tmp.join("community");
+    std::fs::write(&path, &theme).unwrap();
+
+    let output = Command::new(&bin)
+        .args(["fix", path.to_str().unwrap()])
+        .output()
+        .expect("failed to run binary");
+    assert!(
+        output.status.success(),
+        "fix failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("palette 8: #313244 -> "));
+
+    let fixed = std::fs::read_to_string(&path).unwrap();
+    assert!(fixed.starts_with("# keep me\n"));
+    assert!(fixed.contains("font-family = Iosevka\n"));
+    assert!(!fixed.contains("palette = 8=#313244"));
+    let changed: Vec<&str> = theme
+        .lines()
+        .zip(fixed.lines())
+        .filter(|(a, b)| a != b)
+        .map(|(a, _)| a)
+        .collect();
+    assert_eq!(changed, ["palette = 8=#313244"]);
+
+    let bright_black = fixed
+        .lines()
+        .find_map(|l| l.strip_prefix("palette = 8="))
+        .unwrap();
+    let ratio = Color::contrast_ratio(
+        &Color::from_hex(bright_black).unwrap(),
+        &Color::from_hex("#1e1e2e").unwrap(),
+    );
+    assert!(ratio >= 3.0, "bright black contrast {ratio:.2}");
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn cli_fixed_theme_passes_audit() {
+    let bin = cargo_bin();
+    let tmp = std::env::temp_dir().join("nuri_test_fix_then_audit");
+    let _ = std::fs::remove_dir_all(&tmp);
+    std::fs::create_dir_all(&tmp).unwrap();
+
+    // Dim accents, and a selection that hides the text (selection-foreground
+    // is left out, so it defaults to the background), with CRLF line endings
+    let mut theme = String::from(
+        "background = #1e1e2e\r\nforeground = #7f849c\r\nselection-background = #313244\r\n",
+    );
+    for i in 0..16 {
+        theme.push_str(&format!(
+            "palette = {i}=#{:02x}3a4{:x}\r\n",
+            0x30 + i * 4,
+            i
+        ));
+    }
+    let path = tmp.join("dim");
+    std::fs::write(&path, &theme).unwrap();
+
+    for min_contrast in ["4.5", "7"] {
+        let output = Command::new(&bin)
+            .args([
+                "fix",
+                path.to_str().unwrap(),
+                "--min-contrast",
+                min_contrast,
+            ])
+            .output()
+            .expect("failed to run binary");
+        assert!(
+            output.status.success(),
+            "fix failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let output = Command::new(&bin)
+            .args([
+                "audit",
+                path.to_str().unwrap(),
+                "--min-contrast",
+                min_contrast,
+                "--warn-only",
+            ])
+            .output()
+            .expect("failed to run binary");
+        assert!(
+            output.status.success(),
+            "{min_contrast}: {}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+    }
+
+    let fixed = std::fs::read_to_string(&path).unwrap();
+    assert!(!fixed.contains("selection-background = #313244"), "{fixed}");
+    assert!(!fixed.replace("\r\n", "").contains('\n'), "{fixed:?}");
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn cli_neovim_plugin_layout_install() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let tmp = std::env::temp_dir().join("nuri_test_neovim_plugin_cli");
+    let _ = std::fs::remove_dir_all(&tmp);
+
+    let output = Command::new(&bin)
+        .env("XDG_DATA_HOME", &tmp)
+        .args([
+            fixture_dir().join("dark-photo.png").to_str().unwrap(),
+            "--target",
+            "neovim",
+            "--neovim-layout",
+            "plugin",
+            "--install",
+        ])
+        .output()
+        .expect("failed to run binary");
+
+    assert!(
+        output.status.success(),
+        "plugin install failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let root = tmp.join("nvim/site/pack/nuri/start/dark-photo");
+    assert!(root.join("colors/dark-photo.lua").exists());
+    assert!(root.join("lua/dark-photo/palette.lua").exists());
+    assert!(root.join("lua/dark-photo/init.lua").exists());
+    assert!(root.join("lua/lualine/themes/dark-photo.lua").exists());
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn cli_template_target_install() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let tmp = std::env::temp_dir().join("nuri_test_template_cli");
+    let _ = std::fs::remove_dir_all(&tmp);
+    std::fs::create_dir_all(tmp.join("nuri/templates")).unwrap();
+    std::fs::write(
+        tmp.join("nuri/templates/rofi.rasi"),
+        "---\noutput: rofi/themes/{{ name }}.rasi\n---\n* {\n    bg: {{ background.hex }};\n    accent: {{ blue | alpha(0.8) }};\n}\n",
+    )
+    .unwrap();
+
+    let output = Command::new(&bin)
+        .env("XDG_CONFIG_HOME", &tmp)
+        .args([
+            fixture_dir().join("dark-photo.png").to_str().unwrap(),
+            "--target",
+            "template:rofi",
+            "--install",
+        ])
+        .output()
+        .expect("failed to run binary");
+
+    assert!(
+        output.status.success(),
+        "template install failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = std::fs::read_to_string(tmp.join("rofi/themes/dark-photo.rasi")).unwrap();
+    let re = regex::Regex::new(r"(?m)^    bg: #[0-9a-f]{6};\n    accent: #[0-9a-f]{8};$").unwrap();
+    assert!(
+        re.is_match(&content),
+        "unexpected template output: {content}"
+    );
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[test]
+fn cli_unknown_template_errors() {
+    ensure_fixtures();
+    let bin = cargo_bin();
+    let tmp = std::env::temp_dir().join("nuri_test_template_missing");
+    let _ = std::fs::remove_dir_all(&tmp);
+    std::fs::create_dir_all(tmp.join("nuri/templates")).unwrap();
+
+    let output = Command::new(&bin)
+        .env("XDG_CONFIG_HOME", &tmp)
+        .args([
+            fixture_dir().join("dark-photo.png").to_str().unwrap(),
+            "--target",
+            "template:missing",
+        ])
+        .output()
+        .expect("failed to run binary");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("no template named 'missing'"),
+        "unexpected error: {stderr}"
+    );
+
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+// ---------------------------------------------------------------------------
+// Zellij & Neovim snapshot tests
+// ---------------------------------------------------------------------------
+
+fn run_pipeline_with_backend(fixture_name: &str, backend: &dyn ThemeBackend) -> String {
+    ensure_fixtures();
+    let path = fixture_dir().join(fixture_name);
+    let pixels = load_and_prepare(&path).unwrap();
+    let colors = extract_colors(&pixels, 16);
+    let detected_mode = detect_mode(&pixels);
+    let mut palette = assign_slots(&colors, detected_mode);
+    enforce_contrast(&mut palette, DEFAULT_ACCENT_CONTRAST);
     backend.serialize(&palette, "test")
 }
 
@@ -709,6 +2196,11 @@ fn snapshot_neovim_dark_photo() {
     snapshot_test_backend("dark-photo.png", &NeovimBackend, "neovim");
 }
 
+#[test]
+fn snapshot_vim_colorful() {
+    snapshot_test_backend("colorful.png", &VimBackend, "vim");
+}
+
 // ---------------------------------------------------------------------------
 // Trait-level tests
 // ---------------------------------------------------------------------------
@@ -717,10 +2209,7 @@ fn make_test_palette() -> nuri::pipeline::assign::AnsiPalette {
     use nuri::pipeline::extract::ExtractedColor;
     use palette::Oklch;
 
-    let make = |l, c, h, w| ExtractedColor {
-        color: Color::from_oklch(Oklch::new(l, c, h)),
-        weight: w,
-    };
+    let make = |l, c, h, w| ExtractedColor::new(Color::from_oklch(Oklch::new(l, c, h)).to_lab(), w);
     let colors = vec![
         make(0.60, 0.20, 25.0, 0.12),
         make(0.60, 0.20, 145.0, 0.12),
@@ -737,7 +2226,32 @@ fn make_test_palette() -> nuri::pipeline::assign::AnsiPalette {
 #[test]
 fn all_backends_serialize_nonempty() {
     let palette = make_test_palette();
-    for target in [Target::Ghostty, Target::Zellij, Target::Neovim] {
+    for target in [
+        Target::Ghostty,
+        Target::Zellij,
+        Target::Neovim,
+        Target::Vim,
+        Target::Emacs,
+        Target::Foot,
+        Target::Konsole,
+        Target::Xresources,
+        Target::Base16,
+        Target::Base24,
+        Target::Gtk,
+        Target::Waybar,
+        Target::Rofi,
+        Target::Dunst,
+        Target::Hyprland,
+        Target::Dircolors,
+        Target::LsColors,
+        Target::Eza,
+        Target::TmTheme,
+        Target::Fzf,
+        Target::Lazygit,
+        Target::Btop,
+        Target::Gpl,
+        Target::Json,
+    ] {
         let backend = get_backend(target);
         let output = backend.serialize(&palette, "test");
         assert!(
@@ -758,6 +2272,27 @@ fn all_backends_write_to_matches_serialize() {
         (Target::Ghostty, ""),
         (Target::Zellij, ".kdl"),
         (Target::Neovim, ".lua"),
+        (Target::Vim, ".vim"),
+        (Target::Emacs, "-theme.el"),
+        (Target::Foot, ""),
+        (Target::Konsole, ".colorscheme"),
+        (Target::Xresources, ".Xresources"),
+        (Target::Base16, ".yaml"),
+        (Target::Base24, ".yaml"),
+        (Target::Gtk, ".css"),
+        (Target::Waybar, ".css"),
+        (Target::Rofi, ".rasi"),
+        (Target::Dunst, ".conf"),
+        (Target::Hyprland, ".conf"),
+        (Target::Dircolors, ""),
+        (Target::LsColors, ".sh"),
+        (Target::Eza, ".yml"),
+        (Target::TmTheme, ".tmTheme"),
+        (Target::Fzf, ".sh"),
+        (Target::Lazygit, ".yml"),
+        (Target::Btop, ".theme"),
+        (Target::Gpl, ".gpl"),
+        (Target::Json, ".json"),
     ] {
         let backend = get_backend(target);
         let filename = format!("test{ext}");
@@ -782,7 +2317,16 @@ fn all_backends_install_creates_file() {
     let _ = std::fs::remove_dir_all(&tmp);
     std::env::set_var("XDG_CONFIG_HOME", &tmp);
 
-    for target in [Target::Ghostty, Target::Zellij, Target::Neovim] {
+    for target in [
+        Target::Ghostty,
+        Target::Zellij,
+        Target::Neovim,
+        Target::Foot,
+        Target::Gtk,
+        Target::Waybar,
+        Target::Dunst,
+        Target::Hyprland,
+    ] {
         let backend = get_backend(target);
         let path = backend.install(&palette, "test_theme").unwrap();
         assert!(